@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::Location;
+
+/// How a fragment became part of a statement.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum AuditKind {
+    /// Trusted by [ow](./struct.Connection.html#method.ow).
+    Trusted,
+    /// Trusted by [ow_runtime](./struct.Connection.html#method.ow_runtime).
+    Runtime,
+    /// Trusted by [without_escape](./struct.Connection.html#method.without_escape).
+    Unescaped,
+    /// Validated by [int](./struct.Connection.html#method.int).
+    Integer,
+    /// Accepted by [allowlist](./struct.Connection.html#method.allowlist).
+    Allowlist,
+    /// Escaped as a string literal when a statement was converted.
+    Escaped,
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            AuditKind::Trusted   => "trusted",
+            AuditKind::Runtime   => "runtime",
+            AuditKind::Unescaped => "unescaped",
+            AuditKind::Integer   => "integer",
+            AuditKind::Allowlist => "allowlist",
+            AuditKind::Escaped   => "escaped",
+        };
+        f.pad(kind)
+    }
+}
+
+/// A fragment recorded by the audit mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// How the fragment became part of a statement.
+    pub kind:     AuditKind,
+    /// The fragment, or the escaped literal.
+    pub fragment: String,
+    /// The caller that registered the fragment, or `None` if it was escaped.
+    pub location: Option<&'static Location<'static>>,
+    /// Number of times the fragment was registered or escaped at the location.
+    pub count:    u64,
+}
+
+/// The fragments recorded by a connection.
+/// See [set_audit](./struct.Connection.html#method.set_audit).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditReport {
+    entries: HashMap<(AuditKind, Option<&'static Location<'static>>, String), u64>,
+}
+
+impl AuditReport {
+    pub(crate) fn record(&mut self, kind: AuditKind, fragment: &str, location: Option<&'static Location<'static>>) {
+        *self.entries.entry((kind, location, fragment.to_string())).or_insert(0) += 1;
+    }
+
+    /// Return the recorded fragments, sorted by kind, location and fragment.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        let mut entries = self.entries
+            .iter()
+            .map(|((kind, location, fragment), count)| AuditEntry {
+                kind:     *kind,
+                fragment: fragment.clone(),
+                location: *location,
+                count:    *count,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (a.kind, a.location, &a.fragment).cmp(&(b.kind, b.location, &b.fragment)));
+        entries
+    }
+}
+
+/// Dumps one line for each entry.
+///
+/// ```text
+/// trusted   src/main.rs:10:15 (2) "SELECT * FROM users WHERE name ="
+/// escaped   - (1) "'Alice'"
+/// ```
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in self.entries() {
+            match entry.location {
+                Some(location) => write!(f, "{:<9} {}", entry.kind, location)?,
+                None => write!(f, "{:<9} -", entry.kind)?,
+            }
+            writeln!(f, " ({}) {:?}", entry.count, entry.fragment)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let mut report = AuditReport::default();
+        let location = Location::caller();
+        report.record(AuditKind::Escaped, "'Alice'", None);
+        report.record(AuditKind::Trusted, "SELECT", Some(location));
+        report.record(AuditKind::Trusted, "SELECT", Some(location));
+
+        let entries = report.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind,  AuditKind::Trusted);
+        assert_eq!(entries[0].count, 2);
+        assert_eq!(
+            report.to_string(),
+            format!("trusted   {} (2) \"SELECT\"\nescaped   - (1) \"'Alice'\"\n", location)
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! The interface of a database driver, to connect to a database which OverwriteSQL does not support.
+//!
+//! A [Backend](trait.Backend.html) runs the SQL converted by the [Connection](../struct.Connection.html),
+//! in which the overwrite strings are already replaced and the other strings are escaped by the
+//! [escaping rules](trait.Backend.html#method.escape_mode) of the backend. So the backend must send the
+//! SQL as it is, and the escaping rules must be those of the database, or the strings may be injected.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use owsql::Dialect;
+//! use owsql::backend::{Backend, BackendError, Cell, RowCallback};
+//!
+//! /// Records the statements, and returns a row of each `SELECT`.
+//! #[derive(Default)]
+//! struct Recorder {
+//!     statements: RefCell<Vec<String>>,
+//! }
+//!
+//! impl Backend for Recorder {
+//!     fn execute(&self, sql: &str) -> Result<u64, BackendError> {
+//!         self.statements.borrow_mut().push(sql.to_string());
+//!         Ok(1)
+//!     }
+//!
+//!     fn iterate(&self, sql: &str, callback: RowCallback) -> Result<(), BackendError> {
+//!         self.statements.borrow_mut().push(sql.to_string());
+//!         callback(&[("id", Cell::Integer(42)), ("name", Cell::Text("Alice")), ("email", Cell::Null)]);
+//!         Ok(())
+//!     }
+//!
+//!     fn dialect(&self) -> Dialect {
+//!         Dialect::Sqlite
+//!     }
+//! }
+//!
+//! let conn = owsql::backend::connect(Recorder::default());
+//! let rows = conn.rows(conn.ow("SELECT * FROM users WHERE name =") + "O'Reilly").unwrap();
+//! assert_eq!(rows[0].get("id"), Some("42"));
+//! assert_eq!(rows[0].get("email"), None);
+//! let recorder = conn.backend::<Recorder>().unwrap();
+//! assert!(recorder.statements.borrow()[0].contains("'O''Reilly'"));
+//! ```
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::dialect::Dialect;
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::escape::{Charset, EscapeMode};
+
+/// A value of a column read by [iterate](trait.Backend.html#tymethod.iterate).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell<'a> {
+    /// `NULL`
+    Null,
+    /// An integer, such as of `INTEGER` and `BIGINT`.
+    Integer(i64),
+    /// A floating point number, such as of `REAL` and `DOUBLE PRECISION`.
+    Real(f64),
+    /// A text, including the values of the other types which the backend reads as text, such as `DECIMAL`.
+    Text(&'a str),
+    /// Bytes, which are read as UTF-8 by the connection, replacing the invalid sequences.
+    Blob(&'a [u8]),
+}
+
+impl Cell<'_> {
+    /// Return the text of the value as the rows of the connection hold it, or `None` for `NULL`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::backend::Cell;
+    /// assert_eq!(Cell::Integer(42).to_text().as_deref(), Some("42"));
+    /// assert_eq!(Cell::Real(2.0).to_text().as_deref(), Some("2.0"));
+    /// assert_eq!(Cell::Blob(b"hi").to_text().as_deref(), Some("hi"));
+    /// assert_eq!(Cell::Null.to_text(), None);
+    /// ```
+    pub fn to_text(&self) -> Option<Cow<'_, str>> {
+        match *self {
+            Cell::Null          => None,
+            Cell::Integer(i)    => Some(Cow::Owned(i.to_string())),
+            Cell::Real(r)       => Some(Cow::Owned(format!("{:?}", r))),
+            Cell::Text(text)    => Some(Cow::Borrowed(text)),
+            Cell::Blob(bytes)   => Some(String::from_utf8_lossy(bytes)),
+        }
+    }
+}
+
+/// The callback of [iterate](trait.Backend.html#tymethod.iterate), called with the column names and
+/// the values of a row, which returns `false` to stop the rows.
+pub type RowCallback<'a> = &'a mut dyn FnMut(&[(&str, Cell)]) -> bool;
+
+/// The error of a [Backend](trait.Backend.html), which is reported as `OwsqlError::Database` by the
+/// error level of the connection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BackendError {
+    /// The error code of the database.
+    pub code:     Option<i32>,
+    /// The SQLSTATE of the error, which decides whether a [retry policy](../struct.RetryPolicy.html)
+    /// retries the statement.
+    pub sqlstate: Option<String>,
+    /// The message, which is shown on `OwsqlErrorLevel::Debug`.
+    pub message:  String,
+}
+
+impl BackendError {
+    /// Creates an error of the message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { code: None, sqlstate: None, message: message.into() }
+    }
+
+    /// Sets the error code of the database.
+    pub fn code(mut self, code: i32) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Sets the SQLSTATE of the error.
+    pub fn sqlstate(mut self, sqlstate: impl Into<String>) -> Self {
+        self.sqlstate = Some(sqlstate.into());
+        self
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// A database driver. See [the module](index.html) for an example.
+///
+/// The methods are called with the SQL converted by the connection, which may have several statements
+/// separated by `;`.
+pub trait Backend {
+    /// Executes the statements, and return the number of the rows changed by the last one.
+    fn execute(&self, sql: &str) -> std::result::Result<u64, BackendError>;
+
+    /// Executes the statements, and calls the callback with the columns of each row they returned,
+    /// in order. The backend must stop the rows if the callback returns `false`.
+    fn iterate(&self, sql: &str, callback: RowCallback) -> std::result::Result<(), BackendError>;
+
+    /// Parses and plans the statements without executing them.
+    /// The default does nothing, so [validate](../struct.Connection.html#method.validate) always succeeds.
+    fn validate(&self, _sql: &str) -> std::result::Result<(), BackendError> {
+        Ok(())
+    }
+
+    /// Return the dialect of the database, which decides the SQL the connection generates, such as
+    /// the quotation of the identifiers.
+    fn dialect(&self) -> Dialect;
+
+    /// Return how the database reads the string literals. The default is `EscapeMode::Standard`.
+    fn escape_mode(&self) -> EscapeMode {
+        EscapeMode::Standard
+    }
+
+    /// Return the charset in which the database reads the statements. The default is `Charset::Utf8`.
+    fn charset(&self) -> Charset {
+        Charset::Utf8
+    }
+
+    /// Return the maximum length of a statement in bytes the database accepts, if it is known.
+    fn max_statement_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Rolls back the open transaction and closes the connection, called by
+    /// [close](../struct.Connection.html#method.close).
+    fn close(&self) -> std::result::Result<(), BackendError> {
+        Ok(())
+    }
+
+    /// Return whether the connection is lost.
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+/// Creates a connection which runs the statements by the backend.
+pub fn connect<B: Backend + 'static>(backend: B) -> Connection {
+    Connection::new(Box::new(Adapter {
+        backend,
+        last_error_code: std::cell::RefCell::new(None),
+        changes:         std::cell::Cell::new(None),
+    }))
+}
+
+impl Connection {
+    /// Return the backend of a connection made by [connect](./backend/fn.connect.html), or `None` if
+    /// it is not of the type.
+    pub fn backend<B: Backend + 'static>(&self) -> Option<&B> {
+        self.conn.as_any().downcast_ref::<Adapter<B>>().map(|adapter| &adapter.backend)
+    }
+}
+
+/// Runs the statements of the connection by a public backend.
+struct Adapter<B> {
+    backend:         B,
+    last_error_code: std::cell::RefCell<Option<String>>,
+    changes:         std::cell::Cell<Option<u64>>,
+}
+
+impl<B: Backend> Adapter<B> {
+    /// Records the code of the error, and converts it into the error of the level.
+    fn error(&self, e: BackendError, error_level: &OwsqlErrorLevel) -> Result<()> {
+        *self.last_error_code.borrow_mut() = e.sqlstate.clone().or_else(|| e.code.map(|code| code.to_string()));
+        OwsqlError::database(error_level, e.code, e.sqlstate, None, &e.message)
+    }
+}
+
+impl<B: Backend + 'static> OwsqlConn for Adapter<B> {
+    fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        self._execute_changes(query, error_level).map(|_| ())
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(0);
+            } else {
+                return Err(e);
+            },
+        };
+        *self.last_error_code.borrow_mut() = None;
+        match self.backend.execute(&query) {
+            Ok(changes) => {
+                self.changes.set(Some(changes));
+                Ok(changes)
+            },
+            Err(e) => self.error(e, error_level).map(|_| 0),
+        }
+    }
+
+    fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
+        callback: IterateCallback) -> Result<()>
+    {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        *self.last_error_code.borrow_mut() = None;
+        let mut stopped = false;
+        let result = self.backend.iterate(&query, &mut |cells| {
+            if stopped {
+                return false;
+            }
+            let texts = cells.iter().map(|(_, cell)| cell.to_text()).collect::<Vec<_>>();
+            let pairs = cells.iter()
+                .zip(&texts)
+                .map(|((column, _), text)| (*column, text.as_deref()))
+                .collect::<Vec<_>>();
+            stopped = !callback(&pairs);
+            !stopped
+        });
+        match result {
+            Err(e) => self.error(e, error_level),
+            // The same error as the callback of `sqlite3_exec` aborting the query.
+            Ok(()) if stopped => OwsqlError::database(error_level, None, None, None, "query aborted"),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        match self.backend.validate(&query) {
+            Ok(()) => Ok(()),
+            Err(e) => self.error(e, error_level),
+        }
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        self.backend.escape_mode()
+    }
+
+    fn charset(&self) -> Charset {
+        self.backend.charset()
+    }
+
+    fn dialect(&self) -> Dialect {
+        self.backend.dialect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn last_error_code(&self) -> Option<String> {
+        self.last_error_code.borrow().clone()
+    }
+
+    fn changes(&self) -> Option<u64> {
+        self.changes.get()
+    }
+
+    fn max_statement_len(&self) -> Option<usize> {
+        self.backend.max_statement_len()
+    }
+
+    fn close(&self, error_level: &OwsqlErrorLevel) -> Result<()> {
+        match self.backend.close() {
+            Ok(()) => Ok(()),
+            Err(e) => self.error(e, error_level),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.backend.is_closed()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use super::{Backend, BackendError, Cell, RowCallback};
+    use crate::dialect::Dialect;
+    use crate::error::OwsqlError;
+    use crate::escape::EscapeMode;
+
+    /// MySQL-like backend returning three rows, which fails the statements containing `fail`.
+    #[derive(Default)]
+    struct Fake {
+        statements: RefCell<Vec<String>>,
+    }
+
+    impl Backend for Fake {
+        fn execute(&self, sql: &str) -> Result<u64, BackendError> {
+            self.statements.borrow_mut().push(sql.to_string());
+            if sql.contains("fail") {
+                return Err(BackendError::new("deadlock").code(1213).sqlstate("40001"));
+            }
+            Ok(2)
+        }
+
+        fn iterate(&self, sql: &str, callback: RowCallback) -> Result<(), BackendError> {
+            self.statements.borrow_mut().push(sql.to_string());
+            for i in 0..3 {
+                if !callback(&[("i", Cell::Integer(i)), ("half", Cell::Real(i as f64 / 2.0))]) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        fn dialect(&self) -> Dialect {
+            Dialect::Mysql
+        }
+
+        fn escape_mode(&self) -> EscapeMode {
+            EscapeMode::Backslash
+        }
+    }
+
+    #[test]
+    fn connect() {
+        let conn = super::connect(Fake::default());
+        assert_eq!(conn.dialect(), Dialect::Mysql);
+        conn.execute(conn.ow("UPDATE users SET name =") + r"\' OR 1=1 --").unwrap();
+        assert!(conn.backend::<Fake>().unwrap().statements.borrow()[0].contains(r"'\\'' OR 1=1 --'"));
+
+        let rows = conn.rows(conn.ow("SELECT i, half FROM t;")).unwrap();
+        assert_eq!(rows.iter().map(|row| row.get("half").unwrap()).collect::<Vec<_>>(), ["0.0", "0.5", "1.0"]);
+        let mut seen = 0;
+        assert!(conn.iterate(conn.ow("SELECT i FROM t;"), |_| { seen += 1; false }).is_err());
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn error() {
+        let conn = super::connect(Fake::default());
+        assert!(matches!(conn.execute(conn.ow("fail;")),
+            Err(OwsqlError::Database { code: Some(1213), sqlstate: Some(ref sqlstate), .. }) if sqlstate == "40001"));
+        assert_eq!(conn.conn.last_error_code(), Some("40001".to_string()));
+    }
+}
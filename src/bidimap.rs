@@ -1,17 +1,16 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::ops::Deref;
-use std::sync::Arc;
 
 pub struct BidiMap<A, B> {
-    key_value: HashMap<Arc<A>, Arc<B>>,
-    value_key: HashMap<Arc<B>, Arc<A>>,
+    key_value: HashMap<A, B>,
+    value_key: HashMap<B, A>,
 }
 
 impl<A, B> BidiMap<A, B>
 where
-    A: Eq + Hash,
-    B: Eq + Hash,
+    A: Clone + Eq + Hash,
+    B: Clone + Eq + Hash,
 {
     pub fn new() -> Self {
         Self {
@@ -21,26 +20,48 @@ where
     }
 
     pub fn insert(&mut self, a: A, b: B) {
-        let a = Arc::new(a);
-        let b = Arc::new(b);
         self.key_value.insert(a.clone(), b.clone());
         self.value_key.insert(b, a);
     }
 
-    pub fn get(&self, key: &A) -> Option<&B> {
-        self.key_value.get(key).map(Deref::deref)
+    pub fn get<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<&B>
+    where
+        A: Borrow<Q>,
+    {
+        self.key_value.get(key)
     }
 
-    pub fn get_reverse(&self, value: &B) -> Option<&A> {
-        self.value_key.get(value).map(Deref::deref)
+    pub fn get_reverse<Q: ?Sized + Eq + Hash>(&self, value: &Q) -> Option<&A>
+    where
+        B: Borrow<Q>,
+    {
+        self.value_key.get(value)
     }
 
-    pub fn contain(&self, value: &A) -> bool {
+    pub fn contain<Q: ?Sized + Eq + Hash>(&self, value: &Q) -> bool
+    where
+        A: Borrow<Q>,
+    {
         self.key_value.contains_key(value)
     }
 
-    pub fn contain_reverse(&self, value: &B) -> bool {
+    pub fn contain_reverse<Q: ?Sized + Eq + Hash>(&self, value: &Q) -> bool
+    where
+        B: Borrow<Q>,
+    {
         self.value_key.contains_key(value)
     }
-}
 
+    pub fn remove<Q: ?Sized + Eq + Hash>(&mut self, key: &Q) -> Option<B>
+    where
+        A: Borrow<Q>,
+    {
+        let value = self.key_value.remove(key)?;
+        self.value_key.remove(&value);
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.key_value.len()
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Positioned access to a blob, implemented by each backend.
+pub(crate) trait BlobIo {
+    fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+    fn write_at(&mut self, buf: &[u8], offset: u64) -> io::Result<usize>;
+    fn len(&mut self) -> io::Result<u64>;
+}
+
+/// A handle to stream a blob without loading it into memory.
+/// See [blob_open](./struct.Connection.html#method.blob_open).
+///
+/// The blob is closed when the handle is dropped.
+pub struct Blob<'a> {
+    io:     Box<dyn BlobIo + 'a>,
+    offset: u64,
+}
+
+impl<'a> Blob<'a> {
+    pub(crate) fn new(io: Box<dyn BlobIo + 'a>) -> Self {
+        Self { io, offset: 0 }
+    }
+
+    /// Return the size of the blob in bytes.
+    pub fn len(&mut self) -> io::Result<u64> {
+        self.io.len()
+    }
+
+    /// Checks if the blob is empty.
+    pub fn is_empty(&mut self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+}
+
+impl Read for Blob<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.io.read_at(buf, self.offset)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+impl Write for Blob<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.io.write_at(buf, self.offset)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Blob<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let offset = match pos {
+            SeekFrom::Start(n)   => Some(n),
+            SeekFrom::End(n)     => self.io.len()?.checked_add_signed(n),
+            SeekFrom::Current(n) => self.offset.checked_add_signed(n),
+        };
+        match offset {
+            Some(offset) => {
+                self.offset = offset;
+                Ok(offset)
+            },
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")),
+        }
+    }
+}
+
+impl fmt::Debug for Blob<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blob")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
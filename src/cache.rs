@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::lexer::{lex, lex_bracketed};
+use crate::row::Row;
+use crate::time::Instant;
+use crate::token::Token;
+
+/// Keywords followed by a table name.
+const TABLE_KEYWORDS: &[&str] = &["FROM", "INTO", "JOIN", "TABLE", "UPDATE"];
+
+struct Entry {
+    rows:    Vec<Row>,
+    tables:  Vec<String>,
+    expires: Instant,
+    used:    u64,
+}
+
+/// Results of `SELECT` statements keyed by the SQL.
+/// See [enable_query_cache](./struct.Connection.html#method.enable_query_cache).
+pub(crate) struct QueryCache {
+    capacity: usize,
+    ttl:      Duration,
+    entries:  HashMap<String, Entry>,
+    clock:    u64,
+}
+
+impl QueryCache {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Self {
+        Self { capacity, ttl, entries: HashMap::new(), clock: 0 }
+    }
+
+    pub(crate) fn get(&mut self, sql: &str) -> Option<Vec<Row>> {
+        self.clock += 1;
+        match self.entries.get_mut(sql) {
+            Some(entry) if entry.expires > Instant::now() => {
+                entry.used = self.clock;
+                Some(entry.rows.clone())
+            },
+            Some(_) => {
+                self.entries.remove(sql);
+                None
+            },
+            None => None,
+        }
+    }
+
+    /// Inserts the rows, evicting the least recently used entry if the cache is full.
+    pub(crate) fn insert(&mut self, sql: &str, rows: &[Row]) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(sql) {
+            if let Some(lru) = self.entries.iter().min_by_key(|(_, entry)| entry.used).map(|(sql, _)| sql.clone()) {
+                self.entries.remove(&lru);
+            }
+        }
+        self.entries.insert(sql.to_string(), Entry {
+            rows:    rows.to_vec(),
+            tables:  tables(sql),
+            expires: Instant::now() + self.ttl,
+            used:    self.clock,
+        });
+    }
+
+    /// Removes the entries referencing any of the tables written by the statement.
+    /// If no table is found in the statement, all entries are removed.
+    pub(crate) fn invalidate(&mut self, sql: &str) {
+        let written = tables(sql);
+        if written.is_empty() {
+            self.entries.clear();
+        } else {
+            self.entries.retain(|_, entry| !entry.tables.iter().any(|table| written.contains(table)));
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Checks if the SQL is a single `SELECT` statement, whose result may be cached.
+pub(crate) fn is_select(sql: &str) -> bool {
+    let mut tokens = Vec::new();
+    lex(sql, &mut tokens);
+    while let Some(Token::Symbol(symbol)) = tokens.last() {
+        if symbol != ";" {
+            break;
+        }
+        tokens.pop();
+    }
+    matches!(tokens.first(), Some(Token::Keyword(keyword)) if keyword.eq_ignore_ascii_case("SELECT"))
+        && !tokens.iter().any(|token| matches!(token, Token::Symbol(symbol) if symbol == ";"))
+}
+
+/// Return the lowercase names of the tables referenced by the SQL, without the schema.
+pub(crate) fn tables(sql: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    lex_bracketed(sql, &mut tokens);
+
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let follows_table = matches!(&tokens[i], Token::Keyword(keyword)
+            if TABLE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(keyword)));
+        i += 1;
+        if !follows_table {
+            continue;
+        }
+        // Skip `IF NOT EXISTS` and `IF EXISTS`.
+        while let Some(Token::Keyword(keyword)) = tokens.get(i) {
+            if !["IF", "NOT", "EXISTS"].iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+                break;
+            }
+            i += 1;
+        }
+        // `schema.table`, `table alias`, `table1, table2`
+        let mut name = None;
+        while let Some(token) = tokens.get(i) {
+            match token {
+                Token::Identifier(identifier) if name.is_none() => name = Some(identifier),
+                Token::Symbol(symbol) if symbol == "." => name = None,
+                Token::Symbol(symbol) if symbol == "," => {
+                    tables.extend(name.take().map(|name| unquote(name)));
+                },
+                Token::Identifier(_) => (),
+                _ => break,
+            }
+            i += 1;
+        }
+        tables.extend(name.map(|name| unquote(name)));
+    }
+    tables.sort();
+    tables.dedup();
+    tables
+}
+
+/// Return the lowercase identifier without the quotation of `"..."`, `` `...` `` or `[...]`.
+fn unquote(identifier: &str) -> String {
+    let (close, escaped) = match identifier.chars().next() {
+        Some('"') => ('"', "\"\""),
+        Some('`') => ('`', "``"),
+        Some('[') => (']', "]]"),
+        _ => return identifier.to_lowercase(),
+    };
+    let inner = &identifier[1..];
+    let inner = inner.strip_suffix(close).unwrap_or(inner);
+    inner.replace(escaped, &escaped[1..]).to_lowercase()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        assert_eq!(super::tables("SELECT * FROM users u JOIN main.orders o ON u.id = o.user_id;"), ["orders", "users"]);
+        assert_eq!(super::tables("SELECT * FROM \"Users\", items WHERE id IN (SELECT id FROM tags)"), ["items", "tags", "users"]);
+        assert_eq!(super::tables("INSERT INTO users VALUES ('FROM x')"), ["users"]);
+        assert_eq!(super::tables("UPDATE users SET age = 1; DROP TABLE IF EXISTS items;"), ["items", "users"]);
+        assert_eq!(super::tables("UPDATE `users` SET age = 1; DELETE FROM `a``b`;"), ["a`b", "users"]);
+        assert_eq!(super::tables("INSERT INTO [dbo].[Users] VALUES ('[items]'); DROP TABLE [a]]];"), ["a]", "users"]);
+        assert!(super::tables("PRAGMA foreign_keys = ON").is_empty());
+    }
+
+    #[test]
+    fn is_select() {
+        assert!(super::is_select("SELECT * FROM users;"));
+        assert!(super::is_select("select 1"));
+        assert!(!super::is_select("SELECT 1; DELETE FROM users;"));
+        assert!(!super::is_select("INSERT INTO users SELECT * FROM users"));
+    }
+
+    #[test]
+    fn insert() {
+        let mut cache = QueryCache::new(2, Duration::from_secs(60));
+        cache.insert("SELECT * FROM users", &[]);
+        cache.insert("SELECT * FROM items", &[]);
+        assert!(cache.get("SELECT * FROM users").is_some());
+        cache.insert("SELECT * FROM tags", &[]);
+        assert!(cache.get("SELECT * FROM items").is_none());
+        assert!(cache.get("SELECT * FROM users").is_some());
+
+        cache.invalidate("DELETE FROM users");
+        assert!(cache.get("SELECT * FROM users").is_none());
+        assert!(cache.get("SELECT * FROM tags").is_some());
+        cache.invalidate("VACUUM");
+        assert!(cache.get("SELECT * FROM tags").is_none());
+
+        let mut cache = QueryCache::new(2, Duration::from_secs(0));
+        cache.insert("SELECT * FROM users", &[]);
+        assert!(cache.get("SELECT * FROM users").is_none());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+use crate::lexer::is_qualified_identifier;
+use crate::row::Row;
+
+impl Connection {
+    /// Calls a stored procedure, or a function on SQLite, with the arguments escaped in the same way as
+    /// strings concatenated to [ow](./struct.Connection.html#method.ow), and returns the rows.
+    ///
+    /// Dialect                 | Statement
+    /// ----------------------- | ---------
+    /// SQLite                  | `SELECT proc('a', 'b') AS "proc"`
+    /// Postgres, MySQL, Oracle | `CALL proc('a', 'b')`
+    /// SQL Server              | `EXEC proc 'a', 'b'`
+    ///
+    /// It is an error if the name is not an identifier, optionally qualified by a schema.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::params;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let rows = conn.call("upper", params!["O'Reilly"]).unwrap();
+    /// assert_eq!(rows[0].get("upper"), Some("O'REILLY"));
+    /// assert!(conn.call("upper('a'); --", params![]).is_err());
+    /// ```
+    pub fn call(&self, procedure: &'static str, params: &[&(dyn ToString + Sync)]) -> Result<Vec<Row>> {
+        self.rows(self.call_statement(procedure, params, &[]))
+    }
+
+    /// Calls a stored procedure with OUT parameters after `params`, and returns the row of them.
+    /// The OUT parameters are passed as `NULL` on Postgres, whose columns are named by the procedure,
+    /// and as session variables on MySQL, which are selected as `outs`.
+    /// SQLite, SQL Server and Oracle return the error "unsupported OUT parameter".
+    ///
+    /// Dialect  | Statement
+    /// -------- | ---------
+    /// Postgres | `CALL proc('a', NULL)`
+    /// MySQL    | `CALL proc('a', @owsql_out_0); SELECT @owsql_out_0 AS total`
+    pub fn call_out(&self, procedure: &'static str, params: &[&(dyn ToString + Sync)], outs: &[&'static str]) -> Result<Row> {
+        if matches!(self.dialect(), Dialect::Sqlite | Dialect::SqlServer | Dialect::Oracle) {
+            OwsqlError::new(&self.error_level, "unsupported OUT parameter", procedure)?;
+            return Ok(Row::new());
+        }
+        match self.rows(self.call_statement(procedure, params, outs))?.pop() {
+            Some(row) => Ok(row),
+            None => {
+                OwsqlError::new(&self.error_level, "no rows", procedure)?;
+                Ok(Row::new())
+            },
+        }
+    }
+
+    /// Return the statement calling the procedure, or the error overwrite if a name is invalid.
+    fn call_statement(&self, procedure: &str, params: &[&(dyn ToString + Sync)], outs: &[&str]) -> String {
+        if let Some(name) = std::iter::once(&procedure).chain(outs).find(|name| !is_qualified_identifier(name)) {
+            return self.ow_error("invalid procedure name", name);
+        }
+        let mut sql = match self.dialect() {
+            Dialect::Sqlite    => self.ow_generated(format!("SELECT {}(", procedure)),
+            Dialect::SqlServer => self.ow_generated(format!("EXEC {}", procedure)),
+            _                  => self.ow_generated(format!("CALL {}(", procedure)),
+        };
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(&self.ow_generated(",".to_string()));
+            }
+            sql.push_str(&self.operand(&param.to_string()));
+        }
+        let mut tail = String::new();
+        for i in 0..outs.len() {
+            if i > 0 || !params.is_empty() {
+                tail.push_str(", ");
+            }
+            match self.dialect() {
+                Dialect::Mysql => tail.push_str(&format!("@owsql_out_{}", i)),
+                _              => tail.push_str("NULL"),
+            }
+        }
+        if self.dialect() != Dialect::SqlServer {
+            tail.push(')');
+        }
+        match self.dialect() {
+            Dialect::Sqlite => tail.push_str(&format!(" AS \"{}\";", procedure)),
+            Dialect::Mysql if !outs.is_empty() => {
+                let selected = outs.iter().enumerate()
+                    .map(|(i, out)| format!("@owsql_out_{} AS {}", i, out))
+                    .collect::<Vec<_>>();
+                tail.push_str(&format!("; SELECT {};", selected.join(", ")));
+            },
+            _ => tail.push(';'),
+        }
+        sql.push_str(&self.ow_generated(tail));
+        sql
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The digest of the contents of a table, which does not depend on the order of the rows.
+/// See [table_checksum](./struct.Connection.html#method.table_checksum).
+///
+/// It is displayed as `{rows}:{hash}` with the hash in hexadecimal.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Checksum {
+    /// The number of the rows.
+    pub rows: u64,
+    /// The sum of the hashes of the rows.
+    pub hash: u64,
+}
+
+impl Checksum {
+    /// Adds the row of the values in their text form, where `None` is `NULL`.
+    fn add(&mut self, values: &[Option<&str>]) {
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut write = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+            }
+        };
+        for value in values {
+            match value {
+                // The length separates the values, so that ("ab", "c") differs from ("a", "bc").
+                Some(value) => {
+                    write(&[1]);
+                    write(&(value.len() as u64).to_le_bytes());
+                    write(value.as_bytes());
+                },
+                None => write(&[0]),
+            }
+        }
+        self.rows += 1;
+        self.hash = self.hash.wrapping_add(mix(hash));
+    }
+}
+
+/// Scrambles the bits of the hash, so that the sum of the hashes is not linear in the values.
+fn mix(mut hash: u64) -> u64 {
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:016x}", self.rows, self.hash)
+    }
+}
+
+impl Connection {
+    /// Return the digest of the columns of the table, streaming the rows and hashing them on the
+    /// client, so that a replica or a migrated table can be compared with the source.
+    /// The digest does not depend on the order of the rows, and the duplicate rows are counted.
+    ///
+    /// The values are hashed in their text form, which may differ between the backends for
+    /// floating point numbers and timestamps. Compare such columns on the same backend, or through a view
+    /// casting them into text.
+    ///
+    /// # Errors
+    ///
+    /// An invalid table or column returns the error "invalid identifier", and no columns return
+    /// the error "empty column list".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);
+    ///                       CREATE TABLE backup (name TEXT, age INTEGER);
+    ///                       INSERT INTO users VALUES ('Alice', 42), ('Bob', 69);
+    ///                       INSERT INTO backup VALUES ('Bob', 69), ('Alice', 42);")).unwrap();
+    /// let checksum = conn.table_checksum("users", &["name", "age"]).unwrap();
+    /// assert_eq!(checksum.rows, 2);
+    /// assert_eq!(conn.table_checksum("backup", &["name", "age"]), Ok(checksum));
+    /// ```
+    pub fn table_checksum(&self, table: &str, columns: &[&str]) -> Result<Checksum> {
+        let error = |msg: &str, detail: &str| OwsqlError::new(&self.error_level, msg, detail).err().unwrap_or(OwsqlError::AnyError);
+        let table = match self.quote_table(table) {
+            Some(quoted) => quoted,
+            None => return Err(error("invalid identifier", table)),
+        };
+        let mut quoted = Vec::new();
+        for column in columns {
+            match self.quote_column(column) {
+                Some(column) => quoted.push(column),
+                None => return Err(error("invalid identifier", column)),
+            }
+        }
+        if quoted.is_empty() {
+            return Err(error("empty column list", &table));
+        }
+        let mut checksum = Checksum::default();
+        let sql = self.ow_generated(format!("SELECT {} FROM {}", quoted.join(", "), table)) + &self.ow(";");
+        self.iterate(sql, |pairs| {
+            checksum.add(&pairs.iter().map(|&(_, value)| value).collect::<Vec<_>>());
+            true
+        })?;
+        Ok(checksum)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Checksum;
+
+    fn checksum(rows: &[&[Option<&str>]]) -> Checksum {
+        let mut checksum = Checksum::default();
+        rows.iter().for_each(|row| checksum.add(row));
+        checksum
+    }
+
+    #[test]
+    fn order_independent() {
+        let (a, b): (&[Option<&str>], &[Option<&str>]) = (&[Some("Alice"), Some("42")], &[Some("Bob"), None]);
+        assert_eq!(checksum(&[a, b]), checksum(&[b, a]));
+        assert_ne!(checksum(&[a, b]), checksum(&[a]));
+        assert_ne!(checksum(&[a, a]).hash, checksum(&[b, b]).hash);
+        assert_ne!(checksum(&[a, a, b, b]).hash, checksum(&[a, b]).hash);
+    }
+
+    #[test]
+    fn separated_values() {
+        assert_ne!(checksum(&[&[Some("ab"), Some("c")]]), checksum(&[&[Some("a"), Some("bc")]]));
+        assert_ne!(checksum(&[&[None]]), checksum(&[&[Some("")]]));
+        assert_ne!(checksum(&[&[Some("NULL")]]), checksum(&[&[None]]));
+        assert_eq!(checksum(&[]).to_string(), "0:0000000000000000");
+    }
+}
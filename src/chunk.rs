@@ -0,0 +1,138 @@
+use std::ops::Range;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+
+/// The limits of each statement executed by [execute_chunked](./struct.Connection.html#method.execute_chunked).
+/// `None` is unlimited.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ChunkLimit {
+    /// The maximum length of the actual SQL in bytes.
+    /// The limit of the backend is also applied, such as `max_allowed_packet` of MySQL.
+    pub max_bytes:  Option<usize>,
+    /// The maximum number of the values.
+    pub max_values: Option<usize>,
+}
+
+impl Connection {
+    /// Sets the limits of each statement executed by [execute_chunked](#method.execute_chunked).
+    pub fn set_chunk_limit(&mut self, limit: ChunkLimit) {
+        self.chunk_limit = limit;
+    }
+
+    /// Executes `head` followed by the [values](#method.values) of the rows, and returns the number of
+    /// the changed rows.
+    /// If the statement exceeds the [limit](#method.set_chunk_limit), the rows are split and executed
+    /// in chunks within one transaction, or in the running one of
+    /// [with_transaction](#method.with_transaction), so that all or none of them are inserted.
+    /// It is an error "statement too large" if a single row exceeds the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{ChunkLimit, params};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// conn.set_chunk_limit(ChunkLimit { max_values: Some(4), ..Default::default() });
+    /// let changes = conn.execute_chunked("INSERT INTO users (name, age)",
+    ///     &[params!["Alice", 42], params!["Bob", 69], params!["Carol", 50]]);
+    /// assert_eq!(changes, Ok(3));
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+    /// ```
+    pub fn execute_chunked(&self, head: &'static str, rows: &[&[&(dyn ToString + Sync)]]) -> Result<u64> {
+        let chunks = match self.chunks(head, rows)? {
+            Some(chunks) => chunks,
+            None => return Ok(0),
+        };
+        let execute = |conn: &Connection| {
+            let mut changes = 0;
+            for chunk in chunks.iter() {
+                changes += conn.execute_changes(&(conn.ow(head) + &conn.values(&rows[chunk.clone()])))?;
+            }
+            Ok(changes)
+        };
+        if chunks.len() == 1 || self.in_transaction.get() {
+            execute(self)
+        } else {
+            self.with_transaction(|tx| execute(tx))
+        }
+    }
+
+    /// Splits the rows into the ranges within the limits, or return `None` if a row exceeds them
+    /// on `AlwaysOk`.
+    fn chunks(&self, head: &'static str, rows: &[&[&(dyn ToString + Sync)]]) -> Result<Option<Vec<Range<usize>>>> {
+        let max_bytes = match (self.chunk_limit.max_bytes, self.conn.max_statement_len()) {
+            (Some(max), Some(backend)) => Some(max.min(backend)),
+            (max, backend) => max.or(backend),
+        };
+        let max_values = self.chunk_limit.max_values;
+        let mut chunks = Vec::new();
+        if rows.is_empty() || (max_bytes.is_none() && max_values.is_none()) {
+            chunks.push(0..rows.len());
+            return Ok(Some(chunks));
+        }
+
+        let head_len = self.actual_sql(self.ow(head))?.len();
+        let mut start = 0;
+        let mut len = head_len;
+        for (i, row) in rows.iter().enumerate() {
+            // `VALUES (...)` is longer than `, (...)` in a chunk, so the length is not underestimated.
+            let row_len = self.actual_sql(self.values(&[row]))?.len();
+            if max_bytes.is_some_and(|max| head_len + row_len > max)
+                || max_values.is_some_and(|max| row.len() > max)
+            {
+                OwsqlError::new(&self.error_level, "statement too large", &format!("row {}", i))?;
+                return Ok(None);
+            }
+            let values = (i + 1 - start) * row.len();
+            if i > start && (max_bytes.is_some_and(|max| len + row_len > max) || max_values.is_some_and(|max| values > max)) {
+                chunks.push(start..i);
+                start = i;
+                len = head_len;
+            }
+            len += row_len;
+        }
+        chunks.push(start..rows.len());
+        Ok(Some(chunks))
+    }
+
+    fn execute_changes(&self, query: &str) -> Result<u64> {
+        let start = self.start_statement(query);
+        let sql = self.convert_to_valid_syntax(query);
+        self.invalidate_query_cache(&sql);
+        let result = self.conn._execute_changes(self.rewrite(sql), &self.error_level);
+        #[cfg(feature = "otel")]
+        if let Ok(changes) = result {
+            self.set_rows_affected(changes);
+        }
+        self.record_statement(query, start, result.as_ref().err());
+        result
+    }
+}
+
+
+#[cfg(test)]
+#[cfg(feature = "sqlite")]
+mod tests {
+    use crate::params;
+    use super::ChunkLimit;
+
+    #[test]
+    fn chunks() {
+        let mut conn = crate::sqlite::open(":memory:").unwrap();
+        let rows: &[&[&(dyn ToString + Sync)]] = &[params!["Alice", 42], params!["Bob", 69], params!["Carol", 50]];
+        assert_eq!(conn.chunks("INSERT INTO users", rows), Ok(Some(std::iter::once(0..3).collect())));
+
+        conn.set_chunk_limit(ChunkLimit { max_values: Some(4), ..Default::default() });
+        assert_eq!(conn.chunks("INSERT INTO users", rows), Ok(Some(vec![0..2, 2..3])));
+
+        let head_len = "INSERT INTO users ".len();
+        let row_len = conn.actual_sql(conn.values(&rows[..1])).unwrap().len();
+        conn.set_chunk_limit(ChunkLimit { max_bytes: Some(head_len + row_len), ..Default::default() });
+        assert_eq!(conn.chunks("INSERT INTO users", rows), Ok(Some(vec![0..1, 1..2, 2..3])));
+
+        conn.set_chunk_limit(ChunkLimit { max_bytes: Some(head_len), ..Default::default() });
+        assert_eq!(conn.chunks("INSERT INTO users", rows), Err(crate::OwsqlError::Message("statement too large".into())));
+    }
+}
@@ -0,0 +1,185 @@
+use std::ops::Not;
+
+use crate::connection::Connection;
+
+/// A node of the condition tree.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Compare(String, &'static str, String),
+    Null(String, bool),
+    And(Vec<Cond>),
+    Or(Vec<Cond>),
+    Not(Box<Cond>),
+}
+
+/// A boolean condition of columns, which is rendered into a correctly parenthesized `WHERE` clause
+/// by [where_clause](./struct.Connection.html#method.where_clause).
+///
+/// The columns are plain identifiers optionally qualified by a table, such as `users.name`.
+/// The values are the operands as they are concatenated to a statement, so a string is escaped
+/// and a fragment such as [int](./struct.Connection.html#method.int) is trusted.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::Cond;
+/// # let conn = owsql::sqlite::open(":memory:").unwrap();
+/// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);
+/// #                       INSERT INTO users VALUES ('Alice', 42), ('Bob', 69), ('Carol', 50);")).unwrap();
+/// let cond = Cond::eq("name", "Alice").or(Cond::eq("name", "Bob")).and(Cond::gt("age", conn.int(50)));
+/// let sql = conn.ow("SELECT name FROM users") + &conn.where_clause(&cond) + &conn.ow(";");
+/// assert_eq!(conn.actual_sql(&sql).unwrap(),
+///     "SELECT name FROM users WHERE ( \"name\" = 'Alice' OR \"name\" = 'Bob' ) AND \"age\" > 50 ; ");
+/// assert_eq!(conn.rows(&sql).unwrap()[0].get("name"), Some("Bob"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cond {
+    node: Node,
+}
+
+impl Cond {
+    fn compare(column: &str, op: &'static str, value: impl Into<String>) -> Self {
+        Cond { node: Node::Compare(column.to_string(), op, value.into()) }
+    }
+
+    /// `column = value`
+    pub fn eq(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, "=", value)
+    }
+
+    /// `column <> value`
+    pub fn ne(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, "<>", value)
+    }
+
+    /// `column < value`
+    pub fn lt(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, "<", value)
+    }
+
+    /// `column <= value`
+    pub fn le(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, "<=", value)
+    }
+
+    /// `column > value`
+    pub fn gt(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, ">", value)
+    }
+
+    /// `column >= value`
+    pub fn ge(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, ">=", value)
+    }
+
+    /// `column LIKE value`
+    pub fn like(column: &str, value: impl Into<String>) -> Self {
+        Self::compare(column, "LIKE", value)
+    }
+
+    /// `column IS NULL`
+    pub fn is_null(column: &str) -> Self {
+        Cond { node: Node::Null(column.to_string(), true) }
+    }
+
+    /// `column IS NOT NULL`
+    pub fn is_not_null(column: &str) -> Self {
+        Cond { node: Node::Null(column.to_string(), false) }
+    }
+
+    /// `self AND other`
+    pub fn and(self, other: Cond) -> Self {
+        match self.node {
+            Node::And(mut conds) => {
+                conds.push(other);
+                Cond { node: Node::And(conds) }
+            },
+            node => Cond { node: Node::And(vec![Cond { node }, other]) },
+        }
+    }
+
+    /// `self OR other`
+    pub fn or(self, other: Cond) -> Self {
+        match self.node {
+            Node::Or(mut conds) => {
+                conds.push(other);
+                Cond { node: Node::Or(conds) }
+            },
+            node => Cond { node: Node::Or(vec![Cond { node }, other]) },
+        }
+    }
+
+    /// Return the operator if the condition is a compound one, which is parenthesized in another.
+    fn operator(&self) -> Option<&'static str> {
+        match self.node {
+            Node::And(_) => Some("AND"),
+            Node::Or(_)  => Some("OR"),
+            _            => None,
+        }
+    }
+}
+
+/// `NOT self`
+impl Not for Cond {
+    type Output = Cond;
+
+    fn not(self) -> Self::Output {
+        Cond { node: Node::Not(Box::new(self)) }
+    }
+}
+
+impl Connection {
+    /// Return the value to be concatenated as an operand.
+    /// A blank string is not a literal between the fragments, so it is quoted here.
+    pub(crate) fn operand(&self, value: &str) -> String {
+        if value.trim().is_empty() {
+            self.ow_generated(format!("'{}'", value))
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Renders the condition, or returns `Err` with an error token.
+    fn render_cond(&self, cond: &Cond) -> Result<String, String> {
+        let column = |column: &str| match self.quote_column(column) {
+            Some(column) => Ok(column),
+            None => Err(self.ow_error("invalid identifier", column)),
+        };
+        Ok(match &cond.node {
+            Node::Compare(name, op, value) => self.ow_generated(format!("{} {}", column(name)?, op)) + &self.operand(value),
+            Node::Null(name, true) => self.ow_generated(format!("{} IS NULL", column(name)?)),
+            Node::Null(name, false) => self.ow_generated(format!("{} IS NOT NULL", column(name)?)),
+            Node::Not(cond) => {
+                self.ow_generated("NOT (".to_string()) + &self.render_cond(cond)? + &self.ow_generated(")".to_string())
+            },
+            Node::And(conds) | Node::Or(conds) => {
+                let operator = self.ow_generated(cond.operator().unwrap_or_default().to_string());
+                let mut sql = Vec::new();
+                for child in conds {
+                    let rendered = self.render_cond(child)?;
+                    sql.push(match child.operator() {
+                        Some(_) => self.ow_generated("(".to_string()) + &rendered + &self.ow_generated(")".to_string()),
+                        None => rendered,
+                    });
+                }
+                sql.join(&operator)
+            },
+        })
+    }
+
+    /// It is guaranteed to be a `WHERE` clause of the condition, whose compound conditions are
+    /// parenthesized. See [Cond](./struct.Cond.html).
+    /// An invalid column returns the error "invalid identifier" when the statement is executed.
+    pub fn where_clause(&self, cond: &Cond) -> String {
+        match self.render_cond(cond) {
+            Ok(sql) => self.ow_generated("WHERE".to_string()) + &sql,
+            Err(e) => e,
+        }
+    }
+
+    /// It is guaranteed to be the condition in the same way as [where_clause](#method.where_clause)
+    /// without `WHERE`, such as for `HAVING` or `JOIN ... ON`.
+    pub fn cond(&self, cond: &Cond) -> String {
+        self.render_cond(cond).unwrap_or_else(|e| e)
+    }
+}
@@ -1,42 +1,145 @@
-use std::collections::HashSet;
-use std::cell::RefCell;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::cell::{Cell, RefCell};
 use std::fmt;
+use std::iter::FromIterator;
+use std::panic::{self, AssertUnwindSafe, Location};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use crate::Result;
+use crate::audit::{AuditKind, AuditReport};
 use crate::bidimap::BidiMap;
+use crate::blob::{Blob, BlobIo};
+use crate::cache::{QueryCache, is_select};
+use crate::chunk::ChunkLimit;
 use crate::{OwsqlError, OwsqlErrorLevel};
 use crate::constants::OW_MINIMUM_LENGTH;
-use crate::overwrite::{IntoInner, overwrite_new};
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+use crate::gc::{EvictionPolicy, Gc, Map, OverwriteStats};
+use crate::lexer::CommentMode;
+use crate::metrics::{Metrics, SlowQuery};
+use crate::model::TimestampColumns;
+use crate::observer::Observer;
+use crate::overwrite::{IntoInner, TokenAlphabet, derivation_key_new, derived_overwrite_new, derived_value, is_overwrite_like, namespace_new, overwrite_new};
+use crate::retry::{RetryPolicy, is_retryable};
+use crate::rewrite::Rewriter;
 use crate::serial::SerialNumber;
 use crate::parser::*;
-use crate::row::Row;
+use crate::policy::Policy;
+use crate::pretty::pretty;
+use crate::row::{ColumnMatch, Row};
+use crate::time::Instant;
+use crate::token::Token;
+use crate::transaction::{Transaction, TransactionOptions};
+
+pub(crate) type IterateCallback<'a> = &'a mut dyn FnMut(&[(&str, Option<&str>)]) -> bool;
 
 pub(crate) trait OwsqlConn {
     fn _execute(&self, query: Result<String>, error_level: &crate::OwsqlErrorLevel) -> Result<()>;
+    /// Executes a single statement and returns the number of the rows it changed.
+    fn _execute_changes(&self, query: Result<String>, error_level: &crate::OwsqlErrorLevel) -> Result<u64>;
     fn _iterate(&self, query: Result<String>, error_level: &crate::OwsqlErrorLevel,
-        callback: &mut dyn FnMut(&[(&str, Option<&str>)]) -> bool) -> Result<()>;
-    fn must_escape(&self) ->  Box<dyn Fn(char) -> bool>;
-    fn literal_escape(&self, s: &str) -> String;
+        callback: IterateCallback) -> Result<()>;
+    /// Parses and plans the statement without executing it.
+    fn _validate(&self, query: Result<String>, error_level: &crate::OwsqlErrorLevel) -> Result<()>;
+    fn escape_mode(&self) -> EscapeMode;
+    fn charset(&self) -> Charset;
+    /// Return whether `escape_mode` and `charset` are read from the session settings, so that they can
+    /// be read again after [set_session](./struct.Connection.html#method.set_session) changes them.
+    fn detects_escaping(&self) -> bool {
+        true
+    }
+    fn dialect(&self) -> Dialect;
+    /// Return the backend itself, to access the backend specific features.
+    fn as_any(&self) -> &dyn Any;
+    /// Return the error code of the most recent statement, or `None` if it succeeded.
+    fn last_error_code(&self) -> Option<String> {
+        None
+    }
+    /// Return the number of the rows changed by the most recent statement, if the backend knows it.
+    #[cfg_attr(not(feature = "otel"), allow(dead_code))]
+    fn changes(&self) -> Option<u64> {
+        None
+    }
+    /// Return the maximum length of a statement in bytes the backend accepts, if it is known.
+    fn max_statement_len(&self) -> Option<usize> {
+        None
+    }
+    /// Rolls back the open transaction and closes the connection. The backend must not be used after it.
+    fn close(&self, _error_level: &crate::OwsqlErrorLevel) -> Result<()> {
+        Ok(())
+    }
+    /// Return whether the connection is lost.
+    fn is_closed(&self) -> bool {
+        false
+    }
+    fn blob_open(&self, _table: &str, _column: &str, _rowid: i64) -> Result<Box<dyn BlobIo + '_>> {
+        Err(OwsqlError::Message("unsupported blob".into()))
+    }
 }
 
 /// A database connection.
 pub struct Connection {
     pub(crate) conn:              Box<dyn OwsqlConn>,
+    /// Callbacks registered to the backend, which must live as long as the backend.
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    pub(crate) hooks:             RefCell<HashMap<&'static str, Box<dyn Any>>>,
     pub(crate) allowlist:         HashSet<String>,
     pub(crate) serial_number:     RefCell<SerialNumber>,
     pub(crate) ow_len_range:      (usize, usize),
+    pub(crate) token_alphabet:    TokenAlphabet,
+    /// Prefix of the overwrite strings, unique to the connection.
+    pub(crate) namespace:         String,
+    /// Key of the overwrite strings derived by `EvictionPolicy::Rederive`.
+    pub(crate) derivation_key:    [u8; 32],
     pub(crate) overwrite:         RefCell<BidiMap<String, String>>,
     pub(crate) whitespace_around: RefCell<BidiMap<String, String>>,
     pub(crate) error_msg:         RefCell<BidiMap<OwsqlError, String>>,
     pub(crate) error_level:       OwsqlErrorLevel,
+    pub(crate) escape_mode:       Cell<EscapeMode>,
+    pub(crate) charset:           Cell<Charset>,
+    pub(crate) strict:            bool,
+    pub(crate) canonical_spaces:  bool,
+    pub(crate) comment_mode:      CommentMode,
+    pub(crate) chunk_limit:       ChunkLimit,
+    pub(crate) unique_columns:    bool,
+    pub(crate) column_match:      ColumnMatch,
+    pub(crate) timestamp_columns: TimestampColumns,
+    pub(crate) schema_prefix:     Option<String>,
+    pub(crate) policy:            Policy,
+    pub(crate) metrics:           RefCell<Option<Metrics>>,
+    pub(crate) slow_query:        RefCell<Option<SlowQuery>>,
+    pub(crate) observer:          Option<Arc<dyn Observer>>,
+    pub(crate) rewriters:         Vec<Rewriter>,
+    pub(crate) query_tags:        Vec<(String, String)>,
+    #[cfg(feature = "otel")]
+    pub(crate) spans:             RefCell<crate::otel::Spans>,
+    pub(crate) audit:             RefCell<Option<AuditReport>>,
+    pub(crate) last_error_code:   RefCell<Option<String>>,
+    pub(crate) query_cache:       RefCell<Option<QueryCache>>,
+    /// Whether the closure of [with_transaction](#method.with_transaction) is running.
+    pub(crate) in_transaction:    Cell<bool>,
+    /// Session variables set by [set_session](#method.set_session).
+    pub(crate) session:           RefCell<Vec<(String, String)>>,
+    pub(crate) gc:                RefCell<Gc>,
 }
 
 unsafe impl Send for Connection {}
 unsafe impl Sync for Connection {}
 
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.observe(|observer| observer.on_disconnect());
+    }
+}
+
 impl PartialEq for Connection {
     fn eq(&self, other: &Self) -> bool {
-        (&self.conn as *const _) == (&other.conn as *const _)
+        std::ptr::eq(&self.conn, &other.conn)
     }
 }
 
@@ -45,12 +148,67 @@ impl fmt::Debug for Connection {
         f.debug_struct("Connection")
             .field("conn", &(&self.conn as *const _))
             .field("ow_len_range", &self.ow_len_range)
+            .field("token_alphabet", &self.token_alphabet)
+            .field("namespace", &self.namespace)
             .field("error_level", &self.error_level)
+            .field("escape_mode", &self.escape_mode.get())
+            .field("charset", &self.charset.get())
+            .field("strict", &self.strict)
+            .field("canonical_spaces", &self.canonical_spaces)
+            .field("comment_mode", &self.comment_mode)
+            .field("chunk_limit", &self.chunk_limit)
+            .field("unique_columns", &self.unique_columns)
+            .field("column_match", &self.column_match)
+            .field("timestamp_columns", &self.timestamp_columns)
+            .field("schema_prefix", &self.schema_prefix)
             .finish()
     }
 }
 
 impl Connection {
+    pub(crate) fn new(conn: Box<dyn OwsqlConn>) -> Self {
+        let escape_mode = conn.escape_mode();
+        let charset = conn.charset();
+        Self {
+            conn,
+            hooks:             RefCell::new(HashMap::new()),
+            allowlist:         HashSet::new(),
+            serial_number:     RefCell::new(SerialNumber::default()),
+            ow_len_range:      (OW_MINIMUM_LENGTH, OW_MINIMUM_LENGTH),
+            token_alphabet:    TokenAlphabet::default(),
+            namespace:         namespace_new(),
+            derivation_key:    derivation_key_new(),
+            overwrite:         RefCell::new(BidiMap::new()),
+            whitespace_around: RefCell::new(BidiMap::new()),
+            error_msg:         RefCell::new(BidiMap::new()),
+            error_level:       OwsqlErrorLevel::default(),
+            escape_mode:       Cell::new(escape_mode),
+            charset:           Cell::new(charset),
+            strict:            false,
+            canonical_spaces:  false,
+            comment_mode:      CommentMode::default(),
+            chunk_limit:       ChunkLimit::default(),
+            unique_columns:    false,
+            column_match:      ColumnMatch::default(),
+            timestamp_columns: TimestampColumns::default(),
+            schema_prefix:     None,
+            policy:            Policy::default(),
+            metrics:           RefCell::new(None),
+            slow_query:        RefCell::new(None),
+            observer:          None,
+            rewriters:         Vec::new(),
+            query_tags:        Vec::new(),
+            #[cfg(feature = "otel")]
+            spans:             RefCell::default(),
+            audit:             RefCell::new(None),
+            last_error_code:   RefCell::new(None),
+            query_cache:       RefCell::new(None),
+            in_transaction:    Cell::new(false),
+            session:           RefCell::new(Vec::new()),
+            gc:                RefCell::new(Gc::default()),
+        }
+    }
+
     /// Execute a statement without processing the resulting rows if any.
     ///
     /// # Examples
@@ -66,10 +224,68 @@ impl Connection {
     /// ```
     #[inline]
     pub fn execute<T: AsRef<str>>(&self, query: T) -> Result<()> {
-        self.conn._execute(
-            self.convert_to_valid_syntax(query.as_ref(), self.conn.must_escape()),
-            &self.error_level
-        )
+        self.execute_as(query.as_ref(), &self.error_level)
+    }
+
+    /// Execute a statement with the error level only for this call.  
+    /// It applies to the errors detected when the statement is converted and executed.
+    /// The errors registered by [int](#method.int) or [allowlist](#method.allowlist) keep the
+    /// error level of the connection when they were registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{OwsqlError, OwsqlErrorLevel};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.error_level(OwsqlErrorLevel::AlwaysOk).unwrap();
+    /// assert_eq!(conn.execute("INVALID SQL"), Ok(()));
+    /// assert_eq!(conn.execute_with_level("INVALID SQL", OwsqlErrorLevel::Develop), Err(OwsqlError::Database {
+    ///     code: Some(1), sqlstate: None, message: "exec error".into(), constraint: None,
+    /// }));
+    /// ```
+    pub fn execute_with_level<T: AsRef<str>>(&self, query: T, error_level: OwsqlErrorLevel) -> Result<()> {
+        self.execute_as(query.as_ref(), &error_level)
+    }
+
+    fn execute_as(&self, query: &str, error_level: &OwsqlErrorLevel) -> Result<()> {
+        self.execute_converted(query, self.convert_with_level(query, error_level), error_level)
+    }
+
+    /// Execute a statement already converted to SQL.
+    /// `query` is the original statement, which is recorded in the metrics.
+    pub(crate) fn execute_converted(&self, query: &str, sql: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        self.invalidate_query_cache(&sql);
+        let start = self.start_statement(query);
+        let result = self.conn._execute(self.rewrite(sql), error_level);
+        #[cfg(feature = "otel")]
+        if let (Ok(_), Some(changes)) = (&result, self.conn.changes()) {
+            self.set_rows_affected(changes);
+        }
+        self.record_statement(query, start, result.as_ref().err());
+        result
+    }
+
+    /// Converts the statement and asks the database to parse and plan it without executing it,
+    /// to detect syntax errors early, for example, in the fragments supplied by an operator.
+    ///
+    /// SQLite prepares each statement without stepping it.
+    /// PostgreSQL and MySQL prepare and deallocate the statement, so it must be a single statement.  
+    /// Since nothing is executed, a statement referencing a table created by a preceding statement
+    /// is reported as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, id INTEGER);")).unwrap();
+    /// assert!(conn.validate(conn.ow("DELETE FROM users;")).is_ok());
+    /// assert!(conn.validate(conn.ow("DELETE users;")).is_err());
+    /// assert!(conn.validate(conn.ow("SELECT * FROM items;")).is_err());
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(0));
+    /// ```
+    pub fn validate<T: AsRef<str>>(&self, query: T) -> Result<()> {
+        let sql = self.convert_to_valid_syntax(query.as_ref());
+        self.conn._validate(self.rewrite(sql), &self.error_level)
     }
 
     /// Execute a statement and process the resulting rows as plain text.
@@ -98,11 +314,30 @@ impl Connection {
         where
             F: FnMut(&[(&str, Option<&str>)]) -> bool,
     {
-        self.conn._iterate(
-            self.convert_to_valid_syntax(query.as_ref(), self.conn.must_escape()),
-            &self.error_level,
-            &mut callback
-        )
+        self.iterate_as(query.as_ref(), &self.error_level, &mut callback)
+    }
+
+    /// Execute a statement and process the resulting rows with the error level only for this call.
+    /// See [execute_with_level](#method.execute_with_level).
+    pub fn iterate_with_level<T: AsRef<str>, F>(&self, query: T, error_level: OwsqlErrorLevel, mut callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    {
+        self.iterate_as(query.as_ref(), &error_level, &mut callback)
+    }
+
+    fn iterate_as(&self, query: &str, error_level: &OwsqlErrorLevel, callback: IterateCallback) -> Result<()> {
+        self.iterate_converted(query, self.convert_with_level(query, error_level), error_level, callback)
+    }
+
+    /// Execute a statement already converted to SQL and process the resulting rows.
+    /// `query` is the original statement, which is recorded in the metrics.
+    pub(crate) fn iterate_converted(&self, query: &str, sql: Result<String>, error_level: &OwsqlErrorLevel, callback: IterateCallback) -> Result<()> {
+        self.invalidate_query_cache(&sql);
+        let start = self.start_statement(query);
+        let result = self.conn._iterate(self.rewrite(sql), error_level, callback);
+        self.record_statement(query, start, result.as_ref().err());
+        result
     }
 
     /// Execute a statement and returns the rows.
@@ -122,18 +357,293 @@ impl Connection {
     /// }
     /// ```
     pub fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>> {
-        let mut rows: Vec<Row> = Vec::new();
+        self.rows_as(query.as_ref(), &self.error_level)
+    }
+
+    /// Execute a statement and returns the rows with the error level only for this call.
+    /// See [execute_with_level](#method.execute_with_level).
+    pub fn rows_with_level<T: AsRef<str>>(&self, query: T, error_level: OwsqlErrorLevel) -> Result<Vec<Row>> {
+        self.rows_as(query.as_ref(), &error_level)
+    }
+
+    /// Execute a statement and collects the rows into any collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let rows: VecDeque<_> = conn.rows_into(conn.ow("SELECT name FROM users;")).unwrap();
+    /// assert_eq!(rows.len(), 2);
+    /// ```
+    pub fn rows_into<T: AsRef<str>, C: FromIterator<Row>>(&self, query: T) -> Result<C> {
+        self.rows(query).map(|rows| rows.into_iter().collect())
+    }
 
+    /// Execute a statement and returns the values of the first column of the rows,
+    /// each parsed by `FromStr`.  
+    /// It is an error if a value is `NULL` or cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let ids = conn.column::<i64, _>(conn.ow("SELECT id, name FROM users ORDER BY id;")).unwrap();
+    /// assert_eq!(ids, [42, 69]);
+    /// ```
+    pub fn column<V: FromStr, T: AsRef<str>>(&self, query: T) -> Result<Vec<V>> {
+        let mut values = Vec::new();
+        let mut invalid = None;
         self.iterate(query, |pairs| {
+            // A callback may receive the pairs of multiple rows.
+            let first = pairs.first().map(|(column, _)| *column);
+            for (_, value) in pairs.iter().filter(|(column, _)| Some(*column) == first) {
+                match value.map(str::parse) {
+                    Some(Ok(value)) => values.push(value),
+                    _ => {
+                        invalid = Some(value.unwrap_or("NULL").to_string());
+                        return false;
+                    },
+                }
+            }
+            true
+        }).or_else(|e| if invalid.is_some() { Ok(()) } else { Err(e) })?;
+
+        if let Some(value) = invalid {
+            OwsqlError::new(&self.error_level, "invalid column", &value)?;
+            return Ok(Vec::new());
+        }
+        Ok(values)
+    }
+
+    /// Execute a statement and returns the first row, or `None` if there is no row.
+    /// The rest of the rows are not fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let row = conn.first_row(conn.ow("SELECT name FROM users ORDER BY id DESC;")).unwrap();
+    /// assert_eq!(row.unwrap().get("name"), Some("Bob"));
+    /// assert_eq!(conn.first_row(conn.ow("SELECT name FROM users WHERE id = 0;")), Ok(None));
+    /// ```
+    pub fn first_row<T: AsRef<str>>(&self, query: T) -> Result<Option<Row>> {
+        self.rows_up_to(query.as_ref(), 1).map(|rows| rows.into_iter().next())
+    }
+
+    /// Execute a statement and returns the only row.
+    /// It is an error if there is no row or more than one row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let row = conn.single_row(conn.ow("SELECT name FROM users WHERE id =") + &conn.int(42)).unwrap();
+    /// assert_eq!(row.get("name"), Some("Alice"));
+    /// assert!(conn.single_row(conn.ow("SELECT name FROM users;")).is_err());
+    /// ```
+    pub fn single_row<T: AsRef<str>>(&self, query: T) -> Result<Row> {
+        let query = query.as_ref();
+        let mut rows = self.rows_up_to(query, 2)?;
+        match rows.len() {
+            1 => Ok(rows.remove(0)),
+            0 => {
+                OwsqlError::new(&self.error_level, "no rows", query)?;
+                Ok(Row::new())
+            },
+            _ => {
+                OwsqlError::new(&self.error_level, "multiple rows", query)?;
+                Ok(Row::new())
+            },
+        }
+    }
+
+    /// Execute a statement and returns at most `limit` rows, stopping when they are fetched.
+    fn rows_up_to(&self, query: &str, limit: usize) -> Result<Vec<Row>> {
+        let start = self.start_statement(query);
+        let sql = self.convert_to_valid_syntax(query);
+        self.invalidate_query_cache(&sql);
+        let mut rows: Vec<Row> = Vec::new();
+        let result = self.conn._iterate(self.rewrite(sql), &self.error_level, &mut |pairs| {
+            let mut row = Row::new();
+            row.set_column_match(self.column_match);
+            for (column, value) in pairs.iter() {
+                row.insert((*column).to_string(), value.map(|v| v.to_string()));
+            }
+            rows.push(row);
+            rows.len() < limit
+        });
+        // Stopping the rows is not an error.
+        let result = if rows.len() == limit { Ok(()) } else { result };
+        let result = result.and_then(|_| self.check_unique_columns(&rows, &self.error_level));
+        self.record_statement(query, start, result.as_ref().err());
+        result.map(|_| rows)
+    }
+
+    /// Checks the column names of the rows if [set_unique_columns](#method.set_unique_columns) is enabled.
+    fn check_unique_columns(&self, rows: &[Row], error_level: &OwsqlErrorLevel) -> Result<()> {
+        if !self.unique_columns {
+            return Ok(());
+        }
+        match rows.first().and_then(|row| row.duplicate_column()) {
+            Some(column) => OwsqlError::new(error_level, "duplicate column", column),
+            None => Ok(()),
+        }
+    }
+
+    fn rows_as(&self, query: &str, error_level: &OwsqlErrorLevel) -> Result<Vec<Row>> {
+        self.rows_converted(query, self.convert_with_level(query, error_level), self, error_level)
+    }
+
+    /// Execute a statement already converted to SQL and returns the rows.
+    /// `query` is the original statement, which is recorded in the metrics, and the SQL is rewritten
+    /// by the rewriters of `converter`, which converted it.
+    pub(crate) fn rows_converted(&self, query: &str, sql: Result<String>, converter: &Connection, error_level: &OwsqlErrorLevel) -> Result<Vec<Row>> {
+        let cacheable = match (&sql, self.query_cache.borrow_mut().as_mut()) {
+            (Ok(sql), Some(cache)) if is_select(sql) => {
+                if let Some(mut rows) = cache.get(sql) {
+                    rows.iter_mut().for_each(|row| row.set_column_match(self.column_match));
+                    return Ok(rows);
+                }
+                Some(sql.clone())
+            },
+            _ => None,
+        };
+        if cacheable.is_none() {
+            self.invalidate_query_cache(&sql);
+        }
+        let mut rows: Vec<Row> = Vec::new();
+
+        let start = self.start_statement(query);
+        let result = self.conn._iterate(converter.rewrite(sql), error_level, &mut |pairs| {
             let mut row = Row::new();
+            row.set_column_match(self.column_match);
             for (column, value) in pairs.iter() {
                 row.insert((*column).to_string(), value.map(|v| v.to_string()));
             }
             rows.push(row);
             true
-        })?;
+        });
+        let result = result.and_then(|_| self.check_unique_columns(&rows, error_level));
+        self.record_statement(query, start, result.as_ref().err());
+
+        if let (Ok(_), Some(sql), Some(cache)) = (&result, cacheable, self.query_cache.borrow_mut().as_mut()) {
+            cache.insert(&sql, &rows);
+        }
+        result.map(|_| rows)
+    }
+
+    /// Return the number of rows that the query returns.  
+    /// The query is wrapped in `SELECT COUNT(*) FROM (...)`, so it must be a single `SELECT` statement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let sql = conn.ow("SELECT * FROM users WHERE id <") + &conn.int(50) + &conn.ow(";");
+    /// assert_eq!(conn.count(&sql).unwrap(), 1);
+    /// ```
+    pub fn count<T: AsRef<str>>(&self, query: T) -> Result<u64> {
+        let wrap: fn(&str) -> String = match self.dialect() {
+            // Oracle does not accept `AS` before the alias of a table.
+            Dialect::Oracle => |q| format!("SELECT COUNT(*) FROM ({}) owsql_count", q),
+            _               => |q| format!("SELECT COUNT(*) FROM ({}) AS owsql_count", q),
+        };
+        let value = self.scalar(query.as_ref(), wrap)?;
+        match value.as_deref().map(str::parse) {
+            Some(Ok(count)) => Ok(count),
+            None => Ok(0),
+            Some(Err(_)) => OwsqlError::new(&self.error_level, "invalid count", &value.unwrap()).map(|_| 0),
+        }
+    }
+
+    /// Checks if the query returns at least one row.  
+    /// The query is wrapped in `SELECT EXISTS(...)`, or `SELECT CASE WHEN EXISTS(...) THEN 1 ELSE 0 END`
+    /// on SQL Server and Oracle, so it must be a single `SELECT` statement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let sql = conn.ow("SELECT * FROM users WHERE name =");
+    /// assert!(conn.exists(sql.clone() + "Alice").unwrap());
+    /// assert!(!conn.exists(sql.clone() + "Alice' OR 'a'='a").unwrap());
+    /// ```
+    pub fn exists<T: AsRef<str>>(&self, query: T) -> Result<bool> {
+        let wrap: fn(&str) -> String = match self.dialect() {
+            Dialect::SqlServer => |q| format!("SELECT CASE WHEN EXISTS({}) THEN 1 ELSE 0 END", q),
+            Dialect::Oracle    => |q| format!("SELECT CASE WHEN EXISTS({}) THEN 1 ELSE 0 END FROM dual", q),
+            _                  => |q| format!("SELECT EXISTS({})", q),
+        };
+        let value = self.scalar(query.as_ref(), wrap)?;
+        match value.as_deref() {
+            Some("1") | Some("t") | Some("true") => Ok(true),
+            Some("0") | Some("f") | Some("false") | None => Ok(false),
+            Some(other) => OwsqlError::new(&self.error_level, "invalid exists", other).map(|_| false),
+        }
+    }
+
+    /// Return the SQL dialect of the backend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert_eq!(conn.dialect(), owsql::Dialect::Sqlite);
+    /// ```
+    #[inline]
+    pub fn dialect(&self) -> Dialect {
+        self.conn.dialect()
+    }
+
+    /// Closes the connection, and return the error instead of ignoring it as `Drop` does.
+    ///
+    /// Backend | Close
+    /// ------- | -----
+    /// SQLite   | Rolls back the open transaction, and fails with `SQLITE_BUSY` if a statement is not finalized
+    /// Postgres | Terminates the session, and the server rolls back the open transaction
+    /// MySQL    | Quits the session, and the server rolls back the open transaction
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("BEGIN; CREATE TABLE users (name TEXT);")).unwrap();
+    /// assert_eq!(conn.close(), Ok(()));
+    /// ```
+    pub fn close(self) -> Result<()> {
+        self.conn.close(&self.error_level)
+    }
 
-        Ok(rows)
+    /// Return whether the connection is lost, such as by a network failure or a server restart.  
+    /// MySQL checks it by a round trip to the server. SQLite is never closed while the connection is alive.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.conn.is_closed()
     }
 
     /// Return the actual SQL statement.
@@ -152,13 +662,109 @@ impl Connection {
     /// ```
     #[inline]
     pub fn actual_sql<T: AsRef<str>>(&self, query: T) -> Result<String> {
-        self.convert_to_valid_syntax(query.as_ref(), self.conn.must_escape())
+        self.convert_to_valid_syntax(query.as_ref())
+    }
+
+    /// Return the actual SQL statement with each escaped literal replaced with `?`.  
+    /// Trusted fragments, including the values of [int](#method.int) and
+    /// [allowlist](#method.allowlist), are kept. Suitable for logging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT * FROM users WHERE name =") + "Alice" + &conn.ow("AND age <") + &conn.int(50);
+    /// assert_eq!(conn.actual_sql_redacted(&sql).unwrap(), "SELECT * FROM users WHERE name = ? AND age < 50 ");
+    /// ```
+    #[inline]
+    pub fn actual_sql_redacted<T: AsRef<str>>(&self, query: T) -> Result<String> {
+        self.redact_statement(query.as_ref())
+    }
+
+    /// Return the actual SQL statement formatted for reading, one clause per line.  
+    /// Keywords are upper-cased, `AND` and `OR` start an indented line, and subqueries are
+    /// indented. The escaped literals are kept as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("select name from users where name =") + "Alice" + &conn.ow("or age <") + &conn.int(50);
+    /// assert_eq!(conn.actual_sql_pretty(&sql).unwrap(), "SELECT name\nFROM users\nWHERE name = 'Alice'\n    OR age < 50");
+    /// ```
+    #[inline]
+    pub fn actual_sql_pretty<T: AsRef<str>>(&self, query: T) -> Result<String> {
+        self.tokenize_statement(query.as_ref()).map(|tokens| pretty(&tokens))
+    }
+
+    /// Return the tokens of the actual SQL statement.  
+    /// Trusted fragments are split into keywords, identifiers, numbers, quoted strings and
+    /// symbols, and each untrusted value becomes a single escaped [Literal](./enum.Token.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::Token;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT * FROM users WHERE name =") + "Alice' OR 1=1; --";
+    /// assert_eq!(conn.tokenize(&sql).unwrap(), vec![
+    ///     Token::Keyword("SELECT".to_string()),
+    ///     Token::Symbol("*".to_string()),
+    ///     Token::Keyword("FROM".to_string()),
+    ///     Token::Identifier("users".to_string()),
+    ///     Token::Keyword("WHERE".to_string()),
+    ///     Token::Identifier("name".to_string()),
+    ///     Token::Symbol("=".to_string()),
+    ///     Token::Literal("'Alice'' OR 1=1; --'".to_string()),
+    /// ]);
+    /// ```
+    #[inline]
+    pub fn tokenize<T: AsRef<str>>(&self, query: T) -> Result<Vec<Token>> {
+        self.tokenize_statement(query.as_ref())
+    }
+
+    /// Return the errors registered by [ow](#method.ow), [int](#method.int) and
+    /// [allowlist](#method.allowlist) that would be returned when executing the statement.  
+    /// The statement is not sent to the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::OwsqlError;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT * FROM users WHERE age <") + &conn.int("42 OR 1=1; --");
+    /// assert_eq!(conn.pending_errors(&sql), vec![OwsqlError::Message("non integer".to_string())]);
+    /// ```
+    #[inline]
+    pub fn pending_errors<T: AsRef<str>>(&self, query: T) -> Vec<OwsqlError> {
+        self.collect_errors(query.as_ref())
+    }
+
+    /// Checks if executing the statement would return an error registered by [ow](#method.ow),
+    /// [int](#method.int) or [allowlist](#method.allowlist).  
+    /// Errors returned by the database are not detected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert!(conn.has_error(conn.ow("SELECT") + &conn.int("foo")));
+    /// assert!(!conn.has_error(conn.ow("SELECT") + &conn.int(42)));
+    /// ```
+    #[inline]
+    pub fn has_error<T: AsRef<str>>(&self, query: T) -> bool {
+        !self.collect_errors(query.as_ref()).is_empty()
     }
 
     /// Return the overwrite definition string.  
     /// All strings assembled without using this method are escaped.  
+    /// The definition string is prefixed by a namespace unique to the connection, and an escaped
+    /// string that looks like a definition string, such as one of another connection, results in
+    /// a "token collision" error.  
     /// This method does not sanitize.  
     /// A string containing incomplete quotes like the one below will result in an error.  
+    /// With the `dialect-check` feature, a string with an unknown keyword or a dangling `JOIN` for
+    /// the backend also results in an error on `Develop` and `Debug`.  
     ///
     /// # Errors
     ///
@@ -180,27 +786,69 @@ impl Connection {
     /// assert_eq!(sql, conn.ow("SELECT"));
     /// assert_ne!(sql, "SELECT");
     /// ```
+    #[track_caller]
     pub fn ow<T: ?Sized + std::string::ToString>(&self, s: &'static T) -> String {
-        let s = s.to_string();
+        self.ow_trusted(s.to_string())
+    }
+
+    /// Registers a trusted fragment in the same way as [ow](#method.ow), which is static or simulated.
+    #[track_caller]
+    pub(crate) fn ow_trusted(&self, s: String) -> String {
+        self.audit(AuditKind::Trusted, &s);
+        if let Err(e) = self.policy.check(&s) {
+            return self.error_token(e);
+        }
         if !self.overwrite.borrow().contain(&s) {
+            #[cfg(feature = "dialect-check")]
+            if let Err(e) = self.check_grammar(&s) {
+                return self.error_token(e);
+            }
             let result = self.check_valid_literal(&s);
             match result {
                 Ok(_) => {
-                    let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
+                    let overwrite = self.overwrite_new();
                     self.overwrite.borrow_mut().insert(s.to_string(), overwrite);
                 },
-                Err(e) => {
-                    if !self.error_msg.borrow().contain(&e) {
-                        let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
-                        self.error_msg.borrow_mut().insert(e.clone(), overwrite);
-                    }
-                    return format!(" {} ", self.error_msg.borrow().get(&e).unwrap());
-                },
+                Err(e) => return self.error_token(e),
             }
         }
+        self.gc.borrow_mut().pin(Map::Overwrite, &s);
         format!(" {} ", self.overwrite.borrow().get(&s).unwrap())
     }
 
+    /// Like [ow method](#method.ow), but accepts strings assembled at runtime (e.g. a table prefix
+    /// read from a configuration file).  
+    /// The literal validation is the same as [ow method](#method.ow), but an invalid literal is
+    /// returned immediately as an error instead of being reported at execution.  
+    ///
+    /// # Danger
+    ///
+    /// The returned string is trusted as part of the statement, exactly like a static fragment.
+    /// Never pass values entered by users.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let prefix = String::from("tenant1_");
+    /// let sql = conn.ow("SELECT * FROM") + &conn.ow_runtime(prefix + "users").unwrap();
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM tenant1_users ");
+    /// assert!(conn.ow_runtime(String::from("O'Reilly")).is_err());
+    /// ```
+    #[track_caller]
+    pub fn ow_runtime<T: AsRef<str>>(&self, s: T) -> Result<String> {
+        let s = s.as_ref().to_string();
+        self.policy.check(&s)?;
+        if !self.overwrite.borrow().contain(&s) {
+            self.check_valid_literal(&s)?;
+            let overwrite = self.overwrite_new();
+            self.overwrite.borrow_mut().insert(s.to_string(), overwrite);
+        }
+        self.audit(AuditKind::Runtime, &s);
+        self.gc.borrow_mut().pin(Map::Overwrite, &s);
+        Ok(format!(" {} ", self.overwrite.borrow().get(&s).unwrap()))
+    }
+
     /// Does not escape.  
     /// Don't use if the value entered is unreliable (e.g. entered by user).  
     ///
@@ -214,7 +862,7 @@ impl Connection {
     /// # conn.execute(stmt).unwrap();
     /// let age = String::from("42 or 1=1; --");  // input by attcker
     /// let sql = conn.ow("SELECT name FROM users WHERE age <") + unsafe { &conn.without_escape(&age) };
-    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users WHERE age < 42 or 1=1; -- ");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users WHERE age < 42 or 1=1; --\n");
     /// dbg!(conn.execute(&sql));
     /// assert!(conn.rows(&sql).is_ok());
     /// ```
@@ -223,13 +871,18 @@ impl Connection {
     ///
     /// - Use trusted values
     /// - Use in an environment where SQL injection does not occur
+    #[track_caller]
     pub unsafe fn without_escape<T: ?Sized + std::string::ToString>(&self, s: &T) -> String {
         let s = s.to_string();
-        if !self.overwrite.borrow().contain(&s) {
-            let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
+        self.audit(AuditKind::Unescaped, &s);
+        let inserted = !self.overwrite.borrow().contain(&s);
+        if inserted {
+            let overwrite = self.evictable_overwrite_new(&s);
             self.overwrite.borrow_mut().insert(s.to_string(), overwrite);
         }
-        format!(" {} ", self.overwrite.borrow().get(&s).unwrap())
+        let overwrite = format!(" {} ", self.overwrite.borrow().get(&s).unwrap());
+        self.gc_used(Map::Overwrite, &s, inserted);
+        overwrite
     }
 
     /// If there are whitespaces before or after the string entered, or if the string is only
@@ -247,11 +900,14 @@ impl Connection {
     /// ```
     pub fn whitespace_around<T: std::string::ToString>(&self, s: T) -> String {
         let s = s.to_string();
-        if !self.whitespace_around.borrow().contain(&s) {
-            let whitespace_around = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
+        let inserted = !self.whitespace_around.borrow().contain(&s);
+        if inserted {
+            let whitespace_around = self.overwrite_new();
             self.whitespace_around.borrow_mut().insert(s.to_string(), whitespace_around);
         }
-        format!(" {} ", self.whitespace_around.borrow().get(&s).unwrap())
+        let whitespace_around = format!(" {} ", self.whitespace_around.borrow().get(&s).unwrap());
+        self.gc_used(Map::WhitespaceAround, &s, inserted);
+        whitespace_around
     }
 
     /// Return the overwrite definition string in allowlist.  
@@ -272,17 +928,14 @@ impl Connection {
     ///
     /// assert!(conn.execute(sql).is_err());
     /// ```
+    #[track_caller]
     pub fn allowlist<T: ToString>(&self, value: T) -> String {
         let value = value.to_string();
         if self.allowlist.contains(&value) {
+            self.audit(AuditKind::Allowlist, &value);
             format!(" {} ", self.overwrite.borrow().get(&escape_for_allowlist(&value)).unwrap())
         } else {
-            let e = OwsqlError::new(&self.error_level, "deny value", &value).err().unwrap_or(OwsqlError::AnyError);
-            if !self.error_msg.borrow().contain(&e) {
-                let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
-                self.error_msg.borrow_mut().insert(e.clone(), overwrite);
-            }
-            format!(" {} ", self.error_msg.borrow().get(&e).unwrap())
+            self.ow_error("deny value", &value)
         }
     }
 
@@ -321,8 +974,9 @@ impl Connection {
             self.allowlist.insert(value.to_string());
             self.overwrite.borrow_mut().insert(
                 escape_for_allowlist(&value.to_string()),
-                overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range)
+                self.overwrite_new()
             );
+            self.gc.borrow_mut().pin(Map::Overwrite, &escape_for_allowlist(&value.to_string()));
         }
     }
 
@@ -337,36 +991,79 @@ impl Connection {
     /// conn.int("42");            // ok
     /// conn.int("42 or 1=1; --"); // error
     /// ```
+    #[track_caller]
     pub fn int<T: Clone + ToString>(&self, value: T) -> String {
         let value = value.to_string();
         if value.parse::<i64>().is_ok() {
-            if !self.overwrite.borrow().contain(&value) {
-                let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
-                self.overwrite.borrow_mut().insert(value.to_string(), overwrite);
-            }
-            format!(" {} ", self.overwrite.borrow().get(&value).unwrap())
+            self.audit(AuditKind::Integer, &value);
+            self.ow_generated(value)
         } else {
-            let e = OwsqlError::new(&self.error_level, "non integer", &value).err().unwrap_or(OwsqlError::AnyError);
-            if !self.error_msg.borrow().contain(&e) {
-                let overwrite = overwrite_new(self.serial_number.borrow_mut().get(), self.ow_len_range);
-                self.error_msg.borrow_mut().insert(e.clone(), overwrite);
-            }
-            format!(" {} ", self.error_msg.borrow().get(&e).unwrap())
+            self.ow_error("non integer", &value)
         }
     }
 
-    /// You can set a different fixed value or a different length each time.  
-    /// The [ow method](./struct.SqliteConnection.html#method.ow) outputs a random number of about 32
-    /// digits by default.  
-    /// However, if a number less than 32 digits is entered, it will be set to 32 digits.  
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
-    /// conn.set_ow_len(42);       // 42
-    /// conn.set_ow_len(50..100);  // 50-99
-    /// conn.set_ow_len(50..=100); // 50-100
+    /// Generate a new overwrite string in the namespace of the connection.
+    fn overwrite_new(&self) -> String {
+        overwrite_new(&self.namespace, self.token_alphabet, self.serial_number.borrow_mut().get(), self.ow_len_range)
+    }
+
+    /// Generate a new overwrite string of the value which may be evicted, which is derived from
+    /// the value if the eviction policy is `Rederive`.
+    fn evictable_overwrite_new(&self, value: &str) -> String {
+        match self.gc.borrow().policy() {
+            EvictionPolicy::Fail     => self.overwrite_new(),
+            EvictionPolicy::Rederive => derived_overwrite_new(&self.namespace, &self.derivation_key, value),
+        }
+    }
+
+    /// Records the fragment with the caller if the audit mode is enabled.
+    #[track_caller]
+    fn audit(&self, kind: AuditKind, fragment: &str) {
+        if let Some(audit) = self.audit.borrow_mut().as_mut() {
+            audit.record(kind, fragment, Some(Location::caller()));
+        }
+    }
+
+    /// Registers a fragment generated by owsql from a runtime value.  
+    /// Unlike [ow](#method.ow), the definition may be evicted.
+    pub(crate) fn ow_generated(&self, value: String) -> String {
+        let inserted = !self.overwrite.borrow().contain(&value);
+        if inserted {
+            let overwrite = self.evictable_overwrite_new(&value);
+            self.overwrite.borrow_mut().insert(value.to_string(), overwrite);
+        }
+        let overwrite = format!(" {} ", self.overwrite.borrow().get(&value).unwrap());
+        self.gc_used(Map::Overwrite, &value, inserted);
+        overwrite
+    }
+
+    /// Registers an error, which is returned when the statement is executed.
+    pub(crate) fn ow_error(&self, err_msg: &str, detail: &str) -> String {
+        let e = OwsqlError::new(&self.error_level, err_msg, detail).err().unwrap_or(OwsqlError::AnyError);
+        self.error_token(e)
+    }
+
+    /// Registers the error, and return the string replaced with it.
+    fn error_token(&self, e: OwsqlError) -> String {
+        if !self.error_msg.borrow().contain(&e) {
+            let overwrite = self.overwrite_new();
+            self.error_msg.borrow_mut().insert(e.clone(), overwrite);
+        }
+        format!(" {} ", self.error_msg.borrow().get(&e).unwrap())
+    }
+
+    /// You can set a different fixed value or a different length each time.  
+    /// The [ow method](./struct.SqliteConnection.html#method.ow) outputs a random number of about 32
+    /// digits by default.  
+    /// However, if a number less than 32 digits is entered, it will be set to 32 digits.  
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_ow_len(42);       // 42
+    /// conn.set_ow_len(50..100);  // 50-99
+    /// conn.set_ow_len(50..=100); // 50-100
     /// ```
     pub fn set_ow_len<T: 'static + IntoInner>(&mut self, range: T) {
         self.ow_len_range = {
@@ -377,6 +1074,46 @@ impl Connection {
         };
     }
 
+    /// Sets the characters of the random part of the overwrite strings generated from now on.  
+    /// The length is raised if needed, so that the entropy never falls below
+    /// [OW_MINIMUM_ENTROPY_BITS](./constants/constant.OW_MINIMUM_ENTROPY_BITS.html).  
+    /// Return an error if a custom alphabet has less than two characters, duplicates or
+    /// non-alphanumerics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::TokenAlphabet;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_token_alphabet(TokenAlphabet::Hex).unwrap();
+    /// assert_eq!(conn.token_entropy_bits(), 128);
+    /// assert!(conn.set_token_alphabet(TokenAlphabet::Custom("a-z")).is_err());
+    /// ```
+    pub fn set_token_alphabet(&mut self, alphabet: TokenAlphabet) -> Result<()> {
+        alphabet.validate()?;
+        self.token_alphabet = alphabet;
+        Ok(())
+    }
+
+    /// Return the minimum entropy in bits of the random part of the overwrite strings generated
+    /// from now on, by the length set by [set_ow_len](#method.set_ow_len) and the alphabet set by
+    /// [set_token_alphabet](#method.set_token_alphabet).  
+    /// It is at least [OW_MINIMUM_ENTROPY_BITS](./constants/constant.OW_MINIMUM_ENTROPY_BITS.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert_eq!(conn.token_entropy_bits(), 190);
+    /// conn.set_ow_len(64);
+    /// assert_eq!(conn.token_entropy_bits(), 381);
+    /// ```
+    pub fn token_entropy_bits(&self) -> u32 {
+        let minimum = self.token_alphabet.minimum_length();
+        let len = self.ow_len_range.0.min(self.ow_len_range.1).max(minimum);
+        (len as f64 * self.token_alphabet.bits()).floor() as u32
+    }
+
     /// Sets the error level.  
     /// The default value is [OwsqlErrorLevel](./enum.OwsqlErrorLevel.html)::Develop for debug builds and [OwsqlErrorLevel](./enum.OwsqlErrorLevel.html)::Release for release builds.
     ///
@@ -391,5 +1128,657 @@ impl Connection {
         self.error_level = level;
         Ok(())
     }
+
+    /// Sets which characters are escaped in string literals.  
+    /// The default value is detected from the session settings when the connection is opened, and
+    /// again when [set_session](#method.set_session) changes them.  
+    /// Setting [EscapeMode](./enum.EscapeMode.html)::Standard while the server treats backslashes
+    /// as escape characters makes SQL injection possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::EscapeMode;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert_eq!(conn.escape_mode(), EscapeMode::Standard);
+    /// conn.set_escape_mode(EscapeMode::Backslash);
+    /// assert_eq!(conn.actual_sql("\\'").unwrap(), "'\\\\''' ");
+    /// ```
+    pub fn set_escape_mode(&mut self, mode: EscapeMode) {
+        self.escape_mode.set(mode);
+    }
+
+    /// Return the current escape mode.
+    #[inline]
+    pub fn escape_mode(&self) -> EscapeMode {
+        self.escape_mode.get()
+    }
+
+    /// Sets the charset in which the database reads statements.  
+    /// The default value is detected from the session settings when the connection is opened, and
+    /// again when [set_session](#method.set_session) changes them.  
+    /// Literals containing a NUL character are always rejected. In addition, when the charset is a
+    /// multibyte charset such as GBK or Shift_JIS and the escape mode is
+    /// [EscapeMode](./enum.EscapeMode.html)::Backslash, literals in which a non-ASCII character is
+    /// followed by a backslash are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::{Charset, EscapeMode};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert_eq!(conn.charset(), Charset::Utf8);
+    /// assert!(conn.actual_sql("\u{7e3a}\\").is_ok());
+    /// conn.set_charset(Charset::Gbk);
+    /// conn.set_escape_mode(EscapeMode::Backslash);
+    /// assert!(conn.actual_sql("\u{7e3a}\\").is_err());
+    /// assert!(conn.actual_sql("nul\0").is_err());
+    /// ```
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset.set(charset);
+    }
+
+    /// Return the current charset.
+    #[inline]
+    pub fn charset(&self) -> Charset {
+        self.charset.get()
+    }
+
+    /// Sets whether literals in positions where a value is not expected are rejected.  
+    /// In strict mode, a value must follow an operator, an opening parenthesis, a comma or a
+    /// keyword such as `LIKE` or `LIMIT`. This catches fragments that were not wrapped in
+    /// [ow](#method.ow) and would otherwise become quoted literals. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::OwsqlError;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_strict(true);
+    /// let sql = conn.ow("SELECT * FROM users WHERE name =") + "Alice";
+    /// assert!(conn.actual_sql(&sql).is_ok());
+    /// let sql = conn.ow("SELECT * FROM") + "users";
+    /// assert_eq!(conn.actual_sql(&sql), Err(OwsqlError::Message("unexpected literal".into())));
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Sets whether a result with columns of the same name is an error, such as `id` of two joined
+    /// tables, instead of accessing them by the position. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::OwsqlError;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT 1 AS id, 2 AS id;");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_at(0), Some("1"));
+    /// conn.set_unique_columns(true);
+    /// assert_eq!(conn.rows(&sql), Err(OwsqlError::Message("duplicate column".into())));
+    /// ```
+    pub fn set_unique_columns(&mut self, enabled: bool) {
+        self.unique_columns = enabled;
+    }
+
+    /// Sets how the column names of the returned rows are matched. The default is `ColumnMatch::Exact`.
+    /// See [ColumnMatch](./enum.ColumnMatch.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::ColumnMatch;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_column_match(ColumnMatch::Loose);
+    /// let row = conn.single_row(conn.ow("SELECT 'Alice' AS user_name;")).unwrap();
+    /// assert_eq!(row.get("userName"), Some("Alice"));
+    /// ```
+    pub fn set_column_match(&mut self, column_match: ColumnMatch) {
+        self.column_match = column_match;
+    }
+
+    /// Sets whether the actual SQL statement is separated by single spaces.  
+    /// By default, a space follows every fragment and literal, so the spacing depends on how the
+    /// statement was assembled. When enabled, each run of whitespaces outside quoted strings,
+    /// quoted identifiers and comments is collapsed into a single space and both ends are trimmed,
+    /// so that the same statement is always sent as the same text. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT * FROM users\n  WHERE name = ") + "Alice  Smith" + &conn.ow(";");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM users\n  WHERE name =  'Alice  Smith' ; ");
+    /// conn.set_canonical_whitespace(true);
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM users WHERE name = 'Alice  Smith' ;");
+    /// ```
+    pub fn set_canonical_whitespace(&mut self, enabled: bool) {
+        self.canonical_spaces = enabled;
+    }
+
+    /// Sets whether the comments in trusted fragments are sent or removed.  
+    /// Comments `--` and `/* */` are allowed in the fragments of [ow](#method.ow) either way, and
+    /// quotations in them are not checked. A value following a line comment starts on a new line.
+    /// The default is [CommentMode](./enum.CommentMode.html)::Preserve.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::CommentMode;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT /* don't */ * FROM users WHERE name = -- the user's name") + "Alice";
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT /* don't */ * FROM users WHERE name = -- the user's name\n'Alice' ");
+    /// conn.set_comment_mode(CommentMode::Strip);
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT   * FROM users WHERE name =  'Alice' ");
+    /// ```
+    pub fn set_comment_mode(&mut self, mode: CommentMode) {
+        self.comment_mode = mode;
+    }
+
+    /// Sets the policy screening the fragments of [ow](#method.ow) and
+    /// [ow_runtime](#method.ow_runtime), even though they are trusted.  
+    /// A denied fragment of `ow` is reported when the statement is executed, and `ow_runtime`
+    /// returns the error immediately, as
+    /// [OwsqlError::PolicyViolation](./enum.OwsqlError.html#variant.PolicyViolation).
+    /// The default is [Policy::AllowAll](./enum.Policy.html#variant.AllowAll).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{OwsqlError, Policy};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_fragment_policy(Policy::DenyKeywords(&["DROP", "GRANT"]));
+    /// assert_eq!(conn.execute(conn.ow("DROP TABLE users;")), Err(OwsqlError::PolicyViolation {
+    ///     keyword:  "DROP".into(),
+    ///     fragment: "DROP TABLE users;".into(),
+    /// }));
+    /// ```
+    pub fn set_fragment_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    /// Checks if strict mode is enabled.
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Evicts the overwrite definitions of runtime values registered by [int](#method.int),
+    /// [whitespace_around](#method.whitespace_around) and [without_escape](#method.without_escape)
+    /// that have not been used since the previous call, and returns the number of evicted
+    /// definitions.  
+    /// Definitions of [ow](#method.ow), [ow_runtime](#method.ow_runtime) and
+    /// [add_allowlist](#method.add_allowlist) are never evicted.  
+    /// A statement built before its definitions were evicted fails with a token collision,
+    /// since the evicted parts look like overwrite strings, so do not keep built statements across calls,
+    /// unless the [eviction policy](#method.set_eviction_policy) is `Rederive`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.int(42);
+    /// assert_eq!(conn.gc_overwrites(), 0);
+    /// assert_eq!(conn.gc_overwrites(), 1);
+    /// ```
+    pub fn gc_overwrites(&self) -> usize {
+        let entries = self.gc.borrow_mut().collect();
+        self.evict(entries)
+    }
+
+    /// Sets the maximum number of evictable overwrite definitions.  
+    /// When exceeded, the least recently used definitions are evicted.
+    /// See [gc_overwrites](#method.gc_overwrites) for which definitions are evictable.  
+    /// The default value is unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_overwrite_capacity(1000);
+    /// ```
+    pub fn set_overwrite_capacity(&mut self, capacity: usize) {
+        self.gc.borrow_mut().set_capacity(capacity);
+        let entries = self.gc.borrow_mut().overflow();
+        self.evict(entries);
+    }
+
+    /// Sets what happens to a statement built before its definitions were evicted.
+    /// See [EvictionPolicy](./enum.EvictionPolicy.html).  
+    /// The policy applies to the definitions registered after it is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::EvictionPolicy;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_eviction_policy(EvictionPolicy::Rederive);
+    /// let sql = conn.ow("SELECT") + &conn.int(42) + &conn.ow("AS answer;");
+    /// assert_eq!(conn.gc_overwrites(), 0);
+    /// assert_eq!(conn.gc_overwrites(), 1);
+    /// assert_eq!(conn.single_row(&sql).unwrap().get("answer"), Some("42"));
+    /// assert_eq!(conn.overwrite_stats().misses, 1);
+    /// ```
+    pub fn set_eviction_policy(&mut self, policy: EvictionPolicy) {
+        self.gc.borrow_mut().set_policy(policy);
+    }
+
+    /// Return the statistics of the registered overwrite definitions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.ow("SELECT");
+    /// conn.int(42);
+    /// let stats = conn.overwrite_stats();
+    /// assert_eq!(stats.overwrite, 2);
+    /// assert_eq!(stats.evictable, 1);
+    /// ```
+    pub fn overwrite_stats(&self) -> OverwriteStats {
+        let gc = self.gc.borrow();
+        OverwriteStats {
+            overwrite:         self.overwrite.borrow().len(),
+            whitespace_around: self.whitespace_around.borrow().len(),
+            error_msg:         self.error_msg.borrow().len(),
+            evictable:         gc.len(),
+            evicted:           gc.evicted(),
+            hits:              gc.hits(),
+            misses:            gc.misses(),
+        }
+    }
+
+    fn gc_used(&self, map: Map, key: &str, inserted: bool) {
+        self.gc.borrow_mut().used(map, key, inserted);
+        let entries = self.gc.borrow_mut().overflow();
+        self.evict(entries);
+    }
+
+    /// Registers again the evicted overwrite strings of the statement derived by `EvictionPolicy::Rederive`,
+    /// and counts the hits and the misses of the overwrite strings if `count` is set.  
+    /// They are tracked again, but not evicted until the next registration, so that the statement is converted.
+    pub(crate) fn rederive(&self, stmt: &str, count: bool) {
+        if self.gc.borrow().policy() != EvictionPolicy::Rederive {
+            return;
+        }
+        for word in stmt.split(|c: char| !c.is_ascii_alphanumeric()).filter(|word| is_overwrite_like(word)) {
+            let registered = self.overwrite.borrow().contain_reverse(word)
+                || self.whitespace_around.borrow().contain_reverse(word)
+                || self.error_msg.borrow().contain_reverse(word);
+            if registered {
+                if count {
+                    self.gc.borrow_mut().hit();
+                }
+                continue;
+            }
+            let value = match derived_value(&self.namespace, &self.derivation_key, word) {
+                Some(value) if !self.overwrite.borrow().contain(&value) => value,
+                _ => continue,
+            };
+            self.overwrite.borrow_mut().insert(value.clone(), word.to_string());
+            let mut gc = self.gc.borrow_mut();
+            gc.used(Map::Overwrite, &value, true);
+            if count {
+                gc.miss();
+            }
+        }
+    }
+
+    fn evict(&self, entries: Vec<(Map, String)>) -> usize {
+        for (map, key) in entries.iter() {
+            match map {
+                Map::Overwrite        => self.overwrite.borrow_mut().remove(key),
+                Map::WhitespaceAround => self.whitespace_around.borrow_mut().remove(key),
+            };
+        }
+        entries.len()
+    }
+
+    /// Enables or disables the collection of statement-level metrics.  
+    /// Statements are grouped by their normalized form, in which every escaped literal is
+    /// replaced with `?`.  
+    /// Disabling discards the metrics collected so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_metrics(true);
+    /// conn.execute(conn.ow("SELECT") + "42").unwrap();
+    /// conn.execute(conn.ow("SELECT") + "69").unwrap();
+    /// let metrics = conn.metrics().unwrap();
+    /// assert_eq!(metrics.statements()[0].statement, "SELECT ? ");
+    /// assert_eq!(metrics.statements()[0].count, 2);
+    /// ```
+    pub fn set_metrics(&mut self, enabled: bool) {
+        *self.metrics.borrow_mut() = if enabled { Some(Metrics::default()) } else { None };
+    }
+
+    /// Return a snapshot of the collected metrics, or `None` if metrics are disabled.
+    #[inline]
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.metrics.borrow().clone()
+    }
+
+    /// Enables or disables the audit mode, in which the fragments trusted by [ow](#method.ow),
+    /// [ow_runtime](#method.ow_runtime), [without_escape](#method.without_escape),
+    /// [int](#method.int) and [allowlist](#method.allowlist) are recorded with the caller,
+    /// and the literals escaped by the executed statements are recorded without the caller.  
+    /// The escaped literals are kept as they are, so the report grows with distinct values
+    /// and may contain sensitive data.  
+    /// Disabling discards the report.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::AuditKind;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_audit(true);
+    /// let sql = conn.ow("SELECT") + "Alice";
+    /// conn.execute(&sql).unwrap();
+    /// let entries = conn.audit_report().unwrap().entries();
+    /// assert_eq!(entries[0].kind, AuditKind::Trusted);
+    /// assert_eq!(entries[0].location.unwrap().file(), file!());
+    /// assert_eq!(entries[1].kind, AuditKind::Escaped);
+    /// assert_eq!(entries[1].fragment, "'Alice'");
+    /// println!("{}", conn.audit_report().unwrap());
+    /// ```
+    pub fn set_audit(&mut self, enabled: bool) {
+        *self.audit.borrow_mut() = if enabled { Some(AuditReport::default()) } else { None };
+    }
+
+    /// Return a snapshot of the audit report, or `None` if the audit mode is disabled.
+    #[inline]
+    pub fn audit_report(&self) -> Option<AuditReport> {
+        self.audit.borrow().clone()
+    }
+
+    /// Sets the callback invoked with the redacted SQL and the elapsed time when a statement
+    /// takes `threshold` or longer.  
+    /// The statement is measured from the conversion to the end of the execution, including
+    /// fetching the rows, and it is not interrupted however long it takes.
+    /// String literals are redacted as in [actual_sql_redacted](#method.actual_sql_redacted).
+    /// The callback must be `Send`, since the connection can be sent to another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_slow_query_threshold(Duration::from_millis(500), |sql, elapsed| {
+    ///     eprintln!("slow query ({:?}): {}", elapsed, sql);
+    /// });
+    /// ```
+    pub fn set_slow_query_threshold<F: FnMut(&str, Duration) + Send + 'static>(&mut self, threshold: Duration, callback: F) {
+        *self.slow_query.borrow_mut() = Some(SlowQuery { threshold, callback: Box::new(callback) });
+    }
+
+    /// Removes the callback set by [set_slow_query_threshold](#method.set_slow_query_threshold).
+    pub fn clear_slow_query_threshold(&mut self) {
+        *self.slow_query.borrow_mut() = None;
+    }
+
+    /// Enables the cache of the rows returned by [rows](#method.rows) for `SELECT` statements.  
+    /// The rows are keyed by the SQL sent to the database, and are kept for `ttl` at most.
+    /// If the cache has `capacity` entries, the least recently used entry is evicted.
+    ///
+    /// When a statement other than `SELECT` is executed by this connection, the entries
+    /// referencing the tables in the statement are removed. If no table is found in the statement,
+    /// or a transaction is rolled back, all entries are removed.
+    /// Writes by other connections, triggers, foreign key actions and writes to the tables of a view
+    /// are not detected, so choose `ttl` for the staleness that is acceptable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, id INTEGER);")).unwrap();
+    /// conn.enable_query_cache(100, Duration::from_secs(60));
+    /// let sql = conn.ow("SELECT * FROM users;");
+    /// assert_eq!(conn.rows(&sql).unwrap().len(), 0);
+    /// conn.execute(conn.ow("INSERT INTO users VALUES('Alice', 42);")).unwrap();
+    /// assert_eq!(conn.rows(&sql).unwrap().len(), 1);
+    /// ```
+    pub fn enable_query_cache(&mut self, capacity: usize, ttl: Duration) {
+        *self.query_cache.borrow_mut() = Some(QueryCache::new(capacity, ttl));
+    }
+
+    /// Disables the query cache and discards the cached rows.
+    pub fn disable_query_cache(&mut self) {
+        *self.query_cache.borrow_mut() = None;
+    }
+
+    /// Discards the cached rows.
+    pub fn clear_query_cache(&self) {
+        if let Some(cache) = self.query_cache.borrow_mut().as_mut() {
+            cache.clear();
+        }
+    }
+
+    pub(crate) fn invalidate_query_cache(&self, sql: &Result<String>) {
+        if let (Ok(sql), Some(cache)) = (sql, self.query_cache.borrow_mut().as_mut()) {
+            if !is_select(sql) {
+                cache.invalidate(sql);
+            }
+        }
+    }
+
+    /// Opens a blob to read and write it as a stream, without loading it into memory.  
+    /// The table and the column are passed as names to the backend, not as SQL.
+    ///
+    /// Dialect  | Blob
+    /// -------- | ----
+    /// SQLite   | The `BLOB` of the column in the row of `rowid`. It cannot change its size, so use `zeroblob(N)` to allocate it.
+    /// Postgres | The large object whose OID is `rowid`, such as one created by `lo_create(0)`. The table and the column are not used.
+    /// MySQL    | Not supported.
+    ///
+    /// On Postgres, a transaction is open until the blob is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE files (data BLOB); INSERT INTO files VALUES(zeroblob(5));")).unwrap();
+    /// let mut blob = conn.blob_open("files", "data", 1).unwrap();
+    /// blob.write_all(b"owsql").unwrap();
+    /// # drop(blob);
+    /// let mut data = Vec::new();
+    /// conn.blob_open("files", "data", 1).unwrap().read_to_end(&mut data).unwrap();
+    /// assert_eq!(data, b"owsql");
+    /// ```
+    pub fn blob_open(&self, table: &str, column: &str, rowid: i64) -> Result<Blob<'_>> {
+        self.conn.blob_open(table, column, rowid).map(Blob::new)
+    }
+
+    /// Runs the closure in a transaction.  
+    /// It is committed if the closure returns `Ok`, and rolled back if it returns `Err`, panics or the commit fails.  
+    /// Transactions can not be nested; starting one inside the closure returns the error "nested transaction".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// let result: owsql::Result<()> = conn.with_transaction(|tx| {
+    ///     tx.execute(tx.ow("INSERT INTO users VALUES('Alice');"))?;
+    ///     tx.execute(tx.ow("INSERT INTO nothing VALUES('Bob');"))
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(0));
+    /// ```
+    pub fn with_transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction<'_>) -> Result<T>,
+    {
+        self.with_transaction_options(&TransactionOptions::default(), f)
+    }
+
+    /// Runs the closure in a transaction with the isolation level and the other options.
+    /// See [with_transaction](#method.with_transaction).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{IsolationLevel, TransactionOptions};
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// let options = TransactionOptions {
+    ///     isolation: Some(IsolationLevel::Serializable),
+    ///     read_only: true,
+    ///     ..Default::default()
+    /// };
+    /// let result = conn.with_transaction_options(&options, |tx| {
+    ///     tx.execute(tx.ow("INSERT INTO users VALUES('Alice');"))
+    /// });
+    /// assert!(result.is_err());
+    /// ```
+    pub fn with_transaction_options<T, F>(&self, options: &TransactionOptions, f: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction<'_>) -> Result<T>,
+    {
+        if self.in_transaction.get() {
+            return Err(OwsqlError::Message("nested transaction".to_string()));
+        }
+        let dialect = self.dialect();
+        let end = || {
+            for sql in options.end(dialect) {
+                let _ = self.execute_internal(&sql);
+            }
+        };
+        for sql in options.begin(dialect)? {
+            if let Err(e) = self.execute_internal(&sql) {
+                end();
+                return Err(e);
+            }
+        }
+        self.observe(|observer| observer.on_transaction_begin());
+        let tx = Transaction::new(self);
+        self.in_transaction.set(true);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&tx)));
+        self.in_transaction.set(false);
+        let result = match result {
+            Ok(Ok(value)) => match self.execute_internal("COMMIT") {
+                Ok(_) => {
+                    self.observe(|observer| observer.on_commit());
+                    Ok(value)
+                },
+                Err(e) => {
+                    let _ = self.execute_internal("ROLLBACK");
+                    self.observe(|observer| observer.on_rollback());
+                    Err(e)
+                },
+            },
+            Ok(Err(e)) => {
+                let _ = self.execute_internal("ROLLBACK");
+                self.observe(|observer| observer.on_rollback());
+                Err(e)
+            },
+            Err(payload) => {
+                let _ = self.execute_internal("ROLLBACK");
+                self.observe(|observer| observer.on_rollback());
+                end();
+                panic::resume_unwind(payload)
+            },
+        };
+        end();
+        result
+    }
+
+    /// Runs the closure, and runs it again while it fails by a serialization failure or a deadlock.  
+    /// Other errors are returned as they are. When the retries are exhausted,
+    /// [OwsqlError::RetryExhausted](./enum.OwsqlError.html#variant.RetryExhausted) is returned.
+    ///
+    /// Dialect  | Retryable errors
+    /// -------- | ----------------
+    /// SQLite   | `SQLITE_BUSY`, `SQLITE_LOCKED`
+    /// Postgres | `40001` (serialization_failure), `40P01` (deadlock_detected)
+    /// MySQL    | `1213` (ER_LOCK_DEADLOCK), `1205` (ER_LOCK_WAIT_TIMEOUT)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{IsolationLevel, RetryPolicy, TransactionOptions};
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// let options = TransactionOptions {
+    ///     isolation: Some(IsolationLevel::Serializable),
+    ///     ..Default::default()
+    /// };
+    /// conn.with_retry(&RetryPolicy::default(), |conn| {
+    ///     conn.with_transaction_options(&options, |tx| {
+    ///         tx.execute(tx.ow("INSERT INTO users VALUES('Alice');"))
+    ///     })
+    /// }).unwrap();
+    /// ```
+    pub fn with_retry<T, F>(&self, policy: &RetryPolicy, mut f: F) -> Result<T>
+    where
+        F: FnMut(&Connection) -> Result<T>,
+    {
+        let dialect = self.dialect();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            *self.last_error_code.borrow_mut() = None;
+            let error = match f(self) {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            let code = self.last_error_code.borrow().clone();
+            if !code.is_some_and(|code| is_retryable(dialect, &code)) {
+                return Err(error);
+            }
+            if attempts > policy.max_retries {
+                return Err(OwsqlError::RetryExhausted { attempts, error: Box::new(error) });
+            }
+            thread::sleep(policy.backoff(attempts));
+        }
+    }
+
+    /// Executes a statement generated by the library, which is not converted.
+    fn execute_internal(&self, sql: &str) -> Result<()> {
+        if sql.starts_with("ROLLBACK") {
+            self.clear_query_cache();
+        }
+        self.conn._execute(Ok(sql.to_string()), &self.error_level)
+    }
+
+    /// Execute the query wrapped by `wrap`, and return the first value of the first row.
+    fn scalar(&self, query: &str, wrap: fn(&str) -> String) -> Result<Option<String>> {
+        let start = self.start_statement(query);
+        let mut value = None;
+        let result = self.conn._iterate(
+            self.rewrite(self.convert_to_valid_syntax(query).map(|q| wrap(q.trim_end().trim_end_matches(';')))),
+            &self.error_level,
+            &mut |pairs| {
+                value = pairs.first().and_then(|(_, v)| v.map(str::to_string));
+                true
+            }
+        );
+        self.record_statement(query, start, result.as_ref().err());
+        result.map(|_| value)
+    }
+
+    pub(crate) fn record_statement(&self, query: &str, start: Instant, error: Option<&OwsqlError>) {
+        if error.is_some() {
+            *self.last_error_code.borrow_mut() = self.conn.last_error_code();
+        }
+        let elapsed = start.elapsed();
+        if let Some(metrics) = self.metrics.borrow_mut().as_mut() {
+            metrics.record(self.normalize_statement(query), elapsed, error.is_some());
+        }
+        if let Some(slow_query) = self.slow_query.borrow_mut().as_mut() {
+            if elapsed >= slow_query.threshold {
+                (slow_query.callback)(&self.statement_text(query), elapsed);
+            }
+        }
+        self.end_statement(query, elapsed, error);
+    }
+
+    /// Return the statement with the string literals redacted, to be reported.
+    pub(crate) fn statement_text(&self, query: &str) -> String {
+        self.redact_statement(query).unwrap_or_else(|_| self.normalize_statement(query))
+    }
 }
 
@@ -1 +1,4 @@
 pub const OW_MINIMUM_LENGTH: usize = 32;
+pub const OW_PREFIX: &str = "OWSQL";
+/// The minimum entropy of the random part of an overwrite string, whatever the length and the alphabet.
+pub const OW_MINIMUM_ENTROPY_BITS: u32 = 128;
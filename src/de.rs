@@ -0,0 +1,411 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::row::parse_bool;
+
+/// The error of deserializing a row.
+#[derive(Debug)]
+pub(crate) struct DeError(String);
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError(msg.to_string())
+    }
+}
+
+/// Deserializes a row from the pairs of a column name and a value, as a struct or a map by the
+/// names, or as a tuple or a sequence by the positions.
+pub(crate) struct RowDeserializer<'a> {
+    pairs: &'a [(&'a str, Option<&'a str>)],
+}
+
+impl<'a> RowDeserializer<'a> {
+    pub(crate) fn new(pairs: &'a [(&'a str, Option<&'a str>)]) -> Self {
+        Self { pairs }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for RowDeserializer<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        let entries = self.pairs.iter().map(|&(name, value)| Entry::Column(name, value)).collect::<Vec<_>>();
+        visitor.visit_map(Columns { entries: entries.into_iter(), entry: None })
+    }
+
+    /// A field without a column of its name is deserialized from the columns of its prefix, such as
+    /// a struct of a joined table.
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> std::result::Result<V::Value, DeError> {
+        let mut entries = self.pairs.iter().map(|&(name, value)| Entry::Column(name, value)).collect::<Vec<_>>();
+        for &field in fields {
+            if self.pairs.iter().any(|&(name, _)| name == field) {
+                continue;
+            }
+            if let Some(pairs) = prefixed(self.pairs, field) {
+                entries.push(Entry::Nested(field, pairs));
+            }
+        }
+        visitor.visit_map(Columns { entries: entries.into_iter(), entry: None })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_seq(Values(self.pairs.iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct map enum identifier ignored_any
+    }
+}
+
+/// Return the columns of the prefix of the field without the prefix, where the prefix is the field
+/// name if it ends with `_` or `.`, such as `#[serde(rename = "author_")]`, or the field name
+/// followed by `_` or `.`.
+fn prefixed<'a>(pairs: &[(&'a str, Option<&'a str>)], field: &str) -> Option<Vec<(&'a str, Option<&'a str>)>> {
+    let prefixes = if field.ends_with('_') || field.ends_with('.') {
+        vec![field.to_string()]
+    } else {
+        vec![format!("{}_", field), format!("{}.", field)]
+    };
+    prefixes.iter()
+        .map(|prefix| pairs.iter()
+            .filter_map(|&(name, value)| name.strip_prefix(prefix.as_str()).filter(|rest| !rest.is_empty()).map(|rest| (rest, value)))
+            .collect::<Vec<_>>())
+        .find(|nested| !nested.is_empty())
+}
+
+/// An entry of the map of a row.
+enum Entry<'a> {
+    /// A column and its value.
+    Column(&'a str, Option<&'a str>),
+    /// A field and the columns of its prefix.
+    Nested(&'a str, Vec<(&'a str, Option<&'a str>)>),
+}
+
+/// The columns of a row as a map.
+struct Columns<'a> {
+    entries: std::vec::IntoIter<Entry<'a>>,
+    entry:   Option<Entry<'a>>,
+}
+
+impl<'de> MapAccess<'de> for Columns<'_> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, DeError> {
+        match self.entries.next() {
+            Some(entry) => {
+                let name = match entry {
+                    Entry::Column(name, _) | Entry::Nested(name, _) => name,
+                };
+                self.entry = Some(entry);
+                seed.deserialize(name.into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> std::result::Result<V::Value, DeError> {
+        match self.entry.take().ok_or_else(|| DeError("value without a column".to_string()))? {
+            Entry::Column(_, value) => seed.deserialize(ValueDeserializer(value)),
+            Entry::Nested(_, pairs) => seed.deserialize(NestedDeserializer(&pairs)),
+        }
+    }
+}
+
+/// Deserializes the columns of a prefix, which are `None` of an `Option` if all of them are `NULL`,
+/// such as a row of a left joined table which did not match.
+struct NestedDeserializer<'a>(&'a [(&'a str, Option<&'a str>)]);
+
+impl<'de> de::Deserializer<'de> for NestedDeserializer<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        RowDeserializer::new(self.0).deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        if self.0.iter().all(|(_, value)| value.is_none()) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> std::result::Result<V::Value, DeError> {
+        RowDeserializer::new(self.0).deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        RowDeserializer::new(self.0).deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> std::result::Result<V::Value, DeError> {
+        RowDeserializer::new(self.0).deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The values of a row as a sequence.
+struct Values<I>(I);
+
+impl<'de, 'a, I: Iterator<Item = &'a (&'a str, Option<&'a str>)>> SeqAccess<'de> for Values<I> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> std::result::Result<Option<T::Value>, DeError> {
+        match self.0.next() {
+            Some(&(_, value)) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Deserializes the text of a column, where `None` is `NULL`, parsing it into the requested type.
+struct ValueDeserializer<'a>(Option<&'a str>);
+
+impl ValueDeserializer<'_> {
+    /// Return the text, or the error of `NULL`.
+    fn text(&self) -> std::result::Result<&str, DeError> {
+        self.0.ok_or_else(|| DeError("null value".to_string()))
+    }
+
+    /// Parses the text.
+    fn parse<T: std::str::FromStr>(&self) -> std::result::Result<T, DeError> {
+        let text = self.text()?;
+        text.trim().parse().map_err(|_| DeError(format!("invalid value: {}", text)))
+    }
+}
+
+macro_rules! deserialize_parse {
+    ( $( $method:ident => $visit:ident ),* ) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        match self.0 {
+            Some(text) => visitor.visit_str(text),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        let text = self.text()?;
+        visitor.visit_bool(parse_bool(text).ok_or_else(|| DeError(format!("invalid value: {}", text)))?)
+    }
+
+    deserialize_parse! {
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64, deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8, deserialize_u16 => visit_u16, deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64, deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32, deserialize_f64 => visit_f64, deserialize_char => visit_char
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_str(self.text()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_bytes(self.text()?.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        match self.0 {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// A unit variant is deserialized from its name.
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_enum(self.text()?.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl Connection {
+    /// Execute a statement and deserializes each row as it arrives without collecting the rows,
+    /// so that a big result can be processed with constant memory.
+    /// Return `false` from the callback to stop.
+    ///
+    /// A struct or a map is deserialized from the columns by name, and a tuple or a sequence by position.
+    /// The values are parsed from their text into the requested types, where `NULL` is `None`, and
+    /// a unit variant of an enum is deserialized from its name.
+    ///
+    /// A field of a struct without a column of its name is deserialized from the columns prefixed
+    /// with its name and `_` or `.`, so that a JOIN hydrates the nested structs in one query.
+    /// Rename the field to the prefix ending with `_` or `.` to use another prefix, such as
+    /// `#[serde(rename = "author_")]`. An `Option` of a nested struct is `None` if all of its columns are `NULL`.
+    ///
+    /// # Errors
+    ///
+    /// A row which cannot be deserialized stops the iteration and returns the error "invalid row" with the reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name:  String,
+    ///     age:   u32,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER, email TEXT);
+    ///                       INSERT INTO users VALUES ('Alice', 42, NULL), ('Bob', 69, 'bob@example.com');")).unwrap();
+    /// let mut total = 0;
+    /// conn.query_stream::<User>(conn.ow("SELECT * FROM users;"), |user| {
+    ///     total += user.age;
+    ///     true
+    /// }).unwrap();
+    /// assert_eq!(total, 111);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn query_stream<T: DeserializeOwned>(&self, query: impl AsRef<str>, mut callback: impl FnMut(T) -> bool) -> Result<()> {
+        let (mut error, mut stopped) = (None, false);
+        let result = self.iterate(query, |pairs| {
+            let next = match T::deserialize(RowDeserializer::new(pairs)) {
+                Ok(item) => callback(item),
+                Err(e) => {
+                    error = Some(e);
+                    false
+                },
+            };
+            stopped = !next;
+            next
+        });
+        // Stopping the rows is not an error.
+        if !stopped {
+            result?;
+        }
+        match error {
+            Some(e) => OwsqlError::new(&self.error_level, "invalid row", &e.to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::RowDeserializer;
+
+    fn deserialize<T: for<'de> Deserialize<'de>>(pairs: &[(&str, Option<&str>)]) -> Result<T, String> {
+        T::deserialize(RowDeserializer::new(pairs)).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn by_name() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Role { Admin, Guest }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct User { name: String, age: u8, score: Option<f64>, admin: bool, role: Role }
+        let pairs = [("age", Some("42")), ("name", Some("Alice")), ("score", None), ("admin", Some("t")), ("role", Some("Guest"))];
+        assert_eq!(deserialize(&pairs), Ok(User { name: "Alice".into(), age: 42, score: None, admin: true, role: Role::Guest }));
+        let map = deserialize::<HashMap<String, Option<String>>>(&pairs).unwrap();
+        assert_eq!(map["score"], None);
+        assert_eq!(map["age"].as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn by_position() {
+        let pairs = [("name", Some("Alice")), ("age", Some("42"))];
+        assert_eq!(deserialize(&pairs), Ok(("Alice".to_string(), 42i64)));
+        assert_eq!(deserialize(&pairs), Ok(vec!["Alice".to_string(), "42".to_string()]));
+    }
+
+    #[test]
+    fn nested() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Author { id: i64, name: String }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Post {
+            id:       i64,
+            author:   Author,
+            #[serde(rename = "editor_")]
+            reviewer: Option<Author>,
+        }
+        let alice = || Author { id: 2, name: "Alice".into() };
+        let pairs = [("id", Some("1")), ("author_id", Some("2")), ("author_name", Some("Alice")), ("editor_id", None), ("editor_name", None)];
+        assert_eq!(deserialize(&pairs), Ok(Post { id: 1, author: alice(), reviewer: None }));
+        let pairs = [("id", Some("1")), ("author.id", Some("2")), ("author.name", Some("Alice")), ("editor_id", Some("2")), ("editor_name", Some("Alice"))];
+        assert_eq!(deserialize(&pairs), Ok(Post { id: 1, author: alice(), reviewer: Some(alice()) }));
+        assert_eq!(deserialize::<Post>(&[("id", Some("1"))]).unwrap_err(), "missing field `author`");
+    }
+
+    #[test]
+    fn errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct User { name: String, age: u8 }
+        assert_eq!(deserialize::<User>(&[("name", Some("Alice")), ("age", Some("x"))]).unwrap_err(), "invalid value: x");
+        assert_eq!(deserialize::<User>(&[("name", None), ("age", Some("1"))]).unwrap_err(), "null value");
+        assert_eq!(deserialize::<User>(&[("name", Some("Alice"))]).unwrap_err(), "missing field `age`");
+    }
+}
@@ -0,0 +1,105 @@
+/// The SQL dialect of the database backend.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// [SQLite](https://www.sqlite.org)
+    Sqlite,
+    /// [PostgreSQL](https://www.postgresql.org/)
+    Postgres,
+    /// [MySQL](https://www.mysql.com/)
+    Mysql,
+    /// [SQL Server](https://www.microsoft.com/sql-server)
+    SqlServer,
+    /// [Oracle Database](https://www.oracle.com/database/)
+    Oracle,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use super::Dialect;
+    use crate::backend::{Backend, BackendError, Cell, RowCallback};
+    use crate::error::OwsqlError;
+    use crate::params;
+
+    /// Records the statements, and returns a row of `1` for each query.
+    struct Recorder {
+        dialect:    Dialect,
+        statements: RefCell<Vec<String>>,
+    }
+
+    impl Backend for Recorder {
+        fn execute(&self, sql: &str) -> Result<u64, BackendError> {
+            self.statements.borrow_mut().push(sql.trim_end().to_string());
+            Ok(0)
+        }
+
+        fn iterate(&self, sql: &str, callback: RowCallback) -> Result<(), BackendError> {
+            self.statements.borrow_mut().push(sql.trim_end().to_string());
+            callback(&[("id", Cell::Integer(1))]);
+            Ok(())
+        }
+
+        fn dialect(&self) -> Dialect {
+            self.dialect
+        }
+    }
+
+    fn connect(dialect: Dialect) -> crate::Connection {
+        crate::backend::connect(Recorder { dialect, statements: RefCell::new(Vec::new()) })
+    }
+
+    fn statements(conn: &crate::Connection) -> Vec<String> {
+        conn.backend::<Recorder>().unwrap().statements.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn sql_server() {
+        let conn = connect(Dialect::SqlServer);
+        let sql = conn.ow("SELECT name FROM users ORDER BY name");
+        conn.paginate(&sql, 3, 10).unwrap();
+        conn.keyset_page(&sql, "name", Some("O'Reilly"), 10).unwrap();
+        assert!(conn.exists(&sql).unwrap());
+        conn.call("add_user", params!["O'Reilly", conn.boolean(true)]).unwrap();
+        conn.with_transaction(|tx| tx.execute(tx.ow("DELETE FROM users;"))).unwrap();
+        assert_eq!(statements(&conn), [
+            "SELECT name FROM users ORDER BY name OFFSET  20  ROWS FETCH NEXT  11  ROWS ONLY",
+            "SELECT * FROM (SELECT name FROM users ORDER BY name) AS owsql_keyset WHERE  name  >  'O''Reilly'  ORDER BY  name  OFFSET  0  ROWS FETCH NEXT  11  ROWS ONLY",
+            "SELECT CASE WHEN EXISTS(SELECT name FROM users ORDER BY name) THEN 1 ELSE 0 END",
+            "EXEC add_user 'O''Reilly' , 1 ;",
+            "BEGIN TRANSACTION",
+            "DELETE FROM users;",
+            "COMMIT",
+        ]);
+
+        assert_eq!(conn.next_id("orders"), Err(OwsqlError::Message("unsupported sequence".into())));
+        assert_eq!(conn.set_session("NOCOUNT", "ON"), Err(OwsqlError::Message("unsupported session variable: \"NOCOUNT\"".into())));
+        assert_eq!(conn.explain(&sql), Err(OwsqlError::Message("unsupported explain".into())));
+        assert_eq!(conn.execute(conn.ow("SELECT * FROM docs WHERE") + &conn.fts_match("docs", "body", "owsql")),
+            Err(OwsqlError::Message("unsupported full-text search".into())));
+        assert!(statements(&conn).is_empty());
+    }
+
+    #[test]
+    fn oracle() {
+        let conn = connect(Dialect::Oracle);
+        let sql = conn.ow("SELECT name FROM users ORDER BY name");
+        assert_eq!(conn.count(&sql), Ok(1));
+        assert!(conn.exists(&sql).unwrap());
+        conn.paginate(&sql, 1, 10).unwrap();
+        conn.set_session("NLS_DATE_FORMAT", "YYYY-MM-DD").unwrap();
+        assert_eq!(statements(&conn), [
+            "SELECT COUNT(*) FROM (SELECT name FROM users ORDER BY name) owsql_count",
+            "SELECT CASE WHEN EXISTS(SELECT name FROM users ORDER BY name) THEN 1 ELSE 0 END FROM dual",
+            "SELECT name FROM users ORDER BY name OFFSET  0  ROWS FETCH NEXT  11  ROWS ONLY",
+            "ALTER SESSION SET  NLS_DATE_FORMAT  =  'YYYY-MM-DD' ;",
+        ]);
+
+        assert_eq!(conn.with_transaction(|tx| tx.execute(tx.ow("DELETE FROM users;"))),
+            Err(OwsqlError::Message("unsupported transaction".into())));
+        assert_eq!(conn.execute(conn.ow("INSERT INTO users") + &conn.values(&[params!["Alice"]])),
+            Err(OwsqlError::Message("unsupported values".into())));
+        assert!(statements(&conn).is_empty());
+    }
+}
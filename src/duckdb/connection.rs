@@ -0,0 +1,153 @@
+use std::any::Any;
+use std::path::Path;
+
+use duckdb_sys::Statement;
+use duckdb_sys::arrow::array::Array;
+use duckdb_sys::arrow::util::display::{ArrayFormatter, FormatOptions};
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+use crate::lexer::statements;
+
+/// Open a connection to the database at the path.
+pub(crate) fn open<T: AsRef<Path>>(path: T) -> Result<Connection> {
+    if path.as_ref().to_str().is_none_or(|path| path.contains('\0')) {
+        return Err(OwsqlError::Message(format!("invalid path: {:?}", path.as_ref())));
+    }
+    match duckdb_sys::Connection::open(path) {
+        Ok(conn) => Ok(Connection::new(Box::new(Duckdb { conn }))),
+        Err(_) => Err(OwsqlError::Message("failed to connect".into())),
+    }
+}
+
+/// A connection to DuckDB, which is closed with its database on drop.
+struct Duckdb {
+    conn: duckdb_sys::Connection,
+}
+
+impl Duckdb {
+    /// Prepares each statement of the query, and calls `f` with it until it returns `false`.
+    /// The statements are split by the lexer, so that the rows of every statement are read.
+    fn each_statement(&self, query: &str, mut f: impl FnMut(&mut Statement) -> std::result::Result<bool, String>)
+        -> std::result::Result<(), String>
+    {
+        for statement in statements(query, Dialect::Postgres) {
+            let mut prepared = self.conn.prepare(statement).map_err(|e| e.to_string())?;
+            if !f(&mut prepared)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Calls the callback with each row of the executed statement, and return `false` if it stopped the rows.
+fn rows(statement: &Statement, callback: IterateCallback) -> std::result::Result<bool, String> {
+    let columns = statement.column_names();
+    let options = FormatOptions::default();
+    while let Some(chunk) = statement.step().map_err(|e| e.to_string())? {
+        let values = chunk.columns().iter().map(|column| {
+            let formatter = ArrayFormatter::try_new(column.as_ref(), &options).map_err(|e| e.to_string())?;
+            Ok((formatter, column.logical_nulls()))
+        }).collect::<std::result::Result<Vec<_>, String>>()?;
+        for row in 0..chunk.len() {
+            let values = values.iter().map(|(formatter, nulls)| match nulls {
+                Some(nulls) if nulls.is_null(row) => None,
+                _ => Some(formatter.value(row).to_string()),
+            }).collect::<Vec<_>>();
+            let pairs = columns.iter()
+                .zip(&values)
+                .map(|(column, value)| (column.as_str(), value.as_deref()))
+                .collect::<Vec<_>>();
+            if !callback(&pairs) {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Unwraps the query, or return the error of the conversion unless the error level is `AlwaysOk`.
+fn query(query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<Option<String>> {
+    match query {
+        Ok(query) => Ok(Some(query)),
+        Err(_) if *error_level == OwsqlErrorLevel::AlwaysOk => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl OwsqlConn for Duckdb {
+    fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        self._execute_changes(query, error_level).map(|_| ())
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        let query = match self::query(query, error_level)? {
+            Some(query) => query,
+            None => return Ok(0),
+        };
+        let mut changes = 0;
+        let result = self.each_statement(&query, |prepared| {
+            changes = prepared.execute([]).map_err(|e| e.to_string())? as u64;
+            Ok(true)
+        });
+        match result {
+            Ok(()) => Ok(changes),
+            Err(msg) => {
+                OwsqlError::database(error_level, None, None, None, &msg)?;
+                Ok(0)
+            },
+        }
+    }
+
+    fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
+        callback: IterateCallback) -> Result<()>
+    {
+        let query = match self::query(query, error_level)? {
+            Some(query) => query,
+            None => return Ok(()),
+        };
+        let mut stopped = false;
+        let result = self.each_statement(&query, |prepared| {
+            prepared.execute([]).map_err(|e| e.to_string())?;
+            stopped = !rows(prepared, &mut *callback)?;
+            Ok(!stopped)
+        });
+        match result {
+            Err(msg) => OwsqlError::database(error_level, None, None, None, &msg),
+            // The same error as the callback of `sqlite3_exec` aborting the query.
+            Ok(()) if stopped => OwsqlError::database(error_level, None, None, None, "query aborted"),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match self::query(query, error_level)? {
+            Some(query) => query,
+            None => return Ok(()),
+        };
+        match self.each_statement(&query, |_| Ok(true)) {
+            Ok(()) => Ok(()),
+            Err(msg) => OwsqlError::database(error_level, None, None, None, &msg),
+        }
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        EscapeMode::Standard
+    }
+
+    fn charset(&self) -> Charset {
+        Charset::Utf8
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,30 @@
+//! Interface to [DuckDB](https://duckdb.org) of OverwriteSQL.
+//!
+//! The backend uses the [duckdb](https://docs.rs/duckdb) crate, which links the shared library
+//! `libduckdb` of the same version in the directory of `DUCKDB_LIB_DIR`, or downloads it if
+//! `DUCKDB_DOWNLOAD_LIB=1`. DuckDB speaks a dialect close to PostgreSQL, so the connection reports
+//! [Dialect::Postgres](../enum.Dialect.html#variant.Postgres), and the strings are escaped in the
+//! standard way, where a backslash is not an escape character.
+//!
+//! The statements are split at the semicolons and prepared one by one, so that the rows of every
+//! statement are read. The values are read as text by the formatter of
+//! [Arrow](https://docs.rs/arrow), such as `true` for a `BOOLEAN`.
+
+pub(crate) mod connection;
+
+use std::path::Path;
+use crate::Result;
+use crate::connection::Connection;
+
+/// Open a read-write connection to a new or existing database, or an in-memory database by `:memory:`.
+///
+/// # Examples
+///
+/// ```no_run
+/// let conn = owsql::duckdb::open(":memory:").unwrap();
+/// let conn = owsql::duckdb::open("/path/to/analytics.duckdb").unwrap();
+/// ```
+#[inline]
+pub fn open<T: AsRef<Path>>(path: T) -> Result<Connection> {
+    connection::open(path)
+}
@@ -6,10 +6,43 @@ pub enum OwsqlError {
     Message(String),
     /// An any errors.
     AnyError,
+    /// An error reported by the backend.  
+    /// The message follows the error level, and the constraint is omitted on `Release`.
+    ///
+    /// Dialect  | `code`                          | `sqlstate`
+    /// -------- | ------------------------------- | ----------
+    /// SQLite   | The extended result code        | `None`
+    /// Postgres | `None`                          | The SQLSTATE, `08006` if the connection is lost
+    /// MySQL    | The server error code           | The SQLSTATE, `08S01` if the connection is lost
+    Database {
+        /// The numeric error code of the backend.
+        code:       Option<i32>,
+        /// The SQLSTATE of the error.
+        sqlstate:   Option<String>,
+        /// The error message.
+        message:    String,
+        /// The name of the violated constraint. Only Postgres reports it.
+        constraint: Option<String>,
+    },
+    /// The retries of [with_retry](./struct.Connection.html#method.with_retry) are exhausted.
+    RetryExhausted {
+        /// The number of attempts including the first one.
+        attempts: u32,
+        /// The error of the last attempt.
+        error:    Box<OwsqlError>,
+    },
+    /// A trusted fragment is denied by the policy of
+    /// [set_fragment_policy](./struct.Connection.html#method.set_fragment_policy).
+    PolicyViolation {
+        /// The denied keyword.
+        keyword:  String,
+        /// The fragment containing the keyword.
+        fragment: String,
+    },
 }
 
 /// Change the output error message.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum OwsqlErrorLevel {
     /// No error message returned, always return Result::Ok(T).
     AlwaysOk,
@@ -47,11 +80,32 @@ impl OwsqlError {
     }
 }
 
-impl std::string::ToString for OwsqlError {
-    fn to_string(&self) -> String {
+impl OwsqlError {
+    /// Returns the error reported by the backend in the error level.
+    #[allow(unused_variables)]
+    pub(crate) fn database(error_level: &OwsqlErrorLevel, code: Option<i32>, sqlstate: Option<String>,
+        constraint: Option<String>, detail_msg: &str) -> Result<(), OwsqlError>
+    {
+        let (message, constraint) = match error_level {
+            OwsqlErrorLevel::AlwaysOk => return Ok(()),
+            OwsqlErrorLevel::Release  => ("exec error".to_string(), None),
+            OwsqlErrorLevel::Develop  => ("exec error".to_string(), constraint),
+            #[cfg(debug_assertions)]
+            OwsqlErrorLevel::Debug    => (format!("exec error: {}", detail_msg), constraint),
+        };
+        Err(OwsqlError::Database { code, sqlstate, message, constraint })
+    }
+}
+
+impl std::fmt::Display for OwsqlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            OwsqlError::Message(s) => s.to_string(),
-            OwsqlError::AnyError =>   String::from("AnyError"),
+            OwsqlError::Message(s) => write!(f, "{}", s),
+            OwsqlError::AnyError =>   write!(f, "AnyError"),
+            OwsqlError::Database { message, .. } => write!(f, "{}", message),
+            OwsqlError::RetryExhausted { attempts, error } =>
+                write!(f, "retry exhausted after {} attempts: {}", attempts, error),
+            OwsqlError::PolicyViolation { keyword, .. } => write!(f, "policy violation: {}", keyword),
         }
     }
 }
@@ -65,6 +119,21 @@ mod tests {
     fn owsql_error() {
         assert_eq!(OwsqlErrorLevel::default(), OwsqlErrorLevel::Develop);
         assert_eq!(OwsqlError::Message("test".to_string()).to_string(), "test");
+        assert_eq!(
+            OwsqlError::database(&OwsqlErrorLevel::Release, Some(19), None, Some("users_pkey".into()), "detail"),
+            Err(OwsqlError::Database { code: Some(19), sqlstate: None, message: "exec error".into(), constraint: None }));
+        assert_eq!(
+            OwsqlError::database(&OwsqlErrorLevel::Debug, None, Some("23505".into()), Some("users_pkey".into()), "detail"),
+            Err(OwsqlError::Database {
+                code: None, sqlstate: Some("23505".into()), message: "exec error: detail".into(), constraint: Some("users_pkey".into())
+            }));
+        assert_eq!(OwsqlError::database(&OwsqlErrorLevel::AlwaysOk, None, None, None, "detail"), Ok(()));
+        assert_eq!(
+            OwsqlError::RetryExhausted { attempts: 3, error: Box::new(OwsqlError::AnyError) }.to_string(),
+            "retry exhausted after 3 attempts: AnyError");
+        assert_eq!(
+            OwsqlError::PolicyViolation { keyword: "DROP".into(), fragment: "DROP TABLE users".into() }.to_string(),
+            "policy violation: DROP");
         assert_eq!(
             OwsqlError::new(&OwsqlErrorLevel::AlwaysOk, "test", "test"),
             Ok(()));
@@ -0,0 +1,236 @@
+//! Escaping of the values concatenated to trusted fragments.
+//!
+//! [simulate](fn.simulate.html) reproduces the statement a connection would send,
+//! to fuzz and property-test the escaping without a database.
+
+use std::any::Any;
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::dialect::Dialect;
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+
+/// Which characters are escaped in string literals.
+///
+/// The correct mode depends on session settings of the database, such as `sql_mode` of MySQL or
+/// `standard_conforming_strings` of PostgreSQL. It is detected when the connection is opened.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscapeMode {
+    /// Only single quotations are doubled.  
+    /// SQLite, MySQL with `NO_BACKSLASH_ESCAPES` and PostgreSQL with
+    /// `standard_conforming_strings = on`.
+    Standard,
+    /// Single quotations and backslashes are doubled.  
+    /// MySQL without `NO_BACKSLASH_ESCAPES` and PostgreSQL with
+    /// `standard_conforming_strings = off`.
+    Backslash,
+}
+
+impl EscapeMode {
+    pub(crate) fn must_escape(self) -> fn(char) -> bool {
+        match self {
+            EscapeMode::Standard  => |c| c == '\'',
+            EscapeMode::Backslash => |c| c == '\'' || c == '\\',
+        }
+    }
+}
+
+/// The charset in which the database reads statements.
+///
+/// In some multibyte charsets, a backslash (`0x5c`) may be the trailing byte of a character.
+/// The server then swallows the backslash that escapes the next character, so backslash escaping
+/// is not safe for non-ASCII characters in these charsets. It is detected when the connection is opened.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Charset {
+    /// UTF-8, such as `utf8mb4` of MySQL and `UTF8` of PostgreSQL.
+    Utf8,
+    Big5,
+    Cp932,
+    Gb18030,
+    Gbk,
+    Sjis,
+    /// Any other charset, in which a backslash is never a part of a multibyte character.
+    Other,
+}
+
+impl Charset {
+    /// Return the charset of the name used by MySQL or PostgreSQL, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::Charset;
+    /// assert_eq!(Charset::from_name("utf8mb4"), Charset::Utf8);
+    /// assert_eq!(Charset::from_name("SJIS"), Charset::Sjis);
+    /// assert_eq!(Charset::from_name("latin1"), Charset::Other);
+    /// ```
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "utf8" | "utf8mb3" | "utf8mb4" | "unicode" => Charset::Utf8,
+            "big5" | "win950"                          => Charset::Big5,
+            "cp932" | "windows-31j"                    => Charset::Cp932,
+            "gb18030"                                  => Charset::Gb18030,
+            "gbk" | "cp936" | "win936"                 => Charset::Gbk,
+            "sjis" | "shift_jis"                       => Charset::Sjis,
+            _                                          => Charset::Other,
+        }
+    }
+
+    fn has_backslash_trail(self) -> bool {
+        !matches!(self, Charset::Utf8 | Charset::Other)
+    }
+
+    /// Checks an escaped literal, and return the error message if it cannot be sent safely.
+    pub(crate) fn check_literal(self, literal: &str, mode: EscapeMode) -> Option<&'static str> {
+        if literal.contains('\0') {
+            return Some("nul character");
+        }
+        if mode == EscapeMode::Backslash && self.has_backslash_trail() {
+            let mut chars = literal.chars().peekable();
+            while let Some(c) = chars.next() {
+                if !c.is_ascii() && chars.peek() == Some(&'\\') {
+                    return Some("unsafe multibyte character");
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A piece of a statement given to [simulate](fn.simulate.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fragment<'a> {
+    /// A trusted fragment passed to [ow](../struct.Connection.html#method.ow).
+    Ow(&'a str),
+    /// An untrusted string concatenated as it is.
+    Raw(&'a str),
+    /// A value passed to [int](../struct.Connection.html#method.int).
+    Int(&'a str),
+}
+
+/// A backend which only converts statements, and fails to execute them.
+struct Simulator {
+    dialect:     Dialect,
+    escape_mode: EscapeMode,
+    charset:     Charset,
+}
+
+impl OwsqlConn for Simulator {
+    fn _execute(&self, _query: Result<String>, _error_level: &OwsqlErrorLevel) -> Result<()> {
+        Err(OwsqlError::Message("simulated connection".into()))
+    }
+
+    fn _execute_changes(&self, _query: Result<String>, _error_level: &OwsqlErrorLevel) -> Result<u64> {
+        Err(OwsqlError::Message("simulated connection".into()))
+    }
+
+    fn _iterate(&self, _query: Result<String>, _error_level: &OwsqlErrorLevel, _callback: IterateCallback) -> Result<()> {
+        Err(OwsqlError::Message("simulated connection".into()))
+    }
+
+    fn _validate(&self, _query: Result<String>, _error_level: &OwsqlErrorLevel) -> Result<()> {
+        Err(OwsqlError::Message("simulated connection".into()))
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        self.escape_mode
+    }
+
+    fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Return the SQL a SQLite connection would send for the fragments concatenated in order,
+/// or the error it would return, without a database.
+/// Same as [simulate_with](fn.simulate_with.html) with `Dialect::Sqlite`, `EscapeMode::Standard`
+/// and `Charset::Utf8`.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::escape::{Fragment, simulate};
+/// let sql = simulate(&[Fragment::Ow("SELECT * FROM users WHERE name ="), Fragment::Raw("' OR 1=1; --")]);
+/// assert_eq!(sql.unwrap(), "SELECT * FROM users WHERE name = ''' OR 1=1; --' ");
+/// assert!(simulate(&[Fragment::Ow("LIMIT"), Fragment::Int("1 OR 1=1")]).is_err());
+/// ```
+pub fn simulate(fragments: &[Fragment<'_>]) -> Result<String> {
+    simulate_with(fragments, Dialect::Sqlite, EscapeMode::Standard, Charset::Utf8)
+}
+
+/// Return the SQL a connection of the dialect and the session settings would send for the
+/// fragments concatenated in order, or the error it would return on `Develop`.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{Charset, Dialect, EscapeMode};
+/// use owsql::escape::{Fragment, simulate_with};
+/// let fragments = [Fragment::Ow("SELECT"), Fragment::Raw(r"\' OR 1=1; --")];
+/// let sql = simulate_with(&fragments, Dialect::Mysql, EscapeMode::Backslash, Charset::Utf8);
+/// assert_eq!(sql.unwrap(), r"SELECT '\\'' OR 1=1; --' ");
+/// ```
+pub fn simulate_with(fragments: &[Fragment<'_>], dialect: Dialect, escape_mode: EscapeMode, charset: Charset) -> Result<String> {
+    let conn = Connection::new(Box::new(Simulator { dialect, escape_mode, charset }));
+    let mut stmt = String::new();
+    for fragment in fragments {
+        match fragment {
+            Fragment::Ow(s)  => stmt.push_str(&conn.ow_trusted(s.to_string())),
+            Fragment::Raw(s) => stmt.push_str(s),
+            Fragment::Int(s) => stmt.push_str(&conn.int(*s)),
+        }
+    }
+    conn.actual_sql(&stmt)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn must_escape() {
+        let standard = EscapeMode::Standard.must_escape();
+        let backslash = EscapeMode::Backslash.must_escape();
+        assert!(standard('\''));
+        assert!(!standard('\\'));
+        assert!(!standard('"'));
+        assert!(backslash('\''));
+        assert!(backslash('\\'));
+        assert!(!backslash('"'));
+    }
+
+    #[test]
+    fn simulate() {
+        assert_eq!(super::simulate(&[]), Ok(String::new()));
+        assert_eq!(
+            super::simulate(&[Fragment::Ow("SELECT"), Fragment::Raw("a"), Fragment::Ow(","), Fragment::Int("42")]),
+            Ok("SELECT 'a' , 42 ".to_string())
+        );
+        assert_eq!(super::simulate(&[Fragment::Ow("SELECT 'a")]), Err(OwsqlError::Message("invalid literal".into())));
+        let fragments = [Fragment::Ow("SELECT"), Fragment::Raw("\u{7e3a}\\'")];
+        assert_eq!(
+            simulate_with(&fragments, Dialect::Mysql, EscapeMode::Backslash, Charset::Gbk),
+            Err(OwsqlError::Message("unsafe multibyte character".into()))
+        );
+    }
+
+    #[test]
+    fn check_literal() {
+        use EscapeMode::*;
+        assert_eq!(Charset::Utf8.check_literal("'foo'",            Backslash), None);
+        assert_eq!(Charset::Utf8.check_literal("'f\0o'",           Standard),  Some("nul character"));
+        assert_eq!(Charset::Utf8.check_literal("'\u{7e3a}\\\\'", Backslash), None);
+        assert_eq!(Charset::Gbk.check_literal("'\u{7e3a}\\\\'",  Standard),  None);
+        assert_eq!(Charset::Gbk.check_literal("'\u{7e3a}\\\\'",  Backslash), Some("unsafe multibyte character"));
+        assert_eq!(Charset::Gbk.check_literal("'\u{7e3a}'''",     Backslash), None);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::replica::ReplicaSet;
+use crate::row::Row;
+use crate::transaction::Transaction;
+
+/// The methods to build and execute statements, shared by [Connection](./struct.Connection.html),
+/// [Transaction](./struct.Transaction.html) and [ReplicaSet](./struct.ReplicaSet.html),
+/// so that a function can be written once and run inside or outside a transaction.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::Executor;
+///
+/// fn add_user<E: Executor>(db: &E, name: &str) -> owsql::Result<()> {
+///     db.execute(db.ow("INSERT INTO users VALUES(") + name + &db.ow(");"))
+/// }
+///
+/// # let conn = owsql::sqlite::open(":memory:").unwrap();
+/// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+/// add_user(&conn, "Alice").unwrap();
+/// conn.with_transaction(|tx| add_user(tx, "Bob")).unwrap();
+/// assert_eq!(conn.rows(conn.ow("SELECT * FROM users;")).unwrap().len(), 2);
+/// ```
+pub trait Executor {
+    /// See [Connection::ow](./struct.Connection.html#method.ow).
+    #[track_caller]
+    fn ow<T: ?Sized + ToString>(&self, s: &'static T) -> String;
+    /// See [Connection::int](./struct.Connection.html#method.int).
+    fn int<T: Clone + ToString>(&self, value: T) -> String;
+    /// See [Connection::execute](./struct.Connection.html#method.execute).
+    fn execute<T: AsRef<str>>(&self, query: T) -> Result<()>;
+    /// See [Connection::iterate](./struct.Connection.html#method.iterate).
+    fn iterate<T: AsRef<str>, F>(&self, query: T, callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool;
+    /// See [Connection::rows](./struct.Connection.html#method.rows).
+    fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>>;
+}
+
+impl Executor for Connection {
+    #[track_caller]
+    fn ow<T: ?Sized + ToString>(&self, s: &'static T) -> String {
+        Connection::ow(self, s)
+    }
+
+    fn int<T: Clone + ToString>(&self, value: T) -> String {
+        Connection::int(self, value)
+    }
+
+    fn execute<T: AsRef<str>>(&self, query: T) -> Result<()> {
+        Connection::execute(self, query)
+    }
+
+    fn iterate<T: AsRef<str>, F>(&self, query: T, callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    {
+        Connection::iterate(self, query, callback)
+    }
+
+    fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>> {
+        Connection::rows(self, query)
+    }
+}
+
+impl Executor for Transaction<'_> {
+    #[track_caller]
+    fn ow<T: ?Sized + ToString>(&self, s: &'static T) -> String {
+        Connection::ow(self, s)
+    }
+
+    fn int<T: Clone + ToString>(&self, value: T) -> String {
+        Connection::int(self, value)
+    }
+
+    fn execute<T: AsRef<str>>(&self, query: T) -> Result<()> {
+        Connection::execute(self, query)
+    }
+
+    fn iterate<T: AsRef<str>, F>(&self, query: T, callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    {
+        Connection::iterate(self, query, callback)
+    }
+
+    fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>> {
+        Connection::rows(self, query)
+    }
+}
+
+impl Executor for ReplicaSet {
+    #[track_caller]
+    fn ow<T: ?Sized + ToString>(&self, s: &'static T) -> String {
+        Connection::ow(self, s)
+    }
+
+    fn int<T: Clone + ToString>(&self, value: T) -> String {
+        Connection::int(self, value)
+    }
+
+    fn execute<T: AsRef<str>>(&self, query: T) -> Result<()> {
+        ReplicaSet::execute(self, query)
+    }
+
+    fn iterate<T: AsRef<str>, F>(&self, query: T, callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    {
+        ReplicaSet::iterate(self, query, callback)
+    }
+
+    fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>> {
+        ReplicaSet::rows(self, query)
+    }
+}
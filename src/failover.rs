@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::Result;
+use crate::error::OwsqlError;
+
+/// The health of a host of a connection configuration, updated each time a connection is opened.
+/// See `host_status` of `postgres::Config` and `mysql::Config`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostStatus {
+    /// The host name or the IP address.
+    pub host:         String,
+    /// Whether the last connection succeeded, or `None` if it has not been tried.
+    pub healthy:      Option<bool>,
+    /// The number of consecutive failures.
+    pub failures:     u64,
+    /// The error of the last failure.
+    pub last_error:   Option<String>,
+    /// When the host was last tried.
+    pub last_checked: Option<Instant>,
+}
+
+impl HostStatus {
+    fn new(host: &str) -> Self {
+        Self {
+            host:         host.to_string(),
+            healthy:      None,
+            failures:     0,
+            last_error:   None,
+            last_checked: None,
+        }
+    }
+}
+
+/// The hosts of a configuration and their health, shared by the clones of the configuration.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Hosts {
+    hosts:       Vec<String>,
+    round_robin: bool,
+    state:       Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    statuses: Vec<HostStatus>,
+    next:     usize,
+}
+
+impl Hosts {
+    pub(crate) fn push(&mut self, host: &str) {
+        self.hosts.push(host.to_string());
+    }
+
+    pub(crate) fn set_round_robin(&mut self, round_robin: bool) {
+        self.round_robin = round_robin;
+    }
+
+    pub(crate) fn hosts(&self) -> &[String] {
+        &self.hosts
+    }
+
+    /// Connects to the hosts in order until it succeeds, and return the last error if all fail.
+    /// The hosts that failed last time are tried after the others.
+    /// With round-robin, the first host to try rotates on each call.
+    /// If there are no hosts, `connect` is called with `None`.
+    pub(crate) fn connect<T>(&self, mut connect: impl FnMut(Option<&str>) -> Result<T>) -> Result<T> {
+        if self.hosts.is_empty() {
+            return connect(None);
+        }
+
+        let mut order = {
+            let mut state = self.state.lock().unwrap();
+            let start = if self.round_robin {
+                state.next = state.next.wrapping_add(1);
+                state.next.wrapping_sub(1) % self.hosts.len()
+            } else {
+                0
+            };
+            let mut order = self.hosts.iter().cycle().skip(start).take(self.hosts.len()).cloned().collect::<Vec<_>>();
+            order.sort_by_key(|host| state.status(host).healthy == Some(false));
+            order
+        };
+
+        let mut last_error = OwsqlError::Message("failed to open: no host".into());
+        for host in order.drain(..) {
+            let result = connect(Some(&host));
+            let mut state = self.state.lock().unwrap();
+            let status = state.status_mut(&host);
+            status.last_checked = Some(Instant::now());
+            match result {
+                Ok(conn) => {
+                    status.healthy = Some(true);
+                    status.failures = 0;
+                    return Ok(conn);
+                },
+                Err(e) => {
+                    status.healthy = Some(false);
+                    status.failures += 1;
+                    status.last_error = Some(e.to_string());
+                    last_error = e;
+                },
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Return the status of each host in the order they were added.
+    pub(crate) fn statuses(&self) -> Vec<HostStatus> {
+        let state = self.state.lock().unwrap();
+        self.hosts.iter().map(|host| state.status(host)).collect()
+    }
+}
+
+impl State {
+    fn status(&self, host: &str) -> HostStatus {
+        self.statuses.iter().find(|status| status.host == host).cloned().unwrap_or_else(|| HostStatus::new(host))
+    }
+
+    fn status_mut(&mut self, host: &str) -> &mut HostStatus {
+        match self.statuses.iter().position(|status| status.host == host) {
+            Some(i) => &mut self.statuses[i],
+            None => {
+                self.statuses.push(HostStatus::new(host));
+                self.statuses.last_mut().unwrap()
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect(hosts: &Hosts, down: &[&str]) -> Vec<String> {
+        let mut tried = Vec::new();
+        let _ = hosts.connect(|host| {
+            let host = host.unwrap().to_string();
+            tried.push(host.clone());
+            if down.contains(&host.as_str()) {
+                Err(OwsqlError::Message(format!("failed to open: {}", host)))
+            } else {
+                Ok(())
+            }
+        });
+        tried
+    }
+
+    #[test]
+    fn failover() {
+        let mut hosts = Hosts::default();
+        hosts.push("a");
+        hosts.push("b");
+        hosts.push("c");
+        assert_eq!(connect(&hosts, &["a"]), ["a", "b"]);
+        // The unhealthy host is tried last.
+        assert_eq!(connect(&hosts, &[]), ["b"]);
+        assert_eq!(connect(&hosts, &["b", "c"]), ["b", "c", "a"]);
+
+        let statuses = hosts.statuses();
+        assert_eq!(statuses[0].healthy, Some(true));
+        assert_eq!(statuses[1].failures, 1);
+        assert_eq!(statuses[2].last_error.as_deref(), Some("failed to open: c"));
+        assert_eq!(connect(&hosts, &["a", "b", "c"]).len(), 3);
+        assert_eq!(hosts.clone().statuses()[0].failures, 1);
+    }
+
+    #[test]
+    fn round_robin() {
+        let mut hosts = Hosts::default();
+        hosts.push("a");
+        hosts.push("b");
+        hosts.set_round_robin(true);
+        assert_eq!(connect(&hosts, &[]), ["a"]);
+        assert_eq!(connect(&hosts, &[]), ["b"]);
+        assert_eq!(connect(&hosts, &[]), ["a"]);
+        assert!(Hosts::default().connect(|host| {
+            assert!(host.is_none());
+            Ok(())
+        }).is_ok());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::lexer::lex;
+use crate::token::Token;
+
+/// Return the fingerprint of the statement, in which the literals are replaced with `?`, so that
+/// the statements differing only in their values are grouped together, such as for the metrics or
+/// the detection of duplicate queries.
+///
+/// The statement is the expanded SQL, such as [actual_sql](./struct.Connection.html#method.actual_sql).
+/// The strings, the numbers and the placeholders become `?`, and a list of them such as `IN (1, 2, 3)`
+/// becomes a single `?`. The keywords are uppercased, the comments are removed, the tokens are
+/// separated by single spaces, and the trailing `;` is removed.
+///
+/// # Examples
+///
+/// ```
+/// let a = owsql::fingerprint("select * from users where name = 'Alice' and age in (1, 2, 3);");
+/// let b = owsql::fingerprint("SELECT *\n  FROM users -- by name\n WHERE name = 'Bob' AND age IN (4)");
+/// assert_eq!(a, "SELECT * FROM users WHERE name = ? AND age IN (?)");
+/// assert_eq!(a, b);
+/// ```
+pub fn fingerprint(expanded_sql: &str) -> String {
+    let mut tokens = Vec::new();
+    lex(expanded_sql, &mut tokens);
+    let mut words: Vec<String> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let word = match token {
+            Token::Keyword(keyword) => keyword.to_ascii_uppercase(),
+            Token::Number(_) | Token::QuotedString(_) | Token::Literal(_) => "?".to_string(),
+            Token::Symbol(symbol) if symbol == "?" => symbol,
+            // A negative number is a single value.
+            Token::Symbol(symbol) if symbol == "-" && is_value_position(&words) => continue,
+            Token::Identifier(identifier) | Token::Symbol(identifier) => identifier,
+        };
+        // `?, ?` is folded into `?`.
+        let len = words.len();
+        if word == "?" && len >= 2 && words[len - 1] == "," && words[len - 2] == "?" {
+            words.pop();
+            continue;
+        }
+        words.push(word);
+    }
+    while words.last().is_some_and(|word| word == ";") {
+        words.pop();
+    }
+    let mut fingerprint = String::new();
+    for (i, word) in words.iter().enumerate() {
+        let glued = matches!(word.as_str(), "," | ")" | ";") || i > 0 && words[i - 1] == "(";
+        if i > 0 && !glued {
+            fingerprint.push(' ');
+        }
+        fingerprint.push_str(word);
+    }
+    fingerprint
+}
+
+/// Checks if a value, rather than an operator, is expected after the words.
+fn is_value_position(words: &[String]) -> bool {
+    match words.last() {
+        None => true,
+        Some(word) => word != "?" && word != ")" && (word.chars().all(|c| !c.is_alphanumeric() && c != '_')
+            || crate::lexer::is_keyword(word)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::fingerprint;
+
+    #[test]
+    fn literals() {
+        assert_eq!(fingerprint("SELECT 'a''b', \"Name\", E'\\n', $$x$$, 4.2, -1 FROM t WHERE a = - 2 AND b - 3 > c"),
+            "SELECT ?, \"Name\", ? FROM t WHERE a = ? AND b - ? > c");
+        assert_eq!(fingerprint("INSERT INTO t VALUES (1, 'a'), (2, 'b');"), "INSERT INTO t VALUES (?), (?)");
+        assert_eq!(fingerprint("SELECT ? , ? "), "SELECT ?");
+        assert_eq!(fingerprint("SELECT 1 ;;"), "SELECT ?");
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        assert_eq!(fingerprint("  select /* hint */ id\n\tfrom users -- comment\n"), "SELECT id FROM users");
+        assert_eq!(fingerprint("SELECT COUNT( * ) FROM t"), "SELECT COUNT (*) FROM t");
+        assert_eq!(fingerprint(""), "");
+    }
+}
@@ -0,0 +1,155 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::row::{Row, RowError, parse_bool};
+
+/// A type converted from the text of a column by [FromRow](./trait.FromRow.html).
+pub trait FromColumn: Sized {
+    /// Converts the value of a column, where `None` is `NULL`, and return `None` if it cannot be converted.
+    fn from_column(value: Option<&str>) -> Option<Self>;
+}
+
+impl FromColumn for String {
+    fn from_column(value: Option<&str>) -> Option<Self> {
+        value.map(str::to_string)
+    }
+}
+
+impl FromColumn for bool {
+    fn from_column(value: Option<&str>) -> Option<Self> {
+        parse_bool(value?)
+    }
+}
+
+macro_rules! impl_from_column_parse {
+    ( $( $ty:ty ),* ) => {
+        $(
+            impl FromColumn for $ty {
+                fn from_column(value: Option<&str>) -> Option<Self> {
+                    value?.trim().parse().ok()
+                }
+            }
+        )*
+    };
+}
+impl_from_column_parse!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(value: Option<&str>) -> Option<Self> {
+        match value {
+            Some(value) => T::from_column(Some(value)).map(Some),
+            None => Some(None),
+        }
+    }
+}
+
+/// A type converted from a result row, such as a tuple whose elements are the columns by position.
+/// See [query_as](./struct.Connection.html#method.query_as).
+///
+/// It is implemented for the tuples of up to 12 [FromColumn](./trait.FromColumn.html) elements.
+/// The extra columns are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{FromRow, Row, RowError};
+/// let row = Row::from_pairs(&[("name", Some("Alice")), ("age", Some("42")), ("email", None)]);
+/// assert_eq!(<(String, i64, Option<String>)>::from_row(&row), Ok(("Alice".to_string(), 42, None)));
+/// assert_eq!(<(i64,)>::from_row(&row), Err(RowError::InvalidValue("name".to_string())));
+/// ```
+pub trait FromRow: Sized {
+    /// Converts the row.
+    fn from_row(row: &Row) -> std::result::Result<Self, RowError>;
+}
+
+/// Converts the column at the position, which is named by the position if it is missing.
+fn column<T: FromColumn>(row: &Row, index: usize) -> std::result::Result<T, RowError> {
+    let name = row.column_name(index).ok_or_else(|| RowError::MissingColumn(index.to_string()))?;
+    let value = row.get_at(index);
+    T::from_column(value).ok_or_else(|| match value {
+        Some(_) => RowError::InvalidValue(name.to_string()),
+        None => RowError::Null(name.to_string()),
+    })
+}
+
+macro_rules! impl_from_row_tuple {
+    ( $( ( $( $ty:ident $index:tt ),+ ) )* ) => {
+        $(
+            impl<$( $ty: FromColumn ),+> FromRow for ( $( $ty, )+ ) {
+                fn from_row(row: &Row) -> std::result::Result<Self, RowError> {
+                    Ok(( $( column::<$ty>(row, $index)?, )+ ))
+                }
+            }
+        )*
+    };
+}
+impl_from_row_tuple! {
+    (A 0)
+    (A 0, B 1)
+    (A 0, B 1, C 2)
+    (A 0, B 1, C 2, D 3)
+    (A 0, B 1, C 2, D 3, E 4)
+    (A 0, B 1, C 2, D 3, E 4, F 5)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10)
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11)
+}
+
+impl Connection {
+    /// Execute a statement and converts the rows, such as into tuples of the columns by position
+    /// for an ad-hoc query. See [FromRow](./trait.FromRow.html).
+    ///
+    /// # Errors
+    ///
+    /// A row which cannot be converted returns the error "invalid row" with the reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER, score REAL);
+    ///                       INSERT INTO users VALUES ('Alice', 42, 1.5), ('Bob', 69, NULL);")).unwrap();
+    /// let users = conn.query_as::<(String, i64, Option<f64>)>(conn.ow("SELECT name, age, score FROM users;")).unwrap();
+    /// assert_eq!(users, vec![("Alice".to_string(), 42, Some(1.5)), ("Bob".to_string(), 69, None)]);
+    /// ```
+    pub fn query_as<T: FromRow>(&self, query: impl AsRef<str>) -> Result<Vec<T>> {
+        let rows = self.rows(query)?;
+        let mut converted = Vec::with_capacity(rows.len());
+        for row in &rows {
+            match T::from_row(row) {
+                Ok(value) => converted.push(value),
+                Err(e) => {
+                    OwsqlError::new(&self.error_level, "invalid row", &e.to_string())?;
+                    return Ok(Vec::new());
+                },
+            }
+        }
+        Ok(converted)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::FromRow;
+    use crate::row::{Row, RowError};
+
+    #[test]
+    fn tuples() {
+        let row = Row::from_pairs(&[("name", Some("Alice")), ("age", Some("42")), ("score", None), ("admin", Some("t"))]);
+        assert_eq!(<(String,)>::from_row(&row), Ok(("Alice".to_string(),)));
+        assert_eq!(<(String, u8, Option<f64>, bool)>::from_row(&row), Ok(("Alice".to_string(), 42, None, true)));
+        assert_eq!(<(String, String)>::from_row(&row), Ok(("Alice".to_string(), "42".to_string())));
+    }
+
+    #[test]
+    fn errors() {
+        let row = Row::from_pairs(&[("name", Some("Alice")), ("score", None)]);
+        assert_eq!(<(i64,)>::from_row(&row), Err(RowError::InvalidValue("name".to_string())));
+        assert_eq!(<(String, f64)>::from_row(&row), Err(RowError::Null("score".to_string())));
+        assert_eq!(<(String, Option<f64>, String)>::from_row(&row), Err(RowError::MissingColumn("2".to_string())));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::lexer::is_identifier;
+
+/// Converts a search string into an FTS5 query of the terms quoted as strings, so that the
+/// operators and the special characters of the user are searched as they are.
+fn fts5_query(column: &str, query: &str) -> Option<String> {
+    let terms = query.split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{} : ({})", column, terms.join(" ")))
+}
+
+impl Connection {
+    /// It is guaranteed to be a full-text search condition which matches the rows of the column
+    /// containing all the words of the search string. The search string is escaped, and its operators
+    /// and special characters such as `"`, `*` and `AND` are searched as words.  
+    /// A search string without words returns the error "empty search query" when the statement is executed.
+    ///
+    /// Dialect  | Condition
+    /// -------- | ---------
+    /// SQLite   | `"table" MATCH 'column : ("a" "b")'` of an FTS5 table
+    /// Postgres | `"table"."column" @@ plainto_tsquery('a b')` of a `tsvector` or a text column
+    /// MySQL    | ``MATCH (`table`.`column`) AGAINST ('a b' IN NATURAL LANGUAGE MODE)`` of a `FULLTEXT` index
+    ///
+    /// SQL Server and Oracle return the error "unsupported full-text search".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE VIRTUAL TABLE docs USING fts5(title, body);
+    ///                       INSERT INTO docs VALUES ('owsql', 'Overwrite the trusted fragments.');")).unwrap();
+    /// let sql = conn.ow("SELECT title FROM docs WHERE") + &conn.fts_match("docs", "body", "trusted OR 1=1 \"");
+    /// assert_eq!(conn.count(&sql), Ok(0));
+    /// let sql = conn.ow("SELECT title FROM docs WHERE") + &conn.fts_match("docs", "body", "trusted overwrite");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get("title"), Some("owsql"));
+    /// ```
+    pub fn fts_match(&self, table: &str, column: &str, query: &str) -> String {
+        if !is_identifier(table) || !is_identifier(column) {
+            return self.ow_error("invalid identifier", &format!("{}.{}", table, column));
+        }
+        let fts5 = match fts5_query(column, query) {
+            Some(fts5) => fts5,
+            None => return self.ow_error("empty search query", query),
+        };
+        match self.dialect() {
+            Dialect::Sqlite => self.ow_generated(format!("\"{}\" MATCH", table)) + &fts5,
+            Dialect::Postgres => {
+                self.ow_generated(format!("\"{}\".\"{}\" @@ plainto_tsquery(", table, column))
+                    + query
+                    + &self.ow_generated(")".to_string())
+            },
+            Dialect::Mysql => {
+                self.ow_generated(format!("MATCH (`{}`.`{}`) AGAINST (", table, column))
+                    + query
+                    + &self.ow_generated("IN NATURAL LANGUAGE MODE)".to_string())
+            },
+            Dialect::SqlServer | Dialect::Oracle => self.ow_error("unsupported full-text search", query),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn fts5_query() {
+        assert_eq!(super::fts5_query("body", "hello world"), Some(r#"body : ("hello" "world")"#.to_string()));
+        assert_eq!(super::fts5_query("body", r#"it's "quoted" OR NEAR(a b) *"#),
+            Some(r#"body : ("it's" """quoted""" "OR" "NEAR(a" "b)")"#.to_string()));
+        assert_eq!(super::fts5_query("body", " - * \" "), None);
+    }
+}
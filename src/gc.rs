@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+/// Statistics of the overwrite definitions registered in a connection.
+/// See [overwrite_stats](./struct.Connection.html#method.overwrite_stats).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OverwriteStats {
+    /// Number of definitions registered by `ow`, `int`, `allowlist` and so on.
+    pub overwrite:         usize,
+    /// Number of definitions registered by `whitespace_around`.
+    pub whitespace_around: usize,
+    /// Number of registered error definitions.
+    pub error_msg:         usize,
+    /// Number of definitions that can be evicted.
+    pub evictable:         usize,
+    /// Total number of evicted definitions.
+    pub evicted:           u64,
+    /// Number of overwrite strings found registered when a statement is converted.
+    /// Counted only if the eviction policy is `Rederive`.
+    pub hits:              u64,
+    /// Number of evicted overwrite strings derived again when a statement is converted.
+    pub misses:            u64,
+}
+
+/// What happens to a statement built before its definitions were evicted.
+/// See [set_eviction_policy](./struct.Connection.html#method.set_eviction_policy).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EvictionPolicy {
+    /// The statement fails with a token collision.
+    #[default]
+    Fail,
+    /// The evictable overwrite strings carry their values with the HMAC of the connection key,
+    /// so that the evicted values are derived again and the statement still executes.
+    /// The overwrite strings are longer, in proportion to the values.
+    Rederive,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum Map {
+    Overwrite,
+    WhitespaceAround,
+}
+
+/// Tracks the definitions registered from runtime values, which can be evicted.  
+/// Definitions of static fragments and allowlists are never tracked.
+pub(crate) struct Gc {
+    tick:      u64,
+    mark:      u64,
+    capacity:  usize,
+    evicted:   u64,
+    hits:      u64,
+    misses:    u64,
+    policy:    EvictionPolicy,
+    last_used: HashMap<(Map, String), u64>,
+}
+
+impl Default for Gc {
+    fn default() -> Self {
+        Self {
+            tick:      0,
+            mark:      0,
+            capacity:  usize::MAX,
+            evicted:   0,
+            hits:      0,
+            misses:    0,
+            policy:    EvictionPolicy::default(),
+            last_used: HashMap::new(),
+        }
+    }
+}
+
+impl Gc {
+    /// Record a use of a definition. It is tracked if it was just inserted.
+    pub fn used(&mut self, map: Map, key: &str, inserted: bool) {
+        let key = (map, key.to_string());
+        if inserted || self.last_used.contains_key(&key) {
+            self.tick += 1;
+            self.last_used.insert(key, self.tick);
+        }
+    }
+
+    /// Stop tracking a definition, because it is also used as a static fragment.
+    pub fn pin(&mut self, map: Map, key: &str) {
+        self.last_used.remove(&(map, key.to_string()));
+    }
+
+    /// The most recently used definition is always kept.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.last_used.len()
+    }
+
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    pub fn hit(&mut self) {
+        self.hits += 1;
+    }
+
+    pub fn miss(&mut self) {
+        self.misses += 1;
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Return the least recently used definitions exceeding the capacity.
+    pub fn overflow(&mut self) -> Vec<(Map, String)> {
+        if self.last_used.len() <= self.capacity {
+            return Vec::new();
+        }
+        let mut entries = self.last_used.iter().map(|(k, v)| (k.clone(), *v)).collect::<Vec<_>>();
+        entries.sort_by_key(|(_, tick)| *tick);
+        let overflow = entries.len() - self.capacity;
+        self.remove(entries.into_iter().take(overflow).map(|(k, _)| k).collect())
+    }
+
+    /// Return the definitions not used since the previous collection.
+    pub fn collect(&mut self) -> Vec<(Map, String)> {
+        let mark = self.mark;
+        self.mark = self.tick;
+        let entries = self.last_used.iter()
+            .filter(|(_, tick)| **tick <= mark)
+            .map(|(k, _)| k.clone())
+            .collect();
+        self.remove(entries)
+    }
+
+    fn remove(&mut self, entries: Vec<(Map, String)>) -> Vec<(Map, String)> {
+        for entry in entries.iter() {
+            self.last_used.remove(entry);
+        }
+        self.evicted += entries.len() as u64;
+        entries
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow() {
+        let mut gc = Gc::default();
+        gc.used(Map::Overwrite, "a", true);
+        gc.used(Map::Overwrite, "b", true);
+        gc.used(Map::WhitespaceAround, "c", true);
+        gc.used(Map::Overwrite, "a", false);
+        gc.used(Map::Overwrite, "static", false);
+        assert_eq!(gc.len(), 3);
+        assert!(gc.overflow().is_empty());
+        gc.set_capacity(0);
+        assert_eq!(gc.overflow(), vec![(Map::Overwrite, "b".to_string()), (Map::WhitespaceAround, "c".to_string())]);
+        assert_eq!(gc.len(), 1);
+        assert_eq!(gc.evicted(), 2);
+    }
+
+    #[test]
+    fn collect() {
+        let mut gc = Gc::default();
+        gc.used(Map::Overwrite, "a", true);
+        gc.used(Map::Overwrite, "b", true);
+        gc.pin(Map::Overwrite, "b");
+        assert!(gc.collect().is_empty());
+        gc.used(Map::Overwrite, "c", true);
+        assert_eq!(gc.collect(), vec![(Map::Overwrite, "a".to_string())]);
+        assert_eq!(gc.collect(), vec![(Map::Overwrite, "c".to_string())]);
+        assert_eq!(gc.len(), 0);
+    }
+}
@@ -0,0 +1,295 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+
+/// A token of WKT.
+#[derive(Clone, Debug, PartialEq)]
+enum Wkt<'a> {
+    Word(&'a str),
+    Number(f64),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Splits WKT into the tokens, or return `None` if it has an unexpected character.
+fn tokenize(wkt: &str) -> Option<Vec<Wkt<'_>>> {
+    let mut tokens = Vec::new();
+    let mut chars: Peekable<CharIndices> = wkt.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '(' => tokens.push(Wkt::Open),
+            ')' => tokens.push(Wkt::Close),
+            ',' => tokens.push(Wkt::Comma),
+            c if c.is_ascii_whitespace() => (),
+            c if c.is_ascii_alphabetic() => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                tokens.push(Wkt::Word(&wkt[start..end]));
+            },
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    let exponent_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                    if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign) {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                let number = number.parse::<f64>().ok()?;
+                if !number.is_finite() {
+                    return None;
+                }
+                tokens.push(Wkt::Number(number));
+            },
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+/// A recursive descent parser of WKT, which checks the syntax and the number of the points.
+struct Parser<'a> {
+    tokens: Vec<Wkt<'a>>,
+    pos:    usize,
+    /// The number of the coordinates of a point, which is fixed by the first point without a dimension.
+    dims:   Option<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Wkt<'a>> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Wkt<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, token: Wkt<'_>) -> Option<()> {
+        if self.next()? == token { Some(()) } else { None }
+    }
+
+    /// Parses a comma-separated list in parentheses, and return the number of the elements.
+    fn list(&mut self, mut element: impl FnMut(&mut Self) -> Option<()>) -> Option<usize> {
+        self.expect(Wkt::Open)?;
+        let mut count = 0;
+        loop {
+            element(self)?;
+            count += 1;
+            match self.next()? {
+                Wkt::Comma => continue,
+                Wkt::Close => return Some(count),
+                _ => return None,
+            }
+        }
+    }
+
+    fn point(&mut self) -> Option<()> {
+        let mut dims = 0;
+        while let Some(Wkt::Number(_)) = self.peek() {
+            self.next();
+            dims += 1;
+        }
+        if !(2..=4).contains(&dims) || *self.dims.get_or_insert(dims) != dims {
+            return None;
+        }
+        Some(())
+    }
+
+    fn line_string(&mut self) -> Option<()> {
+        if self.list(Self::point)? < 2 { None } else { Some(()) }
+    }
+
+    fn ring(&mut self) -> Option<()> {
+        let start = self.pos;
+        let points = self.list(Self::point)?;
+        let dims = self.dims?;
+        let first = &self.tokens[start + 1..start + 1 + dims];
+        let last = &self.tokens[self.pos - 1 - dims..self.pos - 1];
+        if points < 4 || first != last { None } else { Some(()) }
+    }
+
+    fn polygon(&mut self) -> Option<()> {
+        self.list(Self::ring).map(|_| ())
+    }
+
+    /// Parses a point of `MULTIPOINT`, which may be in parentheses.
+    fn multi_point(&mut self) -> Option<()> {
+        if self.peek() == Some(&Wkt::Open) {
+            self.list(Self::point).filter(|&count| count == 1).map(|_| ())
+        } else {
+            self.point()
+        }
+    }
+
+    fn geometry(&mut self) -> Option<()> {
+        let tag = match self.next()? {
+            Wkt::Word(tag) => tag.to_ascii_uppercase(),
+            _ => return None,
+        };
+        let mut word = self.peek().cloned();
+        if let Some(Wkt::Word(dim)) = word {
+            let dims = match dim.to_ascii_uppercase().as_str() {
+                "Z" | "M" => Some(3),
+                "ZM"      => Some(4),
+                _         => None,
+            };
+            if let Some(dims) = dims {
+                if *self.dims.get_or_insert(dims) != dims {
+                    return None;
+                }
+                self.next();
+                word = self.peek().cloned();
+            }
+        }
+        if let Some(Wkt::Word(empty)) = word {
+            if empty.eq_ignore_ascii_case("EMPTY") {
+                self.next();
+                return Some(());
+            }
+            return None;
+        }
+        match tag.as_str() {
+            "POINT"              => self.list(Self::point).filter(|&count| count == 1).map(|_| ()),
+            "LINESTRING"         => self.line_string(),
+            "POLYGON"            => self.polygon(),
+            "MULTIPOINT"         => self.list(Self::multi_point).map(|_| ()),
+            "MULTILINESTRING"    => self.list(Self::line_string).map(|_| ()),
+            "MULTIPOLYGON"       => self.list(Self::polygon).map(|_| ()),
+            "GEOMETRYCOLLECTION" => self.list(Self::geometry).map(|_| ()),
+            _                    => None,
+        }
+    }
+}
+
+/// Checks if the string is a well-formed WKT geometry.
+fn is_valid_wkt(wkt: &str) -> bool {
+    let tokens = match tokenize(wkt) {
+        Some(tokens) => tokens,
+        None => return false,
+    };
+    let mut parser = Parser { tokens, pos: 0, dims: None };
+    parser.geometry().is_some() && parser.pos == parser.tokens.len()
+}
+
+impl Connection {
+    /// It is guaranteed to be a point geometry of the coordinates.
+    /// A coordinate which is not finite returns the error "invalid coordinate" when the statement is executed.
+    /// See [geometry_wkt](#method.geometry_wkt).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.point(139.7, 35.6);
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT GeomFromText( 'POINT(139.7 35.6)' ) ");
+    /// ```
+    pub fn point(&self, x: f64, y: f64) -> String {
+        if !x.is_finite() || !y.is_finite() {
+            return self.ow_error("invalid coordinate", &format!("{} {}", x, y));
+        }
+        self.geometry_wkt(&format!("POINT({} {})", x, y))
+    }
+
+    /// It is guaranteed to be a geometry of the [WKT](https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry),
+    /// such as `LINESTRING(0 0, 1 1)`. Malformed WKT returns the error "invalid geometry" when the
+    /// statement is executed, instead of reaching the server.
+    ///
+    /// Dialect         | Geometry
+    /// --------------- | --------
+    /// SQLite          | `GeomFromText('POINT(1 2)')` of [SpatiaLite](https://www.gaia-gis.it/fossil/libspatialite/)
+    /// Postgres, MySQL | `ST_GeomFromText('POINT(1 2)')`
+    /// SQL Server      | `geometry::STGeomFromText('POINT(1 2)', 0)`
+    /// Oracle          | `SDO_GEOMETRY('POINT(1 2)')`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use owsql::OwsqlError;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.geometry_wkt("POLYGON((0 0, 1 0, 1 1, 0 0))");
+    /// assert!(conn.actual_sql(&sql).is_ok());
+    /// let sql = conn.ow("SELECT") + &conn.geometry_wkt("POINT(1 2)') --");
+    /// assert_eq!(conn.actual_sql(&sql), Err(OwsqlError::Message("invalid geometry".into())));
+    /// ```
+    pub fn geometry_wkt(&self, wkt: &str) -> String {
+        if !is_valid_wkt(wkt) {
+            return self.ow_error("invalid geometry", wkt);
+        }
+        let (function, close) = match self.dialect() {
+            Dialect::Sqlite    => ("GeomFromText(", ")"),
+            Dialect::SqlServer => ("geometry::STGeomFromText(", ", 0)"),
+            Dialect::Oracle    => ("SDO_GEOMETRY(", ")"),
+            _                  => ("ST_GeomFromText(", ")"),
+        };
+        self.ow_generated(function.to_string()) + wkt + &self.ow_generated(close.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_wkt;
+
+    #[test]
+    fn valid_wkt() {
+        for wkt in [
+            "POINT(1 2)",
+            "point (-1.5 +2e3)",
+            "POINT Z (1 2 3)",
+            "POINT ZM (1 2 3 4)",
+            "POINT EMPTY",
+            "LINESTRING(0 0, 1 1, 2 0)",
+            "POLYGON((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))",
+            "MULTIPOINT(0 0, 1 1)",
+            "MULTIPOINT((0 0), (1 1))",
+            "MULTILINESTRING((0 0, 1 1), (2 2, 3 3))",
+            "MULTIPOLYGON(((0 0, 1 0, 1 1, 0 0)), ((2 2, 3 2, 3 3, 2 2)))",
+            "GEOMETRYCOLLECTION(POINT(1 2), LINESTRING(0 0, 1 1))",
+            "GEOMETRYCOLLECTION EMPTY",
+        ] {
+            assert!(is_valid_wkt(wkt), "{}", wkt);
+        }
+    }
+
+    #[test]
+    fn invalid_wkt() {
+        for wkt in [
+            "",
+            "POINT",
+            "POINT()",
+            "POINT(1)",
+            "POINT(1 2 3 4 5)",
+            "POINT(1 2, 3 4)",
+            "POINT(1 2",
+            "POINT(1 2))",
+            "POINT(1 2) POINT(3 4)",
+            "POINT Z (1 2)",
+            "POINT(1 2')",
+            "POINT(1 2) --",
+            "POINT(NaN 2)",
+            "POINT(1e999 2)",
+            "POINT(1..2 3)",
+            "CIRCLE(1 2)",
+            "LINESTRING(0 0)",
+            "LINESTRING(0 0, 1 1 1)",
+            "POLYGON((0 0, 1 0, 1 1, 0 1))",
+            "POLYGON((0 0, 1 0, 0 0))",
+            "MULTIPOINT((0 0, 1 1))",
+            "GEOMETRYCOLLECTION(1 2)",
+        ] {
+            assert!(!is_valid_wkt(wkt), "{}", wkt);
+        }
+    }
+}
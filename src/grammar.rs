@@ -0,0 +1,189 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::lexer::{KEYWORDS, lex};
+use crate::token::Token;
+
+/// Keywords only some of the backends understand.
+const DIALECT_KEYWORDS: &[(&str, &[Dialect])] = &[
+    ("GLOB",      &[Dialect::Sqlite]),
+    ("ILIKE",     &[Dialect::Postgres]),
+    ("PRAGMA",    &[Dialect::Sqlite]),
+    ("REGEXP",    &[Dialect::Sqlite, Dialect::Mysql]),
+    ("RETURNING", &[Dialect::Sqlite, Dialect::Postgres]),
+];
+
+/// Words which are not in the keywords but look like typos of them, besides plurals.
+const KNOWN_WORDS: &[&str] = &["INDEXES", "VALUE"];
+
+/// Words between the table and JOIN.
+const JOIN_MODIFIERS: &[&str] = &["CROSS", "FULL", "INNER", "LEFT", "NATURAL", "OUTER", "RIGHT"];
+
+/// Keywords ending the join clause.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "EXCEPT", "GROUP", "HAVING", "INTERSECT", "JOIN", "LIMIT", "ORDER", "RETURNING", "UNION",
+    "WHERE",
+];
+
+impl Connection {
+    /// Checks the grammar of a trusted fragment for the backend, on `Develop` and `Debug`.
+    pub(crate) fn check_grammar(&self, fragment: &str) -> Result<()> {
+        if matches!(self.error_level, OwsqlErrorLevel::AlwaysOk | OwsqlErrorLevel::Release) {
+            return Ok(());
+        }
+        match check(fragment, self.dialect()) {
+            Some((err_msg, detail)) => OwsqlError::new(&self.error_level, err_msg, &detail),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Return the error message and its detail if the fragment has an unknown keyword or a dangling JOIN.
+fn check(fragment: &str, dialect: Dialect) -> Option<(&'static str, String)> {
+    let mut tokens = Vec::new();
+    lex(fragment, &mut tokens);
+
+    for token in tokens.iter() {
+        if let Token::Keyword(word) | Token::Identifier(word) = token {
+            if is_unknown_keyword(word, dialect) {
+                return Some(("unknown keyword", word.clone()));
+            }
+        }
+    }
+
+    for (i, token) in tokens.iter().enumerate() {
+        if is_word(token, "JOIN") && is_dangling_join(&tokens[..i], &tokens[i+1..], dialect) {
+            return Some(("dangling JOIN", fragment.to_string()));
+        }
+    }
+
+    None
+}
+
+/// Checks if the word is a keyword of another backend, or an upper case typo of a keyword.
+fn is_unknown_keyword(word: &str, dialect: Dialect) -> bool {
+    if let Some((_, dialects)) = DIALECT_KEYWORDS.iter().find(|(keyword, _)| keyword.eq_ignore_ascii_case(word)) {
+        return !dialects.contains(&dialect);
+    }
+    if word.len() < 4 || !word.chars().all(|c| c.is_ascii_uppercase()) || KNOWN_WORDS.contains(&word) {
+        return false;
+    }
+    let plural = word.strip_suffix('S').is_some_and(|singular| KEYWORDS.contains(&singular));
+    !plural && !KEYWORDS.contains(&word) && KEYWORDS.iter().any(|keyword| keyword.len() >= 4 && is_typo(word, keyword))
+}
+
+/// Checks if the word differs from the keyword by one insertion, deletion, substitution or
+/// transposition of adjacent characters after the first character, such as `BLOB` for `GLOB`.
+fn is_typo(word: &str, keyword: &str) -> bool {
+    let (word, keyword) = (word.as_bytes(), keyword.as_bytes());
+    let prefix = word.iter().zip(keyword).take_while(|(a, b)| a == b).count();
+    let (word, keyword) = (&word[prefix..], &keyword[prefix..]);
+    match word.len() as isize - keyword.len() as isize {
+        _ if prefix == 0 => false,
+        0 if word.is_empty() => false,
+        0 => word[1..] == keyword[1..]
+            || (word.len() >= 2 && word[0] == keyword[1] && word[1] == keyword[0] && word[2..] == keyword[2..]),
+        1  => word[1..] == *keyword,
+        -1 => *word == keyword[1..],
+        _ => false,
+    }
+}
+
+/// Checks if the JOIN has no table, or no condition where the backend requires one.
+fn is_dangling_join(before: &[Token], after: &[Token], dialect: Dialect) -> bool {
+    let modifiers = before.iter()
+        .rev()
+        .map(|token| token.as_str().to_ascii_uppercase())
+        .take_while(|word| JOIN_MODIFIERS.contains(&word.as_str()))
+        .collect::<Vec<_>>();
+    if modifiers.iter().any(|word| word == "CROSS" || word == "NATURAL") {
+        return false;
+    }
+
+    match after.first() {
+        // The table may follow in the next fragment.
+        None => return false,
+        Some(Token::Identifier(_)) => (),
+        Some(Token::Symbol(symbol)) if symbol == "(" => (),
+        Some(_) => return true,
+    }
+
+    let requires_condition = match dialect {
+        Dialect::Sqlite    => false,
+        Dialect::Postgres  |
+        Dialect::SqlServer |
+        Dialect::Oracle    => true,
+        Dialect::Mysql     => modifiers.iter().any(|word| word == "LEFT" || word == "RIGHT"),
+    };
+    if !requires_condition {
+        return false;
+    }
+
+    let mut depth = 0;
+    for token in after {
+        match token {
+            Token::Symbol(symbol) if symbol == "(" => depth += 1,
+            Token::Symbol(symbol) if symbol == ")" && depth == 0 => return true,
+            Token::Symbol(symbol) if symbol == ")" => depth -= 1,
+            Token::Symbol(symbol) if symbol == ";" && depth == 0 => return true,
+            _ if depth > 0 => (),
+            _ if is_word(token, "ON") || is_word(token, "USING") => return false,
+            Token::Keyword(keyword) if CLAUSE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(keyword)) => return true,
+            _ => (),
+        }
+    }
+    // The condition may follow in the next fragment.
+    false
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Keyword(w) | Token::Identifier(w) if w.eq_ignore_ascii_case(word))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keyword() {
+        assert_eq!(check("SELCT * FROM users", Dialect::Sqlite), Some(("unknown keyword", "SELCT".into())));
+        assert_eq!(check("SELECT * FORM users", Dialect::Sqlite), Some(("unknown keyword", "FORM".into())));
+        assert_eq!(check("SELECT * FROM users WHRE age =", Dialect::Sqlite), Some(("unknown keyword", "WHRE".into())));
+        assert_eq!(check("SELECT * FROM users WHERE name ILIKE", Dialect::Mysql), Some(("unknown keyword", "ILIKE".into())));
+        assert_eq!(check("SELECT * FROM users WHERE name ILIKE", Dialect::Postgres), None);
+        assert_eq!(check("SELECT form FROM users", Dialect::Sqlite), None);
+        assert_eq!(check("SELECT * FROM users ORDER BY age NULLS LAST", Dialect::Postgres), None);
+        assert_eq!(check("SELECT 'SELCT' AS \"FORM\"", Dialect::Sqlite), None);
+        assert_eq!(check("SELECT * FROM ORDERS", Dialect::Sqlite), None);
+    }
+
+    #[test]
+    fn dangling_join() {
+        let sql = "SELECT * FROM users JOIN orders WHERE users.id =";
+        assert_eq!(check(sql, Dialect::Postgres), Some(("dangling JOIN", sql.into())));
+        assert_eq!(check(sql, Dialect::Sqlite), None);
+        assert_eq!(check("SELECT * FROM users LEFT JOIN orders;", Dialect::Mysql).map(|e| e.0), Some("dangling JOIN"));
+        assert_eq!(check("SELECT * FROM users JOIN orders;", Dialect::Mysql), None);
+        assert_eq!(check("SELECT * FROM users JOIN ON", Dialect::Sqlite).map(|e| e.0), Some("dangling JOIN"));
+        assert_eq!(check("SELECT * FROM users JOIN orders ON users.id = orders.user_id WHERE", Dialect::Postgres), None);
+        assert_eq!(check("SELECT * FROM users JOIN (SELECT * FROM orders WHERE total > 0) o USING (id)", Dialect::Postgres), None);
+        assert_eq!(check("SELECT * FROM users NATURAL LEFT OUTER JOIN orders;", Dialect::Postgres), None);
+        assert_eq!(check("SELECT * FROM users CROSS JOIN orders;", Dialect::Postgres), None);
+        assert_eq!(check("SELECT * FROM users JOIN", Dialect::Postgres), None);
+        assert_eq!(check("SELECT * FROM users JOIN orders", Dialect::Postgres), None);
+    }
+
+    #[test]
+    fn is_typo() {
+        assert!(super::is_typo("SELCT", "SELECT"));
+        assert!(super::is_typo("SELECTT", "SELECT"));
+        assert!(super::is_typo("SELEKT", "SELECT"));
+        assert!(super::is_typo("SLEECT", "SELECT"));
+        assert!(!super::is_typo("SELECT", "SELECT"));
+        assert!(!super::is_typo("SLECTE", "SELECT"));
+        assert!(!super::is_typo("USERS", "USING"));
+        assert!(!super::is_typo("LAST", "CAST"));
+    }
+}
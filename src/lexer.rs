@@ -0,0 +1,466 @@
+use crate::dialect::Dialect;
+use crate::token::Token;
+
+pub(crate) const KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "BEGIN", "BETWEEN", "BY", "CALL", "CASE",
+    "CAST", "CHECK", "COLUMN", "COMMIT", "CONSTRAINT", "CREATE", "CROSS", "DATABASE", "DEFAULT",
+    "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "END", "ESCAPE", "EXCEPT", "EXISTS",
+    "EXPLAIN", "FALSE", "FETCH", "FOREIGN", "FROM", "FULL", "GLOB", "GRANT", "GROUP", "HAVING",
+    "IF", "ILIKE", "IN", "INDEX", "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "KEY",
+    "LEFT", "LIKE", "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER", "PRAGMA",
+    "PRIMARY", "REFERENCES", "REGEXP", "RETURNING", "REVOKE", "RIGHT", "ROLLBACK", "SELECT",
+    "SET", "TABLE", "THEN", "TO", "TRUE", "TRUNCATE", "UNION", "UNIQUE", "UPDATE", "USING",
+    "VALUES", "VIEW", "WHEN", "WHERE", "WITH",
+];
+
+/// Keywords followed by a value.
+const VALUE_KEYWORDS: &[&str] = &[
+    "AND", "BETWEEN", "CASE", "DEFAULT", "ELSE", "ESCAPE", "GLOB", "ILIKE", "LIKE", "LIMIT", "NOT",
+    "OFFSET", "OR", "REGEXP", "SELECT", "THEN", "WHEN",
+];
+
+const OPERATORS: &[&str] = &["<=", ">=", "<>", "!=", "||", "::", "->>", "->"];
+
+/// How comments in trusted fragments are sent.
+/// See [set_comment_mode](./struct.Connection.html#method.set_comment_mode).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CommentMode {
+    /// Comments are sent as they are.
+    #[default]
+    Preserve,
+    /// Comments are removed before sending.
+    Strip,
+}
+
+/// Checks if the word is a SQL keyword, ignoring case.
+pub(crate) fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Checks if a value may follow the trusted fragment, that is, the fragment ends with an operator,
+/// an opening parenthesis, a comma or a keyword such as `LIKE`.
+pub(crate) fn expects_value(fragment: &str) -> bool {
+    let mut tokens = Vec::new();
+    lex(fragment, &mut tokens);
+    match tokens.last() {
+        Some(Token::Symbol(symbol))   => symbol != ")" && symbol != ";",
+        Some(Token::Keyword(keyword)) => VALUE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(keyword)),
+        _ => false,
+    }
+}
+
+/// Splits a trusted fragment into tokens.
+/// An unterminated quoted string continues until the end of the fragment.
+pub(crate) fn lex(fragment: &str, tokens: &mut Vec<Token>) {
+    lex_quoted(fragment, false, tokens)
+}
+
+/// Splits the SQL into tokens in the same way as [lex](fn.lex.html), but `[..]` is also a quoted
+/// identifier, as on SQL Server and SQLite.
+pub(crate) fn lex_bracketed(sql: &str, tokens: &mut Vec<Token>) {
+    lex_quoted(sql, true, tokens)
+}
+
+fn lex_quoted(fragment: &str, brackets: bool, tokens: &mut Vec<Token>) {
+    let mut rest = fragment;
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_whitespace() {
+            rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len())
+        } else if c == '\'' || c == '"' || c == '`' {
+            let len = quoted_len(rest, c);
+            let quoted = rest[..len].to_string();
+            tokens.push(if c == '\'' { Token::QuotedString(quoted) } else { Token::Identifier(quoted) });
+            len
+        } else if c == '[' && brackets {
+            let len = bracket_len(rest).unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..len].to_string()));
+            len
+        } else if let Some(len) = dollar_quote_len(rest) {
+            tokens.push(Token::QuotedString(rest[..len].to_string()));
+            len
+        } else if let Some((len, _)) = escape_string(rest) {
+            tokens.push(Token::QuotedString(rest[..len].to_string()));
+            len
+        } else if c.is_ascii_digit() {
+            let len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            tokens.push(Token::Number(rest[..len].to_string()));
+            len
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$').unwrap_or(rest.len());
+            let word = rest[..len].to_string();
+            tokens.push(if is_keyword(&word) { Token::Keyword(word) } else { Token::Identifier(word) });
+            len
+        } else if let Some(len) = comment_len(rest) {
+            len
+        } else {
+            let len = OPERATORS.iter()
+                .find(|operator| rest.starts_with(*operator))
+                .map_or(c.len_utf8(), |operator| operator.len());
+            tokens.push(Token::Symbol(rest[..len].to_string()));
+            len
+        };
+        rest = &rest[len..];
+    }
+}
+
+/// Return the byte positions of the `?` placeholders outside quoted strings, quoted identifiers
+/// and comments, as they are quoted on the dialect.
+pub(crate) fn placeholders(fragment: &str, dialect: Dialect) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut offset = 0;
+    while let Some(c) = fragment[offset..].chars().next() {
+        offset += match c {
+            _ if quote_len(fragment, offset, dialect).is_some() => quote_len(fragment, offset, dialect).unwrap(),
+            '-' | '/' if comment_len(&fragment[offset..]).is_some() => comment_len(&fragment[offset..]).unwrap(),
+            '?' => {
+                positions.push(offset);
+                1
+            },
+            _ => c.len_utf8(),
+        };
+    }
+    positions
+}
+
+/// Return the length of the comment at the beginning if any.
+/// A line comment `--` continues until the end of the line, excluding the newline, and a block
+/// comment `/* */` continues until the end if unterminated.
+pub(crate) fn comment_len(s: &str) -> Option<usize> {
+    if s.starts_with("--") {
+        Some(s.find('\n').unwrap_or(s.len()))
+    } else {
+        s.strip_prefix("/*").map(|rest| rest.find("*/").map_or(s.len(), |i| i + 4))
+    }
+}
+
+/// Return the byte ranges of the comments outside quoted strings and quoted identifiers,
+/// as they are quoted on the dialect.
+pub(crate) fn comments(fragment: &str, dialect: Dialect) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    while let Some(c) = fragment[offset..].chars().next() {
+        offset += match quote_len(fragment, offset, dialect) {
+            Some(len) => len,
+            None => match comment_len(&fragment[offset..]) {
+                Some(len) => {
+                    ranges.push((offset, offset + len));
+                    len
+                },
+                None => c.len_utf8(),
+            },
+        };
+    }
+    ranges
+}
+
+/// Return the length of the quoted string or quoted identifier at the byte position if any.
+/// As in the check of the literals, escape strings such as `E'\n'` and dollar-quoted strings are
+/// quoted only on PostgreSQL, backticks on SQLite and MySQL, and brackets on SQL Server.
+/// An unterminated one continues until the end.
+pub(crate) fn quote_len(fragment: &str, offset: usize, dialect: Dialect) -> Option<usize> {
+    let rest = &fragment[offset..];
+    match rest.chars().next()? {
+        c @ ('\'' | '"') => Some(quoted_len(rest, c)),
+        '`' if matches!(dialect, Dialect::Sqlite | Dialect::Mysql) => Some(quoted_len(rest, '`')),
+        '[' if dialect == Dialect::SqlServer => Some(bracket_len(rest).unwrap_or(rest.len())),
+        '$' if dialect == Dialect::Postgres => dollar_quote_len(rest),
+        'E' | 'e' if dialect == Dialect::Postgres && is_word_start(fragment, offset) => escape_string(rest).map(|(len, _)| len),
+        _ => None,
+    }
+}
+
+/// Checks if the fragment ends in a line comment, which would swallow anything following on the same line.
+pub(crate) fn ends_in_line_comment(fragment: &str, dialect: Dialect) -> bool {
+    comments(fragment, dialect).last().is_some_and(|&(start, end)| end == fragment.len() && fragment[start..].starts_with("--"))
+}
+
+/// Checks if a block comment is unterminated or contains `/*`, which some databases nest.
+pub(crate) fn has_open_comment(fragment: &str, dialect: Dialect) -> bool {
+    comments(fragment, dialect).iter().any(|&(start, end)| {
+        let comment = &fragment[start..end];
+        comment.starts_with("/*") && (comment.len() < 4 || !comment.ends_with("*/") || comment[2..].contains("/*"))
+    })
+}
+
+/// Return the fragment without comments. A block comment is replaced with a space, so that the
+/// words around it stay apart.
+pub(crate) fn strip_comments(fragment: &str, dialect: Dialect) -> String {
+    let mut stripped = String::with_capacity(fragment.len());
+    let mut offset = 0;
+    for (start, end) in comments(fragment, dialect) {
+        stripped.push_str(&fragment[offset..start]);
+        if fragment[start..].starts_with("/*") {
+            stripped.push(' ');
+        }
+        offset = end;
+    }
+    stripped.push_str(&fragment[offset..]);
+    stripped
+}
+
+/// Return the statements of the SQL split at the semicolons outside quoted strings, quoted
+/// identifiers and comments, as they are quoted on the dialect. Each statement keeps its semicolon,
+/// and the statements of only whitespace and comments are omitted.
+#[cfg_attr(not(feature = "duckdb"), allow(dead_code))]
+pub(crate) fn statements(sql: &str, dialect: Dialect) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    while let Some(c) = sql[offset..].chars().next() {
+        match quote_len(sql, offset, dialect).or_else(|| comment_len(&sql[offset..])) {
+            Some(len) => offset += len,
+            None if c == ';' => {
+                offset += 1;
+                statements.push(&sql[start..offset]);
+                start = offset;
+            },
+            None => offset += c.len_utf8(),
+        }
+    }
+    statements.push(&sql[start..]);
+    statements.retain(|statement| !strip_comments(statement, dialect).trim_end_matches(';').trim().is_empty());
+    statements
+}
+
+/// Return the opening tag of the PostgreSQL dollar-quoted string at the beginning if any,
+/// such as `$$` or `$body$`. A positional parameter such as `$1` is not a tag.
+pub(crate) fn dollar_tag(s: &str) -> Option<&str> {
+    let rest = s.strip_prefix('$')?;
+    let tag = &rest[..rest.find('$')?];
+    let valid = tag.chars().next().is_none_or(|c| c.is_alphabetic() || c == '_')
+        && tag.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if valid { Some(&s[..tag.len() + 2]) } else { None }
+}
+
+/// Return the length of the dollar-quoted string at the beginning if any, including the tags.
+/// An unterminated one continues until the end.
+pub(crate) fn dollar_quote_len(s: &str) -> Option<usize> {
+    let tag = dollar_tag(s)?;
+    Some(s[tag.len()..].find(tag).map_or(s.len(), |i| tag.len() * 2 + i))
+}
+
+/// Return the length of the PostgreSQL escape string at the beginning if any, such as `E'\n'`,
+/// in which a backslash escapes the next character, and whether it is terminated.
+/// An unterminated one continues until the end.
+pub(crate) fn escape_string(s: &str) -> Option<(usize, bool)> {
+    let rest = s.strip_prefix(|c| c == 'E' || c == 'e')?;
+    if !rest.starts_with('\'') {
+        return None;
+    }
+    let mut chars = rest.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == '\'' {
+            match chars.peek() {
+                Some(&(_, '\'')) => { chars.next(); },
+                _ => return Some((i + 2, true)),
+            }
+        }
+    }
+    Some((s.len(), false))
+}
+
+/// Checks if the byte position is not in the middle of a word.
+pub(crate) fn is_word_start(s: &str, offset: usize) -> bool {
+    !s[..offset].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Checks if the name is a plain identifier.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Checks if the name is a plain identifier optionally qualified by dots, such as `public.users`.
+pub(crate) fn is_qualified_identifier(name: &str) -> bool {
+    name.split('.').all(is_identifier)
+}
+
+/// Return the length of the bracket-quoted identifier of SQL Server, in which `]]` is a `]`,
+/// or `None` if it is unterminated.
+pub(crate) fn bracket_len(s: &str) -> Option<usize> {
+    let mut len = 1;
+    loop {
+        len += s[len..].find(']')? + 1;
+        if !s[len..].starts_with(']') {
+            return Some(len);
+        }
+        len += 1;
+    }
+}
+
+fn quoted_len(s: &str, quote: char) -> usize {
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            match chars.peek() {
+                Some(&(_, next)) if next == quote => { chars.next(); },
+                _ => return i + c.len_utf8(),
+            }
+        }
+    }
+    s.len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect::*;
+
+    #[test]
+    fn is_identifier() {
+        assert!(super::is_identifier("docs"));
+        assert!(!super::is_identifier("docs.body"));
+        assert!(!super::is_identifier("1docs"));
+        assert!(!super::is_identifier(""));
+    }
+
+    #[test]
+    fn is_qualified_identifier() {
+        assert!(super::is_qualified_identifier("add_user"));
+        assert!(super::is_qualified_identifier("public.add_user"));
+        assert!(!super::is_qualified_identifier(""));
+        assert!(!super::is_qualified_identifier("1st"));
+        assert!(!super::is_qualified_identifier("public."));
+        assert!(!super::is_qualified_identifier("f(); DROP TABLE users"));
+        assert!(!super::is_qualified_identifier("foreign_keys = ON; DROP TABLE users"));
+    }
+
+    #[test]
+    fn lex() {
+        let mut tokens = Vec::new();
+        super::lex("select id, \"Name\" FROM users WHERE age >= 4.2 AND name = 'O''Reilly'", &mut tokens);
+        assert_eq!(tokens, vec![
+            Token::Keyword("select".into()),
+            Token::Identifier("id".into()),
+            Token::Symbol(",".into()),
+            Token::Identifier("\"Name\"".into()),
+            Token::Keyword("FROM".into()),
+            Token::Identifier("users".into()),
+            Token::Keyword("WHERE".into()),
+            Token::Identifier("age".into()),
+            Token::Symbol(">=".into()),
+            Token::Number("4.2".into()),
+            Token::Keyword("AND".into()),
+            Token::Identifier("name".into()),
+            Token::Symbol("=".into()),
+            Token::QuotedString("'O''Reilly'".into()),
+        ]);
+    }
+
+    #[test]
+    fn expects_value() {
+        assert!(super::expects_value("SELECT * FROM users WHERE name ="));
+        assert!(super::expects_value("INSERT INTO users VALUES("));
+        assert!(super::expects_value(","));
+        assert!(super::expects_value("WHERE name like"));
+        assert!(super::expects_value("LIMIT"));
+        assert!(!super::expects_value("SELECT * FROM"));
+        assert!(!super::expects_value("WHERE age < 50"));
+        assert!(!super::expects_value("VALUES(1, 2)"));
+        assert!(!super::expects_value("SELECT 1;"));
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(super::placeholders("SELECT * FROM users WHERE name = ? AND age > ?", Sqlite), vec![33, 45]);
+        assert_eq!(super::placeholders("SELECT '?', \"?\" FROM users WHERE name = 'O''?' OR name = ?", Sqlite), vec![57]);
+        assert_eq!(super::placeholders("SELECT 1", Sqlite), Vec::<usize>::new());
+        assert_eq!(super::placeholders("SELECT '?", Sqlite), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn comments() {
+        let mut tokens = Vec::new();
+        super::lex("SELECT -- it's the name\nname /* don't */ FROM users", &mut tokens);
+        assert_eq!(tokens.iter().map(Token::as_str).collect::<Vec<_>>(), ["SELECT", "name", "FROM", "users"]);
+        assert_eq!(super::placeholders("SELECT ? -- ?\n/* ? */ , '--' , ?", Sqlite), vec![7, 31]);
+
+        assert!(super::ends_in_line_comment("SELECT * FROM users WHERE name = -- name", Sqlite));
+        assert!(!super::ends_in_line_comment("SELECT -- name\n", Sqlite));
+        assert!(!super::ends_in_line_comment("SELECT '--'", Sqlite));
+        assert!(super::has_open_comment("SELECT /* name", Sqlite));
+        assert!(super::has_open_comment("SELECT /*/", Sqlite));
+        assert!(super::has_open_comment("SELECT /* /* */", Sqlite));
+        assert!(!super::has_open_comment("SELECT /**/ '/*' -- /*", Sqlite));
+        assert_eq!(super::strip_comments("SELECT/* a */name -- b\nFROM '--'", Sqlite), "SELECT name \nFROM '--'");
+    }
+
+    #[test]
+    fn statements() {
+        assert_eq!(super::statements("SELECT ';'; -- ;\nSELECT $$;$$ /* ; */;  ", Postgres), ["SELECT ';';", " -- ;\nSELECT $$;$$ /* ; */;"]);
+        assert_eq!(super::statements("SELECT 1", Postgres), ["SELECT 1"]);
+        assert_eq!(super::statements(" ; -- ;", Postgres), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn dollar_quotes() {
+        assert_eq!(super::dollar_tag("$$ SELECT 1 $$"), Some("$$"));
+        assert_eq!(super::dollar_tag("$body$ SELECT 1 $body$"), Some("$body$"));
+        assert_eq!(super::dollar_tag("$1 AND $2"), None);
+        assert_eq!(super::dollar_tag("$name"), None);
+        assert_eq!(super::dollar_quote_len("$a$ it's $$ $a$, 1"), Some(15));
+        assert_eq!(super::dollar_quote_len("$$ it's"), Some(7));
+
+        let mut tokens = Vec::new();
+        super::lex("AS $$ SELECT 'it''s' -- ? $$;", &mut tokens);
+        assert_eq!(tokens[1], Token::QuotedString("$$ SELECT 'it''s' -- ? $$".into()));
+        assert_eq!(super::placeholders("$$ ? $$ = $1 AND ?", Postgres), vec![17]);
+        assert!(!super::ends_in_line_comment("$$ -- $$", Postgres));
+        assert_eq!(super::strip_comments("$$ /* body */ $$ /* x */", Postgres), "$$ /* body */ $$  ");
+    }
+
+    #[test]
+    fn backticks() {
+        let mut tokens = Vec::new();
+        super::lex("SELECT `order`, `a``b--c` FROM t", &mut tokens);
+        assert_eq!(tokens[1], Token::Identifier("`order`".into()));
+        assert_eq!(tokens[3], Token::Identifier("`a``b--c`".into()));
+        assert_eq!(super::placeholders("SELECT `?` FROM t WHERE a = ?", Mysql), vec![28]);
+        assert!(super::comments("SELECT `--` FROM t", Mysql).is_empty());
+        assert_eq!(super::comments("SELECT `--` FROM t", Postgres), [(8, 18)]);
+    }
+
+    #[test]
+    fn brackets() {
+        let mut tokens = Vec::new();
+        super::lex_bracketed("SELECT [order], [a]]b c] FROM [t", &mut tokens);
+        assert_eq!(tokens[1], Token::Identifier("[order]".into()));
+        assert_eq!(tokens[3], Token::Identifier("[a]]b c]".into()));
+        assert_eq!(tokens[5], Token::Identifier("[t".into()));
+        let mut tokens = Vec::new();
+        super::lex("SELECT a[1]", &mut tokens);
+        assert_eq!(tokens[2], Token::Symbol("[".into()));
+        assert_eq!(super::placeholders("SELECT [?] FROM t WHERE a = ?", SqlServer), vec![28]);
+        assert!(super::comments("SELECT [--] FROM t", SqlServer).is_empty());
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(super::escape_string("E'it\\'s' || 'x'"), Some((8, true)));
+        assert_eq!(super::escape_string("e'a''b\\\\'"), Some((9, true)));
+        assert_eq!(super::escape_string("E'it\\'s"), Some((7, false)));
+        assert_eq!(super::escape_string("E 'x'"), None);
+
+        let mut tokens = Vec::new();
+        super::lex("SELECT E'\\' -- ?', name", &mut tokens);
+        assert_eq!(tokens[1], Token::QuotedString("E'\\' -- ?'".into()));
+        assert_eq!(super::placeholders("SELECT E'\\' ?', ?", Postgres), vec![16]);
+        assert_eq!(super::placeholders("SELECT some'?'", Postgres), Vec::<usize>::new());
+        assert!(super::comments("SELECT E'\\' -- '", Postgres).is_empty());
+
+        // Only PostgreSQL has escape strings and dollar-quoted strings.
+        assert!(super::ends_in_line_comment("SELECT E'\\' -- '", Sqlite));
+        assert_eq!(super::placeholders("SELECT E'\\' ?', ?", Mysql), vec![12]);
+        assert_eq!(super::placeholders("$$ ? $$", Sqlite), vec![3]);
+    }
+
+    #[test]
+    fn lex_unterminated() {
+        let mut tokens = Vec::new();
+        super::lex("x = 'foo", &mut tokens);
+        assert_eq!(tokens.last(), Some(&Token::QuotedString("'foo".into())));
+    }
+}
@@ -10,7 +10,7 @@
 //! # conn.execute(stmt).unwrap();
 //! let id_input = "42 OR 1=1; --";
 //! let sql = conn.ow("SELECT name FROM users WHERE id = ") + id_input;
-//! println!("[{}]", sql); // [ OWSQL47xyz6km0CfbRt0BA38Z2DxrleESyPPg4 42 OR 1=1; --]
+//! println!("[{}]", sql); // [ OWSQLC0N47xyz6km0CfbRt0BA38Z2DxrleESyPPg4 42 OR 1=1; --]
 //! // At runtime it will be transformed into a query like
 //! // "SELECT name FROM users WHERE id = '42 OR 1=1; --'".
 //! # conn.iterate(&sql, |_| { true }).unwrap();
@@ -63,30 +63,127 @@
 //! ```
 
 
+mod audit;
+pub mod backend;
 mod bidimap;
+mod blob;
+mod cache;
+mod call;
+mod checksum;
+mod chunk;
+mod cond;
 mod connection;
+#[cfg(feature = "serde")]
+mod de;
+mod dialect;
 mod error;
+pub mod escape;
+mod executor;
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+mod failover;
+mod fingerprint;
+mod from_row;
+mod fts;
+mod gc;
+mod geometry;
+#[cfg(feature = "dialect-check")]
+mod grammar;
+mod lexer;
+mod metrics;
+mod model;
+mod observer;
+mod open;
+mod order;
+#[cfg(feature = "otel")]
+mod otel;
 mod overwrite;
+mod page;
 mod parser;
+mod plan;
+mod policy;
+mod pretty;
+mod reader;
+mod replica;
+mod retry;
+mod rewrite;
 mod row;
+mod schema;
+mod sequence;
 mod serial;
+mod session;
+mod sql_enum;
+mod statement;
+mod time;
+pub mod testing;
 mod token;
+mod transaction;
+mod value;
+mod watch;
+#[cfg(feature = "native-tls")]
+mod tls;
 pub mod constants;
 
 #[cfg(feature = "sqlite")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
 pub mod sqlite;
+#[cfg(feature = "sqljs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqljs")))]
+pub mod sqljs;
+#[cfg(feature = "duckdb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "duckdb")))]
+pub mod duckdb;
+#[cfg(feature = "libsql")]
+#[cfg_attr(docsrs, doc(cfg(feature = "libsql")))]
+pub mod libsql;
 #[cfg(feature = "mysql")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mysql")))]
 pub mod mysql;
+#[cfg(feature = "odbc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "odbc")))]
+pub mod odbc;
 #[cfg(feature = "postgres")]
 #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
 pub mod postgres;
 
+pub use crate::audit::{AuditEntry, AuditKind, AuditReport};
+pub use crate::blob::Blob;
+pub use crate::checksum::Checksum;
+pub use crate::chunk::ChunkLimit;
+pub use crate::cond::Cond;
 pub use crate::connection::Connection;
+pub use crate::dialect::Dialect;
 pub use crate::error::{OwsqlError, OwsqlErrorLevel};
-pub use crate::overwrite::IntoInner;
-pub use crate::row::Row;
+pub use crate::escape::{Charset, EscapeMode};
+pub use crate::executor::Executor;
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "postgres", feature = "mysql"))))]
+pub use crate::failover::HostStatus;
+pub use crate::fingerprint::fingerprint;
+pub use crate::from_row::{FromColumn, FromRow};
+pub use crate::gc::{EvictionPolicy, OverwriteStats};
+pub use crate::lexer::CommentMode;
+pub use crate::metrics::{Metrics, StatementMetrics};
+pub use crate::model::TimestampColumns;
+pub use crate::observer::Observer;
+pub use crate::open::open_url;
+pub use crate::order::SortOrder;
+pub use crate::overwrite::{IntoInner, TokenAlphabet};
+pub use crate::page::Page;
+pub use crate::plan::PlanExpectation;
+pub use crate::policy::Policy;
+pub use crate::reader::ReaderHandle;
+pub use crate::replica::ReplicaSet;
+pub use crate::retry::RetryPolicy;
+pub use crate::row::{ColumnMatch, Row, RowError};
+pub use crate::sql_enum::SqlEnum;
+pub use crate::statement::Statement;
+pub use crate::token::Token;
+pub use crate::transaction::{IsolationLevel, Transaction, TransactionBehavior, TransactionOptions};
+pub use crate::value::ArrayElement;
+pub use crate::watch::{ChangeEvent, ChangeKind, Watcher};
+#[cfg(feature = "native-tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+pub use crate::tls::{TlsConfig, TlsMode};
 pub use crate::parser::{html_special_chars, _sanitize_like};
 
 /// A typedef of the result returned by many methods.
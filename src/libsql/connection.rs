@@ -0,0 +1,613 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use native_tls_sys::TlsConnector;
+use serde_json::{json, Value as Json};
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+
+/// The default timeout of connecting to the server, and of each read and write of a request.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Open a connection, checking that the server accepts the token.
+pub fn open(url: &str, auth_token: Option<&str>) -> Result<Connection> {
+    let (endpoint, token) = match Endpoint::parse(url) {
+        Some(parsed) => parsed,
+        None => return Err(OwsqlError::Message(format!("invalid url: {}", url))),
+    };
+    if auth_token.is_some_and(|token| token.chars().any(char::is_control)) {
+        return Err(OwsqlError::Message("invalid auth token".into()));
+    }
+    let conn = Libsql {
+        endpoint,
+        auth_token: auth_token.map(str::to_string).or(token),
+        timeout: Cell::new(DEFAULT_TIMEOUT),
+        baton: RefCell::new(None),
+        changes: Cell::new(None),
+        last_error_code: RefCell::new(None),
+    };
+    match conn.request(json!({ "type": "get_autocommit" })) {
+        Ok(_) => Ok(Connection::new(Box::new(conn))),
+        Err(_) => Err(OwsqlError::Message("failed to connect".into())),
+    }
+}
+
+impl Connection {
+    /// Sets the timeout of connecting to the server, and of each read and write of a request.
+    /// The default is 30 seconds.  
+    /// Only for libSQL.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// let conn = owsql::libsql::open("http://127.0.0.1:8080", None).unwrap();
+    /// conn.set_libsql_timeout(Duration::from_secs(5)).unwrap();
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "libsql")))]
+    pub fn set_libsql_timeout(&self, timeout: Duration) -> Result<()> {
+        let libsql = match self.conn.as_any().downcast_ref::<Libsql>() {
+            Some(libsql) => libsql,
+            None => return Err(OwsqlError::Message("unsupported libsql timeout".into())),
+        };
+        if timeout.is_zero() {
+            return Err(OwsqlError::Message("invalid timeout".into()));
+        }
+        libsql.timeout.set(timeout);
+        Ok(())
+    }
+}
+
+/// The server of the database.
+#[derive(Debug, PartialEq)]
+struct Endpoint {
+    tls:  bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    /// Parses the URL, and return the endpoint and the token of the query parameter `authToken`.
+    /// A token containing control characters is rejected, since it is sent in a header.
+    fn parse(url: &str) -> Option<(Endpoint, Option<String>)> {
+        let (scheme, rest) = url.split_once("://")?;
+        let tls = match scheme {
+            "libsql" | "https" => true,
+            "http" => false,
+            _ => return None,
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.split_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let token = match query.and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("authToken="))) {
+            Some(token) => Some(percent_decode(token).filter(|token| !token.chars().any(char::is_control))?),
+            None => None,
+        };
+        Some((Endpoint { tls, host: host.to_string(), port, path: path.to_string() }, token))
+    }
+
+    /// Return the value of the `Host` header, which has the port unless it is the default of the scheme.
+    fn authority(&self) -> String {
+        match (self.tls, self.port) {
+            (true, 443) | (false, 80) => self.host.clone(),
+            _                         => format!("{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// A connection to libSQL, which sends each request by a new HTTP request.
+struct Libsql {
+    endpoint:        Endpoint,
+    auth_token:      Option<String>,
+    timeout:         Cell<Duration>,
+    /// Baton of the stream of the open transaction.
+    baton:           RefCell<Option<String>>,
+    changes:         Cell<Option<u64>>,
+    last_error_code: RefCell<Option<String>>,
+}
+
+impl Libsql {
+    /// Sends the request of Hrana on the stream of the open transaction, or a new stream, and
+    /// return its response.
+    fn request(&self, request: Json) -> std::result::Result<Json, String> {
+        let body = json!({
+            "baton": *self.baton.borrow(),
+            "requests": [request, { "type": "get_autocommit" }],
+        });
+        let (status, response) = self.post("/v2/pipeline", &body.to_string())?;
+        let response = serde_json::from_slice::<Json>(&response)
+            .map_err(|_| format!("HTTP {}: {}", status, String::from_utf8_lossy(&response)))?;
+        if status != 200 {
+            return Err(response["message"].as_str().map(str::to_string).unwrap_or_else(|| format!("HTTP {}", status)));
+        }
+
+        // The stream is kept only while a transaction is open, and is closed by the server after it.
+        let autocommit = response["results"][1]["response"]["is_autocommit"].as_bool().unwrap_or(true);
+        *self.baton.borrow_mut() = match response["baton"].as_str() {
+            Some(baton) if !autocommit => Some(baton.to_string()),
+            _ => None,
+        };
+
+        let result = &response["results"][0];
+        match result["type"].as_str() {
+            Some("ok") => {
+                *self.last_error_code.borrow_mut() = None;
+                Ok(result["response"].clone())
+            },
+            _ => {
+                *self.last_error_code.borrow_mut() = result["error"]["code"].as_str().map(str::to_string);
+                Err(result["error"]["message"].as_str().unwrap_or("invalid response").to_string())
+            },
+        }
+    }
+
+    /// Sends the request of HTTP/1.1 and return the status and the body of the response.
+    fn post(&self, path: &str, body: &str) -> std::result::Result<(u16, Vec<u8>), String> {
+        let authorization = match &self.auth_token {
+            Some(token) => format!("Authorization: Bearer {}\r\n", token),
+            None => String::new(),
+        };
+        let request = format!(
+            "POST {}{} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+            self.endpoint.path, path, self.endpoint.authority(), body.len(), authorization, body);
+        let mut stream = connect(&self.endpoint.host, self.endpoint.port, self.timeout.get())
+            .map_err(|e| e.to_string())?;
+        if self.endpoint.tls {
+            let connector = TlsConnector::new().map_err(|e| e.to_string())?;
+            let mut stream = connector.connect(&self.endpoint.host, stream).map_err(|e| e.to_string())?;
+            exchange(&mut stream, &request)
+        } else {
+            exchange(&mut stream, &request)
+        }.map_err(|e| e.to_string())
+    }
+
+    /// Sends the request, and converts its error into the error of the level.
+    fn send(&self, request: Json, error_level: &OwsqlErrorLevel) -> Result<Json> {
+        match self.request(request) {
+            Ok(response) => Ok(response),
+            Err(msg) => {
+                OwsqlError::database(error_level, None, None, None, &msg)?;
+                Ok(Json::Null)
+            },
+        }
+    }
+}
+
+/// Connects to the first address of the host which accepts it, with the timeout of connecting,
+/// reading and writing.
+fn connect(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut error = io::Error::new(io::ErrorKind::NotFound, "no address");
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            },
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// Writes the request and reads the response, which ends at its length or the end of the stream.
+fn exchange<S: Read + Write>(stream: &mut S, request: &str) -> io::Result<(u16, Vec<u8>)> {
+    stream.write_all(request.as_bytes())?;
+    let mut buf = Vec::new();
+    let mut chunk = [0; 8192];
+    loop {
+        let n = match stream.read(&mut chunk) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            // Some servers close the TLS stream without notifying it.
+            Err(e) => match response(&buf, true) {
+                Some(response) => return Ok(response),
+                None => return Err(e),
+            },
+        };
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(response) = response(&buf, n == 0) {
+            return Ok(response);
+        }
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete response"));
+        }
+    }
+}
+
+/// Parses the response of HTTP/1.1, and return `None` if it is incomplete.
+fn response(buf: &[u8], eof: bool) -> Option<(u16, Vec<u8>)> {
+    let end = find(buf, b"\r\n\r\n")?;
+    let head = String::from_utf8_lossy(&buf[..end]);
+    let body = &buf[end + 4..];
+    let mut lines = head.split("\r\n");
+    let status = lines.next()?.split(' ').nth(1)?.parse().ok()?;
+    let mut content_length = None;
+    let mut chunked = false;
+    for line in lines {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim().to_ascii_lowercase(), value.trim()),
+            None => continue,
+        };
+        if name == "content-length" {
+            content_length = value.parse::<usize>().ok();
+        } else if name == "transfer-encoding" {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        }
+    }
+    if chunked {
+        dechunk(body).map(|body| (status, body))
+    } else if let Some(len) = content_length {
+        if body.len() >= len { Some((status, body[..len].to_vec())) } else { None }
+    } else if eof {
+        Some((status, body.to_vec()))
+    } else {
+        None
+    }
+}
+
+/// Decodes the chunked body, and return `None` if it is incomplete.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line = find(body, b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line]).ok()?.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[line + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        if body.len() < size + 2 {
+            return None;
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+/// Decodes the percent-encoded component of a URL, and return `None` if it is invalid.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let high = (iter.next()? as char).to_digit(16)?;
+            let low = (iter.next()? as char).to_digit(16)?;
+            bytes.push((high << 4 | low) as u8);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Converts a value of Hrana into the text of SQLite.
+fn text(value: &Json) -> Option<String> {
+    match value["type"].as_str()? {
+        "null" => None,
+        "blob" => base64_decode(value["base64"].as_str()?).map(|blob| String::from_utf8_lossy(&blob).into_owned()),
+        _ => match &value["value"] {
+            Json::String(value) => Some(value.clone()),
+            Json::Null => None,
+            value => Some(value.to_string()),
+        },
+    }
+}
+
+/// Decodes the base64 of a blob, whose padding may be omitted.
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in s.bytes().filter(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+impl OwsqlConn for Libsql {
+    fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        self.changes.set(None);
+        self.send(json!({ "type": "sequence", "sql": query }), error_level)?;
+        Ok(())
+    }
+
+    fn close(&self, error_level: &OwsqlErrorLevel) -> Result<()> {
+        if self.baton.borrow().is_none() {
+            return Ok(());
+        }
+        // Closing the stream rolls back the open transaction.
+        let body = json!({ "baton": *self.baton.borrow(), "requests": [{ "type": "close" }] });
+        self.baton.replace(None);
+        match self.post("/v2/pipeline", &body.to_string()) {
+            Ok((200, _)) => Ok(()),
+            Ok((status, _)) => OwsqlError::database(error_level, None, None, None, &format!("HTTP {}", status)),
+            Err(msg) => OwsqlError::database(error_level, None, None, None, &msg),
+        }
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(0);
+            } else {
+                return Err(e);
+            },
+        };
+        let response = self.send(json!({ "type": "execute", "stmt": { "sql": query, "want_rows": false } }), error_level)?;
+        let changes = response["result"]["affected_row_count"].as_u64().unwrap_or_default();
+        self.changes.set(Some(changes));
+        Ok(changes)
+    }
+
+    fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
+        callback: IterateCallback) -> Result<()>
+    {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        let response = self.send(json!({ "type": "execute", "stmt": { "sql": query } }), error_level)?;
+        let result = &response["result"];
+        self.changes.set(result["affected_row_count"].as_u64());
+
+        let columns = result["cols"].as_array().map(Vec::as_slice).unwrap_or_default().iter()
+            .map(|column| column["name"].as_str().unwrap_or_default())
+            .collect::<Vec<_>>();
+        for row in result["rows"].as_array().map(Vec::as_slice).unwrap_or_default() {
+            let values = row.as_array().map(Vec::as_slice).unwrap_or_default().iter().map(text).collect::<Vec<_>>();
+            let pairs = columns.iter()
+                .zip(&values)
+                .map(|(column, value)| (*column, value.as_deref()))
+                .collect::<Vec<_>>();
+            if !callback(&pairs) {
+                // The same error as the callback of `sqlite3_exec` aborting the query.
+                return OwsqlError::database(error_level, None, None, None, "query aborted");
+            }
+        }
+        Ok(())
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        self.send(json!({ "type": "describe", "sql": query }), error_level)?;
+        Ok(())
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        EscapeMode::Standard
+    }
+
+    fn charset(&self) -> Charset {
+        Charset::Utf8
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn last_error_code(&self) -> Option<String> {
+        self.last_error_code.borrow().clone()
+    }
+
+    fn changes(&self) -> Option<u64> {
+        self.changes.get()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use serde_json::{json, Value as Json};
+    use std::cell::{Cell, RefCell};
+    use std::time::{Duration, Instant};
+    use super::{Endpoint, Libsql, base64_decode, dechunk, find, percent_decode};
+
+    /// Serves the pipeline responses in order, and return the URL and the handle returning the
+    /// headers and the bodies of the requests.
+    fn serve(responses: Vec<Json>) -> (String, thread::JoinHandle<Vec<(String, Json)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            responses.into_iter().map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0; 4096];
+                let (head, body) = loop {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(end) = find(&buf, b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buf[..end]).to_string();
+                        let len = head.lines()
+                            .find_map(|line| line.strip_prefix("Content-Length: "))
+                            .unwrap().parse::<usize>().unwrap();
+                        if buf.len() >= end + 4 + len {
+                            break (head, serde_json::from_slice(&buf[end + 4..end + 4 + len]).unwrap());
+                        }
+                    }
+                };
+                let response = response.to_string();
+                write!(stream, "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                    response.len(), response).unwrap();
+                (head, body)
+            }).collect()
+        });
+        (url, handle)
+    }
+
+    fn ok(response: Json, autocommit: bool) -> Json {
+        json!({
+            "baton": "b1",
+            "base_url": null,
+            "results": [
+                { "type": "ok", "response": response },
+                { "type": "ok", "response": { "type": "get_autocommit", "is_autocommit": autocommit } },
+            ],
+        })
+    }
+
+    #[test]
+    fn pipeline() {
+        let (url, handle) = serve(vec![
+            ok(json!({ "type": "get_autocommit", "is_autocommit": true }), true),
+            ok(json!({ "type": "sequence" }), false),
+            ok(json!({ "type": "execute", "result": {
+                "cols": [{ "name": "name" }, { "name": "age" }, { "name": "avatar" }],
+                "rows": [
+                    [{ "type": "text", "value": "Alice" }, { "type": "integer", "value": "42" }, { "type": "blob", "base64": "aGk" }],
+                    [{ "type": "text", "value": "Bob" }, { "type": "float", "value": 6.5 }, { "type": "null" }],
+                ],
+                "affected_row_count": 0,
+            }}), false),
+            json!({
+                "baton": "b1",
+                "results": [
+                    { "type": "error", "error": { "message": "UNIQUE constraint failed", "code": "SQLITE_CONSTRAINT" } },
+                    { "type": "ok", "response": { "type": "get_autocommit", "is_autocommit": false } },
+                ],
+            }),
+            json!({ "baton": null, "results": [{ "type": "ok", "response": { "type": "close" } }] }),
+        ]);
+        let conn = crate::libsql::open(&format!("{}/?authToken=token", url), None).unwrap();
+        conn.execute(conn.ow("BEGIN;")).unwrap();
+        let rows = conn.rows(conn.ow("SELECT name, age, avatar FROM users WHERE name =") + "O'Reilly").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].get("name"), rows[0].get("age"), rows[0].get("avatar")), (Some("Alice"), Some("42"), Some("hi")));
+        assert_eq!((rows[1].get("name"), rows[1].get("age"), rows[1].get("avatar")), (Some("Bob"), Some("6.5"), None));
+        assert!(conn.execute(conn.ow("INSERT INTO users VALUES ('Alice', 42, NULL);")).is_err());
+        assert_eq!(conn.conn.last_error_code(), Some("SQLITE_CONSTRAINT".to_string()));
+        conn.close().unwrap();
+
+        let requests = handle.join().unwrap();
+        let host = format!("Host: {}\r\n", url.trim_start_matches("http://"));
+        assert!(requests.iter().all(|(head, _)| head.starts_with("POST /v2/pipeline ") && head.contains(&host)));
+        assert!(requests.iter().all(|(head, _)| head.contains("Authorization: Bearer token")));
+        let requests = requests.into_iter().map(|(_, body)| body).collect::<Vec<_>>();
+        assert_eq!(requests[1]["baton"], Json::Null);
+        assert_eq!(requests[1]["requests"][0]["type"], "sequence");
+        assert_eq!(requests[1]["requests"][0]["sql"].as_str().map(str::trim_end), Some("BEGIN;"));
+        assert_eq!(requests[2]["baton"], "b1");
+        assert_eq!(requests[2]["requests"][0]["stmt"]["sql"].as_str().map(str::trim_end),
+            Some("SELECT name, age, avatar FROM users WHERE name = 'O''Reilly'"));
+        assert_eq!(requests[4], json!({ "baton": "b1", "requests": [{ "type": "close" }] }));
+    }
+
+    #[test]
+    fn endpoint() {
+        let endpoint = |url: &str| Endpoint::parse(url).map(|(endpoint, token)| {
+            (endpoint.tls, endpoint.host, endpoint.port, endpoint.path, token)
+        });
+        assert_eq!(endpoint("libsql://db.turso.io"), Some((true, "db.turso.io".to_string(), 443, String::new(), None)));
+        assert_eq!(endpoint("http://127.0.0.1:8080/db/?authToken=t&x=1"),
+            Some((false, "127.0.0.1".to_string(), 8080, "/db".to_string(), Some("t".to_string()))));
+        assert_eq!(endpoint("http://127.0.0.1:8080?authToken=a%2Bb%3D"),
+            Some((false, "127.0.0.1".to_string(), 8080, String::new(), Some("a+b=".to_string()))));
+        assert_eq!(endpoint("http://127.0.0.1:8080?authToken=a%2"), None);
+        assert_eq!(endpoint("http://127.0.0.1:8080?authToken=t%0d%0aX-Injected:%201"), None);
+        assert_eq!(endpoint("http://127.0.0.1:8080?authToken=t%00"), None);
+        assert!(crate::libsql::open("http://127.0.0.1:8080", Some("t\r\nX-Injected: 1")).is_err());
+        assert_eq!(endpoint("postgres://localhost"), None);
+        assert_eq!(endpoint("https://:443"), None);
+
+        let authority = |url: &str| Endpoint::parse(url).unwrap().0.authority();
+        assert_eq!(authority("libsql://db.turso.io"), "db.turso.io");
+        assert_eq!(authority("https://db.turso.io:8443"), "db.turso.io:8443");
+        assert_eq!(authority("http://127.0.0.1:80"), "127.0.0.1");
+        assert_eq!(authority("http://127.0.0.1:443"), "127.0.0.1:443");
+    }
+
+    #[test]
+    fn timeout() {
+        // The server accepts the connection and never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let (endpoint, _) = Endpoint::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let conn = Libsql {
+            endpoint,
+            auth_token: None,
+            timeout: Cell::new(Duration::from_millis(100)),
+            baton: RefCell::new(None),
+            changes: Cell::new(None),
+            last_error_code: RefCell::new(None),
+        };
+        let start = Instant::now();
+        assert!(conn.request(json!({ "type": "get_autocommit" })).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(listener);
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(base64_decode("aGVsbG8="), Some(b"hello".to_vec()));
+        assert_eq!(base64_decode("aGk"), Some(b"hi".to_vec()));
+        assert_eq!(base64_decode("a*"), None);
+        assert_eq!(dechunk(b"3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n"), Some(b"abcde".to_vec()));
+        assert_eq!(dechunk(b"3\r\nab"), None);
+        assert_eq!(percent_decode("a%20b%2f%E3%81%82"), Some("a b/あ".to_string()));
+        assert_eq!(percent_decode("%+1"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+}
@@ -0,0 +1,35 @@
+//! Interface to [libSQL](https://github.com/tursodatabase/libsql), such as a database hosted by Turso,
+//! of OverwriteSQL.
+//!
+//! The statements are sent by the [Hrana](https://github.com/tursodatabase/libsql/blob/main/docs/HRANA_3_SPEC.md)
+//! protocol over HTTP, `POST /v2/pipeline`, and are written in the SQLite dialect.
+//! A transaction is kept on the same stream of the server until it ends, and the other statements
+//! are sent on a new stream. The stream is closed by the server if it is idle for a while, which
+//! rolls back the open transaction.
+//!
+//! The rows of [iterate](../struct.Connection.html#method.iterate) are read from a single statement.
+//! The features of the native library, such as the hooks, the user functions, the blobs and the
+//! backups, are not supported.
+
+pub(crate) mod connection;
+
+use crate::Result;
+use crate::connection::Connection;
+
+/// Open a connection to the database at the URL, such as `libsql://db-name.turso.io` with its auth token.
+///
+/// The scheme `libsql` and `https` are sent over TLS, and `http` is not, such as for a local `sqld`.
+/// The token can also be given by the percent-encoded query parameter `authToken` of the URL.  
+/// Each request times out after 30 seconds, which can be changed by
+/// [set_libsql_timeout](../struct.Connection.html#method.set_libsql_timeout).
+///
+/// # Examples
+///
+/// ```no_run
+/// let conn = owsql::libsql::open("libsql://db-name.turso.io", Some("token")).unwrap();
+/// let conn = owsql::libsql::open("http://127.0.0.1:8080", None).unwrap();
+/// ```
+#[inline]
+pub fn open(url: &str, auth_token: Option<&str>) -> Result<Connection> {
+    connection::open(url, auth_token)
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::fingerprint::fingerprint;
+
+/// Aggregated metrics of a single normalized statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatementMetrics {
+    /// The normalized statement. Escaped literals are replaced with `?`.
+    pub statement: String,
+    /// Number of times the statement was executed.
+    pub count:     u64,
+    /// Number of executions that returned an error.
+    pub errors:    u64,
+    /// Total latency of all executions.
+    pub total:     Duration,
+    /// Maximum latency of a single execution.
+    pub max:       Duration,
+}
+
+impl StatementMetrics {
+    fn new(statement: String) -> Self {
+        Self {
+            statement,
+            count:  0,
+            errors: 0,
+            total:  Duration::default(),
+            max:    Duration::default(),
+        }
+    }
+
+    /// Return the mean latency.
+    #[inline]
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / u128::from(self.count)) as u64)
+        }
+    }
+}
+
+pub(crate) type SlowQueryCallback = Box<dyn FnMut(&str, Duration) + Send>;
+
+/// The callback of [set_slow_query_threshold](./struct.Connection.html#method.set_slow_query_threshold).
+pub(crate) struct SlowQuery {
+    pub(crate) threshold: Duration,
+    pub(crate) callback:  SlowQueryCallback,
+}
+
+/// Statement-level metrics collected by a connection.
+/// See [set_metrics](./struct.Connection.html#method.set_metrics).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    statements: HashMap<String, StatementMetrics>,
+}
+
+impl Metrics {
+    pub(crate) fn record(&mut self, statement: String, elapsed: Duration, is_err: bool) {
+        let entry = self.statements
+            .entry(statement)
+            .or_insert_with_key(|statement| StatementMetrics::new(statement.to_string()));
+        entry.count += 1;
+        entry.total += elapsed;
+        if entry.max < elapsed {
+            entry.max = elapsed;
+        }
+        if is_err {
+            entry.errors += 1;
+        }
+    }
+
+    /// Return the metrics of each statement, sorted by statement.
+    pub fn statements(&self) -> Vec<StatementMetrics> {
+        let mut statements = self.statements.values().cloned().collect::<Vec<_>>();
+        statements.sort_by(|a, b| a.statement.cmp(&b.statement));
+        statements
+    }
+
+    /// Return the metrics merged by the [fingerprint](./fn.fingerprint.html) of each statement,
+    /// sorted by fingerprint, so that the statements differing only in the values of the trusted
+    /// fragments such as [int](./struct.Connection.html#method.int) are grouped together.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_metrics(true);
+    /// conn.execute(conn.ow("SELECT") + &conn.int(1)).unwrap();
+    /// conn.execute(conn.ow("SELECT") + &conn.int(2)).unwrap();
+    /// let metrics = conn.metrics().unwrap();
+    /// assert_eq!(metrics.statements().len(), 2);
+    /// assert_eq!(metrics.fingerprints()[0].statement, "SELECT ?");
+    /// assert_eq!(metrics.fingerprints()[0].count, 2);
+    /// ```
+    pub fn fingerprints(&self) -> Vec<StatementMetrics> {
+        let mut merged: HashMap<String, StatementMetrics> = HashMap::new();
+        for m in self.statements.values() {
+            let entry = merged
+                .entry(fingerprint(&m.statement))
+                .or_insert_with_key(|statement| StatementMetrics::new(statement.to_string()));
+            entry.count += m.count;
+            entry.errors += m.errors;
+            entry.total += m.total;
+            entry.max = entry.max.max(m.max);
+        }
+        let mut statements = merged.into_values().collect::<Vec<_>>();
+        statements.sort_by(|a, b| a.statement.cmp(&b.statement));
+        statements
+    }
+
+    /// Export the metrics in the Prometheus text exposition format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_metrics(true);
+    /// conn.execute(conn.ow("SELECT 1;")).unwrap();
+    /// let text = conn.metrics().unwrap().to_prometheus();
+    /// assert!(text.contains(r#"owsql_statement_calls_total{statement="SELECT 1; "} 1"#));
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let statements = self.statements();
+        let mut text = String::new();
+        write_family(&mut text, &statements, "owsql_statement_calls_total", "counter",
+            "Number of executions.", |m| m.count.to_string());
+        write_family(&mut text, &statements, "owsql_statement_errors_total", "counter",
+            "Number of failed executions.", |m| m.errors.to_string());
+        write_family(&mut text, &statements, "owsql_statement_duration_seconds_total", "counter",
+            "Total latency in seconds.", |m| m.total.as_secs_f64().to_string());
+        write_family(&mut text, &statements, "owsql_statement_duration_seconds_max", "gauge",
+            "Maximum latency in seconds.", |m| m.max.as_secs_f64().to_string());
+        text
+    }
+}
+
+fn write_family<F>(text: &mut String, statements: &[StatementMetrics], name: &str, kind: &str, help: &str, value: F)
+where
+    F: Fn(&StatementMetrics) -> String,
+{
+    writeln!(text, "# HELP {} {}", name, help).unwrap();
+    writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+    for m in statements.iter() {
+        writeln!(text, "{}{{statement=\"{}\"}} {}", name, escape_label(&m.statement), value(m)).unwrap();
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"'  => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+             c   => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let mut metrics = Metrics::default();
+        metrics.record("SELECT ? ".to_string(), Duration::from_millis(10), false);
+        metrics.record("SELECT ? ".to_string(), Duration::from_millis(30), true);
+        metrics.record("DELETE ".to_string(),   Duration::from_millis(5),  false);
+
+        let statements = metrics.statements();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].statement, "DELETE ");
+        assert_eq!(statements[1].count,  2);
+        assert_eq!(statements[1].errors, 1);
+        assert_eq!(statements[1].total,  Duration::from_millis(40));
+        assert_eq!(statements[1].max,    Duration::from_millis(30));
+        assert_eq!(statements[1].mean(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn mean() {
+        let mut metrics = StatementMetrics::new("SELECT ".to_string());
+        metrics.count = 1 << 32;
+        metrics.total = Duration::from_secs(1 << 32);
+        assert_eq!(metrics.mean(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn to_prometheus() {
+        let mut metrics = Metrics::default();
+        metrics.record("SELECT \"a\\b\"\n".to_string(), Duration::from_secs(1), false);
+        let text = metrics.to_prometheus();
+        assert!(text.contains("# TYPE owsql_statement_calls_total counter\n"));
+        assert!(text.contains("owsql_statement_calls_total{statement=\"SELECT \\\"a\\\\b\\\"\\n\"} 1\n"));
+        assert!(text.contains("owsql_statement_duration_seconds_max{statement=\"SELECT \\\"a\\\\b\\\"\\n\"} 1\n"));
+    }
+}
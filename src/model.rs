@@ -0,0 +1,180 @@
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+
+/// The timestamp columns filled by [insert](./struct.Connection.html#method.insert),
+/// [update](./struct.Connection.html#method.update) and [soft_delete](./struct.Connection.html#method.soft_delete).
+/// See [set_timestamp_columns](./struct.Connection.html#method.set_timestamp_columns).
+///
+/// # Examples
+///
+/// ```
+/// use owsql::TimestampColumns;
+/// let columns = TimestampColumns {
+///     deleted_at: None,
+///     ..TimestampColumns::standard()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimestampColumns {
+    /// The column set when a row is inserted.
+    pub created_at: Option<String>,
+    /// The column set when a row is inserted, updated or soft-deleted.
+    pub updated_at: Option<String>,
+    /// The column set when a row is soft-deleted. `None` deletes the rows.
+    pub deleted_at: Option<String>,
+}
+
+impl TimestampColumns {
+    /// Return the columns `created_at`, `updated_at` and `deleted_at`.
+    pub fn standard() -> Self {
+        Self {
+            created_at: Some("created_at".to_string()),
+            updated_at: Some("updated_at".to_string()),
+            deleted_at: Some("deleted_at".to_string()),
+        }
+    }
+}
+
+impl Connection {
+    /// Sets the timestamp columns filled by the insert and update helpers. The default fills none.
+    /// See [TimestampColumns](./struct.TimestampColumns.html).
+    pub fn set_timestamp_columns(&mut self, columns: TimestampColumns) {
+        self.timestamp_columns = columns;
+    }
+
+    /// Return the current timestamp of the backend.
+    fn now(&self) -> &'static str {
+        match self.dialect() {
+            Dialect::Postgres | Dialect::Mysql => "NOW()",
+            _                                  => "CURRENT_TIMESTAMP",
+        }
+    }
+
+    /// Quotes the columns of the values followed by the timestamp columns, or returns an error token.
+    fn quote_columns<'a>(&self, columns: impl Iterator<Item = &'a str>, timestamps: &[&'a Option<String>]) -> Result<Vec<String>, String> {
+        let timestamps = timestamps.iter().filter_map(|column| column.as_deref());
+        columns.chain(timestamps)
+            .map(|column| self.quote_column(column).ok_or_else(|| self.ow_error("invalid identifier", column)))
+            .collect()
+    }
+
+    /// It is guaranteed to be an `INSERT` statement of the values without the terminating `;`,
+    /// filling `created_at` and `updated_at` of [set_timestamp_columns](#method.set_timestamp_columns)
+    /// with the current timestamp. The values are the operands in the same way as [Cond](./struct.Cond.html).
+    /// The table is qualified by [set_schema_prefix](#method.set_schema_prefix) in the same way as [table](#method.table).
+    /// An invalid table or column returns the error "invalid identifier" when the statement is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::TimestampColumns;
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER, created_at TEXT, updated_at TEXT);")).unwrap();
+    /// conn.set_timestamp_columns(TimestampColumns { deleted_at: None, ..TimestampColumns::standard() });
+    /// let sql = conn.insert("users", &[("name", "Alice"), ("age", &conn.int(42))]) + &conn.ow(";");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "INSERT INTO \"users\" (\"name\", \"age\", \"created_at\", \"updated_at\") \
+    ///     VALUES ( 'Alice' , 42 , CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) ; ");
+    /// conn.execute(sql).unwrap();
+    /// ```
+    pub fn insert<V: AsRef<str>>(&self, table: &str, values: &[(&str, V)]) -> String {
+        let table = match self.quote_table(table) {
+            Some(table) => table,
+            None => return self.ow_error("invalid identifier", table),
+        };
+        let timestamps = [&self.timestamp_columns.created_at, &self.timestamp_columns.updated_at];
+        let columns = match self.quote_columns(values.iter().map(|(column, _)| *column), &timestamps) {
+            Ok(columns) => columns,
+            Err(e) => return e,
+        };
+        if columns.is_empty() {
+            return self.ow_error("empty column list", &table);
+        }
+        let now = vec![self.now(); columns.len() - values.len()];
+        let mut sql = self.ow_generated(format!("INSERT INTO {} ({}) VALUES (", table, columns.join(", ")));
+        let separator = self.ow_generated(",".to_string());
+        sql += &values.iter().map(|(_, value)| self.operand(value.as_ref())).collect::<Vec<_>>().join(&separator);
+        if values.is_empty() {
+            sql += &self.ow_generated(format!("{})", now.join(", ")));
+        } else if now.is_empty() {
+            sql += &self.ow_generated(")".to_string());
+        } else {
+            sql += &self.ow_generated(format!(", {})", now.join(", ")));
+        }
+        sql
+    }
+
+    /// It is guaranteed to be an `UPDATE` statement of the values without `WHERE`, filling `updated_at`
+    /// of [set_timestamp_columns](#method.set_timestamp_columns) with the current timestamp.
+    /// Concatenate [where_clause](#method.where_clause) to limit the rows.
+    /// An invalid table or column returns the error "invalid identifier" when the statement is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{Cond, TimestampColumns};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER, updated_at TEXT);
+    ///                       INSERT INTO users VALUES ('Alice', 42, NULL);")).unwrap();
+    /// conn.set_timestamp_columns(TimestampColumns { updated_at: Some("updated_at".into()), ..Default::default() });
+    /// let sql = conn.update("users", &[("age", conn.int(43))]) + &conn.where_clause(&Cond::eq("name", "Alice")) + &conn.ow(";");
+    /// conn.execute(sql).unwrap();
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE age = 43 AND updated_at IS NOT NULL;")), Ok(1));
+    /// ```
+    pub fn update<V: AsRef<str>>(&self, table: &str, values: &[(&str, V)]) -> String {
+        let table = match self.quote_table(table) {
+            Some(table) => table,
+            None => return self.ow_error("invalid identifier", table),
+        };
+        let columns = match self.quote_columns(values.iter().map(|(column, _)| *column), &[&self.timestamp_columns.updated_at]) {
+            Ok(columns) => columns,
+            Err(e) => return e,
+        };
+        if columns.is_empty() {
+            return self.ow_error("empty column list", &table);
+        }
+        let mut sql = self.ow_generated(format!("UPDATE {} SET", table));
+        for (i, column) in columns.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            sql += &match values.get(i) {
+                Some((_, value)) => self.ow_generated(format!("{}{} =", separator, column)) + &self.operand(value.as_ref()),
+                None => self.ow_generated(format!("{}{} = {}", separator, column, self.now())),
+            };
+        }
+        sql
+    }
+
+    /// It is guaranteed to be an `UPDATE` statement setting `deleted_at` and `updated_at` of
+    /// [set_timestamp_columns](#method.set_timestamp_columns) to the current timestamp, or a `DELETE`
+    /// statement if `deleted_at` is `None`, without `WHERE`.
+    /// Concatenate [where_clause](#method.where_clause) to limit the rows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{Cond, TimestampColumns};
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, created_at TEXT, updated_at TEXT, deleted_at TEXT);
+    ///                       INSERT INTO users (name) VALUES ('Alice'), ('Bob');")).unwrap();
+    /// conn.set_timestamp_columns(TimestampColumns::standard());
+    /// conn.execute(conn.soft_delete("users") + &conn.where_clause(&Cond::eq("name", "Bob")) + &conn.ow(";")).unwrap();
+    /// let alive = conn.ow("SELECT * FROM users") + &conn.where_clause(&Cond::is_null("deleted_at")) + &conn.ow(";");
+    /// assert_eq!(conn.count(alive), Ok(1));
+    /// ```
+    pub fn soft_delete(&self, table: &str) -> String {
+        let quoted = match self.quote_table(table) {
+            Some(table) => table,
+            None => return self.ow_error("invalid identifier", table),
+        };
+        if self.timestamp_columns.deleted_at.is_none() {
+            return self.ow_generated(format!("DELETE FROM {}", quoted));
+        }
+        let timestamps = [&self.timestamp_columns.deleted_at, &self.timestamp_columns.updated_at];
+        match self.quote_columns(std::iter::empty(), &timestamps) {
+            Ok(columns) => {
+                let set = columns.iter().map(|column| format!("{} = {}", column, self.now())).collect::<Vec<_>>();
+                self.ow_generated(format!("UPDATE {} SET {}", quoted, set.join(", ")))
+            },
+            Err(e) => e,
+        }
+    }
+}
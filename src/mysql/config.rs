@@ -0,0 +1,232 @@
+extern crate mysql_sys as mysql;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::failover::{HostStatus, Hosts};
+use super::connection::backend;
+#[cfg(feature = "native-tls")]
+use crate::tls::TlsConfig;
+
+/// Connection configuration.
+/// Each value is passed to the driver as it is, so no URL is assembled.
+///
+/// # Examples
+///
+/// ```rust
+/// let conn = owsql::mysql::Config::new()
+///     .host("localhost")
+///     .user("user")
+///     .password("password")
+///     .dbname("db_name")
+///     .open()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Config {
+    hosts:            Hosts,
+    port:             Option<u16>,
+    user:             Option<String>,
+    password:         Option<String>,
+    dbname:           Option<String>,
+    application_name: Option<String>,
+    connect_timeout:  Option<Duration>,
+    charset:          String,
+    collation:        Option<String>,
+    local_infile:     bool,
+    #[cfg(feature = "native-tls")]
+    tls:              Option<TlsConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            hosts:            Hosts::default(),
+            port:             None,
+            user:             None,
+            password:         None,
+            dbname:           None,
+            application_name: None,
+            connect_timeout:  None,
+            charset:          "utf8mb4".to_string(),
+            collation:        None,
+            local_infile:     false,
+            #[cfg(feature = "native-tls")]
+            tls:              None,
+        }
+    }
+}
+
+impl Config {
+    /// Creates a new configuration with no values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the host name or the IP address of the server. The default is `127.0.0.1`.
+    /// If multiple hosts are added, they are tried in order until the connection succeeds,
+    /// and the hosts that failed last time are tried after the others.
+    pub fn host(mut self, host: &str) -> Self {
+        self.hosts.push(host);
+        self
+    }
+
+    /// Rotates the first host to try on each [open](#method.open), to spread the connections
+    /// across the hosts, for example, the readers of [ReplicaSet](../struct.ReplicaSet.html).
+    /// The default is `false`.
+    pub fn round_robin(mut self, round_robin: bool) -> Self {
+        self.hosts.set_round_robin(round_robin);
+        self
+    }
+
+    /// Return the health of each host, which is shared by the clones of the configuration.
+    pub fn host_status(&self) -> Vec<HostStatus> {
+        self.hosts.statuses()
+    }
+
+    /// Sets the port of the server. The default is 3306.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Sets the user name to authenticate with.
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    /// Sets the password to authenticate with.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// Sets the name of the database.
+    pub fn dbname(mut self, dbname: &str) -> Self {
+        self.dbname = Some(dbname.to_string());
+        self
+    }
+
+    /// Sets the `program_name` connection attribute reported to the server.
+    pub fn application_name(mut self, application_name: &str) -> Self {
+        self.application_name = Some(application_name.to_string());
+        self
+    }
+
+    /// Sets the timeout for establishing the connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the connection charset and collation. The default is `utf8mb4`.
+    /// See [open_with_charset](fn.open_with_charset.html).
+    pub fn charset(mut self, charset: &str, collation: Option<&str>) -> Self {
+        self.charset = charset.to_string();
+        self.collation = collation.map(str::to_string);
+        self
+    }
+
+    /// Allows [load_data](../struct.Connection.html#method.load_data) to send data by `LOAD DATA LOCAL INFILE`.
+    /// The default is `false`.
+    /// The server must also enable `local_infile`.
+    /// Only the data given to `load_data` is sent, whatever file the server requests.
+    pub fn local_infile(mut self, enabled: bool) -> Self {
+        self.local_infile = enabled;
+        self
+    }
+
+    /// Connects over TLS.
+    #[cfg(feature = "native-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Open a read-write connection with the configuration.
+    pub fn open(&self) -> Result<Connection> {
+        let mut opts = mysql::OptsBuilder::new()
+            .user(self.user.clone())
+            .pass(self.password.clone())
+            .db_name(self.dbname.clone())
+            .tcp_connect_timeout(self.connect_timeout);
+        if let Some(port) = self.port {
+            opts = opts.tcp_port(port);
+        }
+        if let Some(application_name) = &self.application_name {
+            let mut attrs = HashMap::new();
+            attrs.insert("program_name", application_name.clone());
+            opts = opts.connect_attrs(attrs);
+        }
+
+        let opts: mysql::Opts = opts.into();
+        let collation = self.collation.as_deref();
+        let conn = self.hosts.connect(|host| {
+            let opts = mysql::OptsBuilder::from_opts(opts.clone()).ip_or_hostname(host);
+            #[cfg(feature = "native-tls")]
+            if let Some(tls) = &self.tls {
+                return super::connection::connect_with_tls(opts.into(), &self.charset, collation, tls);
+            }
+            super::connection::connect(opts.into(), &self.charset, collation)
+        })?;
+        backend(&conn, "local infile")?.set_local_infile(self.local_infile);
+        Ok(conn)
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("hosts", &self.hosts.hosts())
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "_"))
+            .field("dbname", &self.dbname)
+            .field("application_name", &self.application_name)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("charset", &self.charset)
+            .field("collation", &self.collation)
+            .field("local_infile", &self.local_infile)
+            .finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open() {
+        let config = Config::new()
+            .host("localhost")
+            .port(3306)
+            .dbname("test")
+            .application_name("owsql")
+            .connect_timeout(Duration::from_secs(5));
+        assert!(config.open().is_ok());
+        assert_eq!(config.clone().charset("sjis", None).open().unwrap().charset(), crate::Charset::Sjis);
+        assert!(config.charset("utf8mb4; DROP TABLE users", None).open().is_err());
+        assert!(!format!("{:?}", Config::new().password("secret")).contains("secret"));
+    }
+
+    #[test]
+    fn failover() {
+        let config = Config::new()
+            .host("owsql.invalid")
+            .host("localhost")
+            .dbname("test")
+            .connect_timeout(Duration::from_secs(5));
+        assert!(config.open().is_ok());
+        let status = config.host_status();
+        assert_eq!(status[0].healthy, Some(false));
+        assert_eq!(status[1].healthy, Some(true));
+        assert!(config.clone().round_robin(true).open().is_ok());
+        assert_eq!(config.host_status()[0].failures, 1);
+    }
+}
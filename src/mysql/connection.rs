@@ -1,43 +1,141 @@
 extern crate mysql_sys as mysql;
-use mysql::{Opts, Conn};
+use mysql::{Opts, OptsBuilder, Conn};
 use mysql::prelude::*;
+#[cfg(feature = "native-tls")]
+use mysql::SslOpts;
 
-use std::collections::HashSet;
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::ops::Deref;
 
 use crate::Result;
-use crate::connection::{Connection, OwsqlConn};
-use crate::bidimap::BidiMap;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
 use crate::error::{OwsqlError, OwsqlErrorLevel};
-use crate::constants::OW_MINIMUM_LENGTH;
-use crate::serial::SerialNumber;
-use crate::parser::escape_string;
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+#[cfg(feature = "native-tls")]
+use crate::tls::{TlsConfig, TlsMode};
 
 /// Open a read-write connection to a new or existing database.
-pub fn open(url: &str) -> Result<Connection> {
-    let opts = match Opts::from_url(&url) {
-        Ok(opts) => opts,
-        Err(e) => return Err(OwsqlError::Message(format!("failed to open: {}", e))),
-    };
+pub fn open(url: &str, charset: &str, collation: Option<&str>) -> Result<Connection> {
+    connect(parse_url(url)?, charset, collation)
+}
+
+/// Open a read-write connection over TLS.
+#[cfg(feature = "native-tls")]
+pub fn open_with_tls(url: &str, charset: &str, collation: Option<&str>, tls: &TlsConfig) -> Result<Connection> {
+    connect_with_tls(parse_url(url)?, charset, collation, tls)
+}
+
+fn parse_url(url: &str) -> Result<Opts> {
+    match Opts::from_url(url) {
+        Ok(opts) => Ok(opts),
+        Err(e) => Err(OwsqlError::Message(format!("failed to open: {}", e))),
+    }
+}
+
+#[cfg(feature = "native-tls")]
+pub(crate) fn connect_with_tls(opts: Opts, charset: &str, collation: Option<&str>, tls: &TlsConfig) -> Result<Connection> {
+    let ssl_opts = SslOpts::default()
+        .with_root_cert_path(tls.root_cert.clone())
+        .with_danger_accept_invalid_certs(tls.accept_invalid_certs)
+        .with_danger_skip_domain_validation(tls.accept_invalid_certs);
+    let tls_opts = OptsBuilder::from_opts(opts.clone()).ssl_opts(ssl_opts).into();
+
+    match tls.mode {
+        TlsMode::Disable => connect(opts, charset, collation),
+        TlsMode::Prefer  => connect(tls_opts, charset, collation)
+            .or_else(|_| connect(opts, charset, collation)),
+        TlsMode::Require => connect(tls_opts, charset, collation),
+    }
+}
+
+pub(crate) fn connect(opts: Opts, charset: &str, collation: Option<&str>) -> Result<Connection> {
+    if !is_valid_name(charset) || !collation.is_none_or(is_valid_name) {
+        return Err(OwsqlError::Message(format!("invalid charset: {} {}", charset, collation.unwrap_or_default())));
+    }
+
+    let mut init = opts.get_init();
+    init.insert(0, match collation {
+        Some(collation) => format!("SET NAMES {} COLLATE {}", charset, collation),
+        None            => format!("SET NAMES {}", charset),
+    });
+    let opts = OptsBuilder::from_opts(opts).init(init);
 
     let conn = match Conn::new(opts) {
         Ok(conn) => conn,
         Err(e) => return Err(OwsqlError::Message(format!("failed to open: {}", e))),
     };
 
-    Ok(Connection {
-        conn:              Box::new(RefCell::new(conn)),
-        allowlist:         HashSet::new(),
-        serial_number:     RefCell::new(SerialNumber::default()),
-        ow_len_range:      (OW_MINIMUM_LENGTH, OW_MINIMUM_LENGTH),
-        overwrite:         RefCell::new(BidiMap::new()),
-        whitespace_around: RefCell::new(BidiMap::new()),
-        error_msg:         RefCell::new(BidiMap::new()),
-        error_level:       OwsqlErrorLevel::default(),
-    })
+    Ok(Connection::new(Box::new(MysqlConn::new(conn))))
+}
+
+/// Charset and collation names are interpolated into `SET NAMES`.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Return the MySQL connection, or an error if the connection is of another database.
+pub(crate) fn backend<'a>(conn: &'a Connection, feature: &str) -> Result<&'a MysqlConn> {
+    match conn.conn.as_any().downcast_ref::<MysqlConn>() {
+        Some(mysql) => Ok(mysql),
+        None => Err(OwsqlError::Message(format!("unsupported {}", feature))),
+    }
+}
+
+/// Quotes each part of the dot-separated name as an identifier.
+pub(crate) fn quote_identifier(name: &str) -> Result<String> {
+    if name.split('.').any(str::is_empty) || name.contains('\0') {
+        return Err(OwsqlError::Message(format!("invalid identifier: {:?}", name)));
+    }
+    Ok(name.split('.').map(|part| format!("`{}`", part.replace('`', "``"))).collect::<Vec<_>>().join("."))
+}
+
+/// A connection and the error code of its most recent failed statement.
+pub(crate) struct MysqlConn {
+    conn:         RefCell<mysql::Conn>,
+    last_error:   RefCell<Option<String>>,
+    local_infile: Cell<bool>,
 }
 
-impl OwsqlConn for RefCell<mysql::Conn> {
+impl MysqlConn {
+    fn new(conn: mysql::Conn) -> Self {
+        Self {
+            conn:         RefCell::new(conn),
+            last_error:   RefCell::new(None),
+            local_infile: Cell::new(false),
+        }
+    }
+
+    /// Checks if `LOAD DATA LOCAL INFILE` is allowed by [Config::local_infile](struct.Config.html#method.local_infile).
+    pub(crate) fn local_infile(&self) -> bool {
+        self.local_infile.get()
+    }
+
+    pub(crate) fn set_local_infile(&self, enabled: bool) {
+        self.local_infile.set(enabled);
+    }
+
+    pub(crate) fn exec_error(&self, error_level: &OwsqlErrorLevel, e: &mysql::Error) -> Result<()> {
+        let (code, sqlstate) = match e {
+            mysql::Error::MySqlError(e) => (Some(e.code as i32), Some(e.state.clone())),
+            mysql::Error::IoError(_) | mysql::Error::CodecError(_) => (None, Some("08S01".to_string())),
+            _ => (None, None),
+        };
+        *self.last_error.borrow_mut() = code.map(|code| code.to_string());
+        OwsqlError::database(error_level, code, sqlstate, None, &e.to_string())
+    }
+}
+
+impl Deref for MysqlConn {
+    type Target = RefCell<mysql::Conn>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl OwsqlConn for MysqlConn {
     fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
         let query = match query {
             Ok(query) => query,
@@ -48,14 +146,33 @@ impl OwsqlConn for RefCell<mysql::Conn> {
             },
         };
 
+        *self.last_error.borrow_mut() = None;
         match self.borrow_mut().query_drop(&query) {
             Ok(_) => Ok(()),
-            Err(e) => OwsqlError::new(&error_level, "exec error", &e.to_string()),
+            Err(e) => self.exec_error(error_level, &e),
+        }
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(0);
+            } else {
+                return Err(e);
+            },
+        };
+
+        *self.last_error.borrow_mut() = None;
+        let mut conn = self.borrow_mut();
+        match conn.query_drop(&query) {
+            Ok(_) => Ok(conn.affected_rows()),
+            Err(e) => self.exec_error(error_level, &e).map(|_| 0),
         }
     }
 
     fn _iterate(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
-        callback: &mut dyn FnMut(&[(&str, Option<&str>)]) -> bool) -> Result<()>
+        callback: IterateCallback) -> Result<()>
     {
         let query = match query {
             Ok(query) => query,
@@ -66,46 +183,91 @@ impl OwsqlConn for RefCell<mysql::Conn> {
             },
         };
 
+        *self.last_error.borrow_mut() = None;
         let mut conn = self.borrow_mut();
         let mut result = match conn.query_iter(&query) {
             Ok(result) => result,
-            Err(e) => return OwsqlError::new(&error_level, "exec error", &e.to_string()),
+            Err(e) => return self.exec_error(error_level, &e),
         };
 
         while let Some(result_set) = result.next_set() {
             let result_set = match result_set {
                 Ok(result_set) => result_set,
-                Err(e) => return OwsqlError::new(&error_level, "exec error", &e.to_string()),
+                Err(e) => return self.exec_error(error_level, &e),
             };
-            let mut pairs: Vec<(String, Option<String>)> = Vec::with_capacity(result_set.affected_rows() as usize);
-
             for row in result_set {
                 let row = match row {
                     Ok(row) => row,
-                    Err(e) => return OwsqlError::new(&error_level, "exec error", &e.to_string()),
+                    Err(e) => return self.exec_error(error_level, &e),
                 };
 
-                for (i, col) in row.columns().iter().enumerate() {
-                    pairs.push((col.name_str().to_string(), row.get(i)));
+                let pairs: Vec<(String, Option<String>)> = row.columns().iter().enumerate()
+                    .map(|(i, col)| (col.name_str().to_string(), row.get(i)))
+                    .collect();
+                let pairs: Vec<(&str, Option<&str>)> = pairs.iter().map(|p| (&*p.0, p.1.as_deref())).collect();
+                if !pairs.is_empty() && !callback(&pairs) {
+                    return OwsqlError::new(error_level, "exec error", "query aborted");
                 }
-
-            }
-
-            let pairs: Vec<(&str, Option<&str>)> = pairs.iter().map(|p| (&*p.0, p.1.as_deref())).collect();
-            if !pairs.is_empty() && !callback(&pairs) {
-                return OwsqlError::new(&error_level, "exec error", "query aborted");
             }
         }
 
         Ok(())
     }
 
-    fn must_escape(&self) -> Box<dyn Fn(char) -> bool> {
-        Box::new(|c| c == '\'' || c == '\\')
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+
+        *self.last_error.borrow_mut() = None;
+        let mut conn = self.borrow_mut();
+        match conn.prep(&query).and_then(|stmt| conn.close(stmt)) {
+            Ok(_) => Ok(()),
+            Err(e) => self.exec_error(error_level, &e),
+        }
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        match self.borrow_mut().query_first::<String, _>("SELECT @@SESSION.sql_mode") {
+            Ok(Some(sql_mode)) if sql_mode.split(',').any(|mode| mode == "NO_BACKSLASH_ESCAPES") => EscapeMode::Standard,
+            _ => EscapeMode::Backslash,
+        }
+    }
+
+    fn charset(&self) -> Charset {
+        match self.borrow_mut().query_first::<String, _>("SELECT @@SESSION.character_set_client") {
+            Ok(Some(charset)) => Charset::from_name(&charset),
+            _ => Charset::Other,
+        }
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Mysql
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn last_error_code(&self) -> Option<String> {
+        self.last_error.borrow().clone()
+    }
+
+    fn changes(&self) -> Option<u64> {
+        Some(self.borrow().affected_rows())
     }
 
-    fn literal_escape(&self, s: &str) -> String {
-        escape_string(&s, self.must_escape())
+    fn max_statement_len(&self) -> Option<usize> {
+        self.borrow_mut().query_first::<usize, _>("SELECT @@SESSION.max_allowed_packet").unwrap_or_default()
+    }
+
+    fn is_closed(&self) -> bool {
+        !self.borrow_mut().ping()
     }
 }
 
@@ -132,17 +294,49 @@ mod tests {
         assert_eq!(format!("{:?}", &conn), format!("{:?}", &conn));
     }
 
+    #[test]
+    fn escape_mode() {
+        let conn = crate::mysql::open("mysql://localhost:3306/test").unwrap();
+        assert_eq!(conn.escape_mode(), crate::EscapeMode::Backslash);
+    }
+
+    #[test]
+    #[cfg(feature = "native-tls")]
+    fn open_with_tls() {
+        use crate::{TlsConfig, TlsMode};
+        let url = "mysql://localhost:3306/test";
+        let disable = TlsConfig { mode: TlsMode::Disable, ..Default::default() };
+        assert!(crate::mysql::open_with_tls(url, &disable).is_ok());
+        assert!(crate::mysql::open_with_tls(url, &TlsConfig::default()).is_ok());
+        let root_cert = TlsConfig { mode: TlsMode::Require, root_cert: Some("/path/to/ca.der".into()), ..Default::default() };
+        assert!(crate::mysql::open_with_tls(url, &root_cert).is_err());
+    }
+
+    #[test]
+    fn charset() {
+        let conn = crate::mysql::open("mysql://localhost:3306/test").unwrap();
+        assert_eq!(conn.charset(), crate::Charset::Utf8);
+        let conn = crate::mysql::open_with_charset("mysql://localhost:3306/test", "sjis", None).unwrap();
+        assert_eq!(conn.charset(), crate::Charset::Sjis);
+        let conn = crate::mysql::open_with_charset("mysql://localhost:3306/test", "utf8mb4", Some("utf8mb4_bin")).unwrap();
+        assert_eq!(conn.charset(), crate::Charset::Utf8);
+        assert_eq!(
+            crate::mysql::open_with_charset("mysql://localhost:3306/test", "utf8mb4; DROP TABLE users", None),
+            Err(OwsqlError::Message("invalid charset: utf8mb4; DROP TABLE users ".into()))
+        );
+    }
+
     #[test]
     #[cfg(debug_assertions)]
     fn execute() {
         let conn = crate::mysql::open("mysql://localhost:3306/test").unwrap();
         assert_eq!(
-            conn.execute("\0"),
-            Err(OwsqlError::Message("exec error".into())),
+            conn.execute(conn.ow("\0")),
+            Err(OwsqlError::Database { code: Some(1064), sqlstate: Some("42000".into()), message: "exec error".into(), constraint: None }),
         );
         assert_eq!(
             conn.execute("invalid query"),
-            Err(OwsqlError::Message("exec error".into())),
+            Err(OwsqlError::Database { code: Some(1064), sqlstate: Some("42000".into()), message: "exec error".into(), constraint: None }),
         );
     }
 
@@ -151,12 +345,12 @@ mod tests {
     fn iterate() {
         let conn = crate::mysql::open("mysql://localhost:3306/test").unwrap();
         assert_eq!(
-            conn.iterate("\0", |_| { unreachable!(); }),
-            Err(OwsqlError::Message("exec error".into())),
+            conn.iterate(conn.ow("\0"), |_| { unreachable!(); }),
+            Err(OwsqlError::Database { code: Some(1064), sqlstate: Some("42000".into()), message: "exec error".into(), constraint: None }),
         );
         assert_eq!(
             conn.iterate("invalid query", |_| { unreachable!(); }),
-            Err(OwsqlError::Message("exec error".into())),
+            Err(OwsqlError::Database { code: Some(1064), sqlstate: Some("42000".into()), message: "exec error".into(), constraint: None }),
         );
     }
 }
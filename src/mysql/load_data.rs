@@ -0,0 +1,99 @@
+extern crate mysql_sys as mysql;
+use mysql::LocalInfileHandler;
+use mysql::prelude::*;
+
+use std::io::{self, Read};
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use super::connection::{backend, quote_identifier};
+
+/// The file name requested by `LOAD DATA LOCAL INFILE`.
+/// The server may request any other file, which is refused.
+const FILE_NAME: &str = "owsql";
+
+impl Connection {
+    /// Loads the rows from the reader into the columns of the table by `LOAD DATA LOCAL INFILE`,
+    /// and return the number of the rows.
+    /// The data must be in the default format of `LOAD DATA`, that is,
+    /// the columns are separated by tabs and the rows by newlines.
+    /// If the columns are empty, all the columns of the table are loaded in order.
+    /// The table and the columns are quoted as identifiers.
+    /// It fails unless the connection is opened with [Config::local_infile](mysql/struct.Config.html#method.local_infile).
+    /// Only for MySQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let conn = owsql::mysql::Config::new()
+    ///     .host("localhost")
+    ///     .dbname("test")
+    ///     .local_infile(true)
+    ///     .open()
+    ///     .unwrap();
+    /// # conn.execute(conn.ow("CREATE TEMPORARY TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// let data = "42\tAlice\n69\tBob\n";
+    /// assert_eq!(conn.load_data("users", &["age", "name"], data.as_bytes()), Ok(2));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "mysql")))]
+    pub fn load_data<R: Read + Send + 'static>(&self, table: &str, columns: &[&str], reader: R) -> Result<u64> {
+        let mysql = backend(self, "load data")?;
+        if !mysql.local_infile() {
+            return Err(OwsqlError::Message("local infile disabled".into()));
+        }
+        let mut sql = format!("LOAD DATA LOCAL INFILE '{}' INTO TABLE {}", FILE_NAME, quote_identifier(table)?);
+        if !columns.is_empty() {
+            let columns = columns.iter().map(|column| quote_identifier(column)).collect::<Result<Vec<_>>>()?;
+            sql += &format!(" ({})", columns.join(","));
+        }
+        let start = self.start_statement(&sql);
+
+        let mut reader = Some(reader);
+        let handler = LocalInfileHandler::new(move |file_name, infile| {
+            if file_name != FILE_NAME.as_bytes() {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied,
+                    format!("unexpected file: {}", String::from_utf8_lossy(file_name))));
+            }
+            match reader.take() {
+                Some(mut reader) => io::copy(&mut reader, infile).map(|_| ()),
+                None => Ok(()),
+            }
+        });
+
+        let mut conn = mysql.borrow_mut();
+        conn.set_local_infile_handler(Some(handler));
+        let result = match conn.query_drop(&sql) {
+            Ok(()) => Ok(conn.affected_rows()),
+            Err(e) => mysql.exec_error(&self.error_level, &e).map(|_| 0),
+        };
+        conn.set_local_infile_handler(None);
+        drop(conn);
+
+        self.record_statement(&sql, start, result.as_ref().err());
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::error::OwsqlError;
+
+    #[test]
+    fn load_data() {
+        let config = crate::mysql::Config::new().host("localhost").dbname("test");
+        let conn = config.clone().local_infile(true).open().unwrap();
+        conn.execute(conn.ow("CREATE TEMPORARY TABLE `user``s` (name TEXT, age INTEGER);")).unwrap();
+        assert_eq!(conn.load_data("user`s", &["age", "name"], &b"42\tAlice\n69\tBob' OR 'a'='a\n"[..]), Ok(2));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM `user``s` WHERE name = 'Bob'' OR ''a''=''a';")), Ok(1));
+        assert_eq!(conn.load_data("user`s", &[], &b"Carol\t50\n"[..]), Ok(1));
+        assert!(conn.load_data("user`s", &[""], &b""[..]).is_err());
+
+        let conn = config.open().unwrap();
+        assert_eq!(
+            conn.load_data("users", &[], &b""[..]),
+            Err(OwsqlError::Message("local infile disabled".into()))
+        );
+    }
+}
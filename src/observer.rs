@@ -0,0 +1,99 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+use crate::time::Instant;
+
+/// Callbacks of the lifecycle events of a connection, to build instrumentation such as tracing spans.
+/// See [set_observer](./struct.Connection.html#method.set_observer).
+///
+/// Every callback does nothing by default.
+/// Statements are passed with the string literals redacted as in
+/// [actual_sql_redacted](./struct.Connection.html#method.actual_sql_redacted).
+pub trait Observer: Send + Sync {
+    /// Called when the observer is installed on the open connection.
+    fn on_connect(&self, _dialect: Dialect) {}
+    /// Called when the connection is dropped.
+    fn on_disconnect(&self) {}
+    /// Called before a statement is executed.
+    fn on_statement_start(&self, _sql: &str) {}
+    /// Called after a statement is executed, including fetching the rows.
+    fn on_statement_end(&self, _sql: &str, _elapsed: Duration, _error: Option<&OwsqlError>) {}
+    /// Called when a statement fails, after [on_statement_end](#method.on_statement_end).
+    fn on_error(&self, _sql: &str, _error: &OwsqlError) {}
+    /// Called when [with_transaction](./struct.Connection.html#method.with_transaction) begins a transaction.
+    fn on_transaction_begin(&self) {}
+    /// Called when the transaction is committed.
+    fn on_commit(&self) {}
+    /// Called when the transaction is rolled back.
+    fn on_rollback(&self) {}
+}
+
+impl Connection {
+    /// Installs the observer of the lifecycle events, replacing the previous one.
+    /// The same observer can be installed on many connections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    /// use owsql::{Observer, OwsqlError};
+    ///
+    /// #[derive(Default)]
+    /// struct Log(Mutex<Vec<String>>);
+    ///
+    /// impl Observer for Log {
+    ///     fn on_statement_end(&self, sql: &str, _elapsed: Duration, error: Option<&OwsqlError>) {
+    ///         self.0.lock().unwrap().push(format!("{}: {}", sql.trim_end(), if error.is_some() { "failed" } else { "ok" }));
+    ///     }
+    /// }
+    ///
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let log = Arc::new(Log::default());
+    /// conn.set_observer(log.clone());
+    /// conn.execute(conn.ow("SELECT") + "Alice").unwrap();
+    /// assert_eq!(*log.0.lock().unwrap(), ["SELECT ?: ok"]);
+    /// ```
+    pub fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        observer.on_connect(self.dialect());
+        self.observer = Some(observer);
+    }
+
+    /// Removes the observer set by [set_observer](#method.set_observer).
+    pub fn clear_observer(&mut self) {
+        self.observer = None;
+    }
+
+    /// Notifies the start of a statement, and return the time it started.
+    pub(crate) fn start_statement(&self, query: &str) -> Instant {
+        if let Some(observer) = self.observer.as_ref() {
+            observer.on_statement_start(&self.statement_text(query));
+        }
+        #[cfg(feature = "otel")]
+        self.start_span(&self.statement_text(query));
+        Instant::now()
+    }
+
+    /// Notifies the end of a statement started by [start_statement](#method.start_statement).
+    pub(crate) fn end_statement(&self, query: &str, elapsed: Duration, error: Option<&OwsqlError>) {
+        if let Some(observer) = self.observer.as_ref() {
+            let sql = self.statement_text(query);
+            observer.on_statement_end(&sql, elapsed, error);
+            if let Some(error) = error {
+                observer.on_error(&sql, error);
+            }
+        }
+        #[cfg(feature = "otel")]
+        self.end_span(error);
+    }
+
+    /// Notifies an event of the observer if it is installed.
+    pub(crate) fn observe(&self, event: fn(&dyn Observer)) {
+        if let Some(observer) = self.observer.as_ref() {
+            event(observer.as_ref());
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+
+use odbc_api::{ConnectionOptions, Cursor, CursorImpl, Error, ResultSetMetadata};
+use odbc_api::handles::{AsStatementRef, SqlText, Statement as _, slice_to_cow_utf8};
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+use super::Profile;
+
+/// Open a connection by the connection string.
+pub(crate) fn open(connection_string: &str, profile: Profile) -> Result<Connection> {
+    let conn = odbc_api::environment()
+        .and_then(|env| env.connect_with_connection_string(connection_string, ConnectionOptions::default()));
+    match conn {
+        Ok(conn) => Ok(Connection::new(Box::new(Odbc {
+            conn,
+            profile,
+            last_error_code: RefCell::new(None),
+            changes:         Cell::new(None),
+        }))),
+        Err(_) => Err(OwsqlError::Message("failed to connect".into())),
+    }
+}
+
+/// A connection by ODBC, which is disconnected on drop.
+struct Odbc {
+    conn:            odbc_api::Connection<'static>,
+    profile:         Profile,
+    /// SQLSTATE of the most recent statement.
+    last_error_code: RefCell<Option<String>>,
+    changes:         Cell<Option<u64>>,
+}
+
+impl Odbc {
+    /// Executes the statements, and calls the callback with each row of each result set until it
+    /// returns `false`. Return the number of the rows changed by the last statement, and whether the
+    /// rows were stopped.
+    fn run(&self, query: &str, callback: Option<IterateCallback>) -> std::result::Result<(u64, bool), Error> {
+        let mut stmt = self.conn.preallocate()?.into_handle();
+        // A statement which changed no rows, such as `DELETE` of no rows, returns `SQL_NO_DATA`.
+        unsafe { stmt.exec_direct(&SqlText::new(query)) }.into_result_bool(&stmt)?;
+        // The results are read by the cursor even if the first one has no columns, so that the
+        // result sets of the following statements are not skipped.
+        let mut cursor = unsafe { CursorImpl::new(stmt) };
+        let mut callback = callback;
+        let mut changes = 0;
+        loop {
+            let mut stmt = cursor.as_stmt_ref();
+            if let Ok(count) = stmt.row_count().into_result(&stmt) {
+                changes = count.max(0) as u64;
+            }
+            if let Some(callback) = callback.as_mut() {
+                let columns = cursor.column_names()?.collect::<std::result::Result<Vec<_>, _>>()?;
+                if !columns.is_empty() {
+                    let mut buf = Vec::new();
+                    while let Some(mut row) = cursor.next_row()? {
+                        let values = (1..=columns.len() as u16).map(|column| {
+                            let value = row.get_text(column, &mut buf)?;
+                            Ok(value.then(|| String::from_utf8_lossy(&buf).into_owned()))
+                        }).collect::<std::result::Result<Vec<_>, Error>>()?;
+                        let pairs = columns.iter()
+                            .zip(&values)
+                            .map(|(column, value)| (column.as_str(), value.as_deref()))
+                            .collect::<Vec<_>>();
+                        if !callback(&pairs) {
+                            return Ok((changes, true));
+                        }
+                    }
+                }
+            }
+            cursor = match cursor.more_results()? {
+                Some(cursor) => cursor,
+                None => return Ok((changes, false)),
+            };
+        }
+    }
+
+    /// Records the SQLSTATE of the error, and converts it into the error of the level.
+    fn error<T: Default>(&self, error: Error, error_level: &OwsqlErrorLevel) -> Result<T> {
+        match error {
+            Error::Diagnostics { record, .. } => {
+                let state = record.state.as_str().to_string();
+                *self.last_error_code.borrow_mut() = Some(state.clone());
+                OwsqlError::database(error_level, Some(record.native_error), Some(state), None,
+                    &slice_to_cow_utf8(&record.message))?;
+            },
+            error => OwsqlError::database(error_level, None, None, None, &error.to_string())?,
+        }
+        Ok(T::default())
+    }
+}
+
+impl OwsqlConn for Odbc {
+    fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        self._execute_changes(query, error_level).map(|_| ())
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(0);
+            } else {
+                return Err(e);
+            },
+        };
+        *self.last_error_code.borrow_mut() = None;
+        match self.run(&query, None) {
+            Ok((changes, _)) => {
+                self.changes.set(Some(changes));
+                Ok(changes)
+            },
+            Err(error) => self.error(error, error_level),
+        }
+    }
+
+    fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
+        callback: IterateCallback) -> Result<()>
+    {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        *self.last_error_code.borrow_mut() = None;
+        match self.run(&query, Some(callback)) {
+            // The same error as the callback of `sqlite3_exec` aborting the query.
+            Ok((_, true)) => OwsqlError::database(error_level, None, None, None, "query aborted"),
+            Ok((changes, false)) => {
+                self.changes.set(Some(changes));
+                Ok(())
+            },
+            Err(error) => self.error(error, error_level),
+        }
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        // Some drivers, such as of SQL Server, defer the preparation to the execution, so the errors
+        // may not be found.
+        match self.conn.prepare(&query) {
+            Ok(_) => Ok(()),
+            Err(error) => self.error(error, error_level),
+        }
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        self.profile.escape_mode
+    }
+
+    fn charset(&self) -> Charset {
+        self.profile.charset
+    }
+
+    /// The escaping is given by the profile, which does not follow the session settings.
+    fn detects_escaping(&self) -> bool {
+        false
+    }
+
+    fn dialect(&self) -> Dialect {
+        self.profile.dialect
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn last_error_code(&self) -> Option<String> {
+        self.last_error_code.borrow().clone()
+    }
+
+    fn changes(&self) -> Option<u64> {
+        self.changes.get()
+    }
+}
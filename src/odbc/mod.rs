@@ -0,0 +1,36 @@
+//! Interface to the databases reached by [ODBC](https://learn.microsoft.com/en-us/sql/odbc/reference/odbc-overview),
+//! such as SQL Server and Oracle, of OverwriteSQL.
+//!
+//! The backend uses the [odbc-api](https://docs.rs/odbc-api) crate, which links the driver manager,
+//! `odbc32` on Windows and `libodbc` of unixODBC on the others, and the statements are sent as they
+//! are to the driver. The dialect, the escaping and the charset
+//! are not known from the driver, so they are given by a [Profile](struct.Profile.html).
+//!
+//! The SQL generated by the library, such as `LIMIT` of [paginate](../struct.Connection.html#method.paginate),
+//! is written in the dialect of the profile. The helpers which have no equivalent in the dialect,
+//! such as [next_id](../struct.Connection.html#method.next_id) on SQL Server and Oracle, return an
+//! "unsupported" error instead.
+
+pub(crate) mod connection;
+mod profile;
+
+pub use profile::Profile;
+
+use crate::Result;
+use crate::connection::Connection;
+
+/// Open a connection by the connection string of the driver.
+///
+/// # Examples
+///
+/// ```no_run
+/// use owsql::odbc::Profile;
+/// let conn = owsql::odbc::open(
+///     "Driver={ODBC Driver 18 for SQL Server};Server=localhost;Database=db_name;UID=user;PWD=password;",
+///     Profile::sql_server(),
+/// ).unwrap();
+/// ```
+#[inline]
+pub fn open(connection_string: &str, profile: Profile) -> Result<Connection> {
+    connection::open(connection_string, profile)
+}
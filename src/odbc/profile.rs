@@ -0,0 +1,67 @@
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+
+/// How the statements are written for the driver: the dialect of the identifiers and the generated
+/// SQL, the escaping of the strings, and the charset in which the database reads the statements.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{Charset, Dialect, EscapeMode};
+/// use owsql::odbc::Profile;
+/// // MySQL without `NO_BACKSLASH_ESCAPES`, reading the statements in Shift_JIS.
+/// let profile = Profile::mysql().charset(Charset::Sjis);
+/// assert_eq!(profile, Profile::new(Dialect::Mysql).escape_mode(EscapeMode::Backslash).charset(Charset::Sjis));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Profile {
+    pub(crate) dialect:     Dialect,
+    pub(crate) escape_mode: EscapeMode,
+    pub(crate) charset:     Charset,
+}
+
+impl Profile {
+    /// Creates a profile of the dialect, with the standard escaping and UTF-8.
+    pub fn new(dialect: Dialect) -> Self {
+        Self {
+            dialect,
+            escape_mode: EscapeMode::Standard,
+            charset:     Charset::Utf8,
+        }
+    }
+
+    /// SQL Server, which quotes the identifiers with `"` by `QUOTED_IDENTIFIER ON`, the default of ODBC.
+    ///
+    /// The strings are written without the prefix `N`, so a column of `VARCHAR` whose collation is
+    /// not UTF-8 receives the characters out of its code page as `?`.
+    pub fn sql_server() -> Self {
+        Self::new(Dialect::SqlServer)
+    }
+
+    /// Oracle, which quotes the identifiers with `"` and has no backslash escapes.
+    pub fn oracle() -> Self {
+        Self::new(Dialect::Oracle)
+    }
+
+    /// PostgreSQL with `standard_conforming_strings = on`.
+    pub fn postgres() -> Self {
+        Self::new(Dialect::Postgres)
+    }
+
+    /// MySQL without `NO_BACKSLASH_ESCAPES`.
+    pub fn mysql() -> Self {
+        Self::new(Dialect::Mysql).escape_mode(EscapeMode::Backslash)
+    }
+
+    /// Sets the escaping of the strings. The default is `EscapeMode::Standard`.
+    pub fn escape_mode(mut self, escape_mode: EscapeMode) -> Self {
+        self.escape_mode = escape_mode;
+        self
+    }
+
+    /// Sets the charset in which the database reads the statements. The default is `Charset::Utf8`.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+}
@@ -0,0 +1,64 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+
+/// Open a read-write connection with the backend selected by the scheme of the URL.
+///
+/// Scheme                         | Backend
+/// ------------------------------ | -------
+/// `sqlite://`                    | [sqlite::open](./sqlite/fn.open.html) with the rest of the URL as the path
+/// `postgres://`, `postgresql://` | [postgres::open](./postgres/fn.open.html) with the URL
+/// `mysql://`                     | [mysql::open](./mysql/fn.open.html) with the URL
+///
+/// A backend whose feature is not enabled returns an error.
+///
+/// # Examples
+///
+/// ```
+/// let conn = owsql::open_url("sqlite://:memory:").unwrap();
+/// assert_eq!(conn.actual_sql(conn.ow("SELECT 1;")).unwrap(), "SELECT 1; ");
+/// assert!(owsql::open_url("oracle://localhost").is_err());
+/// ```
+pub fn open_url(url: &str) -> Result<Connection> {
+    let scheme = match url.find("://") {
+        Some(pos) => &url[..pos],
+        None => return Err(OwsqlError::Message(format!("invalid url: {}", url))),
+    };
+
+    match scheme {
+        #[cfg(feature = "sqlite")]
+        "sqlite" => crate::sqlite::open(&url["sqlite://".len()..]),
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => crate::postgres::open(url),
+        #[cfg(feature = "mysql")]
+        "mysql" => crate::mysql::open(url),
+        _ if ["sqlite", "postgres", "postgresql", "mysql"].contains(&scheme) =>
+            Err(OwsqlError::Message(format!("feature not enabled: {}", scheme))),
+        _ => Err(OwsqlError::Message(format!("unsupported scheme: {}", scheme))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::error::OwsqlError;
+
+    #[test]
+    fn open_url() {
+        assert_eq!(
+            super::open_url("localhost"),
+            Err(OwsqlError::Message("invalid url: localhost".into()))
+        );
+        assert_eq!(
+            super::open_url("oracle://localhost"),
+            Err(OwsqlError::Message("unsupported scheme: oracle".into()))
+        );
+        #[cfg(feature = "sqlite")]
+        assert!(super::open_url("sqlite://:memory:").is_ok());
+        #[cfg(not(feature = "mysql"))]
+        assert_eq!(
+            super::open_url("mysql://localhost:3306/test"),
+            Err(OwsqlError::Message("feature not enabled: mysql".into()))
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::lexer::is_qualified_identifier;
+
+crate::owsql_enum! {
+    /// The direction of [order_by](./struct.Connection.html#method.order_by), which can be parsed
+    /// from query parameters with [SqlEnum::from_sql](./trait.SqlEnum.html#method.from_sql).
+    #[derive(Default)]
+    pub enum SortOrder {
+        /// Ascending.
+        #[default]
+        Asc => "ASC",
+        /// Descending.
+        Desc => "DESC",
+    }
+}
+
+impl Connection {
+    /// Quotes the column name, which is a plain identifier optionally qualified by a table such as `users.id`.
+    pub(crate) fn quote_column(&self, column: &str) -> Option<String> {
+        if !is_qualified_identifier(column) || column.split('.').count() > 2 {
+            return None;
+        }
+        let quote = match self.dialect() {
+            Dialect::Mysql => '`',
+            _              => '"',
+        };
+        Some(column.split('.').map(|part| format!("{}{}{}", quote, part, quote)).collect::<Vec<_>>().join("."))
+    }
+
+    /// Quotes the column names and joins them with the clause, or returns an error token.
+    fn column_clause<'a>(&self, clause: &str, columns: impl Iterator<Item = (&'a str, Option<SortOrder>)>) -> String {
+        let mut list = Vec::new();
+        for (column, order) in columns {
+            let column = match self.quote_column(column) {
+                Some(column) => column,
+                None => return self.ow_error("invalid identifier", column),
+            };
+            list.push(match order {
+                Some(order) => format!("{} {}", column, order),
+                None => column,
+            });
+        }
+        if list.is_empty() {
+            return self.ow_error("empty column list", clause);
+        }
+        self.ow_generated(format!("{} {}", clause, list.join(", ")))
+    }
+
+    /// It is guaranteed to be an `ORDER BY` clause of the columns, which are plain identifiers
+    /// optionally qualified by a table, such as a sort key from query parameters.
+    /// An invalid column returns the error "invalid identifier", and no columns return the error
+    /// "empty column list" when the statement is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{SortOrder, SqlEnum};
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);
+    /// #                       INSERT INTO users VALUES ('Alice', 42), ('Bob', 69), ('Carol', 42);")).unwrap();
+    /// let (sort, direction) = ("age", "desc");  // from query parameters
+    /// let order = SortOrder::from_sql(direction).unwrap_or_default();
+    /// let sql = conn.ow("SELECT name FROM users") + &conn.order_by(&[(sort, order), ("name", SortOrder::Asc)]) + &conn.ow(";");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users ORDER BY \"age\" DESC, \"name\" ASC ; ");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get("name"), Some("Bob"));
+    /// ```
+    pub fn order_by(&self, columns: &[(&str, SortOrder)]) -> String {
+        self.column_clause("ORDER BY", columns.iter().map(|&(column, order)| (column, Some(order))))
+    }
+
+    /// It is guaranteed to be a `GROUP BY` clause of the columns in the same way as [order_by](#method.order_by).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);
+    /// #                       INSERT INTO users VALUES ('Alice', 42), ('Bob', 69), ('Carol', 42);")).unwrap();
+    /// let sql = conn.ow("SELECT age, COUNT(*) AS n FROM users") + &conn.group_by(&["users.age"]) + &conn.ow("ORDER BY n DESC;");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get("age"), Some("42"));
+    /// ```
+    pub fn group_by(&self, columns: &[&str]) -> String {
+        self.column_clause("GROUP BY", columns.iter().map(|&column| (column, None)))
+    }
+}
@@ -0,0 +1,88 @@
+use opentelemetry::KeyValue;
+use opentelemetry::global::{self, BoxedSpan};
+use opentelemetry::trace::{Span, SpanKind, Status, Tracer};
+
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+
+/// The spans of the running statements of a connection.
+#[derive(Default)]
+pub(crate) struct Spans {
+    spans:         Vec<BoxedSpan>,
+    rows_affected: Option<u64>,
+}
+
+impl Connection {
+    /// Starts a client span of the statement as a child of the current context.
+    pub(crate) fn start_span(&self, sql: &str) {
+        let tracer = global::tracer("owsql");
+        let span = tracer.span_builder(operation(sql))
+            .with_kind(SpanKind::Client)
+            .with_attributes(vec![
+                KeyValue::new("db.system", db_system(self.dialect())),
+                KeyValue::new("db.statement", sql.trim_end().to_string()),
+            ])
+            .start(&tracer);
+        self.spans.borrow_mut().spans.push(span);
+    }
+
+    /// Records the number of the rows changed by the running statement.
+    pub(crate) fn set_rows_affected(&self, rows_affected: u64) {
+        self.spans.borrow_mut().rows_affected = Some(rows_affected);
+    }
+
+    /// Ends the span started by [start_span](#method.start_span).
+    pub(crate) fn end_span(&self, error: Option<&OwsqlError>) {
+        let mut spans = self.spans.borrow_mut();
+        let rows_affected = spans.rows_affected.take();
+        if let Some(mut span) = spans.spans.pop() {
+            if let Some(rows_affected) = rows_affected {
+                span.set_attribute(KeyValue::new("db.rows_affected", rows_affected as i64));
+            }
+            if let Some(error) = error {
+                span.set_status(Status::error(error.to_string()));
+            }
+            span.end();
+        }
+    }
+}
+
+/// Return the `db.system` of the semantic conventions.
+fn db_system(dialect: Dialect) -> &'static str {
+    match dialect {
+        Dialect::Sqlite    => "sqlite",
+        Dialect::Postgres  => "postgresql",
+        Dialect::Mysql     => "mysql",
+        Dialect::SqlServer => "mssql",
+        Dialect::Oracle    => "oracle",
+    }
+}
+
+/// Return the span name, which is the first keyword of the statement such as `SELECT`.
+fn operation(sql: &str) -> String {
+    match sql.split_whitespace().next() {
+        Some(word) if word.chars().all(|c| c.is_ascii_alphabetic()) => word.to_ascii_uppercase(),
+        _ => "owsql".to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn operation() {
+        assert_eq!(super::operation("select * FROM users"), "SELECT");
+        assert_eq!(super::operation("  INSERT INTO users VALUES (?)"), "INSERT");
+        assert_eq!(super::operation("(SELECT 1)"), "owsql");
+        assert_eq!(super::operation(""), "owsql");
+    }
+
+    #[test]
+    fn db_system() {
+        use crate::dialect::Dialect;
+        assert_eq!(super::db_system(Dialect::Sqlite), "sqlite");
+        assert_eq!(super::db_system(Dialect::Postgres), "postgresql");
+        assert_eq!(super::db_system(Dialect::Mysql), "mysql");
+    }
+}
@@ -1,21 +1,141 @@
 
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+use crate::Result;
+use crate::constants::{OW_MINIMUM_ENTROPY_BITS, OW_MINIMUM_LENGTH, OW_PREFIX};
+use crate::error::OwsqlError;
+
+/// The characters of the random part of overwrite strings.
+/// See [set_token_alphabet](./struct.Connection.html#method.set_token_alphabet).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TokenAlphabet {
+    /// `A-Z`, `a-z` and `0-9`.
+    #[default]
+    Alphanumeric,
+    /// `a-z` and `0-9`.
+    Lowercase,
+    /// `0-9` and `a-f`.
+    Hex,
+    /// Any distinct ASCII alphanumerics, at least two.
+    Custom(&'static str),
+}
+
+impl TokenAlphabet {
+    fn chars(&self) -> &'static [u8] {
+        match self {
+            TokenAlphabet::Alphanumeric => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+            TokenAlphabet::Lowercase    => b"abcdefghijklmnopqrstuvwxyz0123456789",
+            TokenAlphabet::Hex          => b"0123456789abcdef",
+            TokenAlphabet::Custom(chars) => chars.as_bytes(),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        let chars = self.chars();
+        let distinct = chars.iter().enumerate().all(|(i, c)| !chars[..i].contains(c));
+        if chars.len() < 2 || !distinct || !chars.iter().all(u8::is_ascii_alphanumeric) {
+            return Err(OwsqlError::Message(format!("invalid token alphabet: {:?}", self)));
+        }
+        Ok(())
+    }
+
+    /// Bits of entropy per character.
+    pub(crate) fn bits(&self) -> f64 {
+        (self.chars().len() as f64).log2()
+    }
+
+    /// The minimum length of the random part to reach
+    /// [OW_MINIMUM_ENTROPY_BITS](./constants/constant.OW_MINIMUM_ENTROPY_BITS.html).
+    pub(crate) fn minimum_length(&self) -> usize {
+        OW_MINIMUM_LENGTH.max((OW_MINIMUM_ENTROPY_BITS as f64 / self.bits()).ceil() as usize)
+    }
+}
+
 /// Generate new overwrite string.
+/// The random part is drawn from the operating system's cryptographically secure generator.
 #[doc(hidden)]
-pub(crate) fn overwrite_new(serial: usize, range: (usize, usize)) -> String {
-    use rand::{Rng, thread_rng};
-    use rand::distributions::Alphanumeric;
+pub(crate) fn overwrite_new(namespace: &str, alphabet: TokenAlphabet, serial: usize, range: (usize, usize)) -> String {
+    use rand::Rng;
+    use rand::rngs::OsRng;
     use std::cmp::Ordering;
 
-    format!("OWSQL{}{}",
-        thread_rng()
-        .sample_iter(Alphanumeric)
-        .take( match (range.0).cmp(&range.1) {
-            Ordering::Equal   => range.0,
-            Ordering::Less    => thread_rng().gen_range(range.0, range.1),
-            Ordering::Greater => thread_rng().gen_range(range.1, range.0),
-        })
-        .collect::<String>(),
-        serial.to_string())
+    let minimum = alphabet.minimum_length();
+    let range = (range.0.max(minimum), range.1.max(minimum));
+    let len = match (range.0).cmp(&range.1) {
+        Ordering::Equal   => range.0,
+        Ordering::Less    => OsRng.gen_range(range.0, range.1),
+        Ordering::Greater => OsRng.gen_range(range.1, range.0),
+    };
+    let chars = alphabet.chars();
+    let random = (0..len).map(|_| chars[OsRng.gen_range(0, chars.len())] as char).collect::<String>();
+
+    format!("{}{}{}{}", OW_PREFIX, namespace, random, serial)
+}
+
+/// The marker following the namespace of a derived overwrite string.
+const DERIVED_MARKER: &str = "H";
+
+/// Generate a new key of the derived overwrite strings.
+pub(crate) fn derivation_key_new() -> [u8; 32] {
+    use rand::RngCore;
+    use rand::rngs::OsRng;
+
+    let mut key = [0; 32];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// Return the HMAC-SHA256 of the value with the key, which is bound to the namespace.
+fn derivation_mac(namespace: &str, key: &[u8], value: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts any key length");
+    mac.update(namespace.as_bytes());
+    mac.update(&[0]);
+    mac.update(value);
+    mac
+}
+
+/// Generate the overwrite string derived from the value, which carries the value and its HMAC,
+/// so that it is resolved by [derived_value](fn.derived_value.html) even after it is evicted.
+pub(crate) fn derived_overwrite_new(namespace: &str, key: &[u8], value: &str) -> String {
+    let mac = derivation_mac(namespace, key, value.as_bytes()).finalize().into_bytes();
+    format!("{}{}{}{}{}", OW_PREFIX, namespace, DERIVED_MARKER, hex(&mac), hex(value.as_bytes()))
+}
+
+/// Return the value of the overwrite string derived by [derived_overwrite_new](fn.derived_overwrite_new.html)
+/// with the same namespace and key, or `None` if it is not.
+pub(crate) fn derived_value(namespace: &str, key: &[u8], overwrite: &str) -> Option<String> {
+    let rest = overwrite.strip_prefix(OW_PREFIX)?.strip_prefix(namespace)?.strip_prefix(DERIVED_MARKER)?;
+    let tag = unhex(rest.get(..64)?)?;
+    let value = unhex(&rest[64..])?;
+    derivation_mac(namespace, key, &value).verify(&tag).ok()?;
+    String::from_utf8(value).ok()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+/// Generate a namespace of overwrite strings unique to each connection in the process,
+/// so that an overwrite string of a connection is never valid on another.
+pub(crate) fn namespace_new() -> String {
+    static CONNECTION_ID: AtomicUsize = AtomicUsize::new(0);
+    format!("C{}N", CONNECTION_ID.fetch_add(1, AtomicOrdering::Relaxed))
+}
+
+/// Whether the word looks like an overwrite string of any connection.
+pub(crate) fn is_overwrite_like(word: &str) -> bool {
+    word.strip_prefix(OW_PREFIX)
+        .is_some_and(|rest| rest.len() >= OW_MINIMUM_LENGTH && rest.chars().all(|c| c.is_ascii_alphanumeric()))
 }
 
 pub trait IntoInner { fn into_inner(self) -> (usize, usize); }
@@ -37,6 +157,51 @@ impl IntoInner for std::ops::Range<usize> {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn overwrite_new() {
+        let ns0 = super::namespace_new();
+        let ns1 = super::namespace_new();
+        assert_ne!(ns0, ns1);
+        let ow = super::overwrite_new(&ns0, super::TokenAlphabet::default(), 7, (32, 32));
+        assert!(ow.starts_with(&format!("OWSQL{}", ns0)));
+        assert!(ow.ends_with('7'));
+        assert!(super::is_overwrite_like(&ow));
+        assert!(super::is_overwrite_like(&super::overwrite_new(&ns1, super::TokenAlphabet::Hex, 0, (32, 64))));
+        assert!(!super::is_overwrite_like("OWSQL"));
+        assert!(!super::is_overwrite_like("OWSQLite"));
+        assert!(!super::is_overwrite_like(&format!("{}'", ow)));
+    }
+
+    #[test]
+    fn derived_overwrite() {
+        let key = super::derivation_key_new();
+        let ow = super::derived_overwrite_new("C0N", &key, "-42");
+        assert!(super::is_overwrite_like(&ow));
+        assert_eq!(super::derived_value("C0N", &key, &ow), Some("-42".to_string()));
+        assert_eq!(super::derived_value("C1N", &key, &ow), None);
+        assert_eq!(super::derived_value("C0N", &super::derivation_key_new(), &ow), None);
+        let forged = format!("{}{}", &ow[..ow.len() - 2], "31");
+        assert_eq!(super::derived_value("C0N", &key, &forged), None);
+        assert_eq!(super::derived_value("C0N", &key, "OWSQLC0NH"), None);
+    }
+
+    #[test]
+    fn token_alphabet() {
+        use super::TokenAlphabet;
+        assert_eq!(TokenAlphabet::Alphanumeric.minimum_length(), 32);
+        assert_eq!(TokenAlphabet::Hex.minimum_length(), 32);
+        assert_eq!(TokenAlphabet::Custom("01").minimum_length(), 128);
+        assert!(TokenAlphabet::Lowercase.validate().is_ok());
+        assert!(TokenAlphabet::Custom("ab").validate().is_ok());
+        assert!(TokenAlphabet::Custom("a").validate().is_err());
+        assert!(TokenAlphabet::Custom("aba").validate().is_err());
+        assert!(TokenAlphabet::Custom("ab ").validate().is_err());
+
+        let ow = super::overwrite_new("", TokenAlphabet::Custom("xy"), 0, (32, 32));
+        assert_eq!(ow.len(), 5 + 128 + 1);
+        assert!(ow[5..ow.len()-1].chars().all(|c| c == 'x' || c == 'y'));
+    }
+
     #[test]
     #[allow(clippy::reversed_empty_ranges)]
     fn into_inner() {
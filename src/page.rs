@@ -0,0 +1,150 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+use crate::row::Row;
+
+/// A page of the rows returned by [paginate](./struct.Connection.html#method.paginate)
+/// or [keyset_page](./struct.Connection.html#method.keyset_page).
+#[derive(Debug, Default, PartialEq)]
+pub struct Page {
+    /// The rows of the page.
+    pub rows:     Vec<Row>,
+    /// The number of all rows, if it was counted.
+    pub total:    Option<u64>,
+    /// Whether there is a next page.
+    pub has_next: bool,
+}
+
+impl Connection {
+    /// Execute a `SELECT` statement and returns a page of the rows.
+    /// `page` starts from 1.
+    /// `LIMIT` and `OFFSET` are appended to the statement, or `OFFSET ... ROWS FETCH NEXT ... ROWS ONLY`
+    /// on SQL Server and Oracle, so it must be a single `SELECT` statement and should have `ORDER BY`
+    /// to make the pages stable. SQL Server requires `ORDER BY`.
+    /// One more row than `per_page` is fetched to know if there is a next page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);
+    /// #               INSERT INTO users (name, id) VALUES ('Carol', 50);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let sql = conn.ow("SELECT name FROM users ORDER BY id;");
+    /// let page = conn.paginate(&sql, 1, 2).unwrap();
+    /// assert_eq!(page.rows.len(), 2);
+    /// assert!(page.has_next);
+    /// let page = conn.paginate(&sql, 2, 2).unwrap();
+    /// assert_eq!(page.rows[0].get("name"), Some("Bob"));
+    /// assert!(!page.has_next);
+    /// ```
+    pub fn paginate<T: AsRef<str>>(&self, query: T, page: u64, per_page: u64) -> Result<Page> {
+        self.page(query.as_ref(), page, per_page, false)
+    }
+
+    /// Same as [paginate](#method.paginate), and counts all rows by
+    /// [count](#method.count) into [total](./struct.Page.html#structfield.total).
+    pub fn paginate_with_total<T: AsRef<str>>(&self, query: T, page: u64, per_page: u64) -> Result<Page> {
+        self.page(query.as_ref(), page, per_page, true)
+    }
+
+    /// Execute a `SELECT` statement and returns the page after the last seen value of the column.
+    /// Unlike [paginate](#method.paginate), no rows are skipped by `OFFSET`,
+    /// so the page is fast to fetch even if the table is large.
+    ///
+    /// The statement is wrapped as
+    /// `SELECT * FROM (...) WHERE order_col > last_seen ORDER BY order_col LIMIT limit`,
+    /// with `FETCH NEXT limit ROWS ONLY` on SQL Server and Oracle,
+    /// so it must be a single `SELECT` statement including `order_col`.
+    /// `last_seen` is `None` for the first page, and is escaped as a string otherwise.
+    /// Use [int](#method.int) to compare it as an integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # let stmt = conn.ow(r#"CREATE TABLE users (name TEXT, id INTEGER);
+    /// #               INSERT INTO users (name, id) VALUES ('Alice', 42);
+    /// #               INSERT INTO users (name, id) VALUES ('Bob', 69);
+    /// #               INSERT INTO users (name, id) VALUES ('Carol', 50);"#);
+    /// # conn.execute(stmt).unwrap();
+    /// let sql = conn.ow("SELECT name, id FROM users;");
+    /// let page = conn.keyset_page(&sql, "id", None::<&str>, 2).unwrap();
+    /// assert_eq!(page.rows[1].get("name"), Some("Carol"));
+    /// assert!(page.has_next);
+    /// let last_seen = page.rows[1].get_into::<i64>("id").unwrap();
+    /// let page = conn.keyset_page(&sql, "id", Some(conn.int(last_seen)), 2).unwrap();
+    /// assert_eq!(page.rows[0].get("name"), Some("Bob"));
+    /// assert!(!page.has_next);
+    /// ```
+    pub fn keyset_page<T, V>(&self, query: T, order_col: &'static str, last_seen: Option<V>, limit: u64) -> Result<Page>
+        where
+            T: AsRef<str>,
+            V: AsRef<str>,
+    {
+        let query = query.as_ref();
+        if limit == 0 || limit >= i64::MAX as u64 {
+            OwsqlError::new(&self.error_level, "invalid page", &format!("limit {}", limit))?;
+            return Ok(Page::default());
+        }
+
+        let mut condition = String::new();
+        if let Some(last_seen) = last_seen {
+            condition = self.ow(" WHERE ") + &self.ow(order_col) + &self.ow(" > ") + last_seen.as_ref();
+        }
+        let rest = condition + &self.ow(" ORDER BY ") + &self.ow(order_col) + &self.limit(limit + 1, None);
+        // Oracle does not accept `AS` before the alias of a table.
+        let alias = match self.dialect() {
+            Dialect::Oracle => "owsql_keyset",
+            _               => "AS owsql_keyset",
+        };
+        let sql = self.convert_to_valid_syntax(query).and_then(|q| Ok(format!(
+            "SELECT * FROM ({}) {}{}",
+            q.trim_end().trim_end_matches(';'),
+            alias,
+            self.convert_to_valid_syntax(&rest)?,
+        )));
+        let mut rows = self.rows_converted(query, sql, self, &self.error_level)?;
+
+        let has_next = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+        Ok(Page { rows, total: None, has_next })
+    }
+
+    fn page(&self, query: &str, page: u64, per_page: u64, with_total: bool) -> Result<Page> {
+        let offset = match page.checked_sub(1).and_then(|p| p.checked_mul(per_page)) {
+            Some(offset) if per_page > 0 && per_page < i64::MAX as u64 && offset <= i64::MAX as u64 => offset,
+            _ => {
+                OwsqlError::new(&self.error_level, "invalid page", &format!("page {}, per_page {}", page, per_page))?;
+                return Ok(Page::default());
+            },
+        };
+
+        let limit = self.limit(per_page + 1, Some(offset));
+        let sql = self.convert_to_valid_syntax(query)
+            .and_then(|q| Ok(q.trim_end().trim_end_matches(';').to_string() + &self.convert_to_valid_syntax(&limit)?));
+        let mut rows = self.rows_converted(query, sql, self, &self.error_level)?;
+
+        let has_next = rows.len() as u64 > per_page;
+        rows.truncate(per_page as usize);
+        let total = if with_total { Some(self.count(query)?) } else { None };
+        Ok(Page { rows, total, has_next })
+    }
+
+    /// Return the clause limiting the rows, which is appended to the statement.
+    fn limit(&self, limit: u64, offset: Option<u64>) -> String {
+        match self.dialect() {
+            Dialect::SqlServer | Dialect::Oracle => {
+                self.ow(" OFFSET ") + &self.int(offset.unwrap_or(0)) + &self.ow(" ROWS FETCH NEXT ")
+                    + &self.int(limit) + &self.ow(" ROWS ONLY")
+            },
+            _ => match offset {
+                Some(offset) => self.ow(" LIMIT ") + &self.int(limit) + &self.ow(" OFFSET ") + &self.int(offset),
+                None => self.ow(" LIMIT ") + &self.int(limit),
+            },
+        }
+    }
+}
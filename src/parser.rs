@@ -1,8 +1,16 @@
 use crate::Result;
+use crate::audit::AuditKind;
 use crate::bidimap::BidiMap;
 use crate::connection::Connection;
 use crate::error::{OwsqlError, OwsqlErrorLevel};
-use crate::token::TokenType;
+use crate::escape::EscapeMode;
+use crate::dialect::Dialect;
+use crate::lexer::{
+    CommentMode, bracket_len, comment_len, dollar_quote_len, dollar_tag, ends_in_line_comment, escape_string, expects_value,
+    has_open_comment, is_word_start, lex, quote_len, strip_comments,
+};
+use crate::overwrite::is_overwrite_like;
+use crate::token::{Token, TokenType};
 
 pub fn escape_for_allowlist(value: &str) -> String {
     let error_level = OwsqlErrorLevel::default();
@@ -11,7 +19,9 @@ pub fn escape_for_allowlist(value: &str) -> String {
     parser.consume_string('\'').unwrap_or_default()
 }
 
-/// Convert special characters to HTML entities.
+/// Convert special characters to HTML entities.  
+/// Values passed to a connection are never HTML-escaped automatically; they are only SQL-escaped,
+/// so the raw text is stored. Use this function when rendering the values into HTML.
 ///
 /// # Performed translations
 ///
@@ -70,19 +80,13 @@ pub fn _sanitize_like<T: std::string::ToString>(pattern: T, escape_character: ch
     escaped_str
 }
 
-pub(crate) fn escape_string<F>(s: &str, is_escape_char: F) -> String
-where
-    F: Fn(char) -> bool,
-{
-    let mut escaped = String::new();
+pub(crate) fn push_escaped(out: &mut String, s: &str, must_escape: &dyn Fn(char) -> bool) {
     for c in s.chars() {
-        if is_escape_char(c) {
-            escaped.push(c);
+        if must_escape(c) {
+            out.push(c);
         }
-        escaped.push(c);
+        out.push(c);
     }
-    debug_assert!(!escaped.is_empty());
-    escaped
 }
 
 pub struct Parser<'a> {
@@ -114,36 +118,6 @@ impl<'a> Parser<'a> {
         })
     }
 
-    pub fn skip_whitespace(&mut self) -> Result<()> {
-        self.consume_while(char::is_whitespace).and(Ok(()))
-    }
-
-    pub fn consume_whitespace(&mut self) -> Result<String> {
-        self.consume_while(char::is_whitespace)
-    }
-
-    pub fn consume_except_whitespace(&mut self) -> Result<String> {
-        let mut s = String::new();
-        while !self.eof() {
-            let c = self.next_char()?;
-            if c.is_whitespace() {
-                break;
-            }
-            s.push(self.consume_char()?);
-        }
-        if s.is_empty() {
-            Err( match self.error_level {
-                OwsqlErrorLevel::AlwaysOk |
-                OwsqlErrorLevel::Release  => OwsqlError::AnyError,
-                OwsqlErrorLevel::Develop  => OwsqlError::Message("error: consume_except_whitespace()".to_string()),
-                #[cfg(debug_assertions)]
-                OwsqlErrorLevel::Debug    => OwsqlError::Message("error: consume_except_whitespace(): empty".to_string()),
-            })
-        } else {
-            Ok(s)
-        }
-    }
-
     pub fn consume_string(&mut self, quote: char) -> Result<String> {
         let mut s = quote.to_string();
         self.consume_char()?;
@@ -204,17 +178,51 @@ impl<'a> Parser<'a> {
 }
 
 // I want to write with const fn
-fn check_valid_literal(s: &str, error_level: &OwsqlErrorLevel) -> Result<()> {
+/// Dollar-quoted strings are checked for PostgreSQL, backtick-quoted identifiers for MySQL and SQLite,
+/// and bracket-quoted identifiers for SQL Server.
+fn check_valid_literal(s: &str, error_level: &OwsqlErrorLevel, dialect: Dialect) -> Result<()> {
     let err_msg = "invalid literal";
-    let mut parser = Parser::new(&s, &error_level);
+    if has_open_comment(s, dialect) {
+        return OwsqlError::new(error_level, "invalid comment", s);
+    }
+    let mut parser = Parser::new(s, error_level);
     while !parser.eof() {
-        parser.consume_while(|c| c != '"' && c != '\'').ok();
+        parser.consume_while(|c| !matches!(c, '"' | '\'' | '`' | '[' | '-' | '/' | '$')).ok();
         match parser.next_char() {
-            Ok('"')  => if parser.consume_string('"').is_err() {
-                return OwsqlError::new(error_level, err_msg, &s);
+            // An escape string such as `E'it\'s'`, in which a backslash escapes the quotation.
+            Ok('\'') if dialect == Dialect::Postgres && is_escape_string_start(parser.input, parser.pos) => {
+                match escape_string(&parser.input[parser.pos-1..]) {
+                    Some((len, true)) => parser.pos += len - 1,
+                    _ => return OwsqlError::new(error_level, err_msg, s),
+                }
+            },
+            Ok('"')  if parser.consume_string('"').is_err() => {
+                return OwsqlError::new(error_level, err_msg, s);
+            },
+            Ok('\'') if parser.consume_string('\'').is_err() => {
+                return OwsqlError::new(error_level, err_msg, s);
+            },
+            Ok('`') if matches!(dialect, Dialect::Sqlite | Dialect::Mysql) && parser.consume_string('`').is_err() => {
+                return OwsqlError::new(error_level, err_msg, s);
+            },
+            Ok('[') if dialect == Dialect::SqlServer => match bracket_len(&parser.input[parser.pos..]) {
+                Some(len) => parser.pos += len,
+                None => return OwsqlError::new(error_level, err_msg, s),
+            },
+            Ok('$') if dialect == Dialect::Postgres && dollar_tag(&parser.input[parser.pos..]).is_some() => {
+                let rest = &parser.input[parser.pos..];
+                let tag = dollar_tag(rest).unwrap();
+                if !rest[tag.len()..].contains(tag) {
+                    return OwsqlError::new(error_level, err_msg, s);
+                }
+                parser.pos += dollar_quote_len(rest).unwrap();
             },
-            Ok('\'')  => if parser.consume_string('\'').is_err() {
-                return OwsqlError::new(error_level, err_msg, &s);
+            Ok('`') if !matches!(dialect, Dialect::Sqlite | Dialect::Mysql) => { parser.consume_char().ok(); },
+            Ok('[') | Ok('$') => { parser.consume_char().ok(); },
+            // Quotations in comments are not checked.
+            Ok('-') | Ok('/') => match comment_len(&parser.input[parser.pos..]) {
+                Some(len) => parser.pos += len,
+                None => { parser.consume_char().ok(); },
             },
             _other => (), // Do nothing
         }
@@ -223,108 +231,421 @@ fn check_valid_literal(s: &str, error_level: &OwsqlErrorLevel) -> Result<()> {
     Ok(())
 }
 
+/// Checks if the quotation at the byte position follows a standalone `E`.
+fn is_escape_string_start(s: &str, pos: usize) -> bool {
+    matches!(s[..pos].chars().next_back(), Some('E' | 'e')) && is_word_start(s, pos - 1)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn convert_to_valid_syntax(
     stmt:                   &str,
     must_escape:            &dyn Fn(char) -> bool,
+    check_literal:          &dyn Fn(&str, Option<&str>) -> Result<()>,
+    redact:                 bool,
+    comment_mode:           CommentMode,
+    dialect:                Dialect,
     conn_overwrite:         &BidiMap<String, String>,
     conn_whitespace_around: &BidiMap<String, String>,
     conn_error_msg:         &BidiMap<OwsqlError, String>,
-    error_level:            &OwsqlErrorLevel,
 ) -> Result<String> {
 
-    let mut query = String::new();
-    let tokens = tokenize(stmt, must_escape, conn_overwrite, conn_whitespace_around, conn_error_msg, error_level)?;
+    let mut query = String::with_capacity(stmt.len());
+    let mut preceding = None;
 
-    for token in tokens {
+    for token in Tokens::new(stmt, conn_overwrite, conn_error_msg) {
+        let mut separator = ' ';
         match token {
             TokenType::ErrOverwrite(e) =>
-                return Err(conn_error_msg.get_reverse(&e).unwrap().clone()),
+                return Err(conn_error_msg.get_reverse(e).unwrap().clone()),
+            TokenType::Overwrite(original) => {
+                let fragment = conn_overwrite.get_reverse(original).unwrap();
+                let start = query.len();
+                match comment_mode {
+                    CommentMode::Preserve => query.push_str(fragment),
+                    CommentMode::Strip    => query.push_str(&strip_comments(fragment, dialect)),
+                }
+                // A line comment must not swallow what follows.
+                if ends_in_line_comment(&query[start..], dialect) {
+                    separator = '\n';
+                }
+                preceding = non_empty(fragment).or(preceding);
+            },
+            TokenType::String(span) => {
+                let start = query.len();
+                push_literal(&mut query, span, must_escape, conn_whitespace_around);
+                check_literal(&query[start..], preceding)?;
+                if redact {
+                    query.truncate(start);
+                    query.push('?');
+                }
+            },
+        }
+
+        query.push(separator);
+    }
+
+    Ok(query)
+}
+
+fn normalize_statement(
+    stmt:                   &str,
+    conn_overwrite:         &BidiMap<String, String>,
+    conn_error_msg:         &BidiMap<OwsqlError, String>,
+) -> String {
+
+    let mut query = String::with_capacity(stmt.len());
+
+    for token in Tokens::new(stmt, conn_overwrite, conn_error_msg) {
+        match token {
             TokenType::Overwrite(original) =>
-                query.push_str(conn_overwrite.get_reverse(&original).unwrap()),
-            other => query.push_str(&other.unwrap()),
+                query.push_str(conn_overwrite.get_reverse(original).unwrap()),
+            _other => query.push('?'),
         }
 
         query.push(' ');
     }
 
-    Ok(query)
+    query
+}
+
+fn pending_errors(
+    stmt:                   &str,
+    conn_overwrite:         &BidiMap<String, String>,
+    conn_error_msg:         &BidiMap<OwsqlError, String>,
+) -> Vec<OwsqlError> {
+
+    Tokens::new(stmt, conn_overwrite, conn_error_msg).filter_map(|token| match token {
+        TokenType::ErrOverwrite(e) => conn_error_msg.get_reverse(e).cloned(),
+        _other => None,
+    }).collect()
 }
 
 fn tokenize(
     stmt:                   &str,
     must_escape:            &dyn Fn(char) -> bool,
+    check_literal:          &dyn Fn(&str, Option<&str>) -> Result<()>,
     conn_overwrite:         &BidiMap<String, String>,
     conn_whitespace_around: &BidiMap<String, String>,
     conn_error_msg:         &BidiMap<OwsqlError, String>,
-    error_level:            &OwsqlErrorLevel,
-) -> Result<Vec<TokenType>> {
+) -> Result<Vec<Token>> {
 
-    let mut parser = Parser::new(&stmt, &error_level);
     let mut tokens = Vec::new();
+    let mut preceding = None;
 
-    while !parser.eof() {
-        parser.skip_whitespace().ok();
-
-        if parser.next_char().is_ok() {
-            let mut string = parser.consume_except_whitespace()?;
-            if conn_overwrite.contain_reverse(&string) {
-                tokens.push(TokenType::Overwrite(string));
-            } else if conn_error_msg.contain_reverse(&string) {
-                tokens.push(TokenType::ErrOverwrite(string));
-            } else {
-                let starts_with_whitespace_around = conn_whitespace_around.contain_reverse(&string);
-                if starts_with_whitespace_around {
-                    string = conn_whitespace_around.get_reverse(&string).unwrap().to_string();
-                }
-                let mut overwrite = TokenType::None;
-                'untilow: while !parser.eof() {
-                    let mut whitespace = parser.consume_whitespace().unwrap_or_default();
-                    if starts_with_whitespace_around {
-                        whitespace.remove(0);
-                    }
-                    while let Ok(s) = parser.consume_except_whitespace() {
-                        if conn_overwrite.contain_reverse(&s) {
-                            overwrite = TokenType::Overwrite(s);
-                            break 'untilow;
-                        } else if conn_error_msg.contain_reverse(&s) {
-                            overwrite = TokenType::ErrOverwrite(s);
-                            break 'untilow;
-                        } else if let Some(s) = conn_whitespace_around.get_reverse(&s) {
-                            string.push_str(&whitespace[..whitespace.len()-1]);
-                            string.push_str(&s);
-                        } else {
-                            string.push_str(&whitespace);
-                            string.push_str(&s);
-                        }
-                        whitespace = parser.consume_whitespace().unwrap_or_default();
-                    }
-                }
-                tokens.push(TokenType::String(format!("'{}'", escape_string(&string, must_escape))));
-                if !overwrite.is_none() {
-                    tokens.push(overwrite);
+    for token in Tokens::new(stmt, conn_overwrite, conn_error_msg) {
+        match token {
+            TokenType::ErrOverwrite(e) =>
+                return Err(conn_error_msg.get_reverse(e).unwrap().clone()),
+            TokenType::Overwrite(original) => {
+                let fragment = conn_overwrite.get_reverse(original).unwrap();
+                lex(fragment, &mut tokens);
+                preceding = non_empty(fragment).or(preceding);
+            },
+            TokenType::String(span) => {
+                let mut literal = String::new();
+                push_literal(&mut literal, span, must_escape, conn_whitespace_around);
+                check_literal(&literal, preceding)?;
+                tokens.push(Token::Literal(literal));
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Splits a statement into overwrite definitions and the spans of words between them.
+struct Tokens<'a, 'm> {
+    input:          &'a str,
+    pos:            usize,
+    pending:        Option<TokenType<'a>>,
+    conn_overwrite: &'m BidiMap<String, String>,
+    conn_error_msg: &'m BidiMap<OwsqlError, String>,
+}
+
+impl<'a, 'm> Tokens<'a, 'm> {
+    fn new(input: &'a str, conn_overwrite: &'m BidiMap<String, String>, conn_error_msg: &'m BidiMap<OwsqlError, String>) -> Self {
+        Self {
+            input,
+            pos: 0,
+            pending: None,
+            conn_overwrite,
+            conn_error_msg,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos += leading_whitespace(&self.input[self.pos..]).len();
+    }
+
+    fn next_word(&mut self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let word = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+        self.pos += word.len();
+        word
+    }
+
+    fn overwrite(&self, word: &'a str) -> Option<TokenType<'a>> {
+        if self.conn_overwrite.contain_reverse(word) {
+            Some(TokenType::Overwrite(word))
+        } else if self.conn_error_msg.contain_reverse(word) {
+            Some(TokenType::ErrOverwrite(word))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, 'm> Iterator for Tokens<'a, 'm> {
+    type Item = TokenType<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.take() {
+            return Some(token);
+        }
+
+        self.skip_whitespace();
+        if self.pos >= self.input.len() {
+            return None;
+        }
+
+        let start = self.pos;
+        let word = self.next_word();
+        if let Some(token) = self.overwrite(word) {
+            return Some(token);
+        }
+
+        // The whitespaces before the next overwrite definition and at the end are not included.
+        let mut end = self.pos;
+        loop {
+            self.skip_whitespace();
+            if self.pos >= self.input.len() {
+                break;
+            }
+            let word = self.next_word();
+            if let Some(token) = self.overwrite(word) {
+                self.pending = Some(token);
+                break;
+            }
+            end = self.pos;
+        }
+
+        Some(TokenType::String(&self.input[start..end]))
+    }
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    if s.trim().is_empty() { None } else { Some(s) }
+}
+
+fn leading_whitespace(s: &str) -> &str {
+    &s[..s.find(|c: char| !c.is_whitespace()).unwrap_or(s.len())]
+}
+
+fn without_first_char(s: &str) -> &str {
+    let mut chars = s.chars();
+    chars.next();
+    chars.as_str()
+}
+
+fn without_last_char(s: &str) -> &str {
+    let mut chars = s.chars();
+    chars.next_back();
+    chars.as_str()
+}
+
+/// Writes the span of words as an escaped string literal.  
+/// The whitespaces that [whitespace_around](struct.Connection.html#method.whitespace_around)
+/// added around a definition are removed, and the definition is replaced with the original.
+fn push_literal(
+    query:                  &mut String,
+    span:                   &str,
+    must_escape:            &dyn Fn(char) -> bool,
+    conn_whitespace_around: &BidiMap<String, String>,
+) {
+    query.push('\'');
+
+    let mut rest = span;
+    let mut starts_with_whitespace_around = false;
+    let mut first = true;
+    while !rest.is_empty() {
+        let mut whitespace = leading_whitespace(rest);
+        rest = &rest[whitespace.len()..];
+        let word = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+        rest = &rest[word.len()..];
+
+        if starts_with_whitespace_around {
+            whitespace = without_first_char(whitespace);
+            starts_with_whitespace_around = false;
+        }
+
+        match conn_whitespace_around.get_reverse(word) {
+            Some(original) => {
+                if first {
+                    starts_with_whitespace_around = true;
+                } else {
+                    push_escaped(query, without_last_char(whitespace), must_escape);
                 }
+                push_escaped(query, original, must_escape);
+            },
+            None => {
+                push_escaped(query, whitespace, must_escape);
+                push_escaped(query, word, must_escape);
+            },
+        }
+        first = false;
+    }
+
+    query.push('\'');
+}
+
+/// Collapses each run of whitespaces outside quoted strings, dollar-quoted strings, quoted
+/// identifiers and comments into a single space, and trims both ends.
+/// The whitespaces after a line comment become a single newline, so that the comment still ends.
+fn canonical_whitespace(sql: &str, escape_mode: EscapeMode, dialect: Dialect) -> String {
+    let mut canonical = String::with_capacity(sql.len());
+    let mut separator = None;
+    let mut rest = sql;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            if separator.is_none() && !canonical.is_empty() {
+                separator = Some(' ');
             }
+            rest = &rest[leading_whitespace(rest).len()..];
+            continue;
+        }
+
+        let line_comment = rest.starts_with("--");
+        let len = match c {
+            '\'' | '"' => quoted_len(rest, c, escape_mode == EscapeMode::Backslash),
+            _ => quote_len(sql, sql.len() - rest.len(), dialect).or_else(|| comment_len(rest)).unwrap_or(c.len_utf8()),
+        };
+        if let Some(separator) = separator.take() {
+            canonical.push(separator);
         }
+        canonical.push_str(&rest[..len]);
+        if line_comment {
+            separator = Some('\n');
+        }
+        rest = &rest[len..];
     }
 
-    Ok(tokens)
+    canonical
+}
+
+/// Return the length of the quoted string or identifier at the beginning, including the quotations.
+/// A doubled quotation, or any character after a backslash if `backslash` is set, does not end it.
+fn quoted_len(s: &str, quote: char, backslash: bool) -> usize {
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if backslash && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            match chars.peek() {
+                Some(&(_, next)) if next == quote => { chars.next(); },
+                _ => return i + c.len_utf8(),
+            }
+        }
+    }
+    s.len()
 }
 
 impl Connection {
     #[inline]
     pub(crate) fn check_valid_literal(&self, s: &str) -> Result<()> {
-        check_valid_literal(&s, &self.error_level)
+        check_valid_literal(s, &self.error_level, self.dialect())
+    }
+
+    fn check_escaped_literal(&self, literal: &str, preceding: Option<&str>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        // An untrusted string looking like an overwrite string is either forged or leaked from another connection.
+        if literal.split(|c: char| !c.is_ascii_alphanumeric()).any(is_overwrite_like) {
+            return OwsqlError::new(error_level, "token collision", literal);
+        }
+        if self.strict && !preceding.is_some_and(expects_value) {
+            return OwsqlError::new(error_level, "unexpected literal", literal);
+        }
+        match self.charset().check_literal(literal, self.escape_mode()) {
+            Some(err_msg) => OwsqlError::new(error_level, err_msg, literal),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn convert_to_valid_syntax(&self, stmt: &str) -> Result<String> {
+        self.convert_with_level(stmt, &self.error_level)
+    }
+
+    pub(crate) fn convert_with_level(&self, stmt: &str, error_level: &OwsqlErrorLevel) -> Result<String> {
+        self.rederive(stmt, true);
+        let sql = convert_to_valid_syntax(
+            stmt,
+            &self.escape_mode().must_escape(),
+            &|literal, preceding| {
+                if let Some(audit) = self.audit.borrow_mut().as_mut() {
+                    audit.record(AuditKind::Escaped, literal, None);
+                }
+                self.check_escaped_literal(literal, preceding, error_level)
+            },
+            false,
+            self.comment_mode,
+            self.dialect(),
+            &self.overwrite.borrow(),
+            &self.whitespace_around.borrow(),
+            &self.error_msg.borrow())?;
+        Ok(self.canonicalize(sql))
+    }
+
+    fn canonicalize(&self, sql: String) -> String {
+        if self.canonical_spaces {
+            canonical_whitespace(&sql, self.escape_mode(), self.dialect())
+        } else {
+            sql
+        }
     }
 
     #[inline]
-    pub(crate) fn convert_to_valid_syntax(&self, stmt: &str, must_escape: Box<dyn Fn(char) -> bool>) -> Result<String> {
-        convert_to_valid_syntax(
-            &stmt,
-            &must_escape,
+    pub(crate) fn redact_statement(&self, stmt: &str) -> Result<String> {
+        self.rederive(stmt, false);
+        let sql = convert_to_valid_syntax(
+            stmt,
+            &self.escape_mode().must_escape(),
+            &|literal, preceding| self.check_escaped_literal(literal, preceding, &self.error_level),
+            true,
+            self.comment_mode,
+            self.dialect(),
             &self.overwrite.borrow(),
             &self.whitespace_around.borrow(),
-            &self.error_msg.borrow(),
-            &self.error_level)
+            &self.error_msg.borrow())?;
+        Ok(self.canonicalize(sql))
+    }
+
+    #[inline]
+    pub(crate) fn tokenize_statement(&self, stmt: &str) -> Result<Vec<Token>> {
+        self.rederive(stmt, false);
+        tokenize(
+            stmt,
+            &self.escape_mode().must_escape(),
+            &|literal, preceding| self.check_escaped_literal(literal, preceding, &self.error_level),
+            &self.overwrite.borrow(),
+            &self.whitespace_around.borrow(),
+            &self.error_msg.borrow())
+    }
+
+    #[inline]
+    pub(crate) fn collect_errors(&self, stmt: &str) -> Vec<OwsqlError> {
+        pending_errors(
+            stmt,
+            &self.overwrite.borrow(),
+            &self.error_msg.borrow())
+    }
+
+    #[inline]
+    pub(crate) fn normalize_statement(&self, stmt: &str) -> String {
+        self.rederive(stmt, false);
+        normalize_statement(
+            stmt,
+            &self.overwrite.borrow(),
+            &self.error_msg.borrow())
     }
 }
 
@@ -334,7 +655,7 @@ mod tests {
     use crate::error::*;
 
     #[test]
-    #[cfg(features = "sqlite")]
+    #[cfg(feature = "sqlite")]
     fn check_valid_literals_sqlite() {
         let conn = crate::sqlite::open(":memory:").unwrap();
         assert_eq!(conn.check_valid_literal("O'Reilly"),   Err(OwsqlError::Message("invalid literal".to_string())));
@@ -347,9 +668,9 @@ mod tests {
     }
 
     #[test]
-    #[cfg(features = "mysql")]
+    #[cfg(feature = "mysql")]
     fn check_valid_literals_mysql() {
-        let conn = owsql::mysql::open("mysql://localhost:3306/test").unwrap();
+        let conn = crate::mysql::open("mysql://localhost:3306/test").unwrap();
         assert_eq!(conn.check_valid_literal("O'Reilly"),   Err(OwsqlError::Message("invalid literal".to_string())));
         assert_eq!(conn.check_valid_literal("O\"Reilly"),  Err(OwsqlError::Message("invalid literal".to_string())));
         assert_eq!(conn.check_valid_literal("'O'Reilly'"), Err(OwsqlError::Message("invalid literal".to_string())));
@@ -359,6 +680,53 @@ mod tests {
         assert_eq!(conn.check_valid_literal("'Alice', 'Bob'"), Ok(()));
     }
 
+    #[test]
+    fn check_valid_dollar_quotes() {
+        use crate::dialect::Dialect;
+        let level = OwsqlErrorLevel::Develop;
+        let body = "CREATE FUNCTION f() RETURNS text AS $body$ SELECT 'it''s' || $$ don't $$ $body$ LANGUAGE SQL;";
+        assert_eq!(super::check_valid_literal(body, &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT $$ it's $$", &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT $1, $2", &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT $$ it's", &level, Dialect::Postgres), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT $$ it's $$", &level, Dialect::Mysql), Err(OwsqlError::Message("invalid literal".to_string())));
+    }
+
+    #[test]
+    fn check_valid_escape_strings() {
+        use crate::dialect::Dialect;
+        let level = OwsqlErrorLevel::Develop;
+        assert_eq!(super::check_valid_literal("SELECT E'it\\'s', e'\\n', E'a''b'", &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT E'\\\\' || 'x'", &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT E'it\\'s", &level, Dialect::Postgres), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT name'it\\'s'", &level, Dialect::Postgres), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT E'it\\'s'", &level, Dialect::Sqlite), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT * FROM t WHERE name = é'x' OR name =", &level, Dialect::Postgres), Ok(()));
+    }
+
+    #[test]
+    fn check_valid_backticks() {
+        use crate::dialect::Dialect;
+        let level = OwsqlErrorLevel::Develop;
+        assert_eq!(super::check_valid_literal("SELECT `order`, `it's`, `a``b` FROM t", &level, Dialect::Mysql), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT `it's` FROM t", &level, Dialect::Sqlite), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT `order FROM t", &level, Dialect::Mysql), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT `it's` FROM t", &level, Dialect::Postgres), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT `it's` FROM t", &level, Dialect::Oracle), Err(OwsqlError::Message("invalid literal".to_string())));
+    }
+
+    #[test]
+    fn check_valid_brackets() {
+        use crate::dialect::Dialect;
+        let level = OwsqlErrorLevel::Develop;
+        assert_eq!(super::check_valid_literal("SELECT [it's], [a]]'b] FROM t", &level, Dialect::SqlServer), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT [it's FROM t", &level, Dialect::SqlServer), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT [it's] FROM t", &level, Dialect::Postgres), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT x[1] FROM t", &level, Dialect::Postgres), Ok(()));
+        assert_eq!(super::check_valid_literal("SELECT $$ it's $$", &level, Dialect::SqlServer), Err(OwsqlError::Message("invalid literal".to_string())));
+        assert_eq!(super::check_valid_literal("SELECT E'it\\'s'", &level, Dialect::Oracle), Err(OwsqlError::Message("invalid literal".to_string())));
+    }
+
     #[test]
     fn html_special_chars() {
         assert_eq!(
@@ -375,10 +743,65 @@ mod tests {
     }
 
     #[test]
-    fn escape_string() {
-        assert_eq!(super::escape_string("O'Reilly",   |c| c=='\''),            "O''Reilly");
-        assert_eq!(super::escape_string("O\\'Reilly", |c| c=='\''),            "O\\''Reilly");
-        assert_eq!(super::escape_string("O'Reilly",   |c| c=='\'' || c=='\\'), "O''Reilly");
-        assert_eq!(super::escape_string("O\\'Reilly", |c| c=='\'' || c=='\\'), "O\\\\''Reilly");
+    fn tokens() {
+        use crate::bidimap::BidiMap;
+        use crate::token::TokenType;
+        let mut overwrite = BidiMap::new();
+        let mut error_msg = BidiMap::new();
+        overwrite.insert("SELECT".to_string(), "OW1".to_string());
+        error_msg.insert(OwsqlError::AnyError, "OW2".to_string());
+        let tokens = super::Tokens::new(" OW1 foo  bar  OW2 baz\u{3000}qux \n", &overwrite, &error_msg).collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            TokenType::Overwrite("OW1"),
+            TokenType::String("foo  bar"),
+            TokenType::ErrOverwrite("OW2"),
+            TokenType::String("baz\u{3000}qux"),
+        ]);
+        assert_eq!(super::Tokens::new(" \t ", &overwrite, &error_msg).next(), None);
+    }
+
+    #[test]
+    fn push_literal() {
+        use crate::bidimap::BidiMap;
+        let mut whitespace_around = BidiMap::new();
+        whitespace_around.insert(" a ".to_string(), "WA1".to_string());
+        whitespace_around.insert(" b ".to_string(), "WA2".to_string());
+        let literal = |span: &str| {
+            let mut query = String::new();
+            super::push_literal(&mut query, span, &|c| c == '\'', &whitespace_around);
+            query
+        };
+        assert_eq!(literal("foo'  bar"),             "'foo''  bar'");
+        assert_eq!(literal("WA1  WA2"),              "' a  b '");
+        assert_eq!(literal("WA1WA2"),                "'WA1WA2'");
+        assert_eq!(literal("WA1 WA2"),               "' a  b '");
+        assert_eq!(literal("foo\u{3000}WA1"),        "'foo a '");
+    }
+
+    #[test]
+    fn canonical_whitespace() {
+        use crate::dialect::Dialect::*;
+        use crate::escape::EscapeMode::*;
+        assert_eq!(super::canonical_whitespace("  SELECT *\n\tFROM  users  WHERE name = 'a  b' ", Standard, Sqlite), "SELECT * FROM users WHERE name = 'a  b'");
+        assert_eq!(super::canonical_whitespace("SELECT \"a  b\" , `c  d`", Standard, Sqlite), "SELECT \"a  b\" , `c  d`");
+        assert_eq!(super::canonical_whitespace("SELECT 'it''s  ok'  ,  'x  y'", Standard, Sqlite), "SELECT 'it''s  ok' , 'x  y'");
+        assert_eq!(super::canonical_whitespace("SELECT 'a\\'  b'  ,  1", Backslash, Mysql), "SELECT 'a\\'  b' , 1");
+        assert_eq!(super::canonical_whitespace("SELECT E'a\\'  b'  ,  e'x  y'", Standard, Postgres), "SELECT E'a\\'  b' , e'x  y'");
+        assert_eq!(super::canonical_whitespace("SELECT 1 -- one  two\n\n  , 2 /* a  b */  ;", Standard, Sqlite), "SELECT 1 -- one  two\n, 2 /* a  b */ ;");
+        assert_eq!(super::canonical_whitespace("SELECT E'\\' -- '\n  x", Standard, Sqlite), "SELECT E'\\' -- '\nx");
+        assert_eq!(super::canonical_whitespace("SELECT E'\\' -- '\n  x", Standard, Postgres), "SELECT E'\\' -- ' x");
+    }
+
+    #[test]
+    fn push_escaped() {
+        fn escape_string(s: &str, must_escape: &dyn Fn(char) -> bool) -> String {
+            let mut escaped = String::new();
+            super::push_escaped(&mut escaped, s, must_escape);
+            escaped
+        }
+        assert_eq!(escape_string("O'Reilly",   &|c| c=='\''),            "O''Reilly");
+        assert_eq!(escape_string("O\\'Reilly", &|c| c=='\''),            "O\\''Reilly");
+        assert_eq!(escape_string("O'Reilly",   &|c| c=='\'' || c=='\\'), "O''Reilly");
+        assert_eq!(escape_string("O\\'Reilly", &|c| c=='\'' || c=='\\'), "O\\\\''Reilly");
     }
 }
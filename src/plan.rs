@@ -0,0 +1,243 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+
+/// How a table is accessed in a plan.
+#[derive(Clone, Debug, PartialEq)]
+enum Access {
+    /// Every row of the table is read.
+    Full(String),
+    /// The table is read through the index, whose name is unknown for the primary key of SQLite.
+    Index(String, Option<String>),
+}
+
+/// Return the text after the prefix ending with ` on `, and the word following ` using ` of it.
+fn on_using<'a>(line: &'a str, prefix: &str) -> Option<(&'a str, Option<&'a str>)> {
+    let rest = &line[line.find(prefix)? + prefix.len()..];
+    let table = rest.split_whitespace().next()?;
+    let index = rest.find(" using ").and_then(|i| rest[i + " using ".len()..].split_whitespace().next());
+    Some((table, index))
+}
+
+/// Parses the access of a line of the plan.
+fn parse_access(dialect: Dialect, line: &str) -> Option<Access> {
+    let line = line.trim().trim_start_matches("->").trim();
+    match dialect {
+        // SCAN users / SEARCH users USING INDEX idx (age>?) / SEARCH TABLE users USING INTEGER PRIMARY KEY (rowid=?)
+        Dialect::Sqlite => {
+            let mut words = line.split_whitespace();
+            let kind = words.next()?;
+            let mut table = words.next()?;
+            if table == "TABLE" {
+                table = words.next()?;
+            }
+            let rest = words.collect::<Vec<_>>();
+            let index = rest.iter().position(|&word| word == "INDEX").and_then(|i| rest.get(i + 1));
+            match kind {
+                "SCAN" if index.is_none() => Some(Access::Full(table.to_string())),
+                "SCAN" | "SEARCH" => Some(Access::Index(table.to_string(), index.map(|index| index.to_string()))),
+                _ => None,
+            }
+        },
+        // Seq Scan on users / Index Scan using idx on users / Bitmap Heap Scan on users
+        Dialect::Postgres => {
+            if let Some((table, _)) = on_using(line, "Seq Scan on ") {
+                return Some(Access::Full(table.to_string()));
+            }
+            for prefix in &["Index Scan using ", "Index Only Scan using "] {
+                if let Some(rest) = line.strip_prefix(prefix) {
+                    let index = rest.split_whitespace().next()?;
+                    let (table, _) = on_using(rest, " on ")?;
+                    return Some(Access::Index(table.to_string(), Some(index.to_string())));
+                }
+            }
+            let (table, _) = on_using(line, "Bitmap Heap Scan on ")?;
+            Some(Access::Index(table.to_string(), None))
+        },
+        // Table scan on users / Index lookup on users using idx (age=42) / Index range scan on users using idx
+        Dialect::Mysql => {
+            if let Some((table, _)) = on_using(line, "Table scan on ") {
+                return Some(Access::Full(table.to_string()));
+            }
+            let on = line.find(" on ")?;
+            if !line[..on].to_ascii_lowercase().contains("index") {
+                return None;
+            }
+            let (table, index) = on_using(line, " on ")?;
+            Some(Access::Index(table.to_string(), index.map(str::to_string)))
+        },
+        Dialect::SqlServer | Dialect::Oracle => None,
+    }
+}
+
+/// The expectations of the plan of a statement checked by [check_plan](./struct.Connection.html#method.check_plan).
+/// The tables are named as in the plan, which may be their aliases.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::PlanExpectation;
+/// let expectation = PlanExpectation::new()
+///     .uses_index("users", "users_age")
+///     .no_full_scan("posts");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlanExpectation {
+    index_scans:  Vec<String>,
+    indexes:      Vec<(String, String)>,
+    no_full_scan: Vec<String>,
+}
+
+impl PlanExpectation {
+    /// Creates an expectation which every plan meets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects the table to be read through an index.
+    pub fn index_scan(mut self, table: &str) -> Self {
+        self.index_scans.push(table.to_string());
+        self
+    }
+
+    /// Expects the table to be read through the index.
+    pub fn uses_index(mut self, table: &str, index: &str) -> Self {
+        self.indexes.push((table.to_string(), index.to_string()));
+        self
+    }
+
+    /// Expects no rows of the table to be read without an index.
+    pub fn no_full_scan(mut self, table: &str) -> Self {
+        self.no_full_scan.push(table.to_string());
+        self
+    }
+
+    /// Return the first unmet expectation of the accesses.
+    fn unmet(&self, accesses: &[Access]) -> Option<String> {
+        let is_indexed = |table: &str, index: Option<&str>| accesses.iter().any(|access| match access {
+            Access::Index(t, i) => t == table && (index.is_none() || i.as_deref() == index),
+            _ => false,
+        });
+        if let Some(table) = self.no_full_scan.iter().find(|table| accesses.contains(&Access::Full(table.to_string()))) {
+            return Some(format!("full scan on {}", table));
+        }
+        if let Some(table) = self.index_scans.iter().find(|table| !is_indexed(table, None)) {
+            return Some(format!("no index scan on {}", table));
+        }
+        if let Some((table, index)) = self.indexes.iter().find(|(table, index)| !is_indexed(table, Some(index))) {
+            return Some(format!("index {} not used on {}", index, table));
+        }
+        None
+    }
+}
+
+impl Connection {
+    /// Return the lines of the plan of the statement without executing it, which is
+    /// `EXPLAIN QUERY PLAN` of SQLite, `EXPLAIN` of PostgreSQL or `EXPLAIN FORMAT=TREE` of MySQL.
+    /// SQL Server and Oracle, whose plans are not returned by a statement, return the error "unsupported explain".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// let plan = conn.explain(conn.ow("SELECT * FROM users;")).unwrap();
+    /// assert_eq!(plan, vec!["SCAN users".to_string()]);
+    /// ```
+    pub fn explain<T: AsRef<str>>(&self, query: T) -> Result<Vec<String>> {
+        let (explain, column) = match self.dialect() {
+            Dialect::Sqlite   => (self.ow("EXPLAIN QUERY PLAN"), "detail"),
+            Dialect::Postgres => (self.ow("EXPLAIN"), "QUERY PLAN"),
+            Dialect::Mysql    => (self.ow("EXPLAIN FORMAT=TREE"), "EXPLAIN"),
+            Dialect::SqlServer | Dialect::Oracle => return Err(OwsqlError::Message("unsupported explain".into())),
+        };
+        let rows = self.rows(explain + query.as_ref())?;
+        Ok(rows.iter()
+            .filter_map(|row| row.get(column))
+            .flat_map(str::lines)
+            .map(|line| line.trim_end().to_string())
+            .collect())
+    }
+
+    /// Checks the plan of the statement against the expectation without executing it, so that a
+    /// test fails when the statement silently stops using an index.
+    /// See [PlanExpectation](./struct.PlanExpectation.html).
+    ///
+    /// # Errors
+    ///
+    /// An unmet expectation returns the error "unexpected plan: {reason}" with the plan.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::PlanExpectation;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER); CREATE INDEX users_age ON users (age);")).unwrap();
+    /// let expectation = PlanExpectation::new().uses_index("users", "users_age");
+    /// assert!(conn.check_plan(conn.ow("SELECT * FROM users WHERE age = 42;"), &expectation).is_ok());
+    /// assert!(conn.check_plan(conn.ow("SELECT * FROM users WHERE name = 'Alice';"), &expectation).is_err());
+    /// ```
+    pub fn check_plan<T: AsRef<str>>(&self, query: T, expectation: &PlanExpectation) -> Result<()> {
+        let plan = self.explain(query)?;
+        let accesses = plan.iter().filter_map(|line| parse_access(self.dialect(), line)).collect::<Vec<_>>();
+        match expectation.unmet(&accesses) {
+            Some(reason) => Err(OwsqlError::Message(format!("unexpected plan: {}\n{}", reason, plan.join("\n")))),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics if the plan of the statement does not meet the expectation.
+    /// See [check_plan](#method.check_plan).
+    #[track_caller]
+    pub fn assert_plan<T: AsRef<str>>(&self, query: T, expectation: &PlanExpectation) {
+        if let Err(e) = self.check_plan(query, expectation) {
+            panic!("{}", e);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, parse_access};
+    use crate::dialect::Dialect;
+
+    fn full(table: &str) -> Option<Access> {
+        Some(Access::Full(table.to_string()))
+    }
+
+    fn index(table: &str, index: Option<&str>) -> Option<Access> {
+        Some(Access::Index(table.to_string(), index.map(str::to_string)))
+    }
+
+    #[test]
+    fn sqlite() {
+        assert_eq!(parse_access(Dialect::Sqlite, "SCAN users"), full("users"));
+        assert_eq!(parse_access(Dialect::Sqlite, "SCAN TABLE users"), full("users"));
+        assert_eq!(parse_access(Dialect::Sqlite, "SEARCH users USING INDEX users_age (age=?)"), index("users", Some("users_age")));
+        assert_eq!(parse_access(Dialect::Sqlite, "SCAN users USING COVERING INDEX users_age"), index("users", Some("users_age")));
+        assert_eq!(parse_access(Dialect::Sqlite, "SEARCH TABLE users USING INTEGER PRIMARY KEY (rowid=?)"), index("users", None));
+        assert_eq!(parse_access(Dialect::Sqlite, "USE TEMP B-TREE FOR ORDER BY"), None);
+    }
+
+    #[test]
+    fn postgres() {
+        assert_eq!(parse_access(Dialect::Postgres, "Seq Scan on users  (cost=0.00..25.88 rows=6 width=36)"), full("users"));
+        assert_eq!(parse_access(Dialect::Postgres, "  ->  Seq Scan on users u  (cost=0.00..25.88 rows=6 width=36)"), full("users"));
+        assert_eq!(parse_access(Dialect::Postgres, "Index Scan using users_age on users  (cost=0.15..8.17 rows=1 width=36)"),
+            index("users", Some("users_age")));
+        assert_eq!(parse_access(Dialect::Postgres, "Index Only Scan using users_pkey on users"), index("users", Some("users_pkey")));
+        assert_eq!(parse_access(Dialect::Postgres, "Bitmap Heap Scan on users  (cost=4.20..13.67 rows=6 width=36)"), index("users", None));
+        assert_eq!(parse_access(Dialect::Postgres, "  Filter: (age = 42)"), None);
+    }
+
+    #[test]
+    fn mysql() {
+        assert_eq!(parse_access(Dialect::Mysql, "-> Table scan on users  (cost=0.55 rows=3)"), full("users"));
+        assert_eq!(parse_access(Dialect::Mysql, "    -> Index lookup on users using users_age (age=42)  (cost=0.35 rows=1)"),
+            index("users", Some("users_age")));
+        assert_eq!(parse_access(Dialect::Mysql, "-> Index range scan on users using users_age over (42 < age)"), index("users", Some("users_age")));
+        assert_eq!(parse_access(Dialect::Mysql, "-> Filter: (users.age = 42)  (cost=0.55 rows=1)"), None);
+    }
+}
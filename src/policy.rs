@@ -0,0 +1,63 @@
+use crate::Result;
+use crate::error::OwsqlError;
+use crate::lexer::lex;
+use crate::token::Token;
+
+/// A policy screening the fragments trusted by [ow](./struct.Connection.html#method.ow) and
+/// [ow_runtime](./struct.Connection.html#method.ow_runtime).
+/// See [set_fragment_policy](./struct.Connection.html#method.set_fragment_policy).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Policy {
+    /// Every fragment is allowed.
+    #[default]
+    AllowAll,
+    /// Fragments containing any of the words are denied, ignoring case.
+    /// Words in quoted strings and quoted identifiers are not checked.
+    DenyKeywords(&'static [&'static str]),
+}
+
+impl Policy {
+    /// Checks the fragment, and return
+    /// [OwsqlError::PolicyViolation](./enum.OwsqlError.html#variant.PolicyViolation) if it is denied.
+    pub(crate) fn check(&self, fragment: &str) -> Result<()> {
+        let denied = match self {
+            Policy::AllowAll => return Ok(()),
+            Policy::DenyKeywords(denied) => denied,
+        };
+        let mut tokens = Vec::new();
+        lex(fragment, &mut tokens);
+        for token in tokens {
+            let word = match &token {
+                Token::Keyword(word) | Token::Identifier(word) => word,
+                _ => continue,
+            };
+            if let Some(keyword) = denied.iter().find(|keyword| keyword.eq_ignore_ascii_case(word)) {
+                return Err(OwsqlError::PolicyViolation {
+                    keyword:  keyword.to_string(),
+                    fragment: fragment.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let policy = Policy::DenyKeywords(&["DROP", "GRANT", "pg_sleep"]);
+        assert_eq!(policy.check("SELECT * FROM users WHERE name ="), Ok(()));
+        assert_eq!(policy.check("SELECT * FROM users WHERE name = 'DROP' OR \"grant\" ="), Ok(()));
+        assert_eq!(policy.check("SELECT * FROM dropped"), Ok(()));
+        assert_eq!(policy.check("; drop table users"), Err(OwsqlError::PolicyViolation {
+            keyword:  "DROP".into(),
+            fragment: "; drop table users".into(),
+        }));
+        assert!(policy.check("SELECT PG_SLEEP(10)").is_err());
+        assert_eq!(Policy::default().check("DROP TABLE users"), Ok(()));
+    }
+}
@@ -0,0 +1,194 @@
+extern crate postgres_sys as postgres;
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::failover::{HostStatus, Hosts};
+#[cfg(feature = "native-tls")]
+use crate::tls::TlsConfig;
+
+/// Connection configuration.
+/// Each value is passed to the driver as it is, so no parameter string is assembled.
+///
+/// # Examples
+///
+/// ```rust
+/// let conn = owsql::postgres::Config::new()
+///     .host("localhost")
+///     .user("postgres")
+///     .password("postgres")
+///     .application_name("owsql")
+///     .open()
+///     .unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct Config {
+    hosts:            Hosts,
+    port:             Option<u16>,
+    user:             Option<String>,
+    password:         Option<String>,
+    dbname:           Option<String>,
+    application_name: Option<String>,
+    connect_timeout:  Option<Duration>,
+    #[cfg(feature = "native-tls")]
+    tls:              Option<TlsConfig>,
+}
+
+impl Config {
+    /// Creates a new configuration with no values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the host name or the IP address of the server.
+    /// If multiple hosts are added, they are tried in order until the connection succeeds,
+    /// and the hosts that failed last time are tried after the others.
+    pub fn host(mut self, host: &str) -> Self {
+        self.hosts.push(host);
+        self
+    }
+
+    /// Rotates the first host to try on each [open](#method.open), to spread the connections
+    /// across the hosts, for example, the readers of [ReplicaSet](../struct.ReplicaSet.html).
+    /// The default is `false`.
+    pub fn round_robin(mut self, round_robin: bool) -> Self {
+        self.hosts.set_round_robin(round_robin);
+        self
+    }
+
+    /// Return the health of each host, which is shared by the clones of the configuration.
+    pub fn host_status(&self) -> Vec<HostStatus> {
+        self.hosts.statuses()
+    }
+
+    /// Sets the port of the server. The default is 5432.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Sets the user name to authenticate with.
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    /// Sets the password to authenticate with.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// Sets the name of the database. The default is the user name.
+    pub fn dbname(mut self, dbname: &str) -> Self {
+        self.dbname = Some(dbname.to_string());
+        self
+    }
+
+    /// Sets the `application_name` reported to the server.
+    pub fn application_name(mut self, application_name: &str) -> Self {
+        self.application_name = Some(application_name.to_string());
+        self
+    }
+
+    /// Sets the timeout for establishing the connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Connects over TLS.
+    #[cfg(feature = "native-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Open a read-write connection with the configuration.
+    pub fn open(&self) -> Result<Connection> {
+        let mut config = postgres::Config::new();
+        if let Some(port) = self.port {
+            config.port(port);
+        }
+        if let Some(user) = &self.user {
+            config.user(user);
+        }
+        if let Some(password) = &self.password {
+            config.password(password);
+        }
+        if let Some(dbname) = &self.dbname {
+            config.dbname(dbname);
+        }
+        if let Some(application_name) = &self.application_name {
+            config.application_name(application_name);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            config.connect_timeout(timeout);
+        }
+
+        self.hosts.connect(|host| {
+            let mut config = config.clone();
+            if let Some(host) = host {
+                config.host(host);
+            }
+            #[cfg(feature = "native-tls")]
+            if let Some(tls) = &self.tls {
+                return super::connection::connect_with_tls(config, tls);
+            }
+            super::connection::connect(&config)
+        })
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("hosts", &self.hosts.hosts())
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "_"))
+            .field("dbname", &self.dbname)
+            .field("application_name", &self.application_name)
+            .field("connect_timeout", &self.connect_timeout)
+            .finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open() {
+        let config = Config::new()
+            .host("localhost")
+            .user("postgres")
+            .password("postgres")
+            .application_name("owsql")
+            .connect_timeout(Duration::from_secs(5));
+        assert!(config.open().is_ok());
+        assert!(!format!("{:?}", Config::new().password("secret")).contains("secret"));
+        assert!(Config::new().host("localhost").user("postgres").password("host=evil").open().is_err());
+        assert!(Config::new().user("postgres").open().is_err());
+    }
+
+    #[test]
+    fn failover() {
+        let config = Config::new()
+            .host("owsql.invalid")
+            .host("localhost")
+            .user("postgres")
+            .password("postgres")
+            .connect_timeout(Duration::from_secs(5));
+        assert!(config.open().is_ok());
+        let status = config.host_status();
+        assert_eq!(status[0].healthy, Some(false));
+        assert_eq!(status[1].healthy, Some(true));
+        assert!(config.clone().round_robin(true).open().is_ok());
+        assert_eq!(config.host_status()[0].failures, 1);
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{self, Read, Write};
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use super::connection::{backend, quote_identifier};
+
+impl Connection {
+    /// Loads the rows from the reader into the table by `COPY ... FROM STDIN`,
+    /// and return the number of the rows.
+    /// The data must be in the text format of `COPY`, that is,
+    /// the columns are separated by tabs and the rows by newlines.  
+    /// The table may be qualified by the schema, and each part is quoted as an identifier.
+    /// Only for PostgreSQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let conn = owsql::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+    /// # conn.execute(conn.ow("CREATE TEMPORARY TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// let data = "Alice\t42\nBob\t69\n";
+    /// assert_eq!(conn.copy_in("users", data.as_bytes()), Ok(2));
+    ///
+    /// let mut out = Vec::new();
+    /// conn.copy_out(conn.ow("SELECT name FROM users ORDER BY age;"), &mut out).unwrap();
+    /// assert_eq!(out, b"Alice\nBob\n");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn copy_in<R: Read>(&self, table: &str, mut reader: R) -> Result<u64> {
+        let postgres = backend(self, "copy")?;
+        let table = quote_identifier(table)?;
+        let sql = format!("COPY {} FROM STDIN", table);
+        let start = self.start_statement(&sql);
+
+        let mut client = postgres.borrow_mut();
+        let result = match client.copy_in(sql.as_str()) {
+            Ok(mut writer) => match io::copy(&mut reader, &mut writer) {
+                Ok(_) => writer.finish().or_else(|e| postgres.exec_error(&self.error_level, &e).map(|_| 0)),
+                // The copy is aborted when the writer is dropped without finishing.
+                Err(e) => Err(OwsqlError::Message(format!("failed to copy: {}", e))),
+            },
+            Err(e) => postgres.exec_error(&self.error_level, &e).map(|_| 0),
+        };
+        drop(client);
+
+        self.record_statement(&sql, start, result.as_ref().err());
+        result
+    }
+
+    /// Writes the rows of the query to the writer by `COPY (...) TO STDOUT`,
+    /// and return the number of the bytes.
+    /// The query is converted in the same way as [execute](#method.execute),
+    /// so it must be a single `SELECT` statement.
+    /// Only for PostgreSQL.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn copy_out<T: AsRef<str>, W: Write>(&self, query: T, mut writer: W) -> Result<u64> {
+        let postgres = backend(self, "copy")?;
+        let query = query.as_ref();
+        let sql = match self.rewrite(self.convert_to_valid_syntax(query).map(|sql| format!("COPY ({}) TO STDOUT", sql.trim_end().trim_end_matches(';')))) {
+            Ok(sql) => sql,
+            Err(_) if self.error_level == OwsqlErrorLevel::AlwaysOk => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let start = self.start_statement(query);
+
+        let mut client = postgres.borrow_mut();
+        let result = match client.copy_out(sql.as_str()) {
+            Ok(mut reader) => io::copy(&mut reader, &mut writer)
+                .map_err(|e| OwsqlError::Message(format!("failed to copy: {}", e))),
+            Err(e) => postgres.exec_error(&self.error_level, &e).map(|_| 0),
+        };
+        drop(client);
+
+        self.record_statement(query, start, result.as_ref().err());
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::OwsqlError;
+
+    #[test]
+    fn copy() {
+        let conn = crate::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+        conn.execute(conn.ow("CREATE TEMPORARY TABLE \"owsql copy\" (name TEXT, age INTEGER);")).unwrap();
+        assert_eq!(conn.copy_in("owsql copy", "Alice\t42\nO'Reilly\t\\N\n".as_bytes()), Ok(2));
+        assert!(matches!(conn.copy_in("owsql copy", "Bob\tx\n".as_bytes()), Err(OwsqlError::Database { .. })));
+        assert!(conn.copy_in("", "".as_bytes()).is_err());
+
+        let mut out = Vec::new();
+        let sql = conn.ow("SELECT * FROM \"owsql copy\" WHERE name =") + "O'Reilly";
+        assert_eq!(conn.copy_out(&sql, &mut out), Ok(12));
+        assert_eq!(out, b"O'Reilly\t\\N\n");
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::row::Row;
+use super::connection::backend;
+
+/// The serial number of the cursors, to make their names unique.
+static CURSOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// An iterator over the rows of a server-side cursor, in chunks.
+/// See [rows_chunked](../struct.Connection.html#method.rows_chunked).
+///
+/// The cursor is closed when all rows are fetched, an error occurs, or the iterator is dropped.
+pub struct RowChunks<'a> {
+    conn:            &'a Connection,
+    cursor:          String,
+    chunk_size:      u64,
+    own_transaction: bool,
+    done:            bool,
+}
+
+impl RowChunks<'_> {
+    fn fetch(&self) -> Result<Vec<Row>> {
+        let sql = format!("FETCH FORWARD {} FROM {}", self.chunk_size, self.cursor);
+        self.conn.rows_converted(&sql, Ok(sql.clone()), self.conn, &self.conn.error_level)
+    }
+
+    fn close(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let _ = self.conn.conn._execute(Ok(format!("CLOSE {}", self.cursor)), &self.conn.error_level);
+        if self.own_transaction {
+            let _ = self.conn.conn._execute(Ok("COMMIT".to_string()), &self.conn.error_level);
+        }
+    }
+}
+
+impl Iterator for RowChunks<'_> {
+    type Item = Result<Vec<Row>>;
+
+    fn next(&mut self) -> Option<Result<Vec<Row>>> {
+        if self.done {
+            return None;
+        }
+        let rows = self.fetch();
+        match &rows {
+            Ok(rows) if rows.is_empty() => {
+                self.close();
+                return None;
+            },
+            Ok(rows) if (rows.len() as u64) < self.chunk_size => self.close(),
+            Ok(_) => (),
+            Err(_) => self.close(),
+        }
+        Some(rows)
+    }
+}
+
+impl Drop for RowChunks<'_> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl fmt::Debug for RowChunks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RowChunks")
+            .field("cursor", &self.cursor)
+            .field("chunk_size", &self.chunk_size)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl Connection {
+    /// Execute a `SELECT` statement with a server-side cursor,
+    /// and return an iterator over the rows in chunks of `chunk_size` rows,
+    /// so that all rows are not held in memory at once.
+    ///
+    /// A cursor lives in a transaction. In [with_transaction](#method.with_transaction),
+    /// the cursor is declared in that transaction. Otherwise, a transaction is begun for the cursor
+    /// and committed when the cursor is closed, so no other statements should be executed until then.  
+    /// Only for PostgreSQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let conn = owsql::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+    /// let sql = conn.ow("SELECT generate_series(1, 10) AS n;");
+    /// let chunks = conn.rows_chunked(&sql, 4).unwrap()
+    ///     .map(|chunk| chunk.unwrap().len())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(chunks, [4, 4, 2]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn rows_chunked<T: AsRef<str>>(&self, query: T, chunk_size: u64) -> Result<RowChunks<'_>> {
+        backend(self, "cursor")?;
+        let mut chunks = RowChunks {
+            conn:            self,
+            cursor:          format!("owsql_cursor_{}", CURSOR_ID.fetch_add(1, Ordering::Relaxed)),
+            chunk_size,
+            own_transaction: !self.in_transaction.get(),
+            done:            true,
+        };
+        if chunk_size == 0 || chunk_size > i64::MAX as u64 {
+            OwsqlError::new(&self.error_level, "invalid chunk size", &chunk_size.to_string())?;
+            return Ok(chunks);
+        }
+        let sql = match self.convert_to_valid_syntax(query.as_ref()) {
+            Ok(sql) => sql,
+            Err(_) if self.error_level == OwsqlErrorLevel::AlwaysOk => return Ok(chunks),
+            Err(e) => return Err(e),
+        };
+
+        if chunks.own_transaction {
+            self.conn._execute(Ok("BEGIN".to_string()), &self.error_level)?;
+        }
+        chunks.done = false;
+        let declare = format!("DECLARE {} NO SCROLL CURSOR FOR {}", chunks.cursor, sql.trim_end().trim_end_matches(';'));
+        if let Err(e) = self.conn._execute(self.rewrite(Ok(declare)), &self.error_level) {
+            if chunks.own_transaction {
+                let _ = self.conn._execute(Ok("ROLLBACK".to_string()), &self.error_level);
+            }
+            chunks.done = true;
+            return Err(e);
+        }
+        Ok(chunks)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::OwsqlError;
+
+    #[test]
+    fn rows_chunked() {
+        let conn = crate::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+        let sql = conn.ow("SELECT n FROM generate_series(1, 5) AS n WHERE n::text <>") + "3' OR 'a'='a";
+        let chunks = conn.rows_chunked(&sql, 2).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+
+        let mut chunks = conn.rows_chunked(conn.ow("SELECT generate_series(1, 4) AS n;"), 2).unwrap();
+        assert_eq!(chunks.next().unwrap().unwrap()[0].get("n"), Some("1"));
+        drop(chunks);
+        assert_eq!(conn.count(conn.ow("SELECT 1;")), Ok(1));
+
+        conn.with_transaction(|tx| {
+            let chunks = tx.rows_chunked(tx.ow("SELECT generate_series(1, 4) AS n;"), 4)?;
+            assert_eq!(chunks.count(), 1);
+            Ok(())
+        }).unwrap();
+        assert!(conn.rows_chunked(conn.ow("SELECT * FROM nothing;"), 2).is_err());
+        assert_eq!(conn.rows_chunked(conn.ow("SELECT 1;"), 0).err(), Some(OwsqlError::Message("invalid chunk size".into())));
+    }
+}
@@ -1,6 +1,14 @@
 //! Interface to [PostgreSQL](https://www.postgresql.org/) of OverwriteSQL.
 
+mod config;
 pub(crate) mod connection;
+mod copy;
+mod cursor;
+mod notify;
+
+pub use config::Config;
+pub use cursor::RowChunks;
+pub use notify::Notification;
 
 use crate::Result;
 use crate::connection::Connection;
@@ -17,6 +25,25 @@ use crate::connection::Connection;
 /// ```
 #[inline]
 pub fn open(params: &str) -> Result<Connection> {
-    connection::open(&params)
+    connection::open(params)
 }
 
+
+/// Open a read-write connection over TLS.
+///
+/// The `sslmode` in `params` is overridden by the mode of `tls`.
+///
+/// # Examples
+///
+/// ```rust
+/// use owsql::{TlsConfig, TlsMode};
+/// let params = "host=localhost user=postgres password=postgres";
+/// let tls = TlsConfig { mode: TlsMode::Require, ..Default::default() };
+/// let conn = owsql::postgres::open_with_tls(&params, &tls).unwrap();
+/// ```
+#[cfg(feature = "native-tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+#[inline]
+pub fn open_with_tls(params: &str, tls: &crate::TlsConfig) -> Result<Connection> {
+    connection::open_with_tls(params, tls)
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use postgres_sys::fallible_iterator::FallibleIterator;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::lexer::is_identifier;
+use crate::watch::watch_channel;
+use super::connection::backend;
+
+/// A notification received on a channel.
+/// See [listen](../struct.Connection.html#method.listen).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Notification {
+    /// The process ID of the backend that sent the notification.
+    pub process_id: i32,
+    /// The channel of the notification.
+    pub channel:    String,
+    /// The payload of the notification.
+    pub payload:    String,
+}
+
+impl From<&postgres_sys::Notification> for Notification {
+    fn from(notification: &postgres_sys::Notification) -> Self {
+        Self {
+            process_id: notification.process_id(),
+            channel:    notification.channel().to_string(),
+            payload:    notification.payload().to_string(),
+        }
+    }
+}
+
+impl Connection {
+    /// Starts listening on the channel.
+    /// The channel is quoted as an identifier, so it is case-sensitive.
+    /// Only for PostgreSQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// let conn = owsql::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+    /// conn.listen("events").unwrap();
+    /// conn.notify("events", "Alice' OR 'a'='a").unwrap();
+    /// let notification = conn.wait_notification(Duration::from_secs(1)).unwrap().unwrap();
+    /// assert_eq!(notification.payload, "Alice' OR 'a'='a");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn listen(&self, channel: &str) -> Result<()> {
+        let channel = self.channel(channel)?;
+        self.execute(self.ow("LISTEN ") + &channel + &self.ow(";"))
+    }
+
+    /// Stops listening on the channel.
+    /// Only for PostgreSQL.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn unlisten(&self, channel: &str) -> Result<()> {
+        let channel = self.channel(channel)?;
+        self.execute(self.ow("UNLISTEN ") + &channel + &self.ow(";"))
+    }
+
+    /// Sends a notification with the payload on the channel.
+    /// Both are escaped as strings.
+    /// Only for PostgreSQL.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn notify(&self, channel: &str, payload: &str) -> Result<()> {
+        backend(self, "notify")?;
+        self.execute(self.ow("SELECT pg_notify(") + channel + &self.ow(",") + payload + &self.ow(");"))
+    }
+
+    /// Return the notifications received so far without blocking.
+    /// Only for PostgreSQL.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn notifications(&self) -> Result<Vec<Notification>> {
+        let mut client = backend(self, "notify")?.borrow_mut();
+        let mut notifications = client.notifications();
+        let mut iter = notifications.iter();
+        let mut received = Vec::new();
+        loop {
+            match iter.next() {
+                Ok(Some(notification)) => received.push(Notification::from(&notification)),
+                Ok(None) => return Ok(received),
+                Err(e) => return Err(OwsqlError::Message(format!("failed to receive notification: {}", e))),
+            }
+        }
+    }
+
+    /// Waits for a notification up to the timeout, and return `None` if it timed out.
+    /// Only for PostgreSQL.
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn wait_notification(&self, timeout: Duration) -> Result<Option<Notification>> {
+        let mut client = backend(self, "notify")?.borrow_mut();
+        let mut notifications = client.notifications();
+        let next = notifications.timeout_iter(timeout).next();
+        match next {
+            Ok(notification) => Ok(notification.as_ref().map(Notification::from)),
+            Err(e) => Err(OwsqlError::Message(format!("failed to receive notification: {}", e))),
+        }
+    }
+
+    /// Installs the trigger notifying the changes of the table to [watch](../struct.Connection.html#method.watch),
+    /// which is a plain identifier qualified by [set_schema_prefix](../struct.Connection.html#method.set_schema_prefix).
+    /// The trigger and its function are replaced if they exist.
+    /// Only for PostgreSQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use owsql::ChangeKind;
+    /// let conn = owsql::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+    /// conn.execute(conn.ow("CREATE TEMPORARY TABLE users (name TEXT);")).unwrap();
+    /// conn.install_watch_trigger("users").unwrap();
+    /// let mut watcher = conn.watch("users").unwrap();
+    /// conn.execute(conn.ow("INSERT INTO users VALUES ('Alice');")).unwrap();
+    /// let event = watcher.next_event(Duration::from_secs(1)).unwrap().unwrap();
+    /// assert_eq!(event.kind, ChangeKind::Insert);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
+    pub fn install_watch_trigger(&self, table: &str) -> Result<()> {
+        backend(self, "watch trigger")?;
+        let quoted = match self.quote_table(table) {
+            Some(quoted) if is_identifier(table) => quoted,
+            _ => return OwsqlError::new(&self.error_level, "invalid identifier", table),
+        };
+        let channel = watch_channel(table);
+        self.execute(self.ow_generated(format!(
+            "CREATE OR REPLACE FUNCTION \"{channel}\"() RETURNS trigger AS $$ \
+             BEGIN PERFORM pg_notify('{channel}', TG_OP); RETURN NULL; END; $$ LANGUAGE plpgsql; \
+             DROP TRIGGER IF EXISTS \"{channel}\" ON {table}; \
+             CREATE TRIGGER \"{channel}\" AFTER INSERT OR UPDATE OR DELETE ON {table} \
+             FOR EACH ROW EXECUTE FUNCTION \"{channel}\"();",
+            channel = channel, table = quoted,
+        )))
+    }
+
+    /// Quotes the channel as an identifier.
+    fn channel(&self, channel: &str) -> Result<String> {
+        backend(self, "notify")?;
+        if channel.is_empty() || channel.contains('\0') {
+            return Err(OwsqlError::Message(format!("invalid channel: {:?}", channel)));
+        }
+        Ok(self.ow_generated(format!("\"{}\"", channel.replace('"', "\"\""))))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn listen() {
+        let conn = crate::postgres::open("host=localhost user=postgres password=postgres").unwrap();
+        conn.listen("owsql \"events\"").unwrap();
+        conn.notify("owsql \"events\"", "Alice' OR 'a'='a").unwrap();
+        conn.notify("other", "Bob").unwrap();
+        let notification = conn.wait_notification(Duration::from_secs(1)).unwrap().unwrap();
+        assert_eq!(notification.channel, "owsql \"events\"");
+        assert_eq!(notification.payload, "Alice' OR 'a'='a");
+        assert!(conn.notifications().unwrap().is_empty());
+
+        conn.unlisten("owsql \"events\"").unwrap();
+        conn.notify("owsql \"events\"", "Carol").unwrap();
+        assert_eq!(conn.wait_notification(Duration::from_millis(100)), Ok(None));
+        assert!(conn.listen("").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::token::Token;
+
+/// Keywords starting a new line.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "DELETE", "EXCEPT", "FROM", "GROUP", "HAVING", "INSERT", "INTERSECT", "LIMIT", "ORDER",
+    "RETURNING", "SELECT", "SET", "UNION", "UPDATE", "VALUES", "WHERE", "WITH",
+];
+
+/// Keywords starting a join, which start a new line unless following another.
+const JOIN_KEYWORDS: &[&str] = &["CROSS", "FULL", "INNER", "JOIN", "LEFT", "NATURAL", "OUTER", "RIGHT"];
+
+const INDENT: &str = "    ";
+
+/// Formats the tokens of a statement, one clause per line.
+/// Keywords are upper-cased, `AND` and `OR` start an indented line, and subqueries are indented.
+pub(crate) fn pretty(tokens: &[Token]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    // Whether each open parenthesis encloses a subquery.
+    let mut parens: Vec<bool> = Vec::new();
+    let mut between = false;
+    let mut prev: Option<&Token> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let word = match token {
+            Token::Keyword(keyword) => keyword.to_ascii_uppercase(),
+            Token::Identifier(identifier) if identifier.eq_ignore_ascii_case("NATURAL") => identifier.to_ascii_uppercase(),
+            other => other.as_str().to_string(),
+        };
+        let depth = parens.iter().filter(|subquery| **subquery).count();
+        let in_clause = parens.last().is_none_or(|subquery| *subquery);
+
+        let indent = if !in_clause {
+            None
+        } else if CLAUSE_KEYWORDS.contains(&word.as_str())
+            || (JOIN_KEYWORDS.contains(&word.as_str()) && !prev.is_some_and(is_join_keyword)) {
+            Some(depth)
+        } else if (word == "AND" && !between) || word == "OR" {
+            Some(depth + 1)
+        } else {
+            None
+        };
+        match indent {
+            Some(indent) if !line.trim().is_empty() => {
+                lines.push(line);
+                line = INDENT.repeat(indent);
+            },
+            Some(indent) => line = INDENT.repeat(indent),
+            None if prev.is_some_and(|prev| needs_space(prev, token)) => line.push(' '),
+            None => (),
+        }
+        line.push_str(&word);
+
+        match word.as_str() {
+            "BETWEEN" => between = true,
+            "AND" => between = false,
+            "(" => parens.push(tokens.get(i + 1).is_some_and(|next| is_word(next, "SELECT") || is_word(next, "WITH"))),
+            ")" => { parens.pop(); },
+            ";" => {
+                lines.push(line);
+                line = String::new();
+                parens.clear();
+                prev = None;
+                continue;
+            },
+            _ => (),
+        }
+        prev = Some(token);
+    }
+    if !line.trim().is_empty() {
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+fn needs_space(prev: &Token, token: &Token) -> bool {
+    let no_space_before = [",", ";", ")", ".", "::"];
+    let no_space_after = ["(", ".", "::"];
+    match (prev, token) {
+        (_, Token::Symbol(symbol)) if no_space_before.contains(&symbol.as_str()) => false,
+        (Token::Symbol(symbol), _) if no_space_after.contains(&symbol.as_str()) => false,
+        // A function call.
+        (Token::Identifier(_), Token::Symbol(symbol)) if symbol == "(" => false,
+        _ => true,
+    }
+}
+
+fn is_join_keyword(token: &Token) -> bool {
+    JOIN_KEYWORDS.iter().any(|keyword| is_word(token, keyword))
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Keyword(w) | Token::Identifier(w) if w.eq_ignore_ascii_case(word))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::lex;
+
+    fn format(sql: &str) -> String {
+        let mut tokens = Vec::new();
+        lex(sql, &mut tokens);
+        super::pretty(&tokens)
+    }
+
+    #[test]
+    fn pretty() {
+        assert_eq!(
+            format("select u.name, count(*) from users u left join orders o on o.user_id = u.id where u.age between 20 and 30 and (u.name like 'A%' or u.name = 'Bob') group by u.name order by 2 desc limit 10;"),
+            "SELECT u.name, count(*)\n\
+             FROM users u\n\
+             LEFT JOIN orders o ON o.user_id = u.id\n\
+             WHERE u.age BETWEEN 20 AND 30\n    \
+                 AND (u.name LIKE 'A%' OR u.name = 'Bob')\n\
+             GROUP BY u.name\n\
+             ORDER BY 2 DESC\n\
+             LIMIT 10;"
+        );
+        assert_eq!(
+            format("SELECT * FROM users WHERE age IN (SELECT age FROM admins WHERE active = 1) ; DELETE FROM users"),
+            "SELECT *\n\
+             FROM users\n\
+             WHERE age IN (\n    \
+                 SELECT age\n    \
+                 FROM admins\n    \
+                 WHERE active = 1);\n\
+             DELETE\n\
+             FROM users"
+        );
+        assert_eq!(format("INSERT INTO users (name, age) VALUES ('Alice', 42)"), "INSERT INTO users(name, age)\nVALUES ('Alice', 42)");
+        assert_eq!(format("  "), "");
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+
+/// A cloneable handle of a connection for reading, which can be shared by threads.
+/// See [reader_handle](./struct.Connection.html#method.reader_handle).
+///
+/// The clones share the same connection, and a statement waits until the other clones finish theirs.
+/// Statements are built with the fragments of the locked connection.
+#[derive(Clone, Debug)]
+pub struct ReaderHandle {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ReaderHandle {
+    /// Creates a handle sharing the connection, which should be read-only,
+    /// such as a second connection to the same database.
+    pub fn new(conn: Connection) -> Self {
+        Self { conn: Arc::new(Mutex::new(conn)) }
+    }
+
+    /// Locks the connection, waiting for the other clones.
+    pub fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs the closure with the locked connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let dir = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = dir.path().join("test.db");
+    /// let conn = owsql::sqlite::open(&path).unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice');")).unwrap();
+    /// let reader = conn.reader_handle().unwrap();
+    /// let rows = std::thread::spawn(move || {
+    ///     reader.with(|conn| conn.rows(conn.ow("SELECT name FROM users;")))
+    /// }).join().unwrap().unwrap();
+    /// assert_eq!(rows[0].get("name"), Some("Alice"));
+    /// ```
+    pub fn with<T, F: FnOnce(&Connection) -> T>(&self, f: F) -> T {
+        f(&self.lock())
+    }
+}
+
+impl Connection {
+    /// Return a [ReaderHandle](./struct.ReaderHandle.html) of a second, read-only connection to the
+    /// same database, so that read paths need neither `&mut` nor the ownership of this connection.
+    /// The error level, the schema prefix, the fragment policy, the rewriters and the settings of
+    /// the statements and the overwrite strings are copied.
+    ///
+    /// Only a SQLite database file is supported, which is read concurrently with the writes
+    /// in [WAL mode](https://www.sqlite.org/wal.html). For the other databases, open a second
+    /// connection and pass it to [ReaderHandle::new](./struct.ReaderHandle.html#method.new).
+    pub fn reader_handle(&self) -> Result<ReaderHandle> {
+        let mut reader = self.open_reader()?;
+        reader.error_level       = self.error_level.clone();
+        reader.ow_len_range      = self.ow_len_range;
+        reader.token_alphabet    = self.token_alphabet;
+        reader.strict            = self.strict;
+        reader.canonical_spaces  = self.canonical_spaces;
+        reader.comment_mode      = self.comment_mode;
+        reader.chunk_limit       = self.chunk_limit;
+        reader.unique_columns    = self.unique_columns;
+        reader.column_match      = self.column_match;
+        reader.timestamp_columns = self.timestamp_columns.clone();
+        reader.schema_prefix     = self.schema_prefix.clone();
+        reader.policy            = self.policy;
+        reader.rewriters         = self.rewriters.clone();
+        reader.query_tags        = self.query_tags.clone();
+        reader.escape_mode.set(self.escape_mode.get());
+        reader.charset.set(self.charset.get());
+        reader.set_eviction_policy(self.gc.borrow().policy());
+        Ok(ReaderHandle::new(reader))
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn open_reader(&self) -> Result<Connection> {
+        let path = crate::sqlite::connection::filename(self, "reader handle")?;
+        if path.is_empty() {
+            return Err(OwsqlError::Message("unsupported reader handle: in-memory database".into()));
+        }
+        crate::sqlite::open_readonly(path)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn open_reader(&self) -> Result<Connection> {
+        Err(OwsqlError::Message("unsupported reader handle".into()))
+    }
+}
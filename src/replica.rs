@@ -0,0 +1,186 @@
+use std::cell::Cell;
+use std::ops::Deref;
+use std::time::Duration;
+
+use crate::Result;
+use crate::cache::is_select;
+use crate::connection::Connection;
+use crate::row::Row;
+use crate::time::Instant;
+
+/// A writer connection and its read replicas.
+///
+/// Statements are built with the fragments of the writer, which is reachable by `Deref`, so
+/// [ow](./struct.Connection.html#method.ow) and the other methods need no duplication per member.
+/// [execute](#method.execute), [iterate](#method.iterate) and [rows](#method.rows) convert the
+/// statement by the writer, and send a single `SELECT` statement to the next reader in turn
+/// and any other statement to the writer.
+/// The other methods of `Connection` are called on the writer.
+///
+/// The readers must be of the same database and the same settings as the writer, since the
+/// statement is escaped for the writer. Replication lag is not considered, unless
+/// [after_write_read_from_primary](#method.after_write_read_from_primary) is set.
+///
+/// # Examples
+///
+/// ```
+/// # let dir = temporary::Directory::new("sqlite").unwrap();
+/// # let path = dir.path().join("test.db");
+/// let writer = owsql::sqlite::open(&path).unwrap();
+/// let reader = owsql::sqlite::open_readonly(&path).unwrap();
+/// let db = owsql::ReplicaSet::new(writer, vec![reader]);
+/// db.execute(db.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice');")).unwrap();
+/// let rows = db.rows(db.ow("SELECT * FROM users WHERE name =") + "Alice").unwrap();
+/// assert_eq!(rows.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct ReplicaSet {
+    writer:         Connection,
+    readers:        Vec<Connection>,
+    next:           Cell<usize>,
+    primary_window: Option<Duration>,
+    last_write:     Cell<Option<Instant>>,
+}
+
+impl ReplicaSet {
+    /// Creates a set of the writer and the readers.
+    /// If there are no readers, every statement is sent to the writer.
+    pub fn new(writer: Connection, readers: Vec<Connection>) -> Self {
+        Self { writer, readers, next: Cell::new(0), primary_window: None, last_write: Cell::new(None) }
+    }
+
+    /// Sends the `SELECT` statements to the writer for the duration after a statement is sent to the
+    /// writer, so that the rows just written are read before the readers catch up with the replication.
+    /// The duration should be longer than the replication lag.
+    ///
+    /// Only the statements of [execute](#method.execute), [iterate](#method.iterate) and [rows](#method.rows)
+    /// are tracked. Call [mark_write](#method.mark_write) after writing through the other methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # let dir = temporary::Directory::new("sqlite").unwrap();
+    /// # let path = dir.path().join("test.db");
+    /// # let writer = owsql::sqlite::open(&path).unwrap();
+    /// # let reader = owsql::sqlite::open_readonly(&path).unwrap();
+    /// let mut db = owsql::ReplicaSet::new(writer, vec![reader]);
+    /// db.after_write_read_from_primary(Duration::from_secs(5));
+    /// db.execute(db.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice');")).unwrap();
+    /// // Read from the writer for 5 seconds.
+    /// let rows = db.rows(db.ow("SELECT * FROM users;")).unwrap();
+    /// assert_eq!(rows.len(), 1);
+    /// ```
+    pub fn after_write_read_from_primary(&mut self, duration: Duration) {
+        self.primary_window = Some(duration);
+    }
+
+    /// Sends the `SELECT` statements to the readers regardless of the writes, which is the default.
+    pub fn clear_after_write_read_from_primary(&mut self) {
+        self.primary_window = None;
+    }
+
+    /// Records a write made without this set, such as through [writer](#method.writer), for
+    /// [after_write_read_from_primary](#method.after_write_read_from_primary).
+    pub fn mark_write(&self) {
+        self.last_write.set(Some(Instant::now()));
+    }
+
+    /// Return the writer.
+    #[inline]
+    pub fn writer(&self) -> &Connection {
+        &self.writer
+    }
+
+    /// Return the readers.
+    #[inline]
+    pub fn readers(&self) -> &[Connection] {
+        &self.readers
+    }
+
+    /// Execute a statement on the writer, or on a reader if it is a `SELECT` statement.
+    /// See [Connection::execute](./struct.Connection.html#method.execute).
+    pub fn execute<T: AsRef<str>>(&self, query: T) -> Result<()> {
+        let query = query.as_ref();
+        let sql = self.writer.convert_to_valid_syntax(query);
+        match &sql {
+            Ok(s) if is_select(s) => {
+                let reader = self.reader();
+                let start = reader.start_statement(query);
+                let result = reader.conn._execute(self.writer.rewrite(sql), &self.writer.error_level);
+                reader.record_statement(query, start, result.as_ref().err());
+                result
+            },
+            _ => {
+                self.mark_write();
+                self.writer.execute_converted(query, sql, &self.writer.error_level)
+            },
+        }
+    }
+
+    /// Execute a statement and process the resulting rows on the writer,
+    /// or on a reader if it is a `SELECT` statement.
+    /// See [Connection::iterate](./struct.Connection.html#method.iterate).
+    pub fn iterate<T: AsRef<str>, F>(&self, query: T, mut callback: F) -> Result<()>
+        where
+            F: FnMut(&[(&str, Option<&str>)]) -> bool,
+    {
+        let query = query.as_ref();
+        let sql = self.writer.convert_to_valid_syntax(query);
+        match &sql {
+            Ok(s) if is_select(s) => {
+                let reader = self.reader();
+                let start = reader.start_statement(query);
+                let result = reader.conn._iterate(self.writer.rewrite(sql), &self.writer.error_level, &mut callback);
+                reader.record_statement(query, start, result.as_ref().err());
+                result
+            },
+            _ => {
+                self.mark_write();
+                self.writer.iterate_converted(query, sql, &self.writer.error_level, &mut callback)
+            },
+        }
+    }
+
+    /// Execute a statement and returns the rows on the writer,
+    /// or on a reader if it is a `SELECT` statement.
+    /// See [Connection::rows](./struct.Connection.html#method.rows).
+    pub fn rows<T: AsRef<str>>(&self, query: T) -> Result<Vec<Row>> {
+        let query = query.as_ref();
+        let sql = self.writer.convert_to_valid_syntax(query);
+        match &sql {
+            Ok(s) if is_select(s) => self.reader().rows_converted(query, sql, &self.writer, &self.writer.error_level),
+            _ => {
+                self.mark_write();
+                self.writer.rows_converted(query, sql, &self.writer, &self.writer.error_level)
+            },
+        }
+    }
+
+    /// Return the next reader in turn, or the writer if there are no readers or it was written recently.
+    fn reader(&self) -> &Connection {
+        if self.readers.is_empty() || self.read_from_primary() {
+            return &self.writer;
+        }
+        let next = self.next.get() % self.readers.len();
+        self.next.set(next + 1);
+        &self.readers[next]
+    }
+
+    /// Checks if the writer was written within the duration of
+    /// [after_write_read_from_primary](#method.after_write_read_from_primary).
+    fn read_from_primary(&self) -> bool {
+        match (self.primary_window, self.last_write.get()) {
+            (Some(window), Some(last_write)) => last_write.elapsed() < window,
+            _ => false,
+        }
+    }
+}
+
+impl Deref for ReplicaSet {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.writer
+    }
+}
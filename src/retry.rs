@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::dialect::Dialect;
+
+/// How [with_retry](./struct.Connection.html#method.with_retry) retries a failed closure.
+///
+/// The backoff starts from `initial_backoff` and doubles on each retry up to `max_backoff`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// let policy = owsql::RetryPolicy {
+///     max_retries: 5,
+///     ..Default::default()
+/// };
+/// assert_eq!(policy.initial_backoff, Duration::from_millis(10));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of retries after the first attempt.
+    pub max_retries:     u32,
+    /// The wait before the first retry.
+    pub initial_backoff: Duration,
+    /// The maximum wait between retries.
+    pub max_backoff:     Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries:     3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff:     Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Return the wait before the retry, counted from 1.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Checks if the error code of the backend is a serialization failure or a deadlock.
+///
+/// Dialect    | Codes
+/// ---------- | -----
+/// SQLite     | `SQLITE_BUSY`, `SQLITE_LOCKED`
+/// Postgres   | `40001` (serialization_failure), `40P01` (deadlock_detected)
+/// MySQL      | `1213` (ER_LOCK_DEADLOCK), `1205` (ER_LOCK_WAIT_TIMEOUT)
+/// SQL Server | `40001` (SQLSTATE of a deadlock), `1205` (deadlock victim)
+/// Oracle     | `40001` (SQLSTATE of a serialization failure), `60` (ORA-00060), `8177` (ORA-08177)
+pub(crate) fn is_retryable(dialect: Dialect, code: &str) -> bool {
+    match dialect {
+        Dialect::Sqlite    => matches!(code.parse::<i32>().map(|code| code & 0xff), Ok(5) | Ok(6)),
+        Dialect::Postgres  => code == "40001" || code == "40P01",
+        Dialect::Mysql     => code == "1213" || code == "1205",
+        Dialect::SqlServer => code == "40001" || code == "1205",
+        Dialect::Oracle    => code == "40001" || code == "60" || code == "8177",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff(8), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retryable() {
+        assert!(is_retryable(Dialect::Sqlite, "5"));
+        assert!(is_retryable(Dialect::Sqlite, "517"));  // SQLITE_BUSY_SNAPSHOT
+        assert!(is_retryable(Dialect::Sqlite, "6"));
+        assert!(!is_retryable(Dialect::Sqlite, "1"));
+        assert!(is_retryable(Dialect::Postgres, "40001"));
+        assert!(is_retryable(Dialect::Postgres, "40P01"));
+        assert!(!is_retryable(Dialect::Postgres, "23505"));
+        assert!(is_retryable(Dialect::Mysql, "1213"));
+        assert!(!is_retryable(Dialect::Mysql, "1062"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::Result;
+use crate::connection::Connection;
+
+pub(crate) type Rewriter = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+impl Connection {
+    /// Adds the rewriter of the statements, which is applied to the converted SQL just before it is
+    /// executed, such as to add a query hint or a comment with a trace ID.
+    /// The rewriters are applied in the order they are added, and must be `Send` and `Sync`, since the
+    /// connection can be shared with another thread.
+    ///
+    /// The rewritten SQL is executed as it is, so the rewriter must not add untrusted values.
+    /// The statements generated by the library, such as `BEGIN`, are not rewritten, and
+    /// [actual_sql](#method.actual_sql) returns the SQL before rewriting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let trace_id = 42;
+    /// conn.add_rewriter(move |sql| format!("/* trace_id={} */ {}", trace_id, sql));
+    /// assert_eq!(conn.rows(conn.ow("SELECT 1 AS one;")).unwrap()[0].get("one"), Some("1"));
+    /// ```
+    pub fn add_rewriter<F: Fn(&str) -> String + Send + Sync + 'static>(&mut self, rewriter: F) {
+        self.rewriters.push(Arc::new(rewriter));
+    }
+
+    /// Removes the rewriters added by [add_rewriter](#method.add_rewriter).
+    pub fn clear_rewriters(&mut self) {
+        self.rewriters.clear();
+    }
+
+    /// Sets the tag appended to every executed statement as a comment `/* key=value */`, so that
+    /// the statements in `pg_stat_activity` or the process list are attributed to the endpoint of the
+    /// application. Setting the key again replaces its value.
+    ///
+    /// The characters other than the ASCII alphanumerics and `-_.:/@` are replaced with `_`, so that
+    /// the tag cannot close the comment. The tags are appended after the [rewriters](#method.add_rewriter),
+    /// before the trailing `;`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_query_tag("endpoint", "GET /users");
+    /// conn.set_query_tag("request_id", "42");
+    /// // SELECT 1; is executed as SELECT 1 /* endpoint=GET_/users,request_id=42 */;
+    /// assert!(conn.execute(conn.ow("SELECT 1;")).is_ok());
+    /// ```
+    pub fn set_query_tag(&mut self, key: &str, value: &str) {
+        let (key, value) = (sanitize(key), sanitize(value));
+        match self.query_tags.iter_mut().find(|(k, _)| *k == key) {
+            Some(tag) => tag.1 = value,
+            None => self.query_tags.push((key, value)),
+        }
+    }
+
+    /// Removes the tags set by [set_query_tag](#method.set_query_tag).
+    pub fn clear_query_tags(&mut self) {
+        self.query_tags.clear();
+    }
+
+    /// Applies the rewriters and the query tags to the converted SQL.
+    pub(crate) fn rewrite(&self, sql: Result<String>) -> Result<String> {
+        if self.rewriters.is_empty() && self.query_tags.is_empty() {
+            return sql;
+        }
+        let sql = sql.map(|sql| self.rewriters.iter().fold(sql, |sql, rewriter| rewriter(&sql)))?;
+        Ok(tag(sql, &self.query_tags))
+    }
+}
+
+/// Replaces the characters which may break the comment with `_`.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.:/@".contains(c) { c } else { '_' })
+        .collect()
+}
+
+/// Appends the tags as a comment before the trailing `;` of the SQL.
+fn tag(sql: String, tags: &[(String, String)]) -> String {
+    if tags.is_empty() {
+        return sql;
+    }
+    let comment = tags.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(",");
+    let body = sql.trim_end();
+    match body.strip_suffix(';') {
+        Some(body) => format!("{} /* {} */;", body.trim_end(), comment),
+        None => format!("{} /* {} */", body, comment),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize, tag};
+
+    #[test]
+    fn sanitized() {
+        assert_eq!(sanitize("GET /users?id=1"), "GET_/users_id_1");
+        assert_eq!(sanitize("*/ DROP TABLE users; /*"), "_/_DROP_TABLE_users__/_");
+        assert_eq!(sanitize("trace-id:a.b@c"), "trace-id:a.b@c");
+    }
+
+    #[test]
+    fn tagged() {
+        let tags = vec![("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())];
+        assert_eq!(tag("SELECT 1; ".to_string(), &tags), "SELECT 1 /* a=1,b=2 */;");
+        assert_eq!(tag("SELECT 1".to_string(), &tags[..1]), "SELECT 1 /* a=1 */");
+        assert_eq!(tag("SELECT 1;".to_string(), &[]), "SELECT 1;");
+    }
+}
@@ -1,46 +1,326 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+
+/// The error of [try_get](./struct.Row.html#method.try_get).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RowError {
+    /// The row has no column of the name.
+    MissingColumn(String),
+    /// The value of the column is `NULL`.
+    Null(String),
+    /// The value of the column cannot be converted into the type.
+    InvalidValue(String),
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowError::MissingColumn(column) => write!(f, "missing column: {}", column),
+            RowError::Null(column)          => write!(f, "null value: {}", column),
+            RowError::InvalidValue(column)  => write!(f, "invalid value: {}", column),
+        }
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// How the column names are matched by the accessors of [Row](./struct.Row.html).
+/// A column of exactly the same name is always preferred.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ColumnMatch {
+    /// Only the same name matches.
+    #[default]
+    Exact,
+    /// ASCII case is ignored, e.g. `userName` matches `username`, as Postgres lowercases unquoted identifiers.
+    CaseInsensitive,
+    /// ASCII case and underscores are ignored, e.g. `userName` matches `user_name`.
+    Loose,
+}
+
+impl ColumnMatch {
+    fn matches(self, name: &str, key: &str) -> bool {
+        match self {
+            ColumnMatch::Exact           => name == key,
+            ColumnMatch::CaseInsensitive => name.eq_ignore_ascii_case(key),
+            ColumnMatch::Loose           => {
+                let loose = |s: &str| s.chars().filter(|&c| c != '_').map(|c| c.to_ascii_lowercase()).collect::<String>();
+                loose(name) == loose(key)
+            },
+        }
+    }
+}
+
 /// A single result row of a query.
-#[derive(Debug, PartialEq)]
+///
+/// The columns are kept in the order of the result, including the columns of the same name
+/// such as `id` of two joined tables. A column can be accessed by the position with
+/// [get_at](#method.get_at), or by the qualified name such as `users.id` if the backend reports it,
+/// e.g. SQLite with `PRAGMA full_column_names = ON` and `PRAGMA short_column_names = OFF`,
+/// or an alias `AS "users.id"`. An unqualified name also matches a qualified column.
+/// When several columns match, the last one is used.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Row {
-    value: HashMap<String, Option<String>>,
+    columns:      Vec<(String, Option<String>)>,
+    column_match: ColumnMatch,
 }
 
 impl Row {
     #[inline]
     pub(crate) fn new() -> Self {
-        Self { value: HashMap::new() }
+        Self { columns: Vec::new(), column_match: ColumnMatch::default() }
+    }
+
+    /// Builds a row from the pairs of a column name and a value, to construct expected results
+    /// in tests without a database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::Row;
+    /// let row = Row::from_pairs(&[("name", Some("Alice")), ("email", None)]);
+    /// assert_eq!(row.get("name"), Some("Alice"));
+    /// assert_eq!(row.column_count(), 2);
+    ///
+    /// let rows: Vec<Row> = vec![
+    ///     Row::from([("name", Some("Alice"))]),
+    ///     Row::from([("name", Some("Bob"))]),
+    /// ];
+    /// assert_eq!(rows[1].get("name"), Some("Bob"));
+    /// ```
+    pub fn from_pairs<K: ToString, V: ToString>(pairs: &[(K, Option<V>)]) -> Self {
+        let mut row = Row::new();
+        for (key, value) in pairs {
+            row.insert(key.to_string(), value.as_ref().map(ToString::to_string));
+        }
+        row
     }
 
     #[inline]
     pub(crate) fn insert(&mut self, key: String, value: Option<String>) {
-        self.value.insert(key, value);
+        self.columns.push((key, value));
+    }
+
+    /// Sets how the column names are matched, overriding
+    /// [set_column_match](./struct.Connection.html#method.set_column_match) of the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{ColumnMatch, Row};
+    /// let mut row = Row::from_pairs(&[("user_name", Some("Alice"))]);
+    /// assert_eq!(row.get("userName"), None);
+    /// row.set_column_match(ColumnMatch::Loose);
+    /// assert_eq!(row.get("userName"), Some("Alice"));
+    /// ```
+    #[inline]
+    pub fn set_column_match(&mut self, column_match: ColumnMatch) {
+        self.column_match = column_match;
+    }
+
+    /// Return the value of the column of the name, or its qualified name.
+    fn value(&self, key: &str) -> Option<&Option<String>> {
+        let find = |matches: &dyn Fn(&str) -> bool| self.columns.iter().rev().find(|(name, _)| matches(name));
+        find(&|name| name == key)
+            .or_else(|| find(&|name| unqualified(name) == key))
+            .or_else(|| find(&|name| self.column_match.matches(name, key)))
+            .or_else(|| find(&|name| self.column_match.matches(unqualified(name), key)))
+            .map(|(_, value)| value)
     }
 
     /// Get the value of a column of the result row.
     #[inline]
     pub fn get(&self, key: &str) -> Option<&str> {
-        self.value.get(key)?.as_deref()
+        self.value(key)?.as_deref()
+    }
+
+    /// Get the value of a column, distinguishing a missing column from `NULL`
+    /// unlike [get](#method.get).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::{Row, RowError};
+    /// let row = Row::from_pairs(&[("name", Some("Alice")), ("email", None)]);
+    /// assert_eq!(row.try_get("name"), Ok("Alice"));
+    /// assert_eq!(row.try_get("email"), Err(RowError::Null("email".into())));
+    /// assert_eq!(row.try_get("emial"), Err(RowError::MissingColumn("emial".into())));
+    /// ```
+    pub fn try_get(&self, key: &str) -> Result<&str, RowError> {
+        self.value(key)
+            .ok_or_else(|| RowError::MissingColumn(key.to_string()))?
+            .as_deref()
+            .ok_or_else(|| RowError::Null(key.to_string()))
+    }
+
+    /// Get the value of the column at the position, starting from 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let row = conn.single_row(conn.ow("SELECT 1 AS id, 2 AS id;")).unwrap();
+    /// assert_eq!(row.get_at(0), Some("1"));
+    /// assert_eq!(row.get_at(1), Some("2"));
+    /// assert_eq!(row.get("id"), Some("2"));
+    /// ```
+    #[inline]
+    pub fn get_at(&self, index: usize) -> Option<&str> {
+        self.columns.get(index)?.1.as_deref()
+    }
+
+    /// Get the name of the column at the position, starting from 0.
+    #[inline]
+    pub fn column_name(&self, index: usize) -> Option<&str> {
+        self.columns.get(index).map(|(name, _)| name.as_str())
     }
 
     /// Transforms and gets the columns of the result row.
     #[inline]
     pub fn get_into<T: FromStr>(&self, key: &str) -> Result<T,  <T as std::str::FromStr>::Err> {
-        T::from_str(self.value.get(key).unwrap_or(&None).as_deref().unwrap_or(""))
+        T::from_str(self.value(key).unwrap_or(&None).as_deref().unwrap_or(""))
+    }
+
+    /// Get the boolean of a column, in any of the representations returned by the backends:
+    /// `t`/`f`, `1`/`0` and `true`/`false`.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        parse_bool(self.get(key)?)
+    }
+
+    /// Get the timestamp of a column, in any of the formats returned by the backends.  
+    /// A timestamp without a time zone is treated as UTC.
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn get_datetime(&self, key: &str) -> Option<DateTime<Utc>> {
+        let value = self.get(key)?;
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+            return Some(datetime.with_timezone(&Utc));
+        }
+        if let Ok(datetime) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z") {
+            return Some(datetime.with_timezone(&Utc));
+        }
+        ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .map(|datetime| datetime.and_utc())
+    }
+
+    /// Get the date of a column. The time part of a timestamp is ignored.
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn get_date(&self, key: &str) -> Option<NaiveDate> {
+        let value = self.get(key)?;
+        NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+    }
+
+    /// Get the UUID of a column, in the hyphenated or the 32 hexadecimal digits format.
+    #[cfg(feature = "uuid")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    pub fn get_uuid(&self, key: &str) -> Option<Uuid> {
+        Uuid::parse_str(self.get(key)?).ok()
+    }
+
+    /// Deserializes the JSON value of a column.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_str(self.get(key)?).ok()
+    }
+
+    /// Get the elements of a one-dimensional Postgres array column, such as `{a,"b c"}`.  
+    /// Returns `None` if the column is not an array, or an element is `NULL` or cannot be parsed.
+    pub fn get_array<T: FromStr>(&self, key: &str) -> Option<Vec<T>> {
+        parse_array(self.get(key)?)?.iter().map(|e| e.parse().ok()).collect()
     }
 
     /// Return the number of columns.
     #[inline]
     pub fn column_count(&self) -> usize {
-        self.value.len()
+        self.columns.len()
     }
 
-    /// Get all the column names.  
-    /// Column order is not guaranteed.
+    /// Get all the column names in the order of the result.
     #[inline]
     pub fn column_names(&self) -> Vec<&str> {
-        self.value.keys().map(|k| (*k).as_str()).collect::<Vec<_>>()
+        self.columns.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>()
+    }
+
+    /// Return the first column name which appears more than once.
+    pub(crate) fn duplicate_column(&self) -> Option<&str> {
+        self.columns.iter().enumerate()
+            .find(|(i, (name, _))| self.columns[..*i].iter().any(|(other, _)| other == name))
+            .map(|(_, (name, _))| name.as_str())
+    }
+}
+
+impl<K: ToString, V: ToString, const N: usize> From<[(K, Option<V>); N]> for Row {
+    fn from(pairs: [(K, Option<V>); N]) -> Self {
+        Row::from_pairs(&pairs)
+    }
+}
+
+impl<K: ToString, V: ToString> From<Vec<(K, Option<V>)>> for Row {
+    fn from(pairs: Vec<(K, Option<V>)>) -> Self {
+        Row::from_pairs(&pairs)
+    }
+}
+
+/// Parses a boolean in any of the representations returned by the backends.
+pub(crate) fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        v if v == "1" || v.eq_ignore_ascii_case("t") || v.eq_ignore_ascii_case("true")  => Some(true),
+        v if v == "0" || v.eq_ignore_ascii_case("f") || v.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+/// Return the column name without the table name, such as `id` of `users.id`.
+fn unqualified(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Parses the text representation of a one-dimensional Postgres array.
+fn parse_array(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('{')?.strip_suffix('}')?;
+    let mut elements = Vec::new();
+    if inner.is_empty() {
+        return Some(elements);
+    }
+    let mut chars = inner.chars().peekable();
+    loop {
+        let mut element = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"'  => break,
+                    '\\' => element.push(chars.next()?),
+                    c    => element.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                match c {
+                    ','       => break,
+                    '{' | '"' => return None,
+                    _         => element.push(c),
+                }
+                chars.next();
+            }
+            if element.eq_ignore_ascii_case("NULL") {
+                return None;
+            }
+        }
+        elements.push(element);
+        match chars.next() {
+            None      => return Some(elements),
+            Some(',') => continue,
+            Some(_)   => return None,
+        }
     }
 }
 
@@ -72,5 +352,150 @@ mod tests {
         assert!(row.column_names().contains(&"key3"));
         assert!(!row.column_names().contains(&"key4"));
     }
+
+    #[test]
+    fn from_pairs() {
+        let mut row = Row::new();
+        row.insert("name".to_string(), Some("Alice".to_string()));
+        row.insert("age".to_string(),  Some("42".to_string()));
+        row.insert("email".to_string(), None);
+        assert_eq!(Row::from_pairs(&[("name", Some("Alice")), ("age", Some("42")), ("email", None)]), row);
+        assert_eq!(Row::from([("name", Some("Alice".to_string())), ("age", Some(42.to_string())), ("email", None)]), row);
+        assert_eq!(Row::from(vec![("age", Some(0)), ("name", None), ("age", Some(42))]).get("age"), Some("42"));
+        assert_eq!(Row::from_pairs::<&str, &str>(&[]).column_count(), 0);
+    }
+
+    #[test]
+    fn column_match() {
+        let mut row = Row::from_pairs(&[("username", Some("Alice")), ("users.created_at", Some("2020")), ("userName", Some("Bob"))]);
+        assert_eq!(row.get("userName"),  Some("Bob"));
+        assert_eq!(row.get("USERNAME"),  None);
+        assert_eq!(row.get("createdAt"), None);
+        row.set_column_match(ColumnMatch::CaseInsensitive);
+        assert_eq!(row.get("username"),  Some("Alice"));
+        assert_eq!(row.get("USERNAME"),  Some("Bob"));
+        assert_eq!(row.get("createdAt"), None);
+        row.set_column_match(ColumnMatch::Loose);
+        assert_eq!(row.get("createdAt"), Some("2020"));
+        assert_eq!(row.get("user_name"), Some("Bob"));
+        assert_eq!(row.try_get("created"), Err(RowError::MissingColumn("created".into())));
+    }
+
+    #[test]
+    fn try_get() {
+        let row = Row::from_pairs(&[("users.id", Some("1")), ("email", None)]);
+        assert_eq!(row.try_get("users.id"), Ok("1"));
+        assert_eq!(row.try_get("id"),       Ok("1"));
+        assert_eq!(row.try_get("email"),    Err(RowError::Null("email".into())));
+        assert_eq!(row.try_get("name"),     Err(RowError::MissingColumn("name".into())));
+        assert_eq!(RowError::Null("email".into()).to_string(), "null value: email");
+        assert_eq!(RowError::MissingColumn("name".into()).to_string(), "missing column: name");
+    }
+
+    #[test]
+    fn duplicate_columns() {
+        let row = Row::from_pairs(&[("users.id", Some("1")), ("name", Some("Alice")), ("orders.id", Some("7")), ("id", Some("9"))]);
+        assert_eq!(row.get("users.id"),  Some("1"));
+        assert_eq!(row.get("orders.id"), Some("7"));
+        assert_eq!(row.get("id"),        Some("9"));
+        assert_eq!(row.get("items.id"),  None);
+        assert_eq!(row.get_at(2),        Some("7"));
+        assert_eq!(row.get_at(4),        None);
+        assert_eq!(row.column_name(1),   Some("name"));
+        assert_eq!(row.column_names(),   ["users.id", "name", "orders.id", "id"]);
+        assert_eq!(row.duplicate_column(), None);
+        let row = Row::from_pairs(&[("users.id", Some("1")), ("orders.id", Some("7"))]);
+        assert_eq!(row.get("id"), Some("7"));
+        let row = Row::from_pairs(&[("id", Some("1")), ("name", None), ("id", Some("7"))]);
+        assert_eq!(row.get("id"), Some("7"));
+        assert_eq!(row.get_into::<i32>("id"), Ok(7));
+        assert_eq!(row.duplicate_column(), Some("id"));
+    }
+
+    #[test]
+    fn get_bool() {
+        let mut row = Row::new();
+        for (key, value) in [("t", "t"), ("f", "f"), ("1", "1"), ("0", "0"), ("true", "TRUE"), ("false", "false"), ("2", "2")] {
+            row.insert(key.to_string(), Some(value.to_string()));
+        }
+        row.insert("null".to_string(), None);
+        assert_eq!(row.get_bool("t"),     Some(true));
+        assert_eq!(row.get_bool("f"),     Some(false));
+        assert_eq!(row.get_bool("1"),     Some(true));
+        assert_eq!(row.get_bool("0"),     Some(false));
+        assert_eq!(row.get_bool("true"),  Some(true));
+        assert_eq!(row.get_bool("false"), Some(false));
+        assert_eq!(row.get_bool("2"),     None);
+        assert_eq!(row.get_bool("null"),  None);
+    }
+
+    #[test]
+    fn get_array() {
+        let mut row = Row::new();
+        row.insert("text".to_string(),    Some(r#"{a,"b c","O'Reilly","\"q\\"}"#.to_string()));
+        row.insert("int".to_string(),     Some("{1,-2,3}".to_string()));
+        row.insert("empty".to_string(),   Some("{}".to_string()));
+        row.insert("null".to_string(),    Some("{1,NULL}".to_string()));
+        row.insert("nested".to_string(),  Some("{{1,2},{3,4}}".to_string()));
+        row.insert("invalid".to_string(), Some(r#"{"a}"#.to_string()));
+        assert_eq!(row.get_array::<String>("text"), Some(vec!["a".into(), "b c".into(), "O'Reilly".into(), "\"q\\".into()]));
+        assert_eq!(row.get_array::<i64>("int"), Some(vec![1, -2, 3]));
+        assert_eq!(row.get_array::<i64>("text"), None);
+        assert_eq!(row.get_array::<i64>("empty"), Some(vec![]));
+        assert_eq!(row.get_array::<i64>("null"), None);
+        assert_eq!(row.get_array::<i64>("nested"), None);
+        assert_eq!(row.get_array::<String>("invalid"), None);
+        assert_eq!(row.get_array::<String>("nothing"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn get_uuid() {
+        let mut row = Row::new();
+        row.insert("hyphenated".to_string(), Some("67e55044-10b1-426f-9247-bb680e5fe0c8".to_string()));
+        row.insert("hex".to_string(),        Some("67E5504410B1426F9247BB680E5FE0C8".to_string()));
+        row.insert("invalid".to_string(),    Some("67e55044".to_string()));
+        let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").ok();
+        assert_eq!(row.get_uuid("hyphenated"), id);
+        assert_eq!(row.get_uuid("hex"),        id);
+        assert_eq!(row.get_uuid("invalid"),    None);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn get_json() {
+        let mut row = Row::new();
+        row.insert("array".to_string(),   Some("[1, 2, 3]".to_string()));
+        row.insert("invalid".to_string(), Some("[1, 2,".to_string()));
+        assert_eq!(row.get_json::<Vec<i32>>("array"), Some(vec![1, 2, 3]));
+        assert_eq!(row.get_json::<Vec<String>>("array"), None);
+        assert_eq!(row.get_json::<Vec<i32>>("invalid"), None);
+        assert_eq!(row.get_json::<Vec<i32>>("nothing"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn get_datetime() {
+        use chrono::TimeZone;
+        let mut row = Row::new();
+        let at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+        for (key, value) in [
+            ("iso8601",     "2020-01-02T03:04:05Z"),
+            ("offset",      "2020-01-02T12:04:05+09:00"),
+            ("postgres",    "2020-01-02 03:04:05+00"),
+            ("mysql",       "2020-01-02 03:04:05"),
+            ("invalid",     "2020-01-02 03:04"),
+        ] {
+            row.insert(key.to_string(), Some(value.to_string()));
+        }
+        assert_eq!(row.get_datetime("iso8601"),  Some(at));
+        assert_eq!(row.get_datetime("offset"),   Some(at));
+        assert_eq!(row.get_datetime("postgres"), Some(at));
+        assert_eq!(row.get_datetime("mysql"),    Some(at));
+        assert_eq!(row.get_datetime("invalid"),  None);
+        assert_eq!(row.get_datetime("nothing"),  None);
+        assert_eq!(row.get_date("mysql"), NaiveDate::from_ymd_opt(2020, 1, 2));
+        assert_eq!(row.get_date("nothing"), None);
+    }
 }
 
@@ -0,0 +1,59 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::lexer::is_identifier;
+
+impl Connection {
+    /// Sets the schema qualifying the unqualified tables of [table](#method.table) and the insert
+    /// and update helpers, such as a schema per tenant. The default is none.
+    ///
+    /// # Errors
+    ///
+    /// A schema which is not a plain identifier returns the error "invalid schema name".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_schema_prefix("tenant_42").unwrap();
+    /// let sql = conn.ow("SELECT * FROM") + &conn.table("users") + &conn.ow(";");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM \"tenant_42\".\"users\" ; ");
+    /// assert!(conn.set_schema_prefix("tenant_42; --").is_err());
+    /// ```
+    pub fn set_schema_prefix(&mut self, schema: &str) -> Result<()> {
+        if !is_identifier(schema) {
+            return OwsqlError::new(&self.error_level, "invalid schema name", schema);
+        }
+        self.schema_prefix = Some(schema.to_string());
+        Ok(())
+    }
+
+    /// Clears the schema of [set_schema_prefix](#method.set_schema_prefix).
+    pub fn clear_schema_prefix(&mut self) {
+        self.schema_prefix = None;
+    }
+
+    /// Return the schema of [set_schema_prefix](#method.set_schema_prefix).
+    pub fn schema_prefix(&self) -> Option<&str> {
+        self.schema_prefix.as_deref()
+    }
+
+    /// Quotes the table name qualified by the schema prefix unless it is already qualified.
+    pub(crate) fn quote_table(&self, table: &str) -> Option<String> {
+        match &self.schema_prefix {
+            Some(schema) if !table.contains('.') => self.quote_column(&format!("{}.{}", schema, table)),
+            _ => self.quote_column(table),
+        }
+    }
+
+    /// It is guaranteed to be the quoted table name, which is a plain identifier optionally qualified
+    /// by a schema, such as `users` or `archive.users`. An unqualified table is qualified by
+    /// [set_schema_prefix](#method.set_schema_prefix).
+    /// An invalid table returns the error "invalid identifier" when the statement is executed.
+    pub fn table(&self, name: &str) -> String {
+        match self.quote_table(name) {
+            Some(table) => self.ow_generated(table),
+            None => self.ow_error("invalid identifier", name),
+        }
+    }
+}
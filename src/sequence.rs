@@ -0,0 +1,67 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+
+/// The table of the counters emulating the sequences on SQLite and MySQL.
+const SEQUENCE_TABLE: &str = "owsql_sequences";
+
+impl Connection {
+    /// Allocates the next ID of the sequence, which starts from 1, as a portable replacement of an
+    /// auto-increment column, such as to know the ID before inserting the row.
+    ///
+    /// Dialect  | Sequence
+    /// -------- | --------
+    /// SQLite   | A counter of the table `owsql_sequences`, incremented by `INSERT ... ON CONFLICT ... RETURNING`.
+    /// Postgres | `nextval` of the sequence, which is created if it does not exist, such as `users_id_seq` of a serial column.
+    /// MySQL    | A counter of the table `owsql_sequences`, incremented by `LAST_INSERT_ID(value + 1)`.
+    ///
+    /// SQL Server and Oracle return the error "unsupported sequence".
+    ///
+    /// The table and the sequence are created if they do not exist, in the
+    /// [schema prefix](#method.set_schema_prefix) if it is set. The IDs are not rolled back with the
+    /// transaction on Postgres, and the counter is locked until the transaction ends on SQLite and MySQL.
+    ///
+    /// # Errors
+    ///
+    /// An invalid name returns the error "invalid identifier".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// assert_eq!(conn.next_id("orders"), Ok(1));
+    /// assert_eq!(conn.next_id("orders"), Ok(2));
+    /// assert_eq!(conn.next_id("invoices"), Ok(1));
+    /// ```
+    pub fn next_id(&self, sequence: &str) -> Result<i64> {
+        let error = |msg: &str, detail: &str| OwsqlError::new(&self.error_level, msg, detail).err().unwrap_or(OwsqlError::AnyError);
+        let quoted = match self.quote_table(sequence) {
+            Some(quoted) => quoted,
+            None => return Err(error("invalid identifier", sequence)),
+        };
+        let table = self.table(SEQUENCE_TABLE);
+        let row = match self.dialect() {
+            Dialect::Sqlite => {
+                self.execute(self.ow_generated(format!("CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY, value INTEGER NOT NULL)", table)) + &self.ow(";"))?;
+                self.single_row(self.ow_generated(format!("INSERT INTO {} (name, value) VALUES (", table)) + sequence
+                    + &self.ow(", 1) ON CONFLICT (name) DO UPDATE SET value = value + 1 RETURNING value AS id;"))?
+            },
+            Dialect::Postgres => {
+                self.execute(self.ow_generated(format!("CREATE SEQUENCE IF NOT EXISTS {}", quoted)) + &self.ow(";"))?;
+                self.single_row(self.ow("SELECT nextval(") + &quoted + &self.ow(") AS id;"))?
+            },
+            Dialect::Mysql => {
+                self.execute(self.ow_generated(format!("CREATE TABLE IF NOT EXISTS {} (name VARCHAR(255) PRIMARY KEY, value BIGINT NOT NULL)", table)) + &self.ow(";"))?;
+                self.execute(self.ow_generated(format!("INSERT INTO {} (name, value) VALUES (", table)) + sequence
+                    + &self.ow(", LAST_INSERT_ID(1)) ON DUPLICATE KEY UPDATE value = LAST_INSERT_ID(value + 1);"))?;
+                self.single_row(self.ow("SELECT LAST_INSERT_ID() AS id;"))?
+            },
+            Dialect::SqlServer | Dialect::Oracle => return Err(error("unsupported sequence", sequence)),
+        };
+        match row.get("id").and_then(|id| id.parse().ok()) {
+            Some(id) => Ok(id),
+            None => Err(error("invalid id", sequence)),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+use crate::lexer::is_qualified_identifier;
+
+/// Session variables which change how the server reads string literals.
+const ESCAPING_VARIABLES: &[&str] = &["sql_mode", "standard_conforming_strings", "client_encoding", "character_set_client"];
+
+impl Connection {
+    /// Sets the session variable by the statement of the backend, and tracks it for
+    /// [replay_session](#method.replay_session).
+    ///
+    /// Dialect  | Statement
+    /// -------- | ---------
+    /// SQLite   | `PRAGMA key = value`
+    /// Postgres | `SET key = value`
+    /// MySQL    | `SET SESSION key = value`
+    /// Oracle   | `ALTER SESSION SET key = value`
+    ///
+    /// SQL Server, whose options are not set by `=`, returns the error "unsupported session variable".
+    ///
+    /// The key must consist of alphanumerics and underscores, optionally qualified by dots.
+    /// The value is written as it is if it is an integer, and escaped as a string otherwise.
+    ///
+    /// The variables which change how the server reads string literals, such as `sql_mode` and
+    /// `standard_conforming_strings`, make the connection read its [escape_mode](#method.escape_mode)
+    /// and [charset](#method.charset) again. ODBC, which cannot read them, returns the error
+    /// "unsupported session variable" for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.set_session("foreign_keys", "ON").unwrap();
+    /// assert!(conn.pragma().foreign_keys().unwrap());
+    /// assert_eq!(conn.session_settings(), [("foreign_keys".to_string(), "ON".to_string())]);
+    /// ```
+    pub fn set_session(&self, key: &str, value: &str) -> Result<()> {
+        if !is_qualified_identifier(key) {
+            return Err(OwsqlError::Message(format!("invalid session variable: {:?}", key)));
+        }
+        let name = key.rsplit('.').next().unwrap_or(key);
+        let escaping = ESCAPING_VARIABLES.iter().any(|variable| variable.eq_ignore_ascii_case(name));
+        if escaping && !self.conn.detects_escaping() {
+            return Err(OwsqlError::Message(format!("unsupported session variable: {:?}", key)));
+        }
+        let set = match self.dialect() {
+            Dialect::Sqlite    => self.ow("PRAGMA "),
+            Dialect::Postgres  => self.ow("SET "),
+            Dialect::Mysql     => self.ow("SET SESSION "),
+            Dialect::Oracle    => self.ow("ALTER SESSION SET "),
+            Dialect::SqlServer => return Err(OwsqlError::Message(format!("unsupported session variable: {:?}", key))),
+        };
+        let value_sql = if value.parse::<i64>().is_ok() {
+            self.int(value)
+        } else {
+            value.to_string()
+        };
+        self.execute(set + &self.ow_generated(key.to_string()) + &self.ow(" = ") + &value_sql + &self.ow(";"))?;
+        if escaping {
+            self.escape_mode.set(self.conn.escape_mode());
+            self.charset.set(self.conn.charset());
+        }
+
+        let mut session = self.session.borrow_mut();
+        match session.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some(setting) => setting.1 = value.to_string(),
+            None => session.push((key.to_string(), value.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Return the session variables set by [set_session](#method.set_session) in the order they were first set.
+    pub fn session_settings(&self) -> Vec<(String, String)> {
+        self.session.borrow().clone()
+    }
+
+    /// Sets the session variables tracked by the other connection, for example,
+    /// after reconnecting to the same database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let old = owsql::sqlite::open(":memory:").unwrap();
+    /// old.set_session("foreign_keys", "ON").unwrap();
+    /// let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.replay_session(&old).unwrap();
+    /// assert!(conn.pragma().foreign_keys().unwrap());
+    /// ```
+    pub fn replay_session(&self, from: &Connection) -> Result<()> {
+        for (key, value) in from.session_settings() {
+            self.set_session(&key, &value)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use crate::backend::{Backend, BackendError, RowCallback};
+    use crate::dialect::Dialect;
+    use crate::escape::EscapeMode;
+
+    /// PostgreSQL-like backend which reads `standard_conforming_strings` from the statements.
+    struct Server {
+        standard: Cell<bool>,
+    }
+
+    impl Backend for Server {
+        fn execute(&self, sql: &str) -> Result<u64, BackendError> {
+            if sql.to_lowercase().contains("standard_conforming_strings") {
+                self.standard.set(sql.contains("'on'"));
+            }
+            Ok(0)
+        }
+
+        fn iterate(&self, _sql: &str, _callback: RowCallback) -> Result<(), BackendError> {
+            Ok(())
+        }
+
+        fn dialect(&self) -> Dialect {
+            Dialect::Postgres
+        }
+
+        fn escape_mode(&self) -> EscapeMode {
+            if self.standard.get() { EscapeMode::Standard } else { EscapeMode::Backslash }
+        }
+    }
+
+    #[test]
+    fn set_session_escaping() {
+        let mut conn = crate::backend::connect(Server { standard: Cell::new(true) });
+        assert_eq!(conn.actual_sql(r"\' OR 1=1 --").unwrap(), r"'\'' OR 1=1 --' ");
+        conn.set_session("standard_conforming_strings", "off").unwrap();
+        assert_eq!(conn.escape_mode(), EscapeMode::Backslash);
+        assert_eq!(conn.actual_sql(r"\' OR 1=1 --").unwrap(), r"'\\'' OR 1=1 --' ");
+        conn.set_session("STANDARD_CONFORMING_STRINGS", "on").unwrap();
+        assert_eq!(conn.escape_mode(), EscapeMode::Standard);
+
+        // The other variables keep the escape mode set by the user.
+        conn.set_escape_mode(EscapeMode::Backslash);
+        conn.set_session("application_name", "owsql").unwrap();
+        assert_eq!(conn.escape_mode(), EscapeMode::Backslash);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::connection::Connection;
+
+/// An enum whose variants are fixed SQL fragments, which is defined by [owsql_enum!](./macro.owsql_enum.html).
+pub trait SqlEnum: Copy + 'static {
+    /// All the variants in the order of the definition.
+    const VARIANTS: &'static [Self];
+
+    /// Return the SQL fragment of the variant.
+    fn as_sql(&self) -> &'static str;
+
+    /// Return the variant whose SQL fragment matches ignoring ASCII case, such as a value from
+    /// query parameters.
+    fn from_sql(s: &str) -> Option<Self> {
+        Self::VARIANTS.iter().copied().find(|v| v.as_sql().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
+/// Defines an enum whose variants render to fixed SQL fragments through
+/// [ow_enum](./struct.Connection.html#method.ow_enum), instead of checking strings against an allowlist.
+///
+/// The enum derives `Clone`, `Copy`, `Debug`, `PartialEq`, `Eq` and `Hash`, and implements
+/// [SqlEnum](./trait.SqlEnum.html) and `Display`.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{owsql_enum, SqlEnum};
+///
+/// owsql_enum! {
+///     pub enum Direction {
+///         Asc => "ASC",
+///         Desc => "DESC",
+///     }
+/// }
+///
+/// # let conn = owsql::sqlite::open(":memory:").unwrap();
+/// # conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice'), ('Bob');")).unwrap();
+/// let direction = Direction::from_sql("desc").unwrap_or(Direction::Asc);
+/// let sql = conn.ow("SELECT name FROM users ORDER BY name") + &conn.ow_enum(direction) + &conn.ow(";");
+/// assert_eq!(conn.rows(sql).unwrap()[0].get("name"), Some("Bob"));
+/// assert_eq!(Direction::from_sql("DESC; DROP TABLE users"), None);
+/// ```
+#[macro_export]
+macro_rules! owsql_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $( $(#[$variant_attr:meta])* $variant:ident => $sql:literal ),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $( $(#[$variant_attr])* $variant ),+
+        }
+
+        impl $crate::SqlEnum for $name {
+            const VARIANTS: &'static [Self] = &[ $( $name::$variant ),+ ];
+
+            fn as_sql(&self) -> &'static str {
+                match self {
+                    $( $name::$variant => $sql ),+
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str($crate::SqlEnum::as_sql(self))
+            }
+        }
+    };
+}
+
+impl Connection {
+    /// It is guaranteed to be the fixed SQL fragment of the variant, which is trusted in the same
+    /// way as [ow](#method.ow). See [owsql_enum!](./macro.owsql_enum.html).
+    #[track_caller]
+    pub fn ow_enum<E: SqlEnum>(&self, value: E) -> String {
+        self.ow(value.as_sql())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::SqlEnum;
+
+    owsql_enum! {
+        /// The status of a user.
+        enum Status {
+            Active => "'active'",
+            /// Suspended by an administrator.
+            Suspended => "'suspended'",
+        }
+    }
+
+    #[test]
+    fn sql_enum() {
+        assert_eq!(Status::VARIANTS, &[Status::Active, Status::Suspended]);
+        assert_eq!(Status::Suspended.as_sql(), "'suspended'");
+        assert_eq!(Status::Active.to_string(), "'active'");
+        assert_eq!(Status::from_sql(" 'ACTIVE' "), Some(Status::Active));
+        assert_eq!(Status::from_sql("active"), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::lexer::is_identifier;
+use super::connection::handle;
+
+/// Checks if the name can be used as a schema name of an attached database.
+/// `main` and `temp` are reserved by SQLite.
+fn is_valid_schema(name: &str) -> bool {
+    is_identifier(name)
+        && !name.eq_ignore_ascii_case("main")
+        && !name.eq_ignore_ascii_case("temp")
+}
+
+impl Connection {
+    /// Attaches the database file as the schema, so that trusted fragments can reference its tables
+    /// such as `archive.users`. The path is escaped as a string literal, and the schema name must be
+    /// an identifier of ASCII alphanumerics and underscores other than `main` and `temp`.  
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let dir = temporary::Directory::new("attach").unwrap();
+    /// # let path = dir.path().join("archive.db");
+    /// let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.attach(&path, "archive").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE archive.users (name TEXT); INSERT INTO archive.users VALUES ('Alice');")).unwrap();
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM archive.users;")), Ok(1));
+    /// conn.detach("archive").unwrap();
+    /// assert!(conn.attach(&path, "x; DROP TABLE users").is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn attach<P: AsRef<Path>>(&self, path: P, schema: &str) -> Result<()> {
+        handle(self, "attach")?;
+        let path = match path.as_ref().to_str() {
+            Some(path) => path,
+            None => return Err(OwsqlError::Message(format!("invalid path: {:?}", path.as_ref()))),
+        };
+        if !is_valid_schema(schema) {
+            return Err(OwsqlError::Message(format!("invalid schema name: {:?}", schema)));
+        }
+        self.execute(self.ow("ATTACH DATABASE") + &self.operand(path) + &self.ow_generated(format!("AS {};", schema)))
+    }
+
+    /// Detaches the database attached by [attach](#method.attach).  
+    /// Only for SQLite.
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn detach(&self, schema: &str) -> Result<()> {
+        handle(self, "attach")?;
+        if !is_valid_schema(schema) {
+            return Err(OwsqlError::Message(format!("invalid schema name: {:?}", schema)));
+        }
+        self.execute(self.ow_generated(format!("DETACH DATABASE {};", schema)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn is_valid_schema() {
+        assert!(super::is_valid_schema("archive"));
+        assert!(super::is_valid_schema("_2020"));
+        assert!(!super::is_valid_schema(""));
+        assert!(!super::is_valid_schema("2020"));
+        assert!(!super::is_valid_schema("main"));
+        assert!(!super::is_valid_schema("TEMP"));
+        assert!(!super::is_valid_schema("archive.users"));
+        assert!(!super::is_valid_schema("x; DROP TABLE users"));
+    }
+}
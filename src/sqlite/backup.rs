@@ -0,0 +1,166 @@
+extern crate sqlite3_sys as ffi;
+
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use super::connection::{errmsg, handle};
+
+/// The number of pages copied by a step.
+const PAGES_PER_STEP: i32 = 100;
+
+/// The progress of a backup, passed to the callback after each step.
+/// See [backup_to](../struct.Connection.html#method.backup_to).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Progress {
+    /// The number of pages still to be copied.
+    pub remaining:  u32,
+    /// The number of pages of the source database.
+    pub page_count: u32,
+}
+
+impl Connection {
+    /// Copies the database to the file using the [online backup API](https://www.sqlite.org/backup.html).
+    /// The file is created if it does not exist, and overwritten otherwise.
+    /// `progress` is called after each step of 100 pages.  
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let dir = temporary::Directory::new("backup").unwrap();
+    /// # let path = dir.path().join("backup.db");
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES('Alice');")).unwrap();
+    /// conn.backup_to(&path, |progress| {
+    ///     println!("{}/{} pages remaining", progress.remaining, progress.page_count);
+    /// }).unwrap();
+    ///
+    /// let restored = owsql::sqlite::open(":memory:").unwrap();
+    /// restored.restore_from(&path, |_| ()).unwrap();
+    /// assert_eq!(restored.count(restored.ow("SELECT * FROM users;")), Ok(1));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn backup_to<P, F>(&self, path: P, mut progress: F) -> Result<()>
+        where
+            P: AsRef<Path>,
+            F: FnMut(Progress),
+    {
+        let src = handle(self, "backup")?;
+        let dst = RawConnection::open(path.as_ref(), ffi::SQLITE_OPEN_CREATE | ffi::SQLITE_OPEN_READWRITE)?;
+        backup(src, dst.0, &mut progress)
+    }
+
+    /// Copies the database to another SQLite connection, replacing its contents.
+    /// See [backup_to](#method.backup_to).
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn backup_to_connection<F: FnMut(Progress)>(&self, dst: &Connection, mut progress: F) -> Result<()> {
+        let src = handle(self, "backup")?;
+        let dst_handle = handle(dst, "backup")?;
+        dst.clear_query_cache();
+        backup(src, dst_handle, &mut progress)
+    }
+
+    /// Replaces the database with the contents of the file.
+    /// See [backup_to](#method.backup_to).
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn restore_from<P, F>(&self, path: P, mut progress: F) -> Result<()>
+        where
+            P: AsRef<Path>,
+            F: FnMut(Progress),
+    {
+        let dst = handle(self, "backup")?;
+        let src = RawConnection::open(path.as_ref(), ffi::SQLITE_OPEN_READONLY)?;
+        self.clear_query_cache();
+        backup(src.0, dst, &mut progress)
+    }
+}
+
+/// A connection to the other side of a backup, which is closed on drop.
+struct RawConnection(*mut ffi::sqlite3);
+
+impl RawConnection {
+    fn open(path: &Path, openflags: i32) -> Result<Self> {
+        let path = match path.to_str().map(CString::new) {
+            Some(Ok(path)) => path,
+            _ => return Err(OwsqlError::Message(format!("invalid path: {:?}", path))),
+        };
+        let mut conn = ptr::null_mut();
+        let result = unsafe { ffi::sqlite3_open_v2(path.as_ptr(), &mut conn, openflags, ptr::null()) };
+        // The handle is allocated even if it fails to open, and must be closed.
+        let conn = RawConnection(conn);
+        if result != ffi::SQLITE_OK {
+            return Err(OwsqlError::Message(format!("failed to backup: {}", errmsg(conn.0))));
+        }
+        Ok(conn)
+    }
+}
+
+impl Drop for RawConnection {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_close(self.0) };
+    }
+}
+
+fn backup(src: *mut ffi::sqlite3, dst: *mut ffi::sqlite3, progress: &mut dyn FnMut(Progress)) -> Result<()> {
+    if src == dst {
+        return Err(OwsqlError::Message("failed to backup: same database".into()));
+    }
+    let main = CString::new("main").unwrap();
+    let backup = unsafe { ffi::sqlite3_backup_init(dst, main.as_ptr(), src, main.as_ptr()) };
+    if backup.is_null() {
+        return Err(OwsqlError::Message(format!("failed to backup: {}", errmsg(dst))));
+    }
+
+    let mut result;
+    loop {
+        result = unsafe { ffi::sqlite3_backup_step(backup, PAGES_PER_STEP) };
+        progress(unsafe { Progress {
+            remaining:  ffi::sqlite3_backup_remaining(backup) as u32,
+            page_count: ffi::sqlite3_backup_pagecount(backup) as u32,
+        }});
+        match result {
+            ffi::SQLITE_OK => (),
+            ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => thread::sleep(Duration::from_millis(10)),
+            _ => break,
+        }
+    }
+
+    let finish = unsafe { ffi::sqlite3_backup_finish(backup) };
+    if result == ffi::SQLITE_DONE && finish == ffi::SQLITE_OK {
+        Ok(())
+    } else {
+        Err(OwsqlError::Message(format!("failed to backup: {}", errmsg(dst))))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use temporary::Directory;
+
+    #[test]
+    fn backup_to_connection() {
+        let src = crate::sqlite::open(":memory:").unwrap();
+        src.execute(src.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES('Alice');")).unwrap();
+        let dst = crate::sqlite::open(":memory:").unwrap();
+        let mut steps = Vec::new();
+        src.backup_to_connection(&dst, |progress| steps.push(progress)).unwrap();
+        assert_eq!(steps.last().unwrap().remaining, 0);
+        assert_eq!(dst.count(dst.ow("SELECT * FROM users;")), Ok(1));
+        assert!(src.backup_to_connection(&src, |_| ()).is_err());
+    }
+
+    #[test]
+    fn restore_from() {
+        let dir = Directory::new("sqlite").unwrap();
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        assert!(conn.restore_from(dir.path().join("nothing.db"), |_| ()).is_err());
+        assert!(conn.backup_to(dir.path().join("nothing").join("backup.db"), |_| ()).is_err());
+    }
+}
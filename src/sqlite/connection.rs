@@ -1,18 +1,17 @@
 extern crate sqlite3_sys as ffi;
 
+use std::any::Any;
 use std::ffi::{CStr, CString, c_void};
+use std::io;
 use std::ptr::{self, NonNull};
 use std::path::Path;
-use std::collections::HashSet;
-use std::cell::RefCell;
 
 use crate::Result;
-use crate::connection::{Connection, OwsqlConn};
-use crate::bidimap::BidiMap;
+use crate::blob::BlobIo;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
 use crate::error::{OwsqlError, OwsqlErrorLevel};
-use crate::constants::OW_MINIMUM_LENGTH;
-use crate::serial::SerialNumber;
-use crate::parser::escape_string;
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
 
 /// Open a read-write connection to a new or existing database.
 pub fn open<T: AsRef<Path>>(path: T, openflags: i32) -> Result<Connection> {
@@ -36,20 +35,45 @@ pub fn open<T: AsRef<Path>>(path: T, openflags: i32) -> Result<Connection> {
 
     match open_result {
         ffi::SQLITE_OK =>
-            Ok(Connection {
-                conn:              Box::new(unsafe { NonNull::new_unchecked(conn_ptr) }),
-                allowlist:         HashSet::new(),
-                serial_number:     RefCell::new(SerialNumber::default()),
-                ow_len_range:      (OW_MINIMUM_LENGTH, OW_MINIMUM_LENGTH),
-                overwrite:         RefCell::new(BidiMap::new()),
-                whitespace_around: RefCell::new(BidiMap::new()),
-                error_msg:         RefCell::new(BidiMap::new()),
-                error_level:       OwsqlErrorLevel::default(),
-            }),
+            Ok(Connection::new(Box::new(unsafe { NonNull::new_unchecked(conn_ptr) }))),
         _ => Err(OwsqlError::Message("failed to connect".into())),
     }
 }
 
+/// Return the SQLite handle of the connection.
+/// `feature` is used for the error message if the connection is not SQLite.
+pub(crate) fn handle(conn: &Connection, feature: &str) -> Result<*mut ffi::sqlite3> {
+    match conn.conn.as_any().downcast_ref::<NonNull<ffi::sqlite3>>() {
+        Some(handle) => Ok(handle.as_ptr()),
+        None => Err(OwsqlError::Message(format!("unsupported {}", feature))),
+    }
+}
+
+/// Return the file name of the main database, which is empty for an in-memory or a temporary database.
+pub(crate) fn filename(conn: &Connection, feature: &str) -> Result<String> {
+    let handle = handle(conn, feature)?;
+    let filename = unsafe { ffi::sqlite3_db_filename(handle, b"main\0".as_ptr() as *const _) };
+    if filename.is_null() {
+        return Ok(String::new());
+    }
+    Ok(unsafe { CStr::from_ptr(filename) }.to_string_lossy().into_owned())
+}
+
+/// Keeps the hook alive in the connection, replacing the hook of the same name.
+/// `register` is called with the pointer to the hook, which is passed to the callback.
+pub(crate) fn set_hook<T: 'static>(conn: &Connection, name: &'static str, hook: T, register: impl FnOnce(*mut c_void)) {
+    let mut hook = Box::new(hook);
+    register(&mut *hook as *mut T as *mut c_void);
+    // The previous hook is dropped after it is unregistered.
+    conn.hooks.borrow_mut().insert(name, hook);
+}
+
+/// Drops the hook after `unregister` is called.
+pub(crate) fn remove_hook(conn: &Connection, name: &'static str, unregister: impl FnOnce()) {
+    unregister();
+    conn.hooks.borrow_mut().remove(name);
+}
+
 impl OwsqlConn for NonNull<ffi::sqlite3> {
     fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
         let query = match query {
@@ -62,7 +86,7 @@ impl OwsqlConn for NonNull<ffi::sqlite3> {
         }.as_bytes().to_vec();
         let query = match CString::new(&*query) {
             Ok(string) => string,
-            _ => return OwsqlError::new(&error_level, "invalid query", &String::from_utf8(query).unwrap_or_default()),
+            _ => return OwsqlError::new(error_level, "invalid query", &String::from_utf8(query).unwrap_or_default()),
         };
         let mut err_msg = ptr::null_mut();
 
@@ -79,13 +103,32 @@ impl OwsqlConn for NonNull<ffi::sqlite3> {
         if err_msg.is_null() {
             Ok(())
         } else {
-            OwsqlError::new(&error_level, "exec error",
-                unsafe{ &CStr::from_ptr(ffi::sqlite3_errmsg(self.as_ptr())).to_string_lossy().into_owned() })
+            let code = unsafe { ffi::sqlite3_extended_errcode(self.as_ptr()) };
+            OwsqlError::database(error_level, Some(code), None, None, &errmsg(self.as_ptr()))
         }
     }
 
+    fn close(&self, error_level: &OwsqlErrorLevel) -> Result<()> {
+        if unsafe { ffi::sqlite3_get_autocommit(self.as_ptr()) } == 0 {
+            self._execute(Ok("ROLLBACK".to_string()), error_level)?;
+        }
+        let code = unsafe { ffi::sqlite3_close(self.as_ptr()) };
+        if code == ffi::SQLITE_OK {
+            return Ok(());
+        }
+        let message = errmsg(self.as_ptr());
+        // The handle is released when the remaining statements are finalized.
+        unsafe { ffi::sqlite3_close_v2(self.as_ptr()) };
+        OwsqlError::database(error_level, Some(code), None, None, &message)
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        self._execute(query, error_level)?;
+        Ok(self.changes().unwrap_or_default())
+    }
+
     fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
-        callback: &mut dyn FnMut(&[(&str, Option<&str>)]) -> bool) -> Result<()>
+        callback: IterateCallback) -> Result<()>
     {
         let query = match query {
             Ok(query) => query,
@@ -97,7 +140,7 @@ impl OwsqlConn for NonNull<ffi::sqlite3> {
         }.as_bytes().to_vec();
         let query = match CString::new(&*query) {
             Ok(string) => string,
-            _ => return OwsqlError::new(&error_level, "invalid query", &String::from_utf8(query).unwrap_or_default()),
+            _ => return OwsqlError::new(error_level, "invalid query", &String::from_utf8(query).unwrap_or_default()),
         };
         let mut err_msg = ptr::null_mut();
         let callback = Box::new(callback);
@@ -116,20 +159,145 @@ impl OwsqlConn for NonNull<ffi::sqlite3> {
         if err_msg.is_null() {
             Ok(())
         } else {
-            OwsqlError::new(&error_level, "exec error",
-                unsafe{ &CStr::from_ptr(ffi::sqlite3_errmsg(self.as_ptr())).to_string_lossy().into_owned() })
+            let code = unsafe { ffi::sqlite3_extended_errcode(self.as_ptr()) };
+            OwsqlError::database(error_level, Some(code), None, None, &errmsg(self.as_ptr()))
+        }
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        }.as_bytes().to_vec();
+        let query = match CString::new(&*query) {
+            Ok(string) => string,
+            _ => return OwsqlError::new(error_level, "invalid query", &String::from_utf8(query).unwrap_or_default()),
+        };
+
+        // Prepare each statement until the tail is empty.
+        let mut tail = query.as_ptr();
+        while unsafe { *tail } != 0 {
+            let mut stmt = ptr::null_mut();
+            let result = unsafe { ffi::sqlite3_prepare_v2(self.as_ptr(), tail, -1, &mut stmt, &mut tail) };
+            unsafe { ffi::sqlite3_finalize(stmt) };
+            if result != ffi::SQLITE_OK {
+                let code = unsafe { ffi::sqlite3_extended_errcode(self.as_ptr()) };
+                return OwsqlError::database(error_level, Some(code), None, None, &errmsg(self.as_ptr()));
+            }
+        }
+        Ok(())
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        EscapeMode::Standard
+    }
+
+    fn charset(&self) -> Charset {
+        Charset::Utf8
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn last_error_code(&self) -> Option<String> {
+        match unsafe { ffi::sqlite3_extended_errcode(self.as_ptr()) } {
+            ffi::SQLITE_OK => None,
+            code => Some(code.to_string()),
+        }
+    }
+
+    fn changes(&self) -> Option<u64> {
+        Some(unsafe { ffi::sqlite3_changes(self.as_ptr()) } as u64)
+    }
+
+    fn max_statement_len(&self) -> Option<usize> {
+        Some(unsafe { ffi::sqlite3_limit(self.as_ptr(), ffi::SQLITE_LIMIT_SQL_LENGTH, -1) } as usize)
+    }
+
+    fn blob_open(&self, table: &str, column: &str, rowid: i64) -> Result<Box<dyn BlobIo + '_>> {
+        let (table, column) = match (CString::new(table), CString::new(column)) {
+            (Ok(table), Ok(column)) => (table, column),
+            _ => return Err(OwsqlError::Message("failed to open blob: invalid name".into())),
+        };
+        let main = CString::new("main").unwrap();
+        let mut blob = ptr::null_mut();
+        let result = unsafe {
+            let writable = ffi::sqlite3_db_readonly(self.as_ptr(), main.as_ptr()) == 0;
+            ffi::sqlite3_blob_open(self.as_ptr(), main.as_ptr(), table.as_ptr(), column.as_ptr(),
+                rowid, writable as i32, &mut blob)
+        };
+        if result != ffi::SQLITE_OK {
+            return Err(OwsqlError::Message(format!("failed to open blob: {}", errmsg(self.as_ptr()))));
+        }
+        Ok(Box::new(SqliteBlob { conn: self.as_ptr(), blob }))
+    }
+}
+
+/// An open `sqlite3_blob`, which is closed on drop.
+struct SqliteBlob {
+    conn: *mut ffi::sqlite3,
+    blob: *mut ffi::sqlite3_blob,
+}
+
+impl SqliteBlob {
+    /// Return the number of bytes that can be accessed from the offset, up to `len`.
+    fn available(&self, offset: u64, len: usize) -> i32 {
+        let size = unsafe { ffi::sqlite3_blob_bytes(self.blob) } as u64;
+        size.saturating_sub(offset).min(len as u64) as i32
+    }
+
+    fn error(&self) -> io::Error {
+        io::Error::other(errmsg(self.conn))
+    }
+}
+
+impl BlobIo for SqliteBlob {
+    fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let n = self.available(offset, buf.len());
+        if n == 0 {
+            return Ok(0);
+        }
+        match unsafe { ffi::sqlite3_blob_read(self.blob, buf.as_mut_ptr() as *mut c_void, n, offset as i32) } {
+            ffi::SQLITE_OK => Ok(n as usize),
+            _ => Err(self.error()),
         }
     }
 
-    fn must_escape(&self) -> Box<dyn Fn(char) -> bool> {
-        Box::new(|c| c == '\'')
+    fn write_at(&mut self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let n = self.available(offset, buf.len());
+        if n == 0 && !buf.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "blob cannot grow"));
+        }
+        match unsafe { ffi::sqlite3_blob_write(self.blob, buf.as_ptr() as *const c_void, n, offset as i32) } {
+            ffi::SQLITE_OK => Ok(n as usize),
+            _ => Err(self.error()),
+        }
     }
 
-    fn literal_escape(&self, s: &str) -> String {
-        escape_string(&s, self.must_escape())
+    fn len(&mut self) -> io::Result<u64> {
+        Ok(unsafe { ffi::sqlite3_blob_bytes(self.blob) } as u64)
     }
 }
 
+impl Drop for SqliteBlob {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_blob_close(self.blob) };
+    }
+}
+
+pub(crate) fn errmsg(conn: *mut ffi::sqlite3) -> String {
+    unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(conn)).to_string_lossy().into_owned() }
+}
+
 extern "C" fn process_callback(
     callback: *mut c_void,
     count: i32,
@@ -186,12 +354,12 @@ mod tests {
     fn execute() {
         let conn = crate::sqlite::open(":memory:").unwrap();
         assert_eq!(
-            conn.execute("\0"),
+            conn.execute(conn.ow("\0")),
             Err(OwsqlError::Message("invalid query".into())),
         );
         assert_eq!(
             conn.execute("invalid query"),
-            Err(OwsqlError::Message("exec error".into())),
+            Err(OwsqlError::Database { code: Some(1), sqlstate: None, message: "exec error".into(), constraint: None }),
         );
     }
 
@@ -200,12 +368,12 @@ mod tests {
     fn iterate() {
         let conn = crate::sqlite::open(":memory:").unwrap();
         assert_eq!(
-            conn.iterate("\0", |_| { unreachable!(); }),
+            conn.iterate(conn.ow("\0"), |_| { unreachable!(); }),
             Err(OwsqlError::Message("invalid query".into())),
         );
         assert_eq!(
             conn.iterate("invalid query", |_| { unreachable!(); }),
-            Err(OwsqlError::Message("exec error".into())),
+            Err(OwsqlError::Database { code: Some(1), sqlstate: None, message: "exec error".into(), constraint: None }),
         );
     }
 
@@ -228,6 +396,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn allowlist() {
         let mut conn = crate::sqlite::open(":memory:").unwrap();
         conn.add_allowlist(params!["Alice", "Bob", 42]);
@@ -294,5 +463,25 @@ mod tests {
         conn.set_ow_len(64..32);
         conn.set_ow_len(64..=32);
     }
-}
 
+    #[test]
+    fn blob_open() {
+        use std::io::{Read, Seek, SeekFrom, Write};
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        conn.execute(conn.ow("CREATE TABLE files (data BLOB); INSERT INTO files VALUES(zeroblob(8));")).unwrap();
+        let mut blob = conn.blob_open("files", "data", 1).unwrap();
+        assert_eq!(blob.len().unwrap(), 8);
+        blob.write_all(b"owsql").unwrap();
+        assert_eq!(blob.write(b"overflow").unwrap(), 3);
+        assert_eq!(blob.write(b"!").unwrap_err().kind(), std::io::ErrorKind::WriteZero);
+        blob.seek(SeekFrom::Start(2)).unwrap();
+        let mut data = Vec::new();
+        blob.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"sqlove");
+        assert!(blob.seek(SeekFrom::Current(-9)).is_err());
+        drop(blob);
+        assert_eq!(conn.rows(conn.ow("SELECT CAST(data AS TEXT) AS data FROM files;")).unwrap()[0].get("data"), Some("owsqlove"));
+        assert!(conn.blob_open("files", "data", 2).is_err());
+        assert!(conn.blob_open("nothing", "data", 1).is_err());
+    }
+}
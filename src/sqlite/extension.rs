@@ -0,0 +1,77 @@
+extern crate sqlite3_sys as ffi;
+
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use super::connection::handle;
+
+/// Enables or disables loading extensions by the C API, keeping the SQL function
+/// `load_extension()` disabled.
+fn set_load_extension(conn: *mut ffi::sqlite3, enabled: bool) -> Result<()> {
+    let result = unsafe {
+        ffi::sqlite3_db_config(conn, ffi::SQLITE_DBCONFIG_ENABLE_LOAD_EXTENSION, enabled as i32, ptr::null_mut::<i32>())
+    };
+    if result != ffi::SQLITE_OK {
+        return Err(OwsqlError::Message("unsupported extension".into()));
+    }
+    Ok(())
+}
+
+impl Connection {
+    /// Loads the [extension](https://www.sqlite.org/loadext.html) from the shared library.
+    /// `entry` is the name of the entry point, or `None` to derive it from the file name.  
+    /// Loading is enabled only during this call, and the SQL function `load_extension()` is never enabled,
+    /// so a statement cannot load a library.  
+    /// Only for SQLite.
+    ///
+    /// # Safety
+    ///
+    /// The library runs arbitrary native code in the process, so it must be trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let result = unsafe { conn.load_extension("/path/to/spellfix", None) };
+    /// assert!(result.is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub unsafe fn load_extension<P: AsRef<Path>>(&self, path: P, entry: Option<&str>) -> Result<()> {
+        let conn = handle(self, "extension")?;
+        let path = match path.as_ref().to_str().map(CString::new) {
+            Some(Ok(path)) => path,
+            _ => return Err(OwsqlError::Message(format!("invalid path: {:?}", path.as_ref()))),
+        };
+        let entry = match entry.map(CString::new) {
+            Some(Ok(entry)) => Some(entry),
+            Some(Err(_)) => return Err(OwsqlError::Message(format!("invalid entry point: {:?}", entry))),
+            None => None,
+        };
+
+        set_load_extension(conn, true)?;
+        let mut err_msg = ptr::null_mut();
+        let result = ffi::sqlite3_load_extension(
+            conn,
+            path.as_ptr(),
+            entry.as_ref().map_or(ptr::null(), |entry| entry.as_ptr()),
+            &mut err_msg,
+        );
+        set_load_extension(conn, false)?;
+
+        if result == ffi::SQLITE_OK {
+            return Ok(());
+        }
+        let message = if err_msg.is_null() {
+            String::new()
+        } else {
+            let message = CStr::from_ptr(err_msg).to_string_lossy().into_owned();
+            ffi::sqlite3_free(err_msg as *mut _);
+            message
+        };
+        Err(OwsqlError::Message(format!("failed to load extension: {}", message)))
+    }
+}
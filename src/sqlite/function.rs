@@ -0,0 +1,372 @@
+extern crate sqlite3_sys as ffi;
+
+use std::ffi::{CString, c_void};
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use super::connection::{errmsg, handle};
+
+/// A value passed to or returned from a user-defined function.
+/// See [create_scalar_function](../struct.Connection.html#method.create_scalar_function).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    /// Return the text, or `None` if the value is not a text.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Return the integer, or `None` if the value is not an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// Return the number, or `None` if the value is neither an integer nor a real.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(integer) => Some(*integer as f64),
+            Value::Real(real) => Some(*real),
+            _ => None,
+        }
+    }
+
+    fn from_raw(value: *mut ffi::sqlite3_value) -> Self {
+        unsafe {
+            match ffi::sqlite3_value_type(value) {
+                ffi::SQLITE_INTEGER => Value::Integer(ffi::sqlite3_value_int64(value)),
+                ffi::SQLITE_FLOAT   => Value::Real(ffi::sqlite3_value_double(value)),
+                ffi::SQLITE_TEXT    => {
+                    let text = ffi::sqlite3_value_text(value);
+                    let len = ffi::sqlite3_value_bytes(value) as usize;
+                    Value::Text(String::from_utf8_lossy(slice::from_raw_parts(text, len)).into_owned())
+                },
+                ffi::SQLITE_BLOB    => {
+                    let blob = ffi::sqlite3_value_blob(value) as *const u8;
+                    let len = ffi::sqlite3_value_bytes(value) as usize;
+                    Value::Blob(if len == 0 { Vec::new() } else { slice::from_raw_parts(blob, len).to_vec() })
+                },
+                _ => Value::Null,
+            }
+        }
+    }
+
+    fn set_result(&self, ctx: *mut ffi::sqlite3_context) {
+        unsafe {
+            match self {
+                Value::Null          => ffi::sqlite3_result_null(ctx),
+                Value::Integer(v)    => ffi::sqlite3_result_int64(ctx, *v),
+                Value::Real(v)       => ffi::sqlite3_result_double(ctx, *v),
+                Value::Text(text)    => ffi::sqlite3_result_text(ctx,
+                    text.as_ptr() as *const _, text.len() as c_int, transient()),
+                Value::Blob(blob)    => ffi::sqlite3_result_blob(ctx,
+                    blob.as_ptr() as *const c_void, blob.len() as c_int, transient()),
+            }
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Blob(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+type FunctionResult = std::result::Result<Value, String>;
+
+struct Aggregate<T, I, S, F> {
+    init:     I,
+    step:     S,
+    finalize: F,
+    state:    std::marker::PhantomData<T>,
+}
+
+impl Connection {
+    /// Registers a scalar function, which can be called from the statements of this connection.
+    /// `arity` is the number of the arguments, or -1 for any number.
+    /// An `Err` of the function is raised as the error of the statement.
+    /// The function must be `Send`, since the connection can be sent to another thread.
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::Value;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.create_scalar_function("normalize", 1, |args| {
+    ///     Ok(args[0].as_str().map(|s| s.trim().to_lowercase()).into())
+    /// }).unwrap();
+    /// let rows = conn.rows(conn.ow("SELECT normalize(") + "  Alice " + &conn.ow(") AS name;")).unwrap();
+    /// assert_eq!(rows[0].get("name"), Some("alice"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn create_scalar_function<F>(&self, name: &str, arity: i32, f: F) -> Result<()>
+        where
+            F: FnMut(&[Value]) -> FunctionResult + Send + 'static,
+    {
+        let conn = handle(self, "function")?;
+        let name = function_name(name)?;
+        let f = Box::into_raw(Box::new(f));
+        let result = unsafe { ffi::sqlite3_create_function_v2(
+            conn,
+            name.as_ptr(),
+            arity,
+            ffi::SQLITE_UTF8,
+            f as *mut c_void,
+            Some(call_scalar::<F>),
+            None,
+            None,
+            Some(destroy::<F>),
+        )};
+        check(conn, result)
+    }
+
+    /// Registers an aggregate function, which can be called from the statements of this connection.
+    /// For each group, `init` creates the state, `step` is called for each row,
+    /// and `finalize` returns the result from the state. The callbacks must be `Send` as in
+    /// [create_scalar_function](#method.create_scalar_function).
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::Value;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES('Alice'), ('Bob');")).unwrap();
+    /// conn.create_aggregate_function("longest", 1,
+    ///     String::new,
+    ///     |longest, args| {
+    ///         if let Some(name) = args[0].as_str() {
+    ///             if name.len() > longest.len() {
+    ///                 *longest = name.to_string();
+    ///             }
+    ///         }
+    ///         Ok(())
+    ///     },
+    ///     |longest| Ok(longest.into()),
+    /// ).unwrap();
+    /// let rows = conn.rows(conn.ow("SELECT longest(name) AS name FROM users;")).unwrap();
+    /// assert_eq!(rows[0].get("name"), Some("Alice"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn create_aggregate_function<T, I, S, F>(&self, name: &str, arity: i32, init: I, step: S, finalize: F) -> Result<()>
+        where
+            T: 'static,
+            I: FnMut() -> T + Send + 'static,
+            S: FnMut(&mut T, &[Value]) -> std::result::Result<(), String> + Send + 'static,
+            F: FnMut(T) -> FunctionResult + Send + 'static,
+    {
+        let conn = handle(self, "function")?;
+        let name = function_name(name)?;
+        let aggregate = Box::into_raw(Box::new(Aggregate {
+            init, step, finalize, state: std::marker::PhantomData::<T>,
+        }));
+        let result = unsafe { ffi::sqlite3_create_function_v2(
+            conn,
+            name.as_ptr(),
+            arity,
+            ffi::SQLITE_UTF8,
+            aggregate as *mut c_void,
+            None,
+            Some(call_step::<T, I, S, F>),
+            Some(call_final::<T, I, S, F>),
+            Some(destroy::<Aggregate<T, I, S, F>>),
+        )};
+        check(conn, result)
+    }
+}
+
+fn function_name(name: &str) -> Result<CString> {
+    CString::new(name).map_err(|_| OwsqlError::Message(format!("invalid function name: {:?}", name)))
+}
+
+fn check(conn: *mut ffi::sqlite3, result: c_int) -> Result<()> {
+    if result == ffi::SQLITE_OK {
+        Ok(())
+    } else {
+        Err(OwsqlError::Message(format!("failed to create function: {}", errmsg(conn))))
+    }
+}
+
+/// `SQLITE_TRANSIENT`, which makes SQLite copy the result.
+fn transient() -> Option<ffi::sqlite3_callback> {
+    Some(unsafe { std::mem::transmute::<isize, ffi::sqlite3_callback>(ffi::SQLITE_TRANSIENT as isize) })
+}
+
+fn args(argc: c_int, argv: *mut *mut ffi::sqlite3_value) -> Vec<Value> {
+    (0..argc as isize).map(|i| Value::from_raw(unsafe { *argv.offset(i) })).collect()
+}
+
+fn set_error(ctx: *mut ffi::sqlite3_context, message: &str) {
+    unsafe { ffi::sqlite3_result_error(ctx, message.as_ptr() as *const _, message.len() as c_int) };
+}
+
+/// Calls the function, catching a panic not to unwind into SQLite.
+fn call(ctx: *mut ffi::sqlite3_context, f: impl FnOnce() -> FunctionResult) {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value.set_result(ctx),
+        Ok(Err(message)) => set_error(ctx, &message),
+        Err(_) => set_error(ctx, "panic in function"),
+    }
+}
+
+extern "C" fn call_scalar<F>(ctx: *mut ffi::sqlite3_context, argc: c_int, argv: *mut *mut ffi::sqlite3_value)
+    where
+        F: FnMut(&[Value]) -> FunctionResult,
+{
+    let f = unsafe { &mut *(ffi::sqlite3_user_data(ctx) as *mut F) };
+    call(ctx, || f(&args(argc, argv)));
+}
+
+/// Return the slot of the state in the aggregate context, or `None` if it is not allocated.
+fn state_slot<T>(ctx: *mut ffi::sqlite3_context, allocate: bool) -> Option<*mut *mut T> {
+    let size = if allocate { std::mem::size_of::<*mut T>() as c_int } else { 0 };
+    let slot = unsafe { ffi::sqlite3_aggregate_context(ctx, size) } as *mut *mut T;
+    if slot.is_null() { None } else { Some(slot) }
+}
+
+extern "C" fn call_step<T, I, S, F>(ctx: *mut ffi::sqlite3_context, argc: c_int, argv: *mut *mut ffi::sqlite3_value)
+    where
+        I: FnMut() -> T,
+        S: FnMut(&mut T, &[Value]) -> std::result::Result<(), String>,
+{
+    let aggregate = unsafe { &mut *(ffi::sqlite3_user_data(ctx) as *mut Aggregate<T, I, S, F>) };
+    let slot = match state_slot::<T>(ctx, true) {
+        Some(slot) => slot,
+        None => return unsafe { ffi::sqlite3_result_error_nomem(ctx) },
+    };
+    call(ctx, || {
+        // The aggregate context is zeroed when it is allocated.
+        if unsafe { (*slot).is_null() } {
+            unsafe { *slot = Box::into_raw(Box::new((aggregate.init)())) };
+        }
+        (aggregate.step)(unsafe { &mut **slot }, &args(argc, argv)).map(|_| Value::Null)
+    });
+}
+
+extern "C" fn call_final<T, I, S, F>(ctx: *mut ffi::sqlite3_context)
+    where
+        I: FnMut() -> T,
+        F: FnMut(T) -> FunctionResult,
+{
+    let aggregate = unsafe { &mut *(ffi::sqlite3_user_data(ctx) as *mut Aggregate<T, I, S, F>) };
+    let state = match state_slot::<T>(ctx, false) {
+        Some(slot) if unsafe { !(*slot).is_null() } => {
+            let state = unsafe { Box::from_raw(*slot) };
+            unsafe { *slot = ptr::null_mut() };
+            Some(state)
+        },
+        _ => None,
+    };
+    call(ctx, || {
+        // No rows in the group.
+        let state = state.map_or_else(|| (aggregate.init)(), |state| *state);
+        (aggregate.finalize)(state)
+    });
+}
+
+extern "C" fn destroy<T>(p: *mut c_void) {
+    drop(unsafe { Box::from_raw(p as *mut T) });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn create_scalar_function() {
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        conn.create_scalar_function("typeof_owsql", -1, |args| {
+            Ok(args.iter().map(|arg| match arg {
+                Value::Null       => "null",
+                Value::Integer(_) => "integer",
+                Value::Real(_)    => "real",
+                Value::Text(_)    => "text",
+                Value::Blob(_)    => "blob",
+            }).collect::<Vec<_>>().join(",").into())
+        }).unwrap();
+        let rows = conn.rows(conn.ow("SELECT typeof_owsql(NULL, 1, 1.5, 'a', x'00') AS t;")).unwrap();
+        assert_eq!(rows[0].get("t"), Some("null,integer,real,text,blob"));
+
+        conn.create_scalar_function("fail", 0, |_| Err("failed".to_string())).unwrap();
+        assert!(conn.execute(conn.ow("SELECT fail();")).is_err());
+        conn.create_scalar_function("panic", 0, |_| panic!()).unwrap();
+        assert!(conn.execute(conn.ow("SELECT panic();")).is_err());
+        assert!(conn.create_scalar_function("a\0b", 0, |_| Ok(Value::Null)).is_err());
+
+        let mut calls = 0;
+        conn.create_scalar_function("counter", 0, move |_| {
+            calls += 1;
+            Ok(Value::Integer(calls))
+        }).unwrap();
+        assert_eq!(conn.rows(conn.ow("SELECT counter() + counter() AS n;")).unwrap()[0].get("n"), Some("3"));
+    }
+
+    #[test]
+    fn create_aggregate_function() {
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        conn.execute(conn.ow("CREATE TABLE t (g TEXT, v INTEGER); INSERT INTO t VALUES('a', 1), ('a', 2), ('b', 3);")).unwrap();
+        conn.create_aggregate_function("product", 1,
+            || 1,
+            |product, args| {
+                *product *= args[0].as_i64().ok_or("not integer")?;
+                Ok(())
+            },
+            |product| Ok(Value::Integer(product)),
+        ).unwrap();
+        let rows = conn.rows(conn.ow("SELECT g, product(v) AS p FROM t GROUP BY g ORDER BY g;")).unwrap();
+        assert_eq!(rows[0].get("p"), Some("2"));
+        assert_eq!(rows[1].get("p"), Some("3"));
+        let rows = conn.rows(conn.ow("SELECT product(v) AS p FROM t WHERE g = 'c';")).unwrap();
+        assert_eq!(rows[0].get("p"), Some("1"));
+        assert!(conn.execute(conn.ow("SELECT product(g) FROM t;")).is_err());
+    }
+}
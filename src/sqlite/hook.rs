@@ -0,0 +1,196 @@
+extern crate sqlite3_sys as ffi;
+
+use std::ffi::{CStr, c_void};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::Result;
+use crate::connection::Connection;
+use super::connection::{handle, remove_hook, set_hook};
+
+/// The kind of the change notified to the update hook.
+/// See [set_update_hook](../struct.Connection.html#method.set_update_hook).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Connection {
+    /// Sets the callback invoked when a row of a rowid table is inserted, updated or deleted,
+    /// with the action, the name of the database, the table and the rowid.
+    /// The callback must not modify the database, and must be `Send`, since the connection can be sent
+    /// to another thread.
+    /// `DELETE` without `WHERE` may be optimized and not notified.
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::Action;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// conn.set_update_hook(|action, db, table, rowid| {
+    ///     println!("{:?} {}.{} {}", action, db, table, rowid);
+    /// }).unwrap();
+    /// conn.execute(conn.ow("INSERT INTO users VALUES('Alice');")).unwrap();
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn set_update_hook<F: FnMut(Action, &str, &str, i64) + Send + 'static>(&self, hook: F) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        set_hook(self, "update", hook, |hook| unsafe {
+            ffi::sqlite3_update_hook(conn, Some(call_update_hook::<F>), hook);
+        });
+        Ok(())
+    }
+
+    /// Removes the callback set by [set_update_hook](#method.set_update_hook).
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn remove_update_hook(&self) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        remove_hook(self, "update", || unsafe {
+            ffi::sqlite3_update_hook(conn, None, ptr::null_mut());
+        });
+        Ok(())
+    }
+
+    /// Sets the callback invoked before a transaction is committed.
+    /// If the callback returns `true`, the commit is turned into a rollback.
+    /// The callback must not modify the database.
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// conn.set_commit_hook(|| true).unwrap();
+    /// assert!(conn.execute(conn.ow("INSERT INTO users VALUES('Alice');")).is_err());
+    /// conn.remove_commit_hook().unwrap();
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(0));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn set_commit_hook<F: FnMut() -> bool + Send + 'static>(&self, hook: F) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        set_hook(self, "commit", hook, |hook| unsafe {
+            ffi::sqlite3_commit_hook(conn, Some(call_commit_hook::<F>), hook);
+        });
+        Ok(())
+    }
+
+    /// Removes the callback set by [set_commit_hook](#method.set_commit_hook).
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn remove_commit_hook(&self) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        remove_hook(self, "commit", || unsafe {
+            ffi::sqlite3_commit_hook(conn, None, ptr::null_mut());
+        });
+        Ok(())
+    }
+
+    /// Sets the callback invoked when a transaction is rolled back,
+    /// including a commit turned into a rollback by the commit hook.
+    /// Only for SQLite.
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn set_rollback_hook<F: FnMut() + Send + 'static>(&self, hook: F) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        set_hook(self, "rollback", hook, |hook| unsafe {
+            ffi::sqlite3_rollback_hook(conn, Some(call_rollback_hook::<F>), hook);
+        });
+        Ok(())
+    }
+
+    /// Removes the callback set by [set_rollback_hook](#method.set_rollback_hook).
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn remove_rollback_hook(&self) -> Result<()> {
+        let conn = handle(self, "hook")?;
+        remove_hook(self, "rollback", || unsafe {
+            ffi::sqlite3_rollback_hook(conn, None, ptr::null_mut());
+        });
+        Ok(())
+    }
+}
+
+// A panic must not unwind into SQLite.
+
+extern "C" fn call_update_hook<F>(hook: *mut c_void, action: c_int, db: *const c_char, table: *const c_char, rowid: i64)
+    where
+        F: FnMut(Action, &str, &str, i64),
+{
+    let hook = unsafe { &mut *(hook as *mut F) };
+    let action = match action {
+        ffi::SQLITE_INSERT => Action::Insert,
+        ffi::SQLITE_UPDATE => Action::Update,
+        _                  => Action::Delete,
+    };
+    let db = unsafe { CStr::from_ptr(db) }.to_string_lossy();
+    let table = unsafe { CStr::from_ptr(table) }.to_string_lossy();
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(action, &db, &table, rowid)));
+}
+
+extern "C" fn call_commit_hook<F: FnMut() -> bool>(hook: *mut c_void) -> c_int {
+    let hook = unsafe { &mut *(hook as *mut F) };
+    // Roll back if the hook panics.
+    panic::catch_unwind(AssertUnwindSafe(hook)).unwrap_or(true) as c_int
+}
+
+extern "C" fn call_rollback_hook<F: FnMut()>(hook: *mut c_void) {
+    let hook = unsafe { &mut *(hook as *mut F) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(hook));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::sqlite::Action;
+
+    #[test]
+    fn update_hook() {
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let hook = Arc::clone(&changes);
+        conn.set_update_hook(move |action, db, table, rowid| {
+            hook.lock().unwrap().push((action, db.to_string(), table.to_string(), rowid));
+        }).unwrap();
+        conn.execute(conn.ow("INSERT INTO users VALUES('Alice'); UPDATE users SET name = 'Bob'; DELETE FROM users WHERE name = 'Bob';")).unwrap();
+        assert_eq!(*changes.lock().unwrap(), [
+            (Action::Insert, "main".to_string(), "users".to_string(), 1),
+            (Action::Update, "main".to_string(), "users".to_string(), 1),
+            (Action::Delete, "main".to_string(), "users".to_string(), 1),
+        ]);
+        conn.remove_update_hook().unwrap();
+        conn.execute(conn.ow("INSERT INTO users VALUES('Carol');")).unwrap();
+        assert_eq!(changes.lock().unwrap().len(), 3);
+        assert_eq!(Arc::strong_count(&changes), 1);
+    }
+
+    #[test]
+    fn commit_hook() {
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let commit = Arc::clone(&events);
+        conn.set_commit_hook(move || {
+            commit.lock().unwrap().push("commit");
+            false
+        }).unwrap();
+        let rollback = Arc::clone(&events);
+        conn.set_rollback_hook(move || rollback.lock().unwrap().push("rollback")).unwrap();
+
+        conn.execute(conn.ow("INSERT INTO users VALUES('Alice');")).unwrap();
+        let _ = conn.with_transaction(|tx| {
+            tx.execute(tx.ow("INSERT INTO users VALUES('Bob');"))?;
+            Err::<(), _>(crate::OwsqlError::AnyError)
+        });
+        assert_eq!(*events.lock().unwrap(), ["commit", "rollback"]);
+
+        conn.set_commit_hook(|| panic!()).unwrap();
+        assert!(conn.execute(conn.ow("INSERT INTO users VALUES('Carol');")).is_err());
+        assert_eq!(*events.lock().unwrap(), ["commit", "rollback", "rollback"]);
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(1));
+    }
+}
@@ -1,10 +1,24 @@
 //! Interface to [SQLite](https://www.sqlite.org) of OverwriteSQL.
 
+use std::ffi::CStr;
 use std::path::Path;
 use crate::Result;
 use crate::connection::Connection;
 
+mod attach;
+mod backup;
+mod extension;
 pub(crate) mod connection;
+mod function;
+mod hook;
+mod pragma;
+mod wal;
+
+pub use backup::Progress;
+pub use function::Value;
+pub use hook::Action;
+pub use pragma::{JournalMode, Pragma, Synchronous};
+pub use wal::{Checkpoint, CheckpointMode};
 
 /// Open a read-write connection to a new or existing database.
 ///
@@ -32,14 +46,60 @@ pub fn open_readonly<T: AsRef<Path>>(path: T) -> Result<Connection> {
     connection::open(path, sqlite3_sys::SQLITE_OPEN_READONLY)
 }
 
+/// Open a connection to the named in-memory database shared by the connections in the process,
+/// `file:name?mode=memory&cache=shared`.  
+/// The database is deleted when the last connection to it is [closed](../struct.Connection.html#method.close).
+///
+/// # Examples
+///
+/// ```
+/// let conn = owsql::sqlite::open_shared("test").unwrap();
+/// conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice');")).unwrap();
+/// let other = owsql::sqlite::open_shared("test").unwrap();
+/// assert_eq!(other.count(other.ow("SELECT * FROM users;")), Ok(1));
+/// ```
+pub fn open_shared(name: &str) -> Result<Connection> {
+    connection::open(
+        format!("file:{}?mode=memory&cache=shared", percent_encode(name)),
+        sqlite3_sys::SQLITE_OPEN_CREATE | sqlite3_sys::SQLITE_OPEN_READWRITE | sqlite3_sys::SQLITE_OPEN_URI,
+    )
+}
+
+/// Encodes the characters other than the unreserved characters of URI.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
 /// Return the version number of SQLite.
 ///
 /// For instance, the version `3.32.2` corresponds to the integer `3032002`.
+///
+/// The SQLite is the bundled source with the `sqlite-bundled` feature, and the system `libsqlite3`
+/// with the `sqlite-system` feature, which falls back to the bundled source if pkg-config does not find it.
+/// `sqlite-bundled` wins if both features are enabled.
 #[inline]
 pub fn version() -> usize {
     unsafe { sqlite3_sys::sqlite3_libversion_number() as usize }
 }
 
+/// Return the version of SQLite as a string, such as `"3.32.2"`.
+///
+/// # Examples
+///
+/// ```
+/// let version = owsql::sqlite::version_str();
+/// assert!(version.starts_with("3."));
+/// ```
+pub fn version_str() -> &'static str {
+    let version = unsafe { CStr::from_ptr(sqlite3_sys::sqlite3_libversion()) };
+    version.to_str().unwrap_or_default()
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -67,6 +127,21 @@ mod tests {
         crate::sqlite::open_readonly(path).unwrap();
     }
 
+    #[test]
+    fn open_shared() {
+        let conn = crate::sqlite::open_shared("owsql open_shared?&#").unwrap();
+        conn.execute(conn.ow("CREATE TABLE users(id INTEGER, name TEXT); INSERT INTO users VALUES(42, 'Alice');")).unwrap();
+        let other = crate::sqlite::open_shared("owsql open_shared?&#").unwrap();
+        assert_eq!(other.count(other.ow("SELECT * FROM users;")), Ok(1));
+        let another = crate::sqlite::open_shared("owsql open_shared").unwrap();
+        assert!(another.count(another.ow("SELECT * FROM users;")).is_err());
+        conn.close().unwrap();
+        other.close().unwrap();
+        let conn = crate::sqlite::open_shared("owsql open_shared?&#").unwrap();
+        assert!(conn.count(conn.ow("SELECT * FROM users;")).is_err());
+        assert_eq!(super::percent_encode("a b/c%"), "a%20b%2Fc%25");
+    }
+
     #[test]
     fn should_readonly() {
         let dir = Directory::new("sqlite").unwrap();
@@ -79,7 +154,12 @@ mod tests {
         conn.error_level = OwsqlErrorLevel::Debug;
         assert_eq!(
             conn.execute(conn.ow("INSERT INTO users VALUES(42, 'Alice');")),
-            Err(OwsqlError::Message("exec error: attempt to write a readonly database".to_string()))
+            Err(OwsqlError::Database {
+                code:       Some(8),  // SQLITE_READONLY
+                sqlstate:   None,
+                message:    "exec error: attempt to write a readonly database".to_string(),
+                constraint: None,
+            })
         );
     }
 
@@ -92,6 +172,8 @@ mod tests {
 
     #[test]
     fn version() {
-        crate::sqlite::version();
+        let version = crate::sqlite::version();
+        let (major, minor, patch) = (version / 1_000_000, version / 1_000 % 1_000, version % 1_000);
+        assert_eq!(crate::sqlite::version_str(), format!("{}.{}.{}", major, minor, patch));
     }
 }
@@ -0,0 +1,231 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+
+/// The journal mode of a database.
+/// See [journal_mode](https://www.sqlite.org/pragma.html#pragma_journal_mode).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    Wal,
+    Off,
+}
+
+impl JournalMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            JournalMode::Delete   => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+            JournalMode::Persist  => "PERSIST",
+            JournalMode::Memory   => "MEMORY",
+            JournalMode::Wal      => "WAL",
+            JournalMode::Off      => "OFF",
+        }
+    }
+}
+
+impl FromStr for JournalMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        [JournalMode::Delete, JournalMode::Truncate, JournalMode::Persist,
+         JournalMode::Memory, JournalMode::Wal, JournalMode::Off]
+            .iter()
+            .find(|mode| mode.as_sql().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// How often SQLite syncs to the disk.
+/// See [synchronous](https://www.sqlite.org/pragma.html#pragma_synchronous).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+impl Synchronous {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Synchronous::Off    => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full   => "FULL",
+            Synchronous::Extra  => "EXTRA",
+        }
+    }
+}
+
+impl FromStr for Synchronous {
+    type Err = ();
+
+    /// Parses the number returned by the pragma.
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s {
+            "0" => Ok(Synchronous::Off),
+            "1" => Ok(Synchronous::Normal),
+            "2" => Ok(Synchronous::Full),
+            "3" => Ok(Synchronous::Extra),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Typed access to the pragmas of a SQLite connection.
+/// See [pragma](../struct.Connection.html#method.pragma).
+///
+/// The statements are executed in the same way as [rows](../struct.Connection.html#method.rows).
+pub struct Pragma<'a> {
+    conn: &'a Connection,
+}
+
+impl Pragma<'_> {
+    /// Return the journal mode of the main database.
+    pub fn journal_mode(&self) -> Result<JournalMode> {
+        self.get("journal_mode")
+    }
+
+    /// Sets the journal mode and return the new mode.
+    /// The mode may be unchanged, for example, an in-memory database cannot be `Wal`.
+    pub fn set_journal_mode(&self, mode: JournalMode) -> Result<JournalMode> {
+        self.set("journal_mode", self.conn.ow(mode.as_sql()))
+    }
+
+    /// Return the synchronous flag.
+    pub fn synchronous(&self) -> Result<Synchronous> {
+        self.get("synchronous")
+    }
+
+    /// Sets the synchronous flag.
+    pub fn set_synchronous(&self, synchronous: Synchronous) -> Result<()> {
+        self.execute("synchronous", self.conn.ow(synchronous.as_sql()))
+    }
+
+    /// Checks if foreign key constraints are enforced.
+    pub fn foreign_keys(&self) -> Result<bool> {
+        self.get::<u8>("foreign_keys").map(|foreign_keys| foreign_keys != 0)
+    }
+
+    /// Enables or disables foreign key constraints.
+    /// It has no effect in a transaction.
+    pub fn set_foreign_keys(&self, enabled: bool) -> Result<()> {
+        self.execute("foreign_keys", self.conn.ow(if enabled { "ON" } else { "OFF" }))
+    }
+
+    /// Return the suggested number of cache pages, or the cache size in KiB if it is negative.
+    pub fn cache_size(&self) -> Result<i64> {
+        self.get("cache_size")
+    }
+
+    /// Sets the suggested number of cache pages, or the cache size in KiB if it is negative.
+    pub fn set_cache_size(&self, cache_size: i64) -> Result<()> {
+        self.execute("cache_size", self.conn.int(cache_size))
+    }
+
+    /// Return the maximum number of bytes of memory-mapped I/O.
+    pub fn mmap_size(&self) -> Result<i64> {
+        self.get("mmap_size")
+    }
+
+    /// Sets the maximum number of bytes of memory-mapped I/O and return the new size,
+    /// which is limited by the compile-time maximum.
+    pub fn set_mmap_size(&self, mmap_size: i64) -> Result<i64> {
+        self.set("mmap_size", self.conn.int(mmap_size))
+    }
+
+    fn get<T: FromStr>(&self, name: &'static str) -> Result<T> {
+        self.query(name, self.conn.ow("PRAGMA ") + &self.conn.ow(name) + &self.conn.ow(";"))
+    }
+
+    fn set<T: FromStr>(&self, name: &'static str, value: String) -> Result<T> {
+        self.query(name, self.conn.ow("PRAGMA ") + &self.conn.ow(name) + &self.conn.ow(" = ") + &value + &self.conn.ow(";"))
+    }
+
+    fn execute(&self, name: &'static str, value: String) -> Result<()> {
+        self.check_dialect()?;
+        self.conn.execute(self.conn.ow("PRAGMA ") + &self.conn.ow(name) + &self.conn.ow(" = ") + &value + &self.conn.ow(";"))
+    }
+
+    fn query<T: FromStr>(&self, name: &str, stmt: String) -> Result<T> {
+        self.check_dialect()?;
+        let rows = self.conn.rows(stmt)?;
+        let value = rows.first().and_then(|row| row.get(name)).unwrap_or_default();
+        match value.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                OwsqlError::new(&self.conn.error_level, "invalid pragma", &format!("{} = {}", name, value))?;
+                Err(OwsqlError::AnyError)
+            },
+        }
+    }
+
+    fn check_dialect(&self) -> Result<()> {
+        match self.conn.dialect() {
+            Dialect::Sqlite => Ok(()),
+            _ => Err(OwsqlError::Message("unsupported pragma".into())),
+        }
+    }
+}
+
+impl fmt::Debug for Pragma<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pragma").finish()
+    }
+}
+
+impl Connection {
+    /// Return the typed accessor of the pragmas.
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::JournalMode;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.pragma().set_foreign_keys(true).unwrap();
+    /// assert!(conn.pragma().foreign_keys().unwrap());
+    /// assert_eq!(conn.pragma().journal_mode(), Ok(JournalMode::Memory));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn pragma(&self) -> Pragma<'_> {
+        Pragma { conn: self }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporary::Directory;
+
+    #[test]
+    fn pragma() {
+        let dir = Directory::new("sqlite").unwrap();
+        let conn = crate::sqlite::open(dir.path().join("test.db")).unwrap();
+        let pragma = conn.pragma();
+        assert_eq!(pragma.journal_mode(), Ok(JournalMode::Delete));
+        assert_eq!(pragma.set_journal_mode(JournalMode::Wal), Ok(JournalMode::Wal));
+        assert_eq!(pragma.journal_mode(), Ok(JournalMode::Wal));
+        pragma.set_synchronous(Synchronous::Normal).unwrap();
+        assert_eq!(pragma.synchronous(), Ok(Synchronous::Normal));
+        assert_eq!(pragma.foreign_keys(), Ok(false));
+        pragma.set_foreign_keys(true).unwrap();
+        assert_eq!(pragma.foreign_keys(), Ok(true));
+        pragma.set_cache_size(-4096).unwrap();
+        assert_eq!(pragma.cache_size(), Ok(-4096));
+        assert!(pragma.set_mmap_size(0).is_ok());
+        assert_eq!(pragma.mmap_size(), Ok(0));
+
+        let conn = crate::sqlite::open(":memory:").unwrap();
+        assert_eq!(conn.pragma().set_journal_mode(JournalMode::Wal), Ok(JournalMode::Memory));
+    }
+}
@@ -0,0 +1,158 @@
+extern crate sqlite3_sys as ffi;
+
+use std::ffi::{CStr, CString, c_void};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use super::connection::{errmsg, handle, remove_hook, set_hook};
+
+/// The number of frames of the default automatic checkpoint.
+const AUTOCHECKPOINT_FRAMES: c_int = 1000;
+
+/// How a checkpoint waits for the readers and writers.
+/// See [sqlite3_wal_checkpoint_v2](https://www.sqlite.org/c3ref/wal_checkpoint_v2.html).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CheckpointMode {
+    /// Checkpoint as many frames as possible without waiting.
+    #[default]
+    Passive,
+    /// Wait for the writers, then checkpoint all frames.
+    Full,
+    /// Same as `Full`, and wait for the readers so that the next writer restarts the log.
+    Restart,
+    /// Same as `Restart`, and truncate the log file.
+    Truncate,
+}
+
+/// The result of a checkpoint.
+/// See [wal_checkpoint](../struct.Connection.html#method.wal_checkpoint).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    /// The number of frames in the log file.
+    pub log_frames:          u32,
+    /// The number of frames checkpointed into the database.
+    pub checkpointed_frames: u32,
+}
+
+impl Connection {
+    /// Checkpoints the write-ahead log of the main database into the database file.  
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::{CheckpointMode, JournalMode};
+    /// # let dir = temporary::Directory::new("wal").unwrap();
+    /// let conn = owsql::sqlite::open(dir.path().join("test.db")).unwrap();
+    /// conn.pragma().set_journal_mode(JournalMode::Wal).unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// let checkpoint = conn.wal_checkpoint(CheckpointMode::Truncate).unwrap();
+    /// assert_eq!(checkpoint.log_frames, 0);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn wal_checkpoint(&self, mode: CheckpointMode) -> Result<Checkpoint> {
+        let conn = handle(self, "wal")?;
+        let main = CString::new("main").unwrap();
+        let mode = match mode {
+            CheckpointMode::Passive  => ffi::SQLITE_CHECKPOINT_PASSIVE,
+            CheckpointMode::Full     => ffi::SQLITE_CHECKPOINT_FULL,
+            CheckpointMode::Restart  => ffi::SQLITE_CHECKPOINT_RESTART,
+            CheckpointMode::Truncate => ffi::SQLITE_CHECKPOINT_TRUNCATE,
+        };
+        let (mut log_frames, mut checkpointed_frames) = (0, 0);
+        let result = unsafe {
+            ffi::sqlite3_wal_checkpoint_v2(conn, main.as_ptr(), mode, &mut log_frames, &mut checkpointed_frames)
+        };
+        if result != ffi::SQLITE_OK {
+            return Err(OwsqlError::Message(format!("failed to checkpoint: {}", errmsg(conn))));
+        }
+        // -1 if the database is not in WAL mode.
+        Ok(Checkpoint {
+            log_frames:          log_frames.max(0) as u32,
+            checkpointed_frames: checkpointed_frames.max(0) as u32,
+        })
+    }
+
+    /// Sets the callback invoked after each commit in WAL mode,
+    /// with the name of the database and the number of frames in the log file.  
+    /// The hook replaces the automatic checkpoint, so checkpoint by
+    /// [wal_checkpoint](#method.wal_checkpoint) in the hook or elsewhere.
+    /// Only for SQLite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::sqlite::{CheckpointMode, JournalMode};
+    /// # let dir = temporary::Directory::new("wal").unwrap();
+    /// let conn = owsql::sqlite::open(dir.path().join("test.db")).unwrap();
+    /// conn.pragma().set_journal_mode(JournalMode::Wal).unwrap();
+    /// conn.set_wal_hook(|db, frames| println!("{}: {} frames", db, frames)).unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// conn.wal_checkpoint(CheckpointMode::Passive).unwrap();
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn set_wal_hook<F: FnMut(&str, u32) + 'static>(&self, hook: F) -> Result<()> {
+        let conn = handle(self, "wal")?;
+        set_hook(self, "wal", hook, |hook| unsafe {
+            ffi::sqlite3_wal_hook(conn, Some(call_wal_hook::<F>), hook);
+        });
+        Ok(())
+    }
+
+    /// Removes the callback set by [set_wal_hook](#method.set_wal_hook),
+    /// and restores the automatic checkpoint of 1000 frames.  
+    /// Only for SQLite.
+    #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+    pub fn remove_wal_hook(&self) -> Result<()> {
+        let conn = handle(self, "wal")?;
+        remove_hook(self, "wal", || unsafe {
+            ffi::sqlite3_wal_hook(conn, None, ptr::null_mut());
+            ffi::sqlite3_wal_autocheckpoint(conn, AUTOCHECKPOINT_FRAMES);
+        });
+        Ok(())
+    }
+}
+
+extern "C" fn call_wal_hook<F: FnMut(&str, u32)>(hook: *mut c_void, _conn: *mut ffi::sqlite3, db: *const c_char, frames: c_int) -> c_int {
+    let hook = unsafe { &mut *(hook as *mut F) };
+    let db = unsafe { CStr::from_ptr(db) }.to_string_lossy();
+    // A panic must not unwind into SQLite.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(&db, frames as u32)));
+    ffi::SQLITE_OK
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use temporary::Directory;
+    use crate::sqlite::{CheckpointMode, JournalMode};
+
+    #[test]
+    fn wal_hook() {
+        let dir = Directory::new("sqlite").unwrap();
+        let conn = crate::sqlite::open(dir.path().join("test.db")).unwrap();
+        conn.pragma().set_journal_mode(JournalMode::Wal).unwrap();
+        let commits = Rc::new(RefCell::new(Vec::new()));
+        let hook = Rc::clone(&commits);
+        conn.set_wal_hook(move |db, frames| hook.borrow_mut().push((db.to_string(), frames))).unwrap();
+        conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+        conn.execute(conn.ow("INSERT INTO users VALUES('Alice');")).unwrap();
+        assert_eq!(commits.borrow().len(), 2);
+        assert_eq!(commits.borrow()[0].0, "main");
+        assert!(commits.borrow()[1].1 > commits.borrow()[0].1);
+
+        let checkpoint = conn.wal_checkpoint(CheckpointMode::Full).unwrap();
+        assert_eq!(checkpoint.log_frames, checkpoint.checkpointed_frames);
+        assert_eq!(Rc::strong_count(&commits), 2);
+        conn.remove_wal_hook().unwrap();
+        assert_eq!(Rc::strong_count(&commits), 1);
+        conn.execute(conn.ow("INSERT INTO users VALUES('Bob');")).unwrap();
+        assert_eq!(commits.borrow().len(), 2);
+    }
+}
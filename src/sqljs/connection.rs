@@ -0,0 +1,182 @@
+use std::any::Any;
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::Result;
+use crate::connection::{Connection, IterateCallback, OwsqlConn};
+use crate::error::{OwsqlError, OwsqlErrorLevel};
+use crate::dialect::Dialect;
+use crate::escape::{Charset, EscapeMode};
+
+#[wasm_bindgen]
+extern "C" {
+    /// `Database` of sql.js.
+    pub(crate) type Database;
+
+    #[wasm_bindgen(method, catch)]
+    fn exec(this: &Database, sql: &str) -> std::result::Result<Array, JsValue>;
+
+    #[wasm_bindgen(method, js_name = getRowsModified)]
+    fn get_rows_modified(this: &Database) -> f64;
+
+    #[wasm_bindgen(method, catch, js_name = iterateStatements)]
+    fn iterate_statements(this: &Database, sql: &str) -> std::result::Result<js_sys::Iterator, JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    fn close(this: &Database) -> std::result::Result<(), JsValue>;
+
+    /// `Statement` of sql.js.
+    type Statement;
+
+    #[wasm_bindgen(method)]
+    fn free(this: &Statement) -> bool;
+}
+
+pub(crate) fn from_database(db: JsValue) -> Result<Connection> {
+    let is_database = js_sys::Reflect::get(&db, &JsValue::from_str("exec"))
+        .map(|exec| exec.is_function())
+        .unwrap_or(false);
+    if !is_database {
+        return Err(OwsqlError::Message("invalid database".into()));
+    }
+    Ok(Connection::new(Box::new(db.unchecked_into::<Database>())))
+}
+
+/// Return the message of the exception thrown by sql.js.
+fn errmsg(e: &JsValue) -> String {
+    match e.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => e.as_string().unwrap_or_else(|| format!("{:?}", e)),
+    }
+}
+
+/// Converts a value of sql.js into the text of SQLite.
+fn text(value: &JsValue) -> Option<String> {
+    if value.is_null() || value.is_undefined() {
+        None
+    } else if let Some(string) = value.as_string() {
+        Some(string)
+    } else if let Some(number) = value.as_f64() {
+        Some(number.to_string())
+    } else if let Some(blob) = value.dyn_ref::<Uint8Array>() {
+        Some(String::from_utf8_lossy(&blob.to_vec()).into_owned())
+    } else {
+        // A bigint of `useBigInt`.
+        value.dyn_ref::<js_sys::BigInt>()
+            .and_then(|bigint| bigint.to_string(10).ok())
+            .map(String::from)
+    }
+}
+
+impl OwsqlConn for Database {
+    fn _execute(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        match self.exec(&query) {
+            Ok(_) => Ok(()),
+            Err(e) => OwsqlError::database(error_level, None, None, None, &errmsg(&e)),
+        }
+    }
+
+    fn close(&self, error_level: &OwsqlErrorLevel) -> Result<()> {
+        // sql.js rolls back the open transaction when the database is closed.
+        match Database::close(self) {
+            Ok(()) => Ok(()),
+            Err(e) => OwsqlError::database(error_level, None, None, None, &errmsg(&e)),
+        }
+    }
+
+    fn _execute_changes(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<u64> {
+        self._execute(query, error_level)?;
+        Ok(self.changes().unwrap_or_default())
+    }
+
+    fn _iterate<'a>(&self, query: Result<String>, error_level: &OwsqlErrorLevel,
+        callback: IterateCallback) -> Result<()>
+    {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+        let results = match self.exec(&query) {
+            Ok(results) => results,
+            Err(e) => return OwsqlError::database(error_level, None, None, None, &errmsg(&e)),
+        };
+
+        // `exec` returns `[{ columns, values }]`, a result for each statement which returned rows.
+        for result in results.iter() {
+            let get = |key: &str| js_sys::Reflect::get(&result, &JsValue::from_str(key))
+                .map(|value| Array::from(&value))
+                .unwrap_or_default();
+            let columns = get("columns").iter().map(|column| column.as_string().unwrap_or_default()).collect::<Vec<_>>();
+            for values in get("values").iter() {
+                let values = Array::from(&values).iter().map(|value| text(&value)).collect::<Vec<_>>();
+                let pairs = columns.iter()
+                    .zip(&values)
+                    .map(|(column, value)| (column.as_str(), value.as_deref()))
+                    .collect::<Vec<_>>();
+                if !callback(&pairs) {
+                    // The same error as the callback of `sqlite3_exec` aborting the query.
+                    return OwsqlError::database(error_level, None, None, None, "query aborted");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn _validate(&self, query: Result<String>, error_level: &OwsqlErrorLevel) -> Result<()> {
+        let query = match query {
+            Ok(query) => query,
+            Err(e) => if *error_level == OwsqlErrorLevel::AlwaysOk {
+                return Ok(());
+            } else {
+                return Err(e);
+            },
+        };
+
+        // Prepare each statement without executing it.
+        let statements = match self.iterate_statements(&query) {
+            Ok(statements) => statements,
+            Err(e) => return OwsqlError::database(error_level, None, None, None, &errmsg(&e)),
+        };
+        loop {
+            match statements.next() {
+                Ok(next) if next.done() => return Ok(()),
+                Ok(next) => { next.value().unchecked_into::<Statement>().free(); },
+                Err(e) => return OwsqlError::database(error_level, None, None, None, &errmsg(&e)),
+            }
+        }
+    }
+
+    fn escape_mode(&self) -> EscapeMode {
+        EscapeMode::Standard
+    }
+
+    fn charset(&self) -> Charset {
+        Charset::Utf8
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn changes(&self) -> Option<u64> {
+        Some(self.get_rows_modified() as u64)
+    }
+}
@@ -0,0 +1,45 @@
+//! Interface to [sql.js](https://sql.js.org), SQLite compiled to WebAssembly, of OverwriteSQL.
+//!
+//! The backend runs on `wasm32-unknown-unknown`, such as in a browser or an edge runtime, where the
+//! [sqlite](../sqlite/index.html) backend cannot link the native library. The statements are written
+//! in the SQLite dialect, and the overwrite strings and the escaping work as they do on SQLite.
+//!
+//! sql.js is initialized asynchronously by `initSqlJs` in JavaScript, so the connection is made from
+//! the `Database` it created, which is passed to Rust as a `JsValue`:
+//!
+//! ```js
+//! const SQL = await initSqlJs();
+//! wasm.run(new SQL.Database());
+//! ```
+//!
+//! ```no_run
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[wasm_bindgen]
+//! pub fn run(db: JsValue) -> Result<(), JsValue> {
+//!     let conn = owsql::sqljs::from_database(db).map_err(|e| e.to_string())?;
+//!     conn.execute(conn.ow("CREATE TABLE users (name TEXT); INSERT INTO users VALUES ('Alice');"))
+//!         .map_err(|e| e.to_string())?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The features of the native library, such as the hooks, the user functions, the blobs and the
+//! backups, are not supported. The elapsed time of the statements is measured by `Date.now()` on
+//! `wasm32-unknown-unknown`, which has no `std::time::Instant`. `std::thread::sleep` panics there, so
+//! [Watcher::next_event](../struct.Watcher.html#method.next_event) must be called with a zero timeout.
+
+use wasm_bindgen::JsValue;
+use crate::Result;
+use crate::connection::Connection;
+
+pub(crate) mod connection;
+
+/// Open a connection to the `Database` of sql.js, such as `new SQL.Database()`.
+///
+/// # Errors
+///
+/// A value which is not a `Database` returns the error "invalid database".
+pub fn from_database(db: JsValue) -> Result<Connection> {
+    connection::from_database(db)
+}
@@ -0,0 +1,75 @@
+use crate::Result;
+use crate::connection::Connection;
+use crate::error::OwsqlError;
+use crate::lexer::placeholders;
+
+/// A statement with `?` placeholders, which are replaced by values.
+/// See [prepare](./struct.Connection.html#method.prepare).
+#[derive(Debug)]
+pub struct Statement<'a> {
+    conn:      &'a Connection,
+    fragments: Vec<String>,
+}
+
+impl Statement<'_> {
+    /// Return the number of placeholders.
+    #[inline]
+    pub fn param_count(&self) -> usize {
+        self.fragments.len() - 1
+    }
+
+    /// Replaces the placeholders with the values in order.
+    /// The values are escaped in the same way as strings concatenated to [ow](./struct.Connection.html#method.ow).  
+    /// If the number of the values is different from the placeholders,
+    /// the error "parameter count mismatch" is returned when the statement is executed.
+    pub fn bind(&self, params: &[&(dyn ToString + Sync)]) -> String {
+        if params.len() != self.param_count() {
+            return self.conn.ow_error("parameter count mismatch",
+                &format!("expected {}, got {}", self.param_count(), params.len()));
+        }
+        let mut stmt = self.fragments[0].clone();
+        for (param, fragment) in params.iter().zip(&self.fragments[1..]) {
+            stmt.push_str(&param.to_string());
+            stmt.push_str(fragment);
+        }
+        stmt
+    }
+}
+
+impl Connection {
+    /// Prepares a trusted statement whose values are given by `?` placeholders.
+    /// A `?` in a quoted string or a quoted identifier is not a placeholder.  
+    /// It is an error if the number of the placeholders is different from `params`,
+    /// so a mistake in the statement is detected before it is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::params;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// let insert = conn.prepare("INSERT INTO users VALUES(?, ?);", 2).unwrap();
+    /// conn.execute(insert.bind(params!["Alice", 42])).unwrap();
+    /// conn.execute(insert.bind(params!["Bob' OR 'a'='a", 69])).unwrap();
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(2));
+    ///
+    /// assert!(conn.prepare("SELECT * FROM users WHERE name = ? AND age = ?", 1).is_err());
+    /// ```
+    pub fn prepare(&self, stmt: &'static str, params: usize) -> Result<Statement<'_>> {
+        let positions = placeholders(stmt, self.dialect());
+        if positions.len() != params {
+            OwsqlError::new(&self.error_level, "placeholder count mismatch",
+                &format!("declared {}, found {} at {:?}", params, positions.len(), positions))?;
+        }
+        let mut fragments = Vec::with_capacity(positions.len() + 1);
+        let mut start = 0;
+        for end in positions.into_iter().chain(std::iter::once(stmt.len())) {
+            fragments.push(match &stmt[start..end] {
+                ""       => String::new(),
+                fragment => self.ow(fragment),
+            });
+            start = end + 1;
+        }
+        Ok(Statement { conn: self, fragments })
+    }
+}
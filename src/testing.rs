@@ -0,0 +1,115 @@
+//! Helpers to test that statements built by an application are safe against SQL injection.
+//!
+//! [assert_injection_safe](fn.assert_injection_safe.html) builds a statement with each of the
+//! [payloads](fn.payloads.html), and checks that the payload never changes the statement other
+//! than the contents of the escaped literals.
+
+use crate::connection::Connection;
+use crate::token::Token;
+
+/// The value the statements built with the payloads are compared with, valid as both a string and an integer.
+const HARMLESS: &str = "42";
+
+/// Known injection payloads.
+const PAYLOADS: &[&str] = &[
+    "' OR '1'='1",
+    "' OR 1=1; --",
+    "'; DROP TABLE users; --",
+    "' UNION SELECT name, password FROM users --",
+    "admin'--",
+    "admin' #",
+    "') OR ('a'='a",
+    "1 OR 1=1",
+    "1; DELETE FROM users",
+    "\" OR \"\"=\"",
+    "` OR 1=1 --",
+    "\\' OR 1=1; --",
+    "\\\\' OR 1=1; --",
+    "\\",
+    "'",
+    "''",
+    "*/ OR 1=1 /*",
+    "/* ' */ OR 1=1",
+    "-- ' OR 1=1",
+    "$$; DROP TABLE users; $$",
+    "E'\\'' OR 1=1 --",
+    "\u{7e3a}\\' OR 1=1 --",
+    "\u{bf}' OR 1=1 --",
+    "\u{2019} OR 1=1 --",
+    "%27 OR 1=1 --",
+    "' OR 1=1\0",
+    "'\n OR 1=1 --",
+    "' AND SLEEP(5) --",
+    "'; SELECT pg_sleep(5); --",
+    "' || (SELECT password FROM users LIMIT 1) || '",
+    "?",
+    "$1",
+    "OWSQLC0N00000000000000000000000000000000",
+];
+
+/// Return the known injection payloads, each also prefixed by a plausible value and followed by
+/// a line comment, in a fixed order.
+///
+/// # Examples
+///
+/// ```
+/// assert!(owsql::testing::payloads().any(|payload| payload.contains("DROP TABLE")));
+/// ```
+pub fn payloads() -> impl Iterator<Item = String> {
+    PAYLOADS.iter().flat_map(|payload| vec![
+        payload.to_string(),
+        format!("alice{}", payload),
+        format!("{} --", payload),
+    ])
+}
+
+/// Builds a statement with a harmless value and each of the [payloads](fn.payloads.html), and panics
+/// if a payload changes the statement other than the contents of the escaped literals.
+/// A statement the connection rejects is safe.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::testing::assert_injection_safe;
+/// # let conn = owsql::sqlite::open(":memory:").unwrap();
+/// assert_injection_safe(&conn, |name| conn.ow("SELECT * FROM users WHERE name =") + name);
+/// ```
+///
+/// ```should_panic
+/// use owsql::testing::assert_injection_safe;
+/// # let conn = owsql::sqlite::open(":memory:").unwrap();
+/// assert_injection_safe(&conn, |limit| conn.ow_runtime(format!("SELECT * FROM users LIMIT {}", limit)).unwrap_or_default());
+/// ```
+pub fn assert_injection_safe<F: Fn(&str) -> String>(conn: &Connection, build: F) {
+    if let Err((payload, sql)) = check_injection_safe(conn, build) {
+        panic!("the payload {:?} changed the statement: {}", payload, sql);
+    }
+}
+
+/// Same as [assert_injection_safe](fn.assert_injection_safe.html), but return the first unsafe payload
+/// and the actual SQL built with it instead of panicking.
+pub fn check_injection_safe<F: Fn(&str) -> String>(conn: &Connection, build: F) -> Result<(), (String, String)> {
+    let expected = match conn.tokenize(build(HARMLESS)) {
+        Ok(tokens) => shape(tokens),
+        Err(e) => return Err((HARMLESS.to_string(), e.to_string())),
+    };
+    for payload in payloads() {
+        let stmt = build(&payload);
+        if let Ok(tokens) = conn.tokenize(&stmt) {
+            if shape(tokens) != expected {
+                return Err((payload, conn.actual_sql(&stmt).unwrap_or_default()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Return the tokens with the contents of the escaped literals removed.
+fn shape(tokens: Vec<Token>) -> Vec<Token> {
+    tokens.into_iter()
+        .map(|token| match token {
+            Token::Literal(_) => Token::Literal(String::new()),
+            token => token,
+        })
+        .collect()
+}
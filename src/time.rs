@@ -0,0 +1,47 @@
+//! `Instant` which works on `wasm32-unknown-unknown`, where `std::time::Instant` panics.
+
+#[cfg(not(all(target_arch = "wasm32", feature = "sqljs")))]
+pub(crate) use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "sqljs"))]
+pub(crate) use self::wasm::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "sqljs"))]
+mod wasm {
+    use std::ops::{Add, Sub};
+    use std::time::Duration;
+
+    /// The milliseconds since the epoch by `Date.now()` of JavaScript.
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    pub(crate) struct Instant(f64);
+
+    impl Instant {
+        pub(crate) fn now() -> Instant {
+            Instant(js_sys::Date::now())
+        }
+
+        pub(crate) fn elapsed(&self) -> Duration {
+            Instant::now().saturating_duration_since(*self)
+        }
+
+        pub(crate) fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+            Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Instant;
+
+        fn add(self, duration: Duration) -> Instant {
+            Instant(self.0 + duration.as_secs_f64() * 1000.0)
+        }
+    }
+
+    impl Sub for Instant {
+        type Output = Duration;
+
+        fn sub(self, earlier: Instant) -> Duration {
+            self.saturating_duration_since(earlier)
+        }
+    }
+}
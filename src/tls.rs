@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+/// Whether a connection uses TLS.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TlsMode {
+    /// Do not use TLS.
+    Disable,
+    /// Attempt to connect with TLS, and connect without TLS if it fails.
+    #[default]
+    Prefer,
+    /// Require the use of TLS.
+    Require,
+}
+
+/// TLS configuration of PostgreSQL and MySQL connections.
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{TlsConfig, TlsMode};
+/// let tls = TlsConfig {
+///     mode:      TlsMode::Require,
+///     root_cert: Some("/path/to/ca.der".into()),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TlsConfig {
+    /// Whether the connection uses TLS.
+    pub mode:                 TlsMode,
+    /// The root certificate to trust in addition to the system ones.
+    /// PEM or DER for PostgreSQL, and DER for MySQL.
+    pub root_cert:            Option<PathBuf>,
+    /// Accept invalid certificates and hostnames.
+    /// &#x26a0;&#xfe0f; **Do not use in production**
+    pub accept_invalid_certs: bool,
+}
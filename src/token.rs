@@ -1,51 +1,54 @@
-
-#[derive(Debug, PartialEq)]
-pub enum TokenType {
-    None,
-    String(String),
-    Overwrite(String),
-    ErrOverwrite(String),
+use std::fmt;
+
+/// A token of the statement that will be sent to the database.
+/// See [tokenize](./struct.Connection.html#method.tokenize).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Token {
+    /// A SQL keyword in a trusted fragment, such as `SELECT`.
+    Keyword(String),
+    /// An identifier in a trusted fragment, such as a table name. It may be double-quoted or backtick-quoted.
+    Identifier(String),
+    /// A number in a trusted fragment, including the values of `int()`.
+    Number(String),
+    /// A single-quoted string in a trusted fragment, including the quotations.
+    QuotedString(String),
+    /// An operator or a punctuation in a trusted fragment, such as `=` or `,`.
+    Symbol(String),
+    /// An untrusted value, escaped and quoted as it will be sent.
+    Literal(String),
 }
 
-impl TokenType {
-    pub fn unwrap(self) -> String {
+impl Token {
+    /// Return the text of the token as it will be sent.
+    pub fn as_str(&self) -> &str {
         match self {
-            TokenType::None => String::new(),
-            TokenType::String(s) => s,
-            TokenType::Overwrite(s) => s,
-            TokenType::ErrOverwrite(s) => s,
+            Token::Keyword(s) |
+            Token::Identifier(s) |
+            Token::Number(s) |
+            Token::QuotedString(s) |
+            Token::Symbol(s) |
+            Token::Literal(s) => s,
         }
     }
 
-    pub fn is_none(&self) -> bool {
-        self == &TokenType::None
+    /// Checks if the token comes from an untrusted value.
+    #[inline]
+    pub fn is_literal(&self) -> bool {
+        matches!(self, Token::Literal(_))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn unwrap() {
-        assert_eq!(TokenType::None.unwrap(),                              "");
-        assert_eq!(TokenType::String(String::from("s1")).unwrap(),       "s1");
-        assert_eq!(TokenType::Overwrite(String::from("s2")).unwrap(),    "s2");
-        assert_eq!(TokenType::ErrOverwrite(String::from("s3")).unwrap(), "s3");
-    }
-
-    #[test]
-    fn is_none() {
-        let tests = [
-            (TokenType::None, true),
-            (TokenType::String(String::from("s1")), false),
-            (TokenType::Overwrite(String::from("s2")), false),
-            (TokenType::ErrOverwrite(String::from("s3")), false),
-        ];
-
-        for (token, expect) in tests.iter() {
-            assert_eq!(token.is_none(), *expect);
-        }
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
+/// A token of a statement, borrowed from the input.
+#[derive(Debug, PartialEq)]
+pub enum TokenType<'a> {
+    /// Words which are not overwrite definitions, and the whitespaces between them.
+    String(&'a str),
+    Overwrite(&'a str),
+    ErrOverwrite(&'a str),
+}
@@ -0,0 +1,186 @@
+use std::ops::Deref;
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+
+/// A connection in a transaction.
+/// See [with_transaction](./struct.Connection.html#method.with_transaction).
+///
+/// It dereferences to the [Connection](./struct.Connection.html),
+/// so statements are built and executed in the same way.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+/// The isolation level of a transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted  => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable   => "SERIALIZABLE",
+        }
+    }
+}
+
+/// When SQLite acquires the locks of a transaction.
+/// See [BEGIN TRANSACTION](https://www.sqlite.org/lang_transaction.html).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TransactionBehavior {
+    /// Acquire the locks on the first read or write.
+    #[default]
+    Deferred,
+    /// Acquire the write lock at the beginning.
+    Immediate,
+    /// Acquire the exclusive lock at the beginning.
+    Exclusive,
+}
+
+/// Options of a transaction.
+/// See [with_transaction_options](./struct.Connection.html#method.with_transaction_options).
+///
+/// Option      | SQLite                | Postgres                    | MySQL, SQL Server
+/// ----------- | --------------------- | --------------------------- | -----------------
+/// `isolation` | Always serializable   | `BEGIN ISOLATION LEVEL ...` | `SET TRANSACTION ISOLATION LEVEL ...`
+/// `read_only` | `PRAGMA query_only`   | `BEGIN READ ONLY`           | `START TRANSACTION READ ONLY` on MySQL
+/// `behavior`  | `BEGIN DEFERRED` etc. | Not used                    | Not used
+///
+/// A read-only transaction returns the error "unsupported read-only transaction" on SQL Server.
+/// Oracle, which begins a transaction implicitly, returns the error "unsupported transaction".
+///
+/// # Examples
+///
+/// ```
+/// use owsql::{IsolationLevel, TransactionOptions};
+/// let options = TransactionOptions {
+///     isolation: Some(IsolationLevel::Serializable),
+///     read_only: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransactionOptions {
+    /// The isolation level. `None` is the default of the backend.
+    pub isolation: Option<IsolationLevel>,
+    /// Whether the transaction rejects writes.
+    pub read_only: bool,
+    /// When SQLite acquires the locks.
+    pub behavior:  TransactionBehavior,
+}
+
+impl TransactionOptions {
+    /// Return the statements to begin the transaction, or an error if the backend does not support it.
+    pub(crate) fn begin(&self, dialect: Dialect) -> Result<Vec<String>> {
+        let mut statements = Vec::new();
+        match dialect {
+            Dialect::Sqlite => {
+                if self.read_only {
+                    statements.push("PRAGMA query_only = ON".to_string());
+                }
+                statements.push(match self.behavior {
+                    TransactionBehavior::Deferred  => "BEGIN DEFERRED",
+                    TransactionBehavior::Immediate => "BEGIN IMMEDIATE",
+                    TransactionBehavior::Exclusive => "BEGIN EXCLUSIVE",
+                }.to_string());
+            },
+            Dialect::Postgres => {
+                let mut begin = "BEGIN".to_string();
+                if let Some(isolation) = self.isolation {
+                    begin.push_str(" ISOLATION LEVEL ");
+                    begin.push_str(isolation.as_sql());
+                }
+                if self.read_only {
+                    begin.push_str(" READ ONLY");
+                }
+                statements.push(begin);
+            },
+            Dialect::Mysql => {
+                if let Some(isolation) = self.isolation {
+                    statements.push(format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql()));
+                }
+                statements.push(if self.read_only {
+                    "START TRANSACTION READ ONLY"
+                } else {
+                    "START TRANSACTION"
+                }.to_string());
+            },
+            Dialect::SqlServer => {
+                if self.read_only {
+                    return Err(OwsqlError::Message("unsupported read-only transaction".into()));
+                }
+                if let Some(isolation) = self.isolation {
+                    statements.push(format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql()));
+                }
+                statements.push("BEGIN TRANSACTION".to_string());
+            },
+            Dialect::Oracle => return Err(OwsqlError::Message("unsupported transaction".into())),
+        }
+        Ok(statements)
+    }
+
+    /// Return the statements to restore the connection after the transaction ends.
+    pub(crate) fn end(&self, dialect: Dialect) -> Vec<String> {
+        match dialect {
+            Dialect::Sqlite if self.read_only => vec!["PRAGMA query_only = OFF".to_string()],
+            // The isolation level of SQL Server is kept for the session.
+            Dialect::SqlServer if self.isolation.is_some() => vec!["SET TRANSACTION ISOLATION LEVEL READ COMMITTED".to_string()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn begin() {
+        let options = TransactionOptions {
+            isolation: Some(IsolationLevel::Serializable),
+            read_only: true,
+            behavior:  TransactionBehavior::Immediate,
+        };
+        assert_eq!(options.begin(Dialect::Sqlite).unwrap(), ["PRAGMA query_only = ON", "BEGIN IMMEDIATE"]);
+        assert_eq!(options.begin(Dialect::Postgres).unwrap(), ["BEGIN ISOLATION LEVEL SERIALIZABLE READ ONLY"]);
+        assert_eq!(options.begin(Dialect::Mysql).unwrap(), ["SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", "START TRANSACTION READ ONLY"]);
+        assert_eq!(options.end(Dialect::Sqlite), ["PRAGMA query_only = OFF"]);
+        assert!(options.end(Dialect::Postgres).is_empty());
+        assert_eq!(options.begin(Dialect::SqlServer), Err(OwsqlError::Message("unsupported read-only transaction".into())));
+
+        let options = TransactionOptions::default();
+        assert_eq!(options.begin(Dialect::Sqlite).unwrap(), ["BEGIN DEFERRED"]);
+        assert_eq!(options.begin(Dialect::Postgres).unwrap(), ["BEGIN"]);
+        assert_eq!(options.begin(Dialect::Mysql).unwrap(), ["START TRANSACTION"]);
+        assert!(options.end(Dialect::Sqlite).is_empty());
+        assert_eq!(options.begin(Dialect::Oracle), Err(OwsqlError::Message("unsupported transaction".into())));
+
+        let options = TransactionOptions { isolation: Some(IsolationLevel::Serializable), ..Default::default() };
+        assert_eq!(options.begin(Dialect::SqlServer).unwrap(), ["SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", "BEGIN TRANSACTION"]);
+        assert_eq!(options.end(Dialect::SqlServer), ["SET TRANSACTION ISOLATION LEVEL READ COMMITTED"]);
+    }
+}
@@ -0,0 +1,328 @@
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::parser::push_escaped;
+
+/// An element type of [array](./struct.Connection.html#method.array).
+pub trait ArrayElement {
+    /// The element type of the Postgres array, such as `text`.
+    const PG_TYPE: &'static str;
+    /// Whether the element is a string literal.
+    const QUOTED: bool;
+    /// Return the text of the element.
+    fn text(&self) -> String;
+}
+
+impl ArrayElement for str {
+    const PG_TYPE: &'static str = "text";
+    const QUOTED: bool = true;
+    fn text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArrayElement for String {
+    const PG_TYPE: &'static str = "text";
+    const QUOTED: bool = true;
+    fn text(&self) -> String {
+        self.clone()
+    }
+}
+
+macro_rules! impl_array_element_int {
+    ( $( $ty:ty => $pg_type:expr ),* ) => {
+        $(
+            impl ArrayElement for $ty {
+                const PG_TYPE: &'static str = $pg_type;
+                const QUOTED: bool = false;
+                fn text(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+impl_array_element_int!(i16 => "smallint", i32 => "integer", i64 => "bigint");
+
+impl<T: ArrayElement + ?Sized> ArrayElement for &T {
+    const PG_TYPE: &'static str = T::PG_TYPE;
+    const QUOTED: bool = T::QUOTED;
+    fn text(&self) -> String {
+        (**self).text()
+    }
+}
+
+impl Connection {
+    /// Escapes and quotes a value, or returns the error overwrite.
+    fn quote(&self, value: &str) -> Result<String, String> {
+        let mut literal = String::from("'");
+        push_escaped(&mut literal, value, &self.escape_mode().must_escape());
+        literal.push('\'');
+        match self.charset().check_literal(&literal, self.escape_mode()) {
+            Some(err_msg) => Err(self.ow_error(err_msg, &literal)),
+            None          => Ok(literal),
+        }
+    }
+
+    /// It is guaranteed to be a boolean literal.
+    ///
+    /// Dialect                    | Literal
+    /// -------------------------- | -------
+    /// SQLite, SQL Server, Oracle | `1`, `0`
+    /// Postgres, MySQL            | `TRUE`, `FALSE`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.boolean(true) + &conn.ow("AS active;");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT 1 AS active; ");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_bool("active"), Some(true));
+    /// ```
+    pub fn boolean(&self, value: bool) -> String {
+        let value = match (self.dialect(), value) {
+            (Dialect::Sqlite | Dialect::SqlServer | Dialect::Oracle, true)  => "1",
+            (Dialect::Sqlite | Dialect::SqlServer | Dialect::Oracle, false) => "0",
+            (_, true)  => "TRUE",
+            (_, false) => "FALSE",
+        };
+        self.ow_generated(value.to_string())
+    }
+
+    /// It is guaranteed to be a `VALUES` block of the rows, with each value escaped in the same way as
+    /// strings concatenated to [ow](./struct.Connection.html#method.ow).  
+    /// Use [int](#method.int) or [boolean](#method.boolean) for a value which must not be a string.  
+    /// It is usable for a bulk insert, or as a table joined with others.
+    /// The rows must have the same number of values, or the error "row length mismatch" is returned
+    /// when the statement is executed. No rows return the error "empty values".
+    ///
+    /// Dialect                      | Block
+    /// ---------------------------- | -----
+    /// SQLite, Postgres, SQL Server | `VALUES ('a', 1), ('b', 2)`
+    /// MySQL                        | `VALUES ROW('a', 1), ROW('b', 2)`
+    ///
+    /// Oracle returns the error "unsupported values".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::params;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// # conn.execute(conn.ow("CREATE TABLE users (name TEXT, age INTEGER);")).unwrap();
+    /// let sql = conn.ow("INSERT INTO users (name, age)")
+    ///     + &conn.values(&[params!["Alice", conn.int(42)], params!["O'Reilly", conn.int(69)]]);
+    /// conn.execute(&sql).unwrap();
+    /// assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE age > 50;")), Ok(1));
+    /// ```
+    pub fn values(&self, rows: &[&[&(dyn ToString + Sync)]]) -> String {
+        let width = match rows.first() {
+            Some(row) => row.len(),
+            None => return self.ow_error("empty values", "VALUES"),
+        };
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return self.ow_error("row length mismatch",
+                &format!("expected {}, got {}", width, row.len()));
+        }
+        let open = match self.dialect() {
+            Dialect::Mysql  => "ROW(",
+            Dialect::Oracle => return self.ow_error("unsupported values", "VALUES"),
+            _               => "(",
+        };
+        let mut block = self.ow_generated(format!("VALUES {}", open));
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                block.push_str(&self.ow_generated(format!("), {}", open)));
+            }
+            for (j, value) in row.iter().enumerate() {
+                if j > 0 {
+                    block.push_str(&self.ow_generated(",".to_string()));
+                }
+                block.push_str(&self.operand(&value.to_string()));
+            }
+        }
+        block.push_str(&self.ow_generated(")".to_string()));
+        block
+    }
+
+    /// It is guaranteed to be a Postgres array literal, with each element escaped.  
+    /// Other dialects return the error "unsupported array" when the statement is executed.
+    ///
+    /// Elements             | Literal
+    /// -------------------- | -------
+    /// `&["a", "O'Reilly"]` | `ARRAY['a','O''Reilly']::text[]`
+    /// `&[1_i64, 2]`        | `ARRAY[1,2]::bigint[]`
+    ///
+    /// Use [get_array](./struct.Row.html#method.get_array) to retrieve an array column.
+    pub fn array<T: ArrayElement>(&self, values: &[T]) -> String {
+        if self.dialect() != Dialect::Postgres {
+            return self.ow_error("unsupported array", &format!("{}[]", T::PG_TYPE));
+        }
+        let mut literal = String::from("ARRAY[");
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                literal.push(',');
+            }
+            if T::QUOTED {
+                match self.quote(&value.text()) {
+                    Ok(quoted) => literal.push_str(&quoted),
+                    Err(overwrite) => return overwrite,
+                }
+            } else {
+                literal.push_str(&value.text());
+            }
+        }
+        literal.push_str(&format!("]::{}[]", T::PG_TYPE));
+        self.ow_generated(literal)
+    }
+
+    /// It is guaranteed to be a timestamp literal in UTC.
+    ///
+    /// Dialect            | Literal
+    /// ------------------ | -------
+    /// SQLite, SQL Server | `'2020-01-02T03:04:05.678Z'` (ISO 8601)
+    /// Postgres           | `TIMESTAMPTZ '2020-01-02 03:04:05.678+00:00'`
+    /// MySQL              | `'2020-01-02 03:04:05.678'`
+    /// Oracle             | `TIMESTAMP '2020-01-02 03:04:05.678 +00:00'`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.datetime(at) + &conn.ow("AS at;");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT '2020-01-02T03:04:05Z' AS at; ");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_datetime("at"), Some(at));
+    /// ```
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn datetime(&self, value: DateTime<Utc>) -> String {
+        let value = match self.dialect() {
+            Dialect::Postgres => format!("TIMESTAMPTZ '{}'", value.format("%Y-%m-%d %H:%M:%S%.f+00:00")),
+            Dialect::Mysql    => format!("'{}'", value.format("%Y-%m-%d %H:%M:%S%.f")),
+            Dialect::Oracle   => format!("TIMESTAMP '{}'", value.format("%Y-%m-%d %H:%M:%S%.f +00:00")),
+            _                 => format!("'{}'", value.format("%Y-%m-%dT%H:%M:%S%.fZ")),
+        };
+        self.ow_generated(value)
+    }
+
+    /// It is guaranteed to be a date literal.
+    ///
+    /// Dialect                 | Literal
+    /// ----------------------- | -------
+    /// SQLite, SQL Server      | `'2020-01-02'`
+    /// Postgres, MySQL, Oracle | `DATE '2020-01-02'`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let date = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.date(date) + &conn.ow("AS date;");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_date("date"), Some(date));
+    /// ```
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn date(&self, value: NaiveDate) -> String {
+        let value = match self.dialect() {
+            Dialect::Postgres |
+            Dialect::Mysql    |
+            Dialect::Oracle   => format!("DATE '{}'", value.format("%Y-%m-%d")),
+            _                 => format!("'{}'", value.format("%Y-%m-%d")),
+        };
+        self.ow_generated(value)
+    }
+
+    /// It is guaranteed to be a UUID literal.
+    ///
+    /// Dialect                           | Literal
+    /// --------------------------------- | -------
+    /// SQLite, MySQL, SQL Server, Oracle | `'67e55044-10b1-426f-9247-bb680e5fe0c8'`
+    /// Postgres                          | `UUID '67e55044-10b1-426f-9247-bb680e5fe0c8'`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uuid::Uuid;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    /// let sql = conn.ow("SELECT") + &conn.uuid(id) + &conn.ow("AS id;");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_uuid("id"), Some(id));
+    /// ```
+    #[cfg(feature = "uuid")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    pub fn uuid(&self, value: Uuid) -> String {
+        let value = match self.dialect() {
+            Dialect::Postgres => format!("UUID '{}'", value.to_hyphenated()),
+            _                 => format!("'{}'", value.to_hyphenated()),
+        };
+        self.ow_generated(value)
+    }
+
+    /// It is guaranteed to be a 16 bytes binary literal of a UUID, for a `BLOB` column of SQLite,
+    /// a `BINARY(16)` column of MySQL and SQL Server, or a `RAW(16)` column of Oracle.  
+    /// Use `HEX(column)` to retrieve it with [get_uuid](./struct.Row.html#method.get_uuid).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uuid::Uuid;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    /// let sql = conn.ow("SELECT HEX(") + &conn.uuid_binary(id) + &conn.ow(") AS id;");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT HEX( X'67e5504410b1426f9247bb680e5fe0c8' ) AS id; ");
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_uuid("id"), Some(id));
+    /// ```
+    #[cfg(feature = "uuid")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    pub fn uuid_binary(&self, value: Uuid) -> String {
+        match self.dialect() {
+            Dialect::Postgres  => self.ow_error("unsupported binary uuid", &value.to_string()),
+            Dialect::SqlServer => self.ow_generated(format!("0x{}", value.to_simple())),
+            Dialect::Oracle    => self.ow_generated(format!("HEXTORAW('{}')", value.to_simple())),
+            _                  => self.ow_generated(format!("X'{}'", value.to_simple())),
+        }
+    }
+
+    /// It is guaranteed to be an escaped JSON string literal of the value.
+    ///
+    /// Dialect       | Literal
+    /// ------------- | -------
+    /// SQLite, MySQL | `'{"name":"O''Reilly"}'`
+    /// Postgres      | `'{"name":"O''Reilly"}'::jsonb`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// let mut doc = HashMap::new();
+    /// doc.insert("name", "O'Reilly");
+    /// let sql = conn.ow("SELECT") + &conn.json(&doc) + &conn.ow("AS doc;");
+    /// assert_eq!(conn.actual_sql(&sql).unwrap(), r#"SELECT '{"name":"O''Reilly"}' AS doc; "#);
+    /// assert_eq!(conn.rows(&sql).unwrap()[0].get_json::<HashMap<String, String>>("doc").unwrap()["name"], "O'Reilly");
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json<T: ?Sized + Serialize>(&self, value: &T) -> String {
+        let json = match serde_json::to_string(value) {
+            Ok(json) => json,
+            Err(e) => return self.ow_error("invalid json", &e.to_string()),
+        };
+        let mut literal = match self.quote(&json) {
+            Ok(literal) => literal,
+            Err(overwrite) => return overwrite,
+        };
+        if self.dialect() == Dialect::Postgres {
+            literal.push_str("::jsonb");
+        }
+        self.ow_generated(literal)
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+#[cfg(feature = "sqlite")]
+use std::collections::VecDeque;
+#[cfg(feature = "sqlite")]
+use std::sync::{Arc, Mutex};
+
+use crate::Result;
+use crate::connection::Connection;
+use crate::dialect::Dialect;
+use crate::error::OwsqlError;
+use crate::lexer::is_identifier;
+use crate::time::Instant;
+
+/// The interval of polling a MySQL table.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The kind of the change of a [ChangeEvent](./struct.ChangeEvent.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+    /// The table changed, but how is unknown, such as by polling.
+    Unknown,
+}
+
+/// A change of the table watched by [watch](./struct.Connection.html#method.watch).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChangeEvent {
+    /// The table.
+    pub table: String,
+    /// The kind of the change.
+    pub kind:  ChangeKind,
+    /// The rowid of the changed row, which is known only for SQLite.
+    pub rowid: Option<i64>,
+}
+
+/// Where the changes come from.
+enum Source {
+    #[cfg(feature = "sqlite")]
+    Hook(Arc<Mutex<VecDeque<ChangeEvent>>>),
+    #[cfg(feature = "postgres")]
+    Notify(String),
+    Checksum(Option<String>),
+}
+
+/// A stream of the changes of a table. See [watch](./struct.Connection.html#method.watch).
+///
+/// The watching stops when this is dropped.
+pub struct Watcher<'conn> {
+    conn:   &'conn Connection,
+    table:  String,
+    source: Source,
+}
+
+impl Watcher<'_> {
+    /// Return the table.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Return the changes received so far without blocking.
+    pub fn events(&mut self) -> Result<Vec<ChangeEvent>> {
+        let mut events = Vec::new();
+        while let Some(event) = self.next_event(Duration::from_secs(0))? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Waits for a change up to the timeout, and return `None` if it timed out.
+    pub fn next_event(&mut self, timeout: Duration) -> Result<Option<ChangeEvent>> {
+        match &mut self.source {
+            // The hook is called only while this connection executes a statement.
+            #[cfg(feature = "sqlite")]
+            Source::Hook(events) => Ok(events.lock().unwrap().pop_front()),
+            #[cfg(feature = "postgres")]
+            Source::Notify(channel) => {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    let notification = self.conn.wait_notification(deadline.saturating_duration_since(Instant::now()))?;
+                    match notification {
+                        Some(notification) if notification.channel == *channel => {
+                            let kind = match notification.payload.as_str() {
+                                "INSERT" => ChangeKind::Insert,
+                                "UPDATE" => ChangeKind::Update,
+                                "DELETE" => ChangeKind::Delete,
+                                _        => ChangeKind::Unknown,
+                            };
+                            return Ok(Some(ChangeEvent { table: self.table.clone(), kind, rowid: None }));
+                        },
+                        Some(_) => continue,
+                        None => return Ok(None),
+                    }
+                }
+            },
+            Source::Checksum(last) => {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    let checksum = checksum(self.conn, &self.table)?;
+                    if *last != checksum {
+                        *last = checksum;
+                        return Ok(Some(ChangeEvent { table: self.table.clone(), kind: ChangeKind::Unknown, rowid: None }));
+                    }
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+                }
+            },
+        }
+    }
+}
+
+impl Drop for Watcher<'_> {
+    fn drop(&mut self) {
+        match &self.source {
+            #[cfg(feature = "sqlite")]
+            Source::Hook(_) => { let _ = self.conn.remove_update_hook(); },
+            #[cfg(feature = "postgres")]
+            Source::Notify(channel) => { let _ = self.conn.unlisten(channel); },
+            Source::Checksum(_) => (),
+        }
+    }
+}
+
+/// Return the checksum of the MySQL table.
+fn checksum(conn: &Connection, table: &str) -> Result<Option<String>> {
+    let row = conn.single_row(conn.ow("CHECKSUM TABLE") + &conn.table(table) + &conn.ow(";"))?;
+    Ok(row.get("Checksum").map(str::to_string))
+}
+
+/// Return the channel notified by the trigger of [install_watch_trigger](./struct.Connection.html#method.install_watch_trigger).
+#[cfg(feature = "postgres")]
+pub(crate) fn watch_channel(table: &str) -> String {
+    format!("owsql_watch_{}", table)
+}
+
+impl Connection {
+    /// Starts watching the changes of the table, which is a plain identifier, so that a cache can be
+    /// invalidated. See [Watcher](./struct.Watcher.html).
+    ///
+    /// Dialect  | Changes
+    /// -------- | -------
+    /// SQLite   | The rows changed by this connection, with the rowid. The update hook is replaced while watching.
+    /// Postgres | The notifications of [install_watch_trigger](#method.install_watch_trigger). The other notifications are discarded while waiting.
+    /// MySQL    | Polling `CHECKSUM TABLE`, whose changes are `ChangeKind::Unknown`.
+    ///
+    /// # Errors
+    ///
+    /// An invalid table returns the error "invalid identifier".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use owsql::ChangeKind;
+    /// # let conn = owsql::sqlite::open(":memory:").unwrap();
+    /// conn.execute(conn.ow("CREATE TABLE users (name TEXT);")).unwrap();
+    /// let mut watcher = conn.watch("users").unwrap();
+    /// conn.execute(conn.ow("INSERT INTO users VALUES ('Alice');")).unwrap();
+    /// let events = watcher.events().unwrap();
+    /// assert_eq!(events[0].kind, ChangeKind::Insert);
+    /// assert_eq!(events[0].rowid, Some(1));
+    /// ```
+    pub fn watch(&self, table: &str) -> Result<Watcher<'_>> {
+        if !is_identifier(table) {
+            OwsqlError::new(&self.error_level, "invalid identifier", table)?;
+        }
+        let source = match self.dialect() {
+            #[cfg(feature = "sqlite")]
+            Dialect::Sqlite => {
+                let events = Arc::new(Mutex::new(VecDeque::new()));
+                let (queue, watched) = (Arc::clone(&events), table.to_string());
+                self.set_update_hook(move |action, _, table, rowid| {
+                    if table.eq_ignore_ascii_case(&watched) {
+                        let kind = match action {
+                            crate::sqlite::Action::Insert => ChangeKind::Insert,
+                            crate::sqlite::Action::Update => ChangeKind::Update,
+                            crate::sqlite::Action::Delete => ChangeKind::Delete,
+                        };
+                        queue.lock().unwrap().push_back(ChangeEvent { table: watched.clone(), kind, rowid: Some(rowid) });
+                    }
+                })?;
+                Source::Hook(events)
+            },
+            #[cfg(feature = "postgres")]
+            Dialect::Postgres => {
+                let channel = watch_channel(table);
+                self.listen(&channel)?;
+                Source::Notify(channel)
+            },
+            Dialect::Mysql => Source::Checksum(checksum(self, table)?),
+            #[allow(unreachable_patterns)]
+            _ => return Err(OwsqlError::Message("unsupported watch".into())),
+        };
+        Ok(Watcher { conn: self, table: table.to_string(), source })
+    }
+}
@@ -0,0 +1,68 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+#[cfg(feature = "duckdb")]
+#[cfg(debug_assertions)]
+mod duckdb {
+    fn prepare() -> owsql::Connection {
+        let conn = owsql::duckdb::open(":memory:").unwrap();
+        let stmt = conn.ow(stmt());
+        conn.execute(&stmt).unwrap();
+        conn
+    }
+
+    fn stmt() -> &'static str {
+        r#"CREATE TABLE users (name TEXT, age INTEGER);
+           INSERT INTO users (name, age) VALUES ('Alice', 42);
+           INSERT INTO users (name, age) VALUES ('Bob', 69);
+           INSERT INTO users (name, age) VALUES ('Carol', 50);"#
+    }
+
+    #[test]
+    fn open() {
+        let _conn = owsql::duckdb::open(":memory:").unwrap();
+    }
+
+    #[test]
+    #[should_panic = "exec error"]
+    fn execute_should_error() {
+        let conn = prepare();
+        conn.execute(conn.ow("SELECT * FROM no_such_table;")).unwrap();
+    }
+
+    #[test]
+    fn validate() {
+        let conn = prepare();
+        assert!(conn.validate(conn.ow("DELETE FROM users WHERE name =") + "Alice").is_ok());
+        assert!(conn.validate(conn.ow("DELETE users;")).is_err());
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+    }
+
+    #[test]
+    fn iterate_2sets() {
+        let conn = prepare();
+        let mut names = Vec::new();
+        conn.iterate(conn.ow("SELECT name FROM users ORDER BY age; SELECT name FROM users ORDER BY name;"), |pairs| {
+            names.push(pairs[0].1.unwrap().to_string());
+            true
+        }).unwrap();
+        assert_eq!(names, ["Alice", "Carol", "Bob", "Alice", "Bob", "Carol"]);
+    }
+
+    #[test]
+    fn rows() {
+        let conn = prepare();
+        let rows = conn.rows(conn.ow("SELECT name, age, age > 45 AS senior, NULL AS note FROM users WHERE name =") + "Bob").unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("age"), Some("69"));
+        assert_eq!(rows[0].get("senior"), Some("true"));
+        assert_eq!(rows[0].get("note"), None);
+    }
+
+    #[test]
+    fn injection_safe() {
+        let conn = prepare();
+        let name = r"Alice' OR '1'='1\";
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + name), Ok(0));
+        conn.execute(conn.ow("UPDATE users SET name =") + name + &conn.ow("WHERE age > 45;")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + name), Ok(2));
+    }
+}
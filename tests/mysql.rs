@@ -1,3 +1,4 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
 #[cfg(feature = "mysql")]
 #[cfg(debug_assertions)]
 mod mysql {
@@ -8,6 +9,15 @@ mod mysql {
         ($msg:expr) => { Err(owsql::OwsqlError::Message($msg.to_string())) };
     }
 
+    macro_rules! syntax_err {
+        ($msg:expr) => { Err(owsql::OwsqlError::Database {
+            code:       Some(1064),
+            sqlstate:   Some("42000".to_string()),
+            message:    $msg.to_string(),
+            constraint: None,
+        }) };
+    }
+
     fn prepare() -> owsql::Connection {
         let conn = owsql::mysql::open("mysql://localhost:3306/test").unwrap();
         let stmt = conn.ow(stmt());
@@ -41,6 +51,87 @@ mod mysql {
         conn.execute(stmt()).unwrap();
     }
 
+    #[test]
+    fn validate() {
+        let conn = prepare();
+        assert!(conn.validate(conn.ow("DELETE FROM users WHERE name =") + "Alice").is_ok());
+        assert!(conn.validate(conn.ow("DELETE users;")).is_err());
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+    }
+
+    #[test]
+    fn backticks() {
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE TEMPORARY TABLE `order` (`it's` TEXT, `select` INTEGER);")).unwrap();
+        conn.execute(conn.ow("INSERT INTO `order` (`it's`, `select`) VALUES (") + "Alice" + &conn.ow(", 42);")).unwrap();
+        let rows = conn.rows(conn.ow("SELECT `it's` AS name FROM `order` WHERE `select` =") + &conn.int(42)).unwrap();
+        assert_eq!(rows[0].get("name"), Some("Alice"));
+        assert_eq!(conn.execute(conn.ow("SELECT `order FROM users;")), err!("invalid literal"));
+    }
+
+    #[test]
+    fn call() {
+        let conn = prepare();
+        conn.execute(conn.ow(r#"DROP PROCEDURE IF EXISTS count_older;
+                                CREATE PROCEDURE count_older(IN min_age INT, OUT total INT)
+                                    SELECT COUNT(*) INTO total FROM users WHERE age > min_age;"#)).unwrap();
+        let row = conn.call_out("count_older", params![45], &["total"]).unwrap();
+        assert_eq!(row.get("total"), Some("2"));
+        let row = conn.call_out("count_older", params!["0"], &["total"]).unwrap();
+        assert_eq!(row.get("total"), Some("3"));
+    }
+
+    #[test]
+    fn values() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT users.name, v.title FROM users JOIN (")
+            + &conn.values(&[params!["Alice", "Dr."], params!["Bob' OR 'a'='a", "Mr."]])
+            + &conn.ow(") AS v (name, title) ON users.name = v.name;");
+        let rows = conn.rows(&sql).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("title"), Some("Dr."));
+    }
+
+    #[test]
+    fn execute_chunked() {
+        let mut conn = prepare();
+        conn.set_chunk_limit(ChunkLimit { max_bytes: Some(64), ..Default::default() });
+        let changes = conn.execute_chunked("INSERT INTO users (name, age)",
+            &[params!["Dave", 30], params!["Eve' OR 'a'='a", 25], params!["Fay", 60]]);
+        assert_eq!(changes, Ok(3));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(6));
+    }
+
+    #[test]
+    fn order_by() {
+        use owsql::SortOrder;
+        let conn = prepare();
+        let sql = conn.ow("SELECT name FROM users") + &conn.order_by(&[("users.age", SortOrder::Desc)]) + &conn.ow(";");
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users ORDER BY `users`.`age` DESC ; ");
+        assert_eq!(conn.rows(sql).unwrap()[0].get("name"), Some("Bob"));
+    }
+
+    #[test]
+    fn watch() {
+        use std::time::Duration;
+        use owsql::ChangeKind;
+        let conn = prepare();
+        let mut watcher = conn.watch("users").unwrap();
+        assert_eq!(watcher.next_event(Duration::from_millis(100)), Ok(None));
+        conn.execute(conn.ow("UPDATE users SET age = 43 WHERE name = 'Alice';")).unwrap();
+        assert_eq!(watcher.next_event(Duration::from_secs(1)).unwrap().map(|event| event.kind), Some(ChangeKind::Unknown));
+    }
+
+    #[test]
+    fn next_id() {
+        let conn = prepare();
+        conn.execute(conn.ow("DROP TABLE IF EXISTS owsql_sequences;")).unwrap();
+        assert_eq!(conn.next_id("orders"), Ok(1));
+        assert_eq!(conn.next_id("orders"), Ok(2));
+        assert_eq!(conn.next_id("invoices"), Ok(1));
+        assert_eq!(conn.next_id("orders; DROP TABLE users"), err!("invalid identifier"));
+    }
+
     #[test]
     fn iterate() {
         let conn = prepare();
@@ -89,9 +180,10 @@ mod mysql {
     fn rows() {
         let conn = prepare();
         let expects = [("Carol", 50), ("Bob", 69), ("Alice", 42),];
-        let sql = conn.ow("SELECT * FROM users;");
+        let sql = conn.ow("SELECT * FROM users ORDER BY name DESC;");
 
         let rows = conn.rows(&sql).unwrap();
+        assert_eq!(rows.len(), 3);
         for (i, row) in rows.iter().enumerate() {
             assert_eq!(row.get("name").unwrap(), expects[i].0);
             assert_eq!(row.get("age").unwrap(),  expects[i].1.to_string());
@@ -103,7 +195,7 @@ mod mysql {
         let conn = prepare();
         let expects = [("Carol", 50), ("Bob", 69), ("Alice", 42),];
 
-        conn.rows(&conn.ow("SELECT * FROM users;")).unwrap().iter().enumerate().for_each(|(i, row)| {
+        conn.rows(&conn.ow("SELECT * FROM users ORDER BY name DESC;")).unwrap().iter().enumerate().for_each(|(i, row)| {
             assert_eq!(row.get("name").unwrap(), expects[i].0);
             assert_eq!(row.get("age").unwrap(),  expects[i].1.to_string());
         });
@@ -288,18 +380,18 @@ mod mysql {
         let name = conn.allowlist("Bob");
         let integer = conn.int("50 or 1=1; --");
 
-        assert_eq!(conn.execute("INVALID SQL"),                     err!());
-        assert_eq!(conn.execute("'endless"),                        err!());
+        assert_eq!(conn.execute("INVALID SQL"),                     syntax_err!("exec error"));
+        assert_eq!(conn.execute("'endless"),                        syntax_err!("exec error"));
         assert_eq!(conn.execute(&single_quote),                     err!());
         assert_eq!(conn.execute(&name),                             err!());
         assert_eq!(conn.execute(&integer),                          err!());
-        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), err!());
-        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), err!());
+        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), syntax_err!("exec error"));
+        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), syntax_err!("exec error"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!());
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!());
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!());
-        assert_eq!(conn.rows("INVALID SQL"),                        err!());
-        assert_eq!(conn.rows("'endless"),                           err!());
+        assert_eq!(conn.rows("INVALID SQL"),                        syntax_err!("exec error"));
+        assert_eq!(conn.rows("'endless"),                           syntax_err!("exec error"));
         assert_eq!(conn.rows(&single_quote),                        err!());
         assert_eq!(conn.rows(&name),                                err!());
         assert_eq!(conn.rows(&integer),                             err!());
@@ -314,18 +406,18 @@ mod mysql {
         let name = conn.allowlist("Bob");
         let integer = conn.int("50 or 1=1; --");
 
-        assert_eq!(conn.execute("INVALID SQL"),                     err!("exec error"));
-        assert_eq!(conn.execute("'endless"),                        err!("exec error"));
+        assert_eq!(conn.execute("INVALID SQL"),                     syntax_err!("exec error"));
+        assert_eq!(conn.execute("'endless"),                        syntax_err!("exec error"));
         assert_eq!(conn.execute(&single_quote),                     err!("invalid literal"));
         assert_eq!(conn.execute(&name),                             err!("deny value"));
         assert_eq!(conn.execute(&integer),                          err!("non integer"));
-        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), err!("exec error"));
-        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), err!("exec error"));
+        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), syntax_err!("exec error"));
+        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), syntax_err!("exec error"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!("invalid literal"));
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!("deny value"));
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!("non integer"));
-        assert_eq!(conn.rows("INVALID SQL"),                        err!("exec error"));
-        assert_eq!(conn.rows("'endless"),                           err!("exec error"));
+        assert_eq!(conn.rows("INVALID SQL"),                        syntax_err!("exec error"));
+        assert_eq!(conn.rows("'endless"),                           syntax_err!("exec error"));
         assert_eq!(conn.rows(&single_quote),                        err!("invalid literal"));
         assert_eq!(conn.rows(&name),                                err!("deny value"));
         assert_eq!(conn.rows(&integer),                             err!("non integer"));
@@ -341,23 +433,23 @@ mod mysql {
         let integer = conn.int("50 or 1=1; --");
 
         assert_eq!(conn.execute("INVALID SQL"),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
         assert_eq!(conn.execute("'endless"),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
         assert_eq!(conn.execute(&single_quote),                     err!("invalid literal: '"));
         assert_eq!(conn.execute(&name),                             err!("deny value: Bob"));
         assert_eq!(conn.execute(&integer),                          err!("non integer: 50 or 1=1; --"));
         assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
         assert_eq!(conn.iterate("'endless",    |_| unreachable!()),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!("invalid literal: '"));
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!("deny value: Bob"));
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!("non integer: 50 or 1=1; --"));
         assert_eq!(conn.rows("INVALID SQL"),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'INVALID SQL\'\' at line 1 }"));
         assert_eq!(conn.rows("'endless"),
-            err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
+            syntax_err!("exec error: MySqlError { ERROR 1064 (42000): You have an error in your SQL syntax; check the manual that corresponds to your MariaDB server version for the right syntax to use near \'\'\'\'endless\'\' at line 1 }"));
         assert_eq!(conn.rows(&single_quote),                        err!("invalid literal: '"));
         assert_eq!(conn.rows(&name),                                err!("deny value: Bob"));
         assert_eq!(conn.rows(&integer),                             err!("non integer: 50 or 1=1; --"));
@@ -411,7 +503,7 @@ mod mysql {
         for (sql, actual_sql, result) in sqls {
             assert_eq!(conn.actual_sql(&sql).unwrap(), actual_sql);
             conn.iterate(&sql, |pairs| {
-                for (_, (_, value)) in pairs.iter().enumerate() {
+                for (_, value) in pairs.iter() {
                     assert_eq!(*value.as_ref().unwrap(), result);
                 }
                 true
@@ -434,4 +526,15 @@ mod mysql_release_build {
         );
     }
 
+    #[test]
+    fn set_session_escaping() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT * FROM users WHERE name =") + "\\' OR 1=1 --";
+        conn.set_session("sql_mode", "NO_BACKSLASH_ESCAPES").unwrap();
+        assert_eq!(conn.escape_mode(), EscapeMode::Standard);
+        assert_eq!(conn.count(&sql), Ok(0));
+        conn.set_session("sql_mode", "STRICT_TRANS_TABLES").unwrap();
+        assert_eq!(conn.escape_mode(), EscapeMode::Backslash);
+        assert_eq!(conn.count(&sql), Ok(0));
+    }
 }
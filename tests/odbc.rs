@@ -0,0 +1,65 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+#[cfg(feature = "odbc")]
+#[cfg(debug_assertions)]
+mod odbc {
+    use owsql::Dialect;
+    use owsql::odbc::Profile;
+
+    /// The SQLite ODBC driver, which needs no server.
+    const CONNECTION_STRING: &str = "Driver={SQLite3};Database=:memory:;";
+
+    fn prepare() -> owsql::Connection {
+        let conn = owsql::odbc::open(CONNECTION_STRING, Profile::new(Dialect::Sqlite)).unwrap();
+        let stmt = conn.ow(stmt());
+        conn.execute(&stmt).unwrap();
+        conn
+    }
+
+    fn stmt() -> &'static str {
+        r#"CREATE TABLE users (name TEXT, age INTEGER);
+           INSERT INTO users (name, age) VALUES ('Alice', 42);
+           INSERT INTO users (name, age) VALUES ('Bob', 69);
+           INSERT INTO users (name, age) VALUES ('Carol', 50);"#
+    }
+
+    #[test]
+    fn open() {
+        let conn = owsql::odbc::open(CONNECTION_STRING, Profile::new(Dialect::Sqlite)).unwrap();
+        assert_eq!(conn.dialect(), Dialect::Sqlite);
+        assert!(owsql::odbc::open("Driver={No Such Driver};", Profile::sql_server()).is_err());
+    }
+
+    #[test]
+    #[should_panic = "exec error"]
+    fn execute_should_error() {
+        let conn = prepare();
+        conn.execute(conn.ow("SELECT * FROM no_such_table;")).unwrap();
+    }
+
+    #[test]
+    fn rows() {
+        let conn = prepare();
+        let rows = conn.rows(conn.ow("SELECT name, age, NULL AS note FROM users WHERE age >") + &conn.int(45) + &conn.ow("ORDER BY age DESC;")).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get("name"), Some("Bob"));
+        assert_eq!(rows[1].get("age"), Some("50"));
+        assert_eq!(rows[0].get("note"), None);
+    }
+
+    #[test]
+    fn injection_safe() {
+        let conn = prepare();
+        let name = "Alice' OR '1'='1";
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + name), Ok(0));
+        conn.execute(conn.ow("UPDATE users SET name =") + name + &conn.ow("WHERE age > 45;")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + name), Ok(2));
+    }
+
+    #[test]
+    fn set_session_escaping() {
+        let conn = owsql::odbc::open(CONNECTION_STRING, Profile::mysql()).unwrap();
+        assert_eq!(conn.set_session("sql_mode", "NO_BACKSLASH_ESCAPES"),
+            Err(owsql::OwsqlError::Message("unsupported session variable: \"sql_mode\"".into())));
+        assert_eq!(conn.escape_mode(), owsql::EscapeMode::Backslash);
+    }
+}
@@ -1,3 +1,4 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
 
 #[cfg(feature = "sqlite")]
 #[cfg(debug_assertions)]
@@ -9,6 +10,15 @@ mod sqlite {
         ($msg:expr) => { Err(owsql::OwsqlError::Message($msg.to_string())) };
     }
 
+    macro_rules! syntax_err {
+        ($msg:expr) => { Err(owsql::OwsqlError::Database {
+            code:       Some(1),
+            sqlstate:   None,
+            message:    $msg.to_string(),
+            constraint: None,
+        }) };
+    }
+
     fn prepare() -> owsql::Connection {
         let conn = owsql::sqlite::open(":memory:").unwrap();
         let stmt = conn.ow(stmt());
@@ -304,7 +314,7 @@ mod sqlite {
 
         conn.rows(conn.ow("SELECT name FROM users WHERE age = 12345;")).unwrap().iter() .all(|row| {
             assert_eq!(
-                owsql::html_special_chars(&row.get("name").unwrap()),
+                owsql::html_special_chars(row.get("name").unwrap()),
                 "&lt;script&gt;alert(&quot;&amp;1&quot;);&lt;/script&gt;"
             );
             true
@@ -356,18 +366,18 @@ mod sqlite {
         let name = conn.allowlist("Bob");
         let integer = conn.int("50 or 1=1; --");
 
-        assert_eq!(conn.execute("INVALID SQL"),                     err!());
-        assert_eq!(conn.execute("'endless"),                        err!());
+        assert_eq!(conn.execute("INVALID SQL"),                     syntax_err!("exec error"));
+        assert_eq!(conn.execute("'endless"),                        syntax_err!("exec error"));
         assert_eq!(conn.execute(&single_quote),                     err!());
         assert_eq!(conn.execute(&name),                             err!());
         assert_eq!(conn.execute(&integer),                          err!());
-        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), err!());
-        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), err!());
+        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), syntax_err!("exec error"));
+        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), syntax_err!("exec error"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!());
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!());
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!());
-        assert_eq!(conn.rows("INVALID SQL"),                        err!());
-        assert_eq!(conn.rows("'endless"),                           err!());
+        assert_eq!(conn.rows("INVALID SQL"),                        syntax_err!("exec error"));
+        assert_eq!(conn.rows("'endless"),                           syntax_err!("exec error"));
         assert_eq!(conn.rows(&single_quote),                        err!());
         assert_eq!(conn.rows(&name),                                err!());
         assert_eq!(conn.rows(&integer),                             err!());
@@ -382,18 +392,18 @@ mod sqlite {
         let name = conn.allowlist("Bob");
         let integer = conn.int("50 or 1=1; --");
 
-        assert_eq!(conn.execute("INVALID SQL"),                     err!("exec error"));
-        assert_eq!(conn.execute("'endless"),                        err!("exec error"));
+        assert_eq!(conn.execute("INVALID SQL"),                     syntax_err!("exec error"));
+        assert_eq!(conn.execute("'endless"),                        syntax_err!("exec error"));
         assert_eq!(conn.execute(&single_quote),                     err!("invalid literal"));
         assert_eq!(conn.execute(&name),                             err!("deny value"));
         assert_eq!(conn.execute(&integer),                          err!("non integer"));
-        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), err!("exec error"));
-        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), err!("exec error"));
+        assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()), syntax_err!("exec error"));
+        assert_eq!(conn.iterate("'endless",    |_| unreachable!()), syntax_err!("exec error"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!("invalid literal"));
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!("deny value"));
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!("non integer"));
-        assert_eq!(conn.rows("INVALID SQL"),                        err!("exec error"));
-        assert_eq!(conn.rows("'endless"),                           err!("exec error"));
+        assert_eq!(conn.rows("INVALID SQL"),                        syntax_err!("exec error"));
+        assert_eq!(conn.rows("'endless"),                           syntax_err!("exec error"));
         assert_eq!(conn.rows(&single_quote),                        err!("invalid literal"));
         assert_eq!(conn.rows(&name),                                err!("deny value"));
         assert_eq!(conn.rows(&integer),                             err!("non integer"));
@@ -409,23 +419,23 @@ mod sqlite {
         let integer = conn.int("50 or 1=1; --");
 
         assert_eq!(conn.execute("INVALID SQL"),
-            err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
+            syntax_err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
         assert_eq!(conn.execute("'endless"),
-            err!("exec error: near \"\'\'\'endless\'\": syntax error"));
+            syntax_err!("exec error: near \"\'\'\'endless\'\": syntax error"));
         assert_eq!(conn.execute(&single_quote),                     err!("invalid literal: '"));
         assert_eq!(conn.execute(&name),                             err!("deny value: Bob"));
         assert_eq!(conn.execute(&integer),                          err!("non integer: 50 or 1=1; --"));
         assert_eq!(conn.iterate("INVALID SQL", |_| unreachable!()),
-            err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
+            syntax_err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
         assert_eq!(conn.iterate("'endless",    |_| unreachable!()),
-            err!("exec error: near \"\'\'\'endless\'\": syntax error"));
+            syntax_err!("exec error: near \"\'\'\'endless\'\": syntax error"));
         assert_eq!(conn.iterate(&single_quote, |_| unreachable!()), err!("invalid literal: '"));
         assert_eq!(conn.iterate(&name,         |_| unreachable!()), err!("deny value: Bob"));
         assert_eq!(conn.iterate(&integer,      |_| unreachable!()), err!("non integer: 50 or 1=1; --"));
         assert_eq!(conn.rows("INVALID SQL"),
-            err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
+            syntax_err!("exec error: near \"\'INVALID SQL\'\": syntax error"));
         assert_eq!(conn.rows("'endless"),
-            err!("exec error: near \"\'\'\'endless\'\": syntax error"));
+            syntax_err!("exec error: near \"\'\'\'endless\'\": syntax error"));
         assert_eq!(conn.rows(&single_quote),                        err!("invalid literal: '"));
         assert_eq!(conn.rows(&name),                                err!("deny value: Bob"));
         assert_eq!(conn.rows(&integer),                             err!("non integer: 50 or 1=1; --"));
@@ -504,27 +514,28 @@ mod sqlite {
     fn range() {
         let mut conn = owsql::sqlite::open(":memory:").unwrap();
         conn.set_ow_len(1);
-        assert_eq!(1+5+32+1+1, conn.ow("0").len());
+        // The namespace of the connection follows the prefix.
+        let ns = conn.ow("0").len() - (1+5+32+1+1);
         conn.set_ow_len(42);
-        assert_eq!(1+5+42+1+1, conn.ow("1").len());
+        assert_eq!(1+5+ns+42+1+1, conn.ow("1").len());
         conn.set_ow_len(0..32);
-        assert_eq!(1+5+32+1+1, conn.ow("2").len());
+        assert_eq!(1+5+ns+32+1+1, conn.ow("2").len());
         conn.set_ow_len(0..=32);
-        assert_eq!(1+5+32+1+1, conn.ow("3").len());
+        assert_eq!(1+5+ns+32+1+1, conn.ow("3").len());
         conn.set_ow_len(64..64);
-        assert_eq!(1+5+64+1+1, conn.ow("4").len());
+        assert_eq!(1+5+ns+64+1+1, conn.ow("4").len());
         conn.set_ow_len(64..=64);
-        assert_eq!(1+5+64+1+1, conn.ow("5").len());
+        assert_eq!(1+5+ns+64+1+1, conn.ow("5").len());
         conn.set_ow_len(64..32);
-        assert!(1+5+33+1+1 <= conn.ow("6").len() && conn.ow("6").len() <= 1+5+64+1+1);
+        assert!(1+5+ns+33+1+1 <= conn.ow("6").len() && conn.ow("6").len() <= 1+5+ns+64+1+1);
         conn.set_ow_len(64..=32);
-        assert!(1+5+32+1+1 <= conn.ow("7").len() && conn.ow("7").len() <= 1+5+64+1+1);
+        assert!(1+5+ns+32+1+1 <= conn.ow("7").len() && conn.ow("7").len() <= 1+5+ns+64+1+1);
         conn.set_ow_len(100..110);
-        assert!(1+5+100+1+1 <= conn.ow("8").len() && conn.ow("8").len() <= 1+5+109+1+1);
+        assert!(1+5+ns+100+1+1 <= conn.ow("8").len() && conn.ow("8").len() <= 1+5+ns+109+1+1);
         conn.set_ow_len(..64);
-        assert!(1+5+32+1+1 <= conn.ow("9").len() && conn.ow("9").len() <= 1+5+63+1+1);
+        assert!(1+5+ns+32+1+1 <= conn.ow("9").len() && conn.ow("9").len() <= 1+5+ns+63+1+1);
         conn.set_ow_len(..=64);
-        assert!(1+5+32+2+1 <= conn.ow("10").len() && conn.ow("10").len() <= 1+5+64+2+1);
+        assert!(1+5+ns+32+2+1 <= conn.ow("10").len() && conn.ow("10").len() <= 1+5+ns+64+2+1);
     }
 
     #[test]
@@ -574,6 +585,1278 @@ mod sqlite {
         assert!(executed);
     }
 
+    #[test]
+    fn metrics() {
+        let mut conn = prepare();
+        assert_eq!(conn.metrics(), None);
+        conn.set_metrics(true);
+
+        let sql = conn.ow("SELECT name FROM users WHERE age <");
+        conn.rows(sql.clone() + "50").unwrap();
+        conn.rows(sql.clone() + "60").unwrap();
+        conn.execute("INVALID SQL").unwrap_err();
+
+        let statements = conn.metrics().unwrap().statements();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].statement, "? ");
+        assert_eq!(statements[0].errors, 1);
+        assert_eq!(statements[1].statement, "SELECT name FROM users WHERE age < ? ");
+        assert_eq!(statements[1].count, 2);
+        assert_eq!(statements[1].errors, 0);
+        assert!(statements[1].max <= statements[1].total);
+
+        conn.set_metrics(false);
+        assert_eq!(conn.metrics(), None);
+    }
+
+    #[test]
+    fn escape_mode() {
+        let mut conn = prepare();
+        assert_eq!(conn.escape_mode(), EscapeMode::Standard);
+        assert_eq!(conn.actual_sql(r"O\'Reilly").unwrap(), r"'O\''Reilly' ");
+        conn.set_escape_mode(EscapeMode::Backslash);
+        assert_eq!(conn.actual_sql(r"O\'Reilly").unwrap(), r"'O\\''Reilly' ");
+    }
+
+    #[test]
+    fn ow_runtime() {
+        let conn = prepare();
+        let table = String::from("users");
+        let sql = conn.ow("SELECT name FROM") + &conn.ow_runtime(&table).unwrap() + &conn.ow("WHERE age =") + "42";
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users WHERE age = '42' ");
+        assert_eq!(conn.rows(&sql).unwrap()[0].get("name"), Some("Alice"));
+        assert_eq!(conn.ow_runtime(&table), conn.ow_runtime("users"));
+        assert_eq!(conn.ow_runtime("users"), Ok(conn.ow("users")));
+        assert_eq!(conn.ow_runtime(String::from("O'Reilly")), err!("invalid literal"));
+    }
+
+    #[test]
+    fn pending_errors() {
+        let mut conn = prepare();
+        conn.add_allowlist(params!["Alice"]);
+        let valid = conn.ow("SELECT * FROM users WHERE name =") + &conn.allowlist("Alice") + &conn.ow("AND age <") + &conn.int(50);
+        let invalid = conn.ow("SELECT * FROM users WHERE name =") + &conn.allowlist("Bob") + &conn.ow("AND age <") + &conn.int("x");
+        assert_eq!(conn.pending_errors(&valid), vec![]);
+        assert!(!conn.has_error(&valid));
+        assert_eq!(conn.pending_errors(&invalid), vec![
+            OwsqlError::Message("deny value".into()),
+            OwsqlError::Message("non integer".into()),
+        ]);
+        assert!(conn.has_error(&invalid));
+        assert!(conn.has_error(conn.ow("'")));
+        assert!(!conn.has_error("INVALID SQL"));
+    }
+
+    #[test]
+    fn gc_overwrites() {
+        let mut conn = prepare();
+        conn.add_allowlist(params!["Alice"]);
+        let select = conn.ow("SELECT name FROM users WHERE age =");
+        let allow = conn.allowlist("Alice");
+        let stats = conn.overwrite_stats();
+        assert_eq!(stats.evictable, 0);
+
+        for age in 0..10 {
+            conn.int(age);
+        }
+        conn.whitespace_around("  foo  ");
+        assert_eq!(conn.overwrite_stats().evictable, 11);
+        assert_eq!(conn.gc_overwrites(), 0);
+        let sql = select.clone() + &conn.int(42);
+        assert_eq!(conn.gc_overwrites(), 11);
+        assert_eq!(conn.rows(&sql).unwrap()[0].get("name"), Some("Alice"));
+        assert_eq!(conn.gc_overwrites(), 1);
+        assert_eq!(conn.actual_sql(&sql), Err(OwsqlError::Message("token collision".into())));
+
+        conn.set_overwrite_capacity(2);
+        for age in 0..10 {
+            conn.int(age);
+        }
+        let stats = conn.overwrite_stats();
+        assert_eq!(stats.evictable, 2);
+        assert_eq!(stats.evicted, 20);
+        assert_eq!(stats.overwrite, 3 + 2);
+        assert_eq!(conn.actual_sql(&select).unwrap(), "SELECT name FROM users WHERE age = ");
+        assert_eq!(conn.actual_sql(&allow).unwrap(), "'Alice' ");
+    }
+
+    #[test]
+    fn eviction_policy() {
+        let mut conn = prepare();
+        conn.set_eviction_policy(owsql::EvictionPolicy::Rederive);
+        conn.set_overwrite_capacity(1);
+        let sql = conn.ow("SELECT name FROM users WHERE age =") + &conn.int(42) + &conn.ow("OR age =") + &conn.int(69);
+        for age in 0..10 {
+            conn.int(age);
+        }
+        assert_eq!(conn.overwrite_stats().evictable, 1);
+        let rows = conn.rows(&sql).unwrap();
+        assert_eq!(rows.len(), 2);
+        let stats = conn.overwrite_stats();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+
+        // A forged overwrite string is not derived.
+        let forged = sql.replace("3432", "3431");
+        assert_ne!(forged, sql);
+        assert_eq!(conn.rows(&forged), Err(OwsqlError::Message("token collision".into())));
+    }
+
+    #[test]
+    fn store_raw_text() {
+        let conn = prepare();
+        let name = "<b>O'Reilly & \"Sons\"</b>";
+        conn.execute(conn.ow("INSERT INTO users VALUES(") + name + &conn.ow(", 1);")).unwrap();
+        let rows = conn.rows(conn.ow("SELECT name FROM users WHERE age = 1;")).unwrap();
+        assert_eq!(rows[0].get("name"), Some(name));
+        assert_eq!(html_special_chars(rows[0].get("name").unwrap()), "&lt;b&gt;O&#39;Reilly &amp; &quot;Sons&quot;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn charset() {
+        let mut conn = prepare();
+        assert_eq!(conn.charset(), Charset::Utf8);
+        assert_eq!(conn.execute(conn.ow("SELECT") + "Alice\0' OR 1=1"), err!("nul character"));
+        assert_eq!(conn.tokenize(conn.ow("SELECT") + "\0"), err!("nul character"));
+
+        let sql = conn.ow("SELECT * FROM users WHERE name =") + "\u{7e3a}\\' OR 1=1; --";
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM users WHERE name = '\u{7e3a}\\'' OR 1=1; --' ");
+        conn.set_charset(Charset::Sjis);
+        assert!(conn.actual_sql(&sql).is_ok());
+        conn.set_escape_mode(EscapeMode::Backslash);
+        assert_eq!(conn.actual_sql(&sql), err!("unsafe multibyte character"));
+        conn.set_charset(Charset::Utf8);
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM users WHERE name = '\u{7e3a}\\\\'' OR 1=1; --' ");
+    }
+
+    #[test]
+    fn count_and_exists() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT * FROM users WHERE age >") + &conn.int(45);
+        assert_eq!(conn.count(&sql), Ok(2));
+        assert_eq!(conn.count(sql.clone() + &conn.ow(";")), Ok(2));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + "Alice' OR '1'='1"), Ok(0));
+        assert_eq!(conn.exists(&sql), Ok(true));
+        assert_eq!(conn.exists(conn.ow("SELECT * FROM users WHERE age >") + &conn.int(100)), Ok(false));
+        assert_eq!(conn.count(conn.ow("SELECT") + &conn.int("x")), err!("non integer"));
+        assert!(conn.exists(conn.ow("SELECT * FROM nothing")).is_err());
+    }
+
+    #[test]
+    fn strict() {
+        let mut conn = prepare();
+        let forgotten = conn.ow("SELECT * FROM users WHERE age <") + &conn.int(50) + "ORDER BY age";
+        let valid = conn.ow("SELECT * FROM users WHERE name =") + "Alice" + &conn.ow("OR name LIKE") + "B%" + &conn.ow("LIMIT") + "10";
+        assert!(!conn.is_strict());
+        assert!(conn.actual_sql(&forgotten).is_ok());
+        conn.set_strict(true);
+        assert_eq!(conn.actual_sql(&forgotten), err!("unexpected literal"));
+        assert_eq!(conn.rows(&forgotten), err!("unexpected literal"));
+        assert_eq!(conn.actual_sql("SELECT 1"), err!("unexpected literal"));
+        assert_eq!(conn.tokenize(conn.ow("SELECT 1;") + "DROP TABLE users"), err!("unexpected literal"));
+        assert_eq!(conn.rows(&valid).unwrap().len(), 2);
+        assert!(conn.actual_sql(conn.ow("INSERT INTO users VALUES(") + "Dave" + &conn.ow(",") + "20" + &conn.ow(")")).is_ok());
+        assert!(conn.actual_sql(conn.ow("SELECT * FROM users WHERE name =") + "Alice" + &conn.ow("")).is_ok());
+    }
+
+    #[test]
+    fn actual_sql_redacted() {
+        let conn = prepare();
+        let sql = conn.ow("UPDATE users SET name =") + "O'Reilly" + &conn.ow("WHERE name = 'Alice' OR age IN (") + &conn.int(42) + &conn.ow(",") + "69" + &conn.ow(");");
+        assert_eq!(
+            conn.actual_sql_redacted(&sql).unwrap(),
+            "UPDATE users SET name = ? WHERE name = 'Alice' OR age IN ( 42 , ? ); ");
+        assert_eq!(
+            conn.actual_sql(&sql).unwrap(),
+            "UPDATE users SET name = 'O''Reilly' WHERE name = 'Alice' OR age IN ( 42 , '69' ); ");
+        assert_eq!(conn.actual_sql_redacted(conn.ow("SELECT") + "\0"), err!("nul character"));
+        assert_eq!(conn.actual_sql_redacted(conn.ow("SELECT") + &conn.int("x")), err!("non integer"));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json() {
+        let mut conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE docs (body TEXT);")).unwrap();
+        let body = vec!["O'Reilly", "back\\slash", "\"quoted\""];
+        conn.execute(conn.ow("INSERT INTO docs VALUES(") + &conn.json(&body) + &conn.ow(");")).unwrap();
+        let rows = conn.rows(conn.ow("SELECT body FROM docs;")).unwrap();
+        assert_eq!(rows[0].get_json::<Vec<String>>("body").unwrap(), body);
+
+        conn.set_escape_mode(EscapeMode::Backslash);
+        conn.set_charset(Charset::Gbk);
+        assert_eq!(conn.actual_sql(conn.json(&"\u{7e3a}\n")), err!("unsafe multibyte character"));
+    }
+
+    #[test]
+    fn boolean() {
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE flags (name TEXT, active BOOLEAN);")).unwrap();
+        conn.execute(conn.ow("INSERT INTO flags VALUES('a',") + &conn.boolean(true) + &conn.ow(");")).unwrap();
+        conn.execute(conn.ow("INSERT INTO flags VALUES('b',") + &conn.boolean(false) + &conn.ow(");")).unwrap();
+        let rows = conn.rows(conn.ow("SELECT active FROM flags WHERE active =") + &conn.boolean(true)).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get_bool("active"), Some(true));
+        assert_eq!(conn.exists(conn.ow("SELECT 1 FROM flags WHERE active =") + &conn.boolean(false)), Ok(true));
+    }
+
+    #[test]
+    fn array() {
+        let conn = prepare();
+        assert_eq!(conn.actual_sql(conn.ow("SELECT") + &conn.array(&["a", "b"])), err!("unsupported array"));
+    }
+
+    #[test]
+    fn with_transaction() {
+        let conn = prepare();
+        let insert = |tx: &owsql::Transaction, name: &str| {
+            tx.execute(tx.ow("INSERT INTO users VALUES(") + name + &tx.ow(", 0);"))
+        };
+        assert_eq!(conn.with_transaction(|tx| { insert(tx, "Dave")?; tx.count(tx.ow("SELECT * FROM users;")) }), Ok(4));
+        assert_eq!(conn.with_transaction(|tx| { insert(tx, "Eve")?; tx.execute("invalid query") }), syntax_err!("exec error"));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            conn.with_transaction::<(), _>(|tx| { insert(tx, "Frank")?; panic!("abort") })
+        }));
+        assert!(result.is_err());
+        let rows = conn.rows(conn.ow("SELECT name FROM users WHERE age = 0;")).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some("Dave"));
+        assert_eq!(conn.with_transaction(|tx| tx.with_transaction(|tx| insert(tx, "Gil"))), err!("nested transaction"));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE age = 0;")), Ok(1));
+    }
+
+    #[test]
+    fn with_transaction_failed_commit() {
+        let conn = prepare();
+        conn.execute(conn.ow("PRAGMA foreign_keys = ON; CREATE TABLE orders (user TEXT REFERENCES users(name) DEFERRABLE INITIALLY DEFERRED);")).unwrap();
+        conn.execute(conn.ow("CREATE UNIQUE INDEX users_name ON users(name);")).unwrap();
+        let result = conn.with_transaction(|tx| tx.execute(tx.ow("INSERT INTO orders VALUES('Nobody');")));
+        assert!(result.is_err());
+        assert_eq!(conn.with_transaction(|tx| tx.execute(tx.ow("INSERT INTO orders VALUES('Alice');"))), Ok(()));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM orders;")), Ok(1));
+    }
+
+    #[test]
+    fn with_transaction_options() {
+        use owsql::{IsolationLevel, TransactionBehavior, TransactionOptions};
+        let conn = prepare();
+        let read_only = TransactionOptions { read_only: true, ..Default::default() };
+        assert_eq!(conn.with_transaction_options(&read_only, |tx| tx.count(tx.ow("SELECT * FROM users;"))), Ok(3));
+        assert!(conn.with_transaction_options(&read_only, |tx| tx.execute(tx.ow("DELETE FROM users;"))).is_err());
+        let options = TransactionOptions {
+            isolation: Some(IsolationLevel::Serializable),
+            behavior:  TransactionBehavior::Immediate,
+            ..Default::default()
+        };
+        assert_eq!(conn.with_transaction_options(&options, |tx| tx.execute(tx.ow("DELETE FROM users;"))), Ok(()));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(0));
+    }
+
+    #[test]
+    fn with_retry() {
+        use owsql::{OwsqlError, RetryPolicy};
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let conn = owsql::sqlite::open(&path).unwrap();
+        conn.execute(conn.ow(stmt())).unwrap();
+        let locker = owsql::sqlite::open(&path).unwrap();
+        locker.execute(locker.ow("BEGIN EXCLUSIVE;")).unwrap();
+
+        let policy = RetryPolicy { max_retries: 2, ..Default::default() };
+        let mut attempts = 0;
+        let result = conn.with_retry(&policy, |conn| {
+            attempts += 1;
+            conn.execute(conn.ow("DELETE FROM users;"))
+        });
+        assert_eq!(attempts, 3);
+        let busy = OwsqlError::Database { code: Some(5), sqlstate: None, message: "exec error".into(), constraint: None };
+        assert_eq!(result, Err(OwsqlError::RetryExhausted { attempts: 3, error: Box::new(busy) }));
+
+        let mut attempts = 0;
+        let result = conn.with_retry(&policy, |conn| {
+            attempts += 1;
+            if attempts == 2 {
+                locker.execute(locker.ow("COMMIT;")).unwrap();
+            }
+            conn.count(conn.ow("SELECT * FROM users;"))
+        });
+        assert_eq!((attempts, result), (2, Ok(3)));
+
+        let mut attempts = 0;
+        assert_eq!(conn.with_retry(&policy, |conn| { attempts += 1; conn.execute("invalid query") }), syntax_err!("exec error"));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn database_error() {
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE UNIQUE INDEX users_name ON users (name);")).unwrap();
+        match conn.execute(conn.ow("INSERT INTO users VALUES(") + "Alice" + &conn.ow(", 0);")) {
+            Err(OwsqlError::Database { code, .. }) => assert_eq!(code, Some(2067)),  // SQLITE_CONSTRAINT_UNIQUE
+            result => panic!("{:?}", result),
+        }
+        match conn.execute(conn.ow("SELECT * FROM nothing;")) {
+            Err(OwsqlError::Database { code, message, .. }) => assert_eq!((code, &*message), (Some(1), "exec error")),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn error_level_per_call() {
+        let mut conn = prepare();
+        conn.error_level(OwsqlErrorLevel::Release).unwrap();
+        conn.set_strict(true);
+        let invalid = conn.ow("INVALID SQL;");
+        let unexpected = conn.ow("SELECT * FROM users") + "Alice";
+        assert_eq!(conn.execute(&invalid),                                                  syntax_err!("exec error"));
+        assert_eq!(conn.execute(&unexpected),                                               err!());
+        assert_eq!(conn.execute_with_level(&invalid, OwsqlErrorLevel::Debug),
+            syntax_err!("exec error: near \"INVALID\": syntax error"));
+        assert_eq!(conn.execute_with_level(&unexpected, OwsqlErrorLevel::Develop),          err!("unexpected literal"));
+        assert_eq!(conn.execute_with_level(&unexpected, OwsqlErrorLevel::Debug),            err!("unexpected literal: 'Alice'"));
+        assert_eq!(conn.iterate_with_level(&unexpected, OwsqlErrorLevel::Develop, |_| true), err!("unexpected literal"));
+        assert_eq!(conn.rows_with_level(&invalid, OwsqlErrorLevel::AlwaysOk),               Ok(vec![]));
+        assert_eq!(conn.execute(&unexpected),                                               err!());
+    }
+
+    #[test]
+    fn prepare_statement() {
+        let conn = prepare();
+        let select = conn.prepare("SELECT name FROM users WHERE name = ? OR age > ? OR name = '?';", 2).unwrap();
+        assert_eq!(select.param_count(), 2);
+        let rows = conn.rows(select.bind(params!["Alice", 60])).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(conn.rows(select.bind(params!["Alice' OR 'a'='a", 100])).unwrap().len(), 0);
+        assert_eq!(conn.execute(select.bind(params!["Alice"])), err!("parameter count mismatch"));
+        assert_eq!(conn.prepare("SELECT ? AS a, ?", 1).unwrap_err(), OwsqlError::Message("placeholder count mismatch".into()));
+        assert_eq!(conn.count(conn.prepare("SELECT ? AS name", 1).unwrap().bind(params!["x"])), Ok(1));
+    }
+
+    #[test]
+    fn paginate() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT name FROM users ORDER BY age;");
+        let page = conn.paginate_with_total(&sql, 1, 2).unwrap();
+        assert_eq!(page.rows.iter().map(|row| row.get("name").unwrap()).collect::<Vec<_>>(), ["Alice", "Carol"]);
+        assert_eq!(page.total, Some(3));
+        assert!(page.has_next);
+        let page = conn.paginate(&sql, 2, 2).unwrap();
+        assert_eq!(page.rows.len(), 1);
+        assert_eq!(page.total, None);
+        assert!(!page.has_next);
+        assert!(conn.paginate(&sql, 3, 2).unwrap().rows.is_empty());
+        assert_eq!(conn.paginate(&sql, 0, 2), err!("invalid page"));
+        assert_eq!(conn.paginate(&sql, 1, 0), err!("invalid page"));
+        assert_eq!(conn.paginate(&sql, u64::MAX, 2), err!("invalid page"));
+        let sql = conn.ow("SELECT name FROM users WHERE name =") + "Alice' OR 'a'='a";
+        assert_eq!(conn.paginate_with_total(&sql, 1, 10).unwrap().total, Some(0));
+    }
+
+    #[test]
+    fn keyset_page() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT * FROM users;");
+        let page = conn.keyset_page(&sql, "name", None::<&str>, 2).unwrap();
+        assert_eq!(page.rows.iter().map(|row| row.get("name").unwrap()).collect::<Vec<_>>(), ["Alice", "Bob"]);
+        assert!(page.has_next);
+        let page = conn.keyset_page(&sql, "name", page.rows[1].get("name"), 2).unwrap();
+        assert_eq!(page.rows.iter().map(|row| row.get("name").unwrap()).collect::<Vec<_>>(), ["Carol"]);
+        assert!(!page.has_next);
+        let page = conn.keyset_page(&sql, "age", Some(conn.int(42)), 5).unwrap();
+        assert_eq!(page.rows.iter().map(|row| row.get("age").unwrap()).collect::<Vec<_>>(), ["50", "69"]);
+        assert!(conn.keyset_page(&sql, "name", Some("' OR 'a'='a"), 5).unwrap().rows.len() == 3);
+        assert!(conn.keyset_page(&sql, "name", Some("Z' OR 'a'='a"), 5).unwrap().rows.is_empty());
+        assert_eq!(conn.keyset_page(&sql, "age", Some("x"), 0), err!("invalid page"));
+        assert_eq!(conn.keyset_page(&sql, "age", Some(conn.int("x")), 5), err!("non integer"));
+    }
+
+    #[test]
+    fn query_cache() {
+        let mut conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE items (name TEXT);")).unwrap();
+        conn.enable_query_cache(10, std::time::Duration::from_secs(60));
+        let users = conn.ow("SELECT * FROM users;");
+        let items = conn.ow("SELECT * FROM items;");
+        assert_eq!(conn.rows(&users).unwrap().len(), 3);
+        assert_eq!(conn.rows(&items).unwrap().len(), 0);
+
+        // A cached statement is not executed.
+        conn.set_metrics(true);
+        assert_eq!(conn.rows(&users).unwrap().len(), 3);
+        assert!(conn.metrics().unwrap().statements().is_empty());
+
+        conn.execute(conn.ow("DELETE FROM users WHERE name = 'Bob';")).unwrap();
+        assert_eq!(conn.rows(&users).unwrap().len(), 2);
+        assert_eq!(conn.metrics().unwrap().statements().len(), 2);
+        assert_eq!(conn.rows(&items).unwrap().len(), 0);
+        assert_eq!(conn.metrics().unwrap().statements().len(), 2);
+
+        // A write to the quoted table invalidates the entries of the table.
+        conn.execute(conn.ow("INSERT INTO `users` VALUES ('Bob', 69);")).unwrap();
+        assert_eq!(conn.rows(&users).unwrap().len(), 3);
+        conn.execute(conn.ow("DELETE FROM [users] WHERE name = 'Bob';")).unwrap();
+        assert_eq!(conn.rows(&users).unwrap().len(), 2);
+
+        let _ = conn.with_transaction(|tx| {
+            tx.execute(tx.ow("INSERT INTO items VALUES ('pen');"))?;
+            assert_eq!(tx.rows(&items).unwrap().len(), 1);
+            Err::<(), _>(owsql::OwsqlError::AnyError)
+        });
+        assert_eq!(conn.rows(&items).unwrap().len(), 0);
+
+        conn.disable_query_cache();
+        conn.enable_query_cache(10, std::time::Duration::from_secs(0));
+        assert_eq!(conn.rows(&users).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn set_session() {
+        let conn = prepare();
+        conn.set_session("foreign_keys", "ON").unwrap();
+        conn.set_session("main.cache_size", "-4096").unwrap();
+        assert!(conn.pragma().foreign_keys().unwrap());
+        assert_eq!(conn.pragma().cache_size(), Ok(-4096));
+
+        // The value is escaped, so the statement is not injected.
+        conn.set_session("foreign_keys", "OFF; DROP TABLE users").unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+        assert!(conn.set_session("foreign_keys = ON; DROP TABLE users; --", "ON").is_err());
+        conn.set_session("FOREIGN_KEYS", "ON").unwrap();
+        assert_eq!(conn.session_settings(), [
+            ("foreign_keys".to_string(), "ON".to_string()),
+            ("main.cache_size".to_string(), "-4096".to_string()),
+        ]);
+
+        let new = owsql::sqlite::open(":memory:").unwrap();
+        new.replay_session(&conn).unwrap();
+        assert!(new.pragma().foreign_keys().unwrap());
+        assert_eq!(new.pragma().cache_size(), Ok(-4096));
+        assert_eq!(new.session_settings(), conn.session_settings());
+    }
+
+    #[test]
+    fn slow_query_threshold() {
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let mut conn = prepare();
+        let slow = Arc::new(Mutex::new(Vec::new()));
+        let callback = Arc::clone(&slow);
+        conn.set_slow_query_threshold(Duration::from_secs(0), move |sql, _| callback.lock().unwrap().push(sql.to_string()));
+        conn.execute(conn.ow("SELECT * FROM users WHERE name =") + "Alice").unwrap();
+        conn.rows(conn.ow("SELECT * FROM users WHERE age >") + &conn.int(50)).unwrap();
+        assert_eq!(*slow.lock().unwrap(), ["SELECT * FROM users WHERE name = ? ", "SELECT * FROM users WHERE age > 50 "]);
+
+        let callback = Arc::clone(&slow);
+        conn.set_slow_query_threshold(Duration::from_secs(3600), move |sql, _| callback.lock().unwrap().push(sql.to_string()));
+        conn.execute(conn.ow("SELECT * FROM users;")).unwrap();
+        assert_eq!(slow.lock().unwrap().len(), 2);
+
+        conn.clear_slow_query_threshold();
+        assert_eq!(Arc::strong_count(&slow), 1);
+    }
+
+    #[test]
+    fn validate() {
+        let conn = prepare();
+        let sql = conn.ow("DELETE FROM users WHERE name =") + "Alice' OR 1=1; DROP TABLE users; --";
+        assert!(conn.validate(&sql).is_ok());
+        assert!(conn.validate(conn.ow("DELETE FROM users; DELETE users;")).is_err());
+        assert!(conn.validate(conn.ow("CREATE TABLE items (name TEXT); SELECT * FROM items;")).is_err());
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+        assert!(conn.validate(conn.ow("  ;  -- comment")).is_ok());
+    }
+
+    #[test]
+    fn rows_into() {
+        use std::collections::VecDeque;
+        let conn = prepare();
+        let rows: VecDeque<_> = conn.rows_into(conn.ow("SELECT * FROM users ORDER BY age;")).unwrap();
+        assert_eq!(rows.front().unwrap().get("name"), Some("Alice"));
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn column() {
+        let conn = prepare();
+        assert_eq!(conn.column::<String, _>(conn.ow("SELECT name, age FROM users ORDER BY age DESC;")).unwrap(), ["Bob", "Carol", "Alice"]);
+        assert_eq!(conn.column::<u8, _>(conn.ow("SELECT age FROM users WHERE name =") + "Bob"), Ok(vec![69]));
+        assert_eq!(conn.column::<u8, _>(conn.ow("SELECT age FROM users WHERE age > 100;")), Ok(vec![]));
+        assert_eq!(
+            conn.column::<u8, _>(conn.ow("SELECT name FROM users;")),
+            Err(owsql::OwsqlError::Message("invalid column".into()))
+        );
+        assert!(conn.column::<String, _>(conn.ow("SELECT NULL;")).is_err());
+    }
+
+    #[test]
+    fn first_row() {
+        let mut conn = prepare();
+        conn.set_metrics(true);
+        let row = conn.first_row(conn.ow("SELECT * FROM users ORDER BY age;")).unwrap().unwrap();
+        assert_eq!(row.get("name"), Some("Alice"));
+        assert_eq!(conn.metrics().unwrap().statements()[0].errors, 0);
+        assert_eq!(conn.first_row(conn.ow("SELECT * FROM users WHERE name =") + "Alice' OR 'a'='a"), Ok(None));
+        assert!(conn.first_row(conn.ow("SELECT * FROM items;")).is_err());
+    }
+
+    #[test]
+    fn single_row() {
+        let conn = prepare();
+        let row = conn.single_row(conn.ow("SELECT * FROM users WHERE name =") + "Carol").unwrap();
+        assert_eq!(row.get_into::<u8>("age"), Ok(50));
+        assert_eq!(
+            conn.single_row(conn.ow("SELECT * FROM users WHERE age > 100;")),
+            Err(owsql::OwsqlError::Message("no rows".into()))
+        );
+        assert_eq!(
+            conn.single_row(conn.ow("SELECT * FROM users WHERE age > 45;")),
+            Err(owsql::OwsqlError::Message("multiple rows".into()))
+        );
+    }
+
+    #[test]
+    fn audit_report() {
+        use owsql::AuditKind;
+        let mut conn = prepare();
+        conn.add_allowlist(owsql::params!["Alice"]);
+        conn.set_audit(true);
+        let line = line!() + 1;
+        let sql = conn.ow("SELECT * FROM users WHERE name =") + &conn.allowlist("Alice") + &conn.ow("OR age =") + &conn.int(69);
+        conn.execute(&sql).unwrap();
+        conn.execute(conn.ow("SELECT * FROM users WHERE name =") + "Bob").unwrap();
+
+        let entries = conn.audit_report().unwrap().entries();
+        let kinds = entries.iter().map(|entry| (entry.kind, entry.fragment.as_str(), entry.count)).collect::<Vec<_>>();
+        assert_eq!(kinds, [
+            (AuditKind::Trusted,   "SELECT * FROM users WHERE name =", 1),
+            (AuditKind::Trusted,   "OR age =", 1),
+            (AuditKind::Trusted,   "SELECT * FROM users WHERE name =", 1),
+            (AuditKind::Integer,   "69", 1),
+            (AuditKind::Allowlist, "Alice", 1),
+            (AuditKind::Escaped,   "'Bob'", 1),
+        ]);
+        assert!(entries[..5].iter().all(|entry| entry.location.unwrap().file() == file!()));
+        assert_eq!(entries[0].location.unwrap().line(), line);
+
+        conn.set_audit(false);
+        assert!(conn.audit_report().is_none());
+    }
+
+    #[test]
+    fn fragment_policy() {
+        use owsql::{OwsqlError, Policy};
+        let mut conn = prepare();
+        let drop = conn.ow("DROP TABLE users;");
+        conn.set_fragment_policy(Policy::DenyKeywords(&["DROP", "GRANT"]));
+        let violation = OwsqlError::PolicyViolation { keyword: "DROP".into(), fragment: "DROP TABLE users;".into() };
+        assert_eq!(conn.execute(conn.ow("DROP TABLE users;")), Err(violation.clone()));
+        assert_eq!(conn.ow_runtime("drop table users;"), Err(OwsqlError::PolicyViolation {
+            keyword: "DROP".into(), fragment: "drop table users;".into(),
+        }));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users WHERE name =") + "DROP"), Ok(0));
+
+        // A fragment registered before the policy was set is not screened.
+        conn.execute(&drop).unwrap();
+        assert!(conn.execute(conn.ow("SELECT * FROM users;")).is_err());
+    }
+
+    #[test]
+    fn replica_set() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let mut writer = owsql::sqlite::open(&path).unwrap();
+        writer.set_metrics(true);
+        let mut readers = vec![owsql::sqlite::open_readonly(&path).unwrap(), owsql::sqlite::open_readonly(&path).unwrap()];
+        readers.iter_mut().for_each(|reader| reader.set_metrics(true));
+        let db = owsql::ReplicaSet::new(writer, readers);
+
+        db.execute(db.ow(stmt())).unwrap();
+        let sql = db.ow("SELECT * FROM users WHERE name =") + "Alice' OR 'a'='a";
+        assert_eq!(db.rows(&sql).unwrap().len(), 0);
+        assert_eq!(db.rows(db.ow("SELECT * FROM users WHERE age >") + &db.int(45)).unwrap().len(), 2);
+        let mut names = Vec::new();
+        db.iterate(db.ow("SELECT name FROM users ORDER BY age;"), |pairs| {
+            names.push(pairs[0].1.unwrap().to_string());
+            true
+        }).unwrap();
+        assert_eq!(names, ["Alice", "Carol", "Bob"]);
+        db.execute(db.ow("UPDATE users SET age = 0;")).unwrap();
+
+        let count = |conn: &owsql::Connection| conn.metrics().unwrap().statements().iter().map(|s| s.count).sum::<u64>();
+        assert_eq!(count(db.writer()), 2);
+        assert_eq!(count(&db.readers()[0]), 2);
+        assert_eq!(count(&db.readers()[1]), 1);
+        assert_eq!(db.readers()[1].count(db.readers()[1].ow("SELECT * FROM users WHERE age = 0;")), Ok(3));
+    }
+
+    #[test]
+    fn replica_set_read_your_writes() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let mut writer = owsql::sqlite::open(&path).unwrap();
+        writer.set_metrics(true);
+        let mut reader = owsql::sqlite::open_readonly(&path).unwrap();
+        reader.set_metrics(true);
+        let mut db = owsql::ReplicaSet::new(writer, vec![reader]);
+        db.after_write_read_from_primary(std::time::Duration::from_secs(3600));
+
+        let count = |conn: &owsql::Connection| conn.metrics().unwrap().statements().iter().map(|s| s.count).sum::<u64>();
+        let select = db.ow("SELECT * FROM users;");
+        assert!(db.rows(&select).is_err());
+        assert_eq!((count(db.writer()), count(&db.readers()[0])), (0, 1));
+        db.execute(db.ow(stmt())).unwrap();
+        assert_eq!(db.rows(&select).unwrap().len(), 3);
+        assert_eq!((count(db.writer()), count(&db.readers()[0])), (2, 1));
+        db.clear_after_write_read_from_primary();
+        assert_eq!(db.rows(&select).unwrap().len(), 3);
+        assert_eq!((count(db.writer()), count(&db.readers()[0])), (2, 2));
+    }
+
+    #[test]
+    fn replica_set_rewriter() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let mut writer = owsql::sqlite::open(&path).unwrap();
+        writer.add_rewriter(|sql| sql.replace("ORDER BY age", "ORDER BY age DESC"));
+        let db = owsql::ReplicaSet::new(writer, vec![owsql::sqlite::open_readonly(&path).unwrap()]);
+        db.execute(db.ow(stmt())).unwrap();
+        let rows = db.rows(db.ow("SELECT name FROM users ORDER BY age;")).unwrap();
+        assert_eq!(rows[0].get("name"), Some("Bob"));
+    }
+
+    #[test]
+    fn replica_set_audit() {
+        use owsql::AuditKind;
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let mut writer = owsql::sqlite::open(&path).unwrap();
+        writer.set_audit(true);
+        let db = owsql::ReplicaSet::new(writer, vec![owsql::sqlite::open_readonly(&path).unwrap()]);
+        db.execute(db.ow("CREATE TABLE users (name TEXT);")).unwrap();
+        db.execute(db.ow("INSERT INTO users VALUES (") + "Alice" + &db.ow(");")).unwrap();
+        db.iterate(db.ow("INSERT INTO users VALUES (") + "Alice" + &db.ow(");"), |_| true).unwrap();
+        db.rows(db.ow("INSERT INTO users VALUES (") + "Alice" + &db.ow(");")).unwrap();
+
+        let entries = db.writer().audit_report().unwrap().entries();
+        let escaped = entries.iter().filter(|entry| entry.kind == AuditKind::Escaped).map(|entry| (entry.fragment.as_str(), entry.count)).collect::<Vec<_>>();
+        assert_eq!(escaped, [("'Alice'", 3)]);
+    }
+
+    #[test]
+    fn token_collision() {
+        let conn = prepare();
+        let other = prepare();
+        let select = conn.ow("SELECT * FROM users WHERE name =");
+        // A token of another connection is not trusted.
+        let forged = other.ow("'' OR 1=1;").trim().to_string();
+        assert_eq!(conn.execute(select.clone() + &forged), Err(OwsqlError::Message("token collision".into())));
+        assert_eq!(conn.execute(select.clone() + "OWSQLxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx1"), Err(OwsqlError::Message("token collision".into())));
+        assert!(!select.contains(other.ow("SELECT * FROM users WHERE name =").trim()));
+        assert_eq!(conn.rows(select + "OWSQLite").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn token_alphabet() {
+        let mut conn = prepare();
+        conn.set_token_alphabet(TokenAlphabet::Custom("01")).unwrap();
+        assert_eq!(conn.token_entropy_bits(), 128);
+        let sql = conn.ow("SELECT name FROM users WHERE age =") + &conn.int(42);
+        assert!(sql.len() > 2 * (5 + 128));
+        assert_eq!(conn.rows(&sql).unwrap()[0].get("name"), Some("Alice"));
+        assert!(conn.set_token_alphabet(TokenAlphabet::Custom("")).is_err());
+        assert_eq!(conn.token_entropy_bits(), 128);
+    }
+
+    #[test]
+    #[cfg(feature = "dialect-check")]
+    fn dialect_check() {
+        let mut conn = prepare();
+        assert_eq!(conn.rows(conn.ow("SELECT * FORM users;")), err!("unknown keyword"));
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users WHERE name ILIKE") + "a%"), err!("unknown keyword"));
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users JOIN WHERE age =") + &conn.int(42)), err!("dangling JOIN"));
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users u JOIN users v ON u.age = v.age;")).unwrap().len(), 3);
+        conn.error_level(OwsqlErrorLevel::Release).unwrap();
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users WHERE name GLOB") + "A*").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn actual_sql_pretty() {
+        let conn = prepare();
+        let sql = conn.ow("select * from users where age >") + &conn.int(45) + &conn.ow("and name <>") + "Bob' OR 'a'='a";
+        assert_eq!(conn.actual_sql_pretty(&sql).unwrap(), "SELECT *\nFROM users\nWHERE age > 45\n    AND name <> 'Bob'' OR ''a''=''a'");
+        assert_eq!(conn.actual_sql_pretty(conn.ow("SELECT") + "\0"), err!("nul character"));
+    }
+
+    #[test]
+    fn canonical_whitespace() {
+        let mut conn = prepare();
+        conn.set_canonical_whitespace(true);
+        let sql = conn.ow("SELECT name\nFROM users -- adults only\nWHERE age >") + &conn.int(45) + &conn.ow("AND name <>") + "Bob  Smith" + &conn.ow(";");
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name FROM users -- adults only\nWHERE age > 45 AND name <> 'Bob  Smith' ;");
+        assert_eq!(conn.actual_sql_redacted(&sql).unwrap(), "SELECT name FROM users -- adults only\nWHERE age > 45 AND name <> ? ;");
+        assert_eq!(conn.rows(&sql).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn comments() {
+        let mut conn = prepare();
+        let sql = conn.ow("SELECT name /* don't */ FROM users -- the user's name\nWHERE name = -- input") + "\nOR 1=1 --";
+        assert_eq!(conn.rows(&sql).unwrap().len(), 0);
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name /* don't */ FROM users -- the user's name\nWHERE name = -- input\n'OR 1=1 --' ");
+        assert_eq!(conn.actual_sql(conn.ow("SELECT /* name") + "*/ 1 /*" + &conn.ow("*/")), err!("invalid comment"));
+        assert_eq!(conn.tokenize(conn.ow("SELECT -- DROP\n1")).unwrap().len(), 2);
+        // `E'...'` is not an escape string on SQLite, so the line comment is detected.
+        let escape = conn.ow("SELECT E'\\' -- '") + "payload" + &conn.ow("AS x;");
+        assert_eq!(conn.actual_sql(&escape).unwrap(), "SELECT E'\\' -- '\n'payload' AS x; ");
+
+        conn.set_comment_mode(CommentMode::Strip);
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT name   FROM users \nWHERE name =  'OR 1=1 --' ");
+        assert_eq!(conn.rows(conn.ow("SELECT name FROM users WHERE age = 42; -- the answer")).unwrap()[0].get("name"), Some("Alice"));
+    }
+
+    #[test]
+    fn call() {
+        let conn = prepare();
+        let rows = conn.call("upper", params!["Bob' OR 'a'='a"]).unwrap();
+        assert_eq!(rows[0].get("upper"), Some("BOB' OR 'A'='A"));
+        assert_eq!(conn.call("upper('a') --", params![]), err!("invalid procedure name"));
+        assert_eq!(conn.call_out("upper", params!["a"], &["total"]), err!("unsupported OUT parameter"));
+        assert_eq!(conn.call("length", params![""]).unwrap()[0].get("length"), Some("0"));
+    }
+
+    #[test]
+    fn values() {
+        let conn = prepare();
+        let sql = conn.ow("INSERT INTO users (name, age)") + &conn.values(&[params!["Dave' OR 'a'='a", conn.int(30)], params!["Eve", conn.int(25)]]);
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "INSERT INTO users (name, age) VALUES ( 'Dave'' OR ''a''=''a' , 30 ), ( 'Eve' , 25 ) ");
+        conn.execute(&sql).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(5));
+
+        let sql = conn.ow("SELECT users.name, v.column2 AS title FROM users JOIN (")
+            + &conn.values(&[params!["Alice", "Dr."], params!["Bob", "Mr."]])
+            + &conn.ow(") v ON users.name = v.column1 ORDER BY users.name;");
+        let rows = conn.rows(&sql).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get("title"), Some("Mr."));
+
+        let sql = conn.ow("SELECT * FROM (") + &conn.values(&[params!["", 1]]) + &conn.ow(");");
+        assert_eq!(conn.actual_sql(&sql).unwrap(), "SELECT * FROM ( VALUES ( '' , '1' ) ); ");
+        assert_eq!(conn.single_row(&sql).unwrap().get("column1"), Some(""));
+
+        assert_eq!(conn.execute(conn.ow("INSERT INTO users (name, age)") + &conn.values(&[params!["Fay", 1], params!["Gil"]])), err!("row length mismatch"));
+        assert_eq!(conn.execute(conn.ow("INSERT INTO users (name, age)") + &conn.values(&[])), err!("empty values"));
+    }
+
+    #[test]
+    fn execute_chunked() {
+        let mut conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE members (name TEXT UNIQUE, age INTEGER);")).unwrap();
+        conn.set_chunk_limit(ChunkLimit { max_values: Some(2), ..Default::default() });
+        let changes = conn.execute_chunked("INSERT INTO members (name, age)",
+            &[params!["Alice", 42], params!["Bob' OR 'a'='a", 69], params!["Carol", 50]]);
+        assert_eq!(changes, Ok(3));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM members WHERE name = 'Bob'' OR ''a''=''a';")), Ok(1));
+
+        // The chunks are rolled back together.
+        let changes = conn.execute_chunked("INSERT INTO members (name, age)", &[params!["Dave", 30], params!["Alice", 42]]);
+        assert!(changes.is_err());
+        assert_eq!(conn.count(conn.ow("SELECT * FROM members;")), Ok(3));
+
+        conn.set_chunk_limit(ChunkLimit { max_values: Some(1), ..Default::default() });
+        assert_eq!(conn.execute_chunked("INSERT INTO members (name, age)", &[params!["Eve", 25]]), err!("statement too large"));
+    }
+
+    #[test]
+    fn observer() {
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        #[derive(Default)]
+        struct Events(Mutex<Vec<String>>);
+
+        impl Observer for Events {
+            fn on_connect(&self, dialect: Dialect) {
+                self.0.lock().unwrap().push(format!("connect {:?}", dialect));
+            }
+            fn on_disconnect(&self) {
+                self.0.lock().unwrap().push("disconnect".to_string());
+            }
+            fn on_statement_start(&self, sql: &str) {
+                self.0.lock().unwrap().push(format!("start {}", sql));
+            }
+            fn on_statement_end(&self, sql: &str, _elapsed: Duration, error: Option<&OwsqlError>) {
+                self.0.lock().unwrap().push(format!("end {} {}", sql, error.is_some()));
+            }
+            fn on_error(&self, _sql: &str, error: &OwsqlError) {
+                self.0.lock().unwrap().push(format!("error {}", error));
+            }
+            fn on_transaction_begin(&self) {
+                self.0.lock().unwrap().push("begin".to_string());
+            }
+            fn on_commit(&self) {
+                self.0.lock().unwrap().push("commit".to_string());
+            }
+            fn on_rollback(&self) {
+                self.0.lock().unwrap().push("rollback".to_string());
+            }
+        }
+
+        let events = Arc::new(Events::default());
+        let mut conn = prepare();
+        conn.set_observer(events.clone());
+        conn.rows(conn.ow("SELECT * FROM users WHERE name =") + "Alice").unwrap();
+        let _ = conn.with_transaction(|tx| tx.execute(tx.ow("INSERT INTO users VALUES ('Dave', 30);")));
+        let _ = conn.with_transaction(|tx| tx.execute(tx.ow("INSERT INTO nothing VALUES ('Dave', 30);")));
+        drop(conn);
+
+        assert_eq!(*events.0.lock().unwrap(), [
+            "connect Sqlite",
+            "start SELECT * FROM users WHERE name = ? ",
+            "end SELECT * FROM users WHERE name = ?  false",
+            "begin",
+            "start INSERT INTO users VALUES ('Dave', 30); ",
+            "end INSERT INTO users VALUES ('Dave', 30);  false",
+            "commit",
+            "begin",
+            "start INSERT INTO nothing VALUES ('Dave', 30); ",
+            "end INSERT INTO nothing VALUES ('Dave', 30);  true",
+            "error exec error",
+            "rollback",
+            "disconnect",
+        ]);
+    }
+
+    #[test]
+    fn duplicate_columns() {
+        let mut conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE orders (id INTEGER, user TEXT); INSERT INTO orders VALUES (7, 'Alice');")).unwrap();
+        conn.execute(conn.ow("ALTER TABLE users ADD COLUMN id INTEGER; UPDATE users SET id = age;")).unwrap();
+        let sql = conn.ow("SELECT users.id, orders.id FROM users JOIN orders ON users.name = orders.user;");
+        let row = conn.single_row(&sql).unwrap();
+        assert_eq!(row.column_names(), ["id", "id"]);
+        assert_eq!(row.get_at(0), Some("42"));
+        assert_eq!(row.get_at(1), Some("7"));
+        conn.execute(conn.ow("PRAGMA short_column_names = OFF; PRAGMA full_column_names = ON;")).unwrap();
+        let row = conn.single_row(&sql).unwrap();
+        assert_eq!(row.get("users.id"),  Some("42"));
+        assert_eq!(row.get("orders.id"), Some("7"));
+        conn.execute(conn.ow("PRAGMA full_column_names = OFF; PRAGMA short_column_names = ON;")).unwrap();
+        conn.set_unique_columns(true);
+        assert_eq!(conn.single_row(&sql), err!("duplicate column"));
+        assert_eq!(conn.rows(&sql), err!("duplicate column"));
+        assert!(conn.rows(conn.ow("SELECT users.id AS user_id, orders.id FROM users JOIN orders ON users.name = orders.user;")).is_ok());
+    }
+
+    #[test]
+    fn executor() {
+        use owsql::Executor;
+        fn names<E: Executor>(db: &E, age: i32) -> Vec<String> {
+            let mut names = Vec::new();
+            db.iterate(db.ow("SELECT name FROM users WHERE age >") + &db.int(age) + &db.ow("ORDER BY name;"), |pairs| {
+                names.push(pairs[0].1.unwrap().to_string());
+                true
+            }).unwrap();
+            names
+        }
+        let conn = prepare();
+        assert_eq!(names(&conn, 45), ["Bob", "Carol"]);
+        conn.with_transaction(|tx| {
+            tx.execute(tx.ow("DELETE FROM users WHERE name =") + "Bob")?;
+            assert_eq!(names(tx, 45), ["Carol"]);
+            assert_eq!(Executor::rows(tx, tx.ow("SELECT * FROM users;"))?.len(), 2);
+            Ok(())
+        }).unwrap();
+        let db = owsql::ReplicaSet::new(conn, vec![]);
+        assert_eq!(names(&db, 0), ["Alice", "Carol"]);
+    }
+
+    #[test]
+    fn reader_handle() {
+        use owsql::sqlite::JournalMode;
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let conn = owsql::sqlite::open(&path).unwrap();
+        conn.pragma().set_journal_mode(JournalMode::Wal).unwrap();
+        conn.execute(conn.ow(stmt())).unwrap();
+        let reader = conn.reader_handle().unwrap();
+
+        conn.execute(conn.ow("BEGIN; DELETE FROM users WHERE name =") + "Bob" + &conn.ow(";")).unwrap();
+        let handles = (0..2).map(|_| {
+            let reader = reader.clone();
+            std::thread::spawn(move || reader.with(|conn| conn.count(conn.ow("SELECT * FROM users;"))))
+        }).collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Ok(3));
+        }
+        conn.execute(conn.ow("COMMIT;")).unwrap();
+        assert_eq!(reader.with(|conn| conn.count(conn.ow("SELECT * FROM users;"))), Ok(2));
+        assert!(reader.with(|conn| conn.execute(conn.ow("DELETE FROM users;"))).is_err());
+
+        let memory = owsql::sqlite::open(":memory:").unwrap();
+        assert_eq!(memory.reader_handle().unwrap_err(), owsql::OwsqlError::Message("unsupported reader handle: in-memory database".into()));
+    }
+
+    #[test]
+    fn reader_handle_settings() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let mut conn = owsql::sqlite::open(&path).unwrap();
+        conn.execute(conn.ow(stmt())).unwrap();
+        conn.set_fragment_policy(owsql::Policy::DenyKeywords(&["DROP"]));
+        conn.add_rewriter(|sql| sql.replace("ORDER BY age", "ORDER BY age DESC"));
+        let reader = conn.reader_handle().unwrap();
+
+        assert_eq!(reader.with(|conn| conn.execute(conn.ow("DROP TABLE users;"))), Err(OwsqlError::PolicyViolation {
+            keyword:  "DROP".into(),
+            fragment: "DROP TABLE users;".into(),
+        }));
+        let rows = reader.with(|conn| conn.rows(conn.ow("SELECT name FROM users ORDER BY age;"))).unwrap();
+        assert_eq!(rows[0].get("name"), Some("Bob"));
+    }
+
+    #[test]
+    fn close() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("test.db");
+        let conn = owsql::sqlite::open(&path).unwrap();
+        conn.execute(conn.ow(stmt())).unwrap();
+        conn.execute(conn.ow("BEGIN; DELETE FROM users;")).unwrap();
+        assert!(!conn.is_closed());
+        assert_eq!(conn.close(), Ok(()));
+
+        let conn = owsql::sqlite::open(&path).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+        assert_eq!(conn.close(), Ok(()));
+    }
+
+    #[test]
+    fn attach() {
+        let dir = temporary::Directory::new("sqlite").unwrap();
+        let path = dir.path().join("it's.db");
+        let conn = prepare();
+        conn.attach(&path, "archive").unwrap();
+        conn.execute(conn.ow("CREATE TABLE archive.orders (user TEXT, item TEXT); INSERT INTO archive.orders VALUES ('Bob', 'pen');")).unwrap();
+        let row = conn.single_row(conn.ow("SELECT age FROM users JOIN archive.orders ON users.name = orders.user WHERE item =") + "pen").unwrap();
+        assert_eq!(row.get("age"), Some("69"));
+        assert_eq!(conn.attach(&path, "main"), err!("invalid schema name: \"main\""));
+        assert_eq!(conn.detach("archive; DROP TABLE users"), err!("invalid schema name: \"archive; DROP TABLE users\""));
+        conn.detach("archive").unwrap();
+        assert!(conn.execute(conn.ow("SELECT * FROM archive.orders;")).is_err());
+        assert!(conn.detach("archive").is_err());
+        assert!(path.exists());
+        conn.attach("", "scratch").unwrap();
+        conn.detach("scratch").unwrap();
+    }
+
+    #[test]
+    fn load_extension() {
+        let mut conn = prepare();
+        let result = unsafe { conn.load_extension("/path/to/nothing", Some("sqlite3_nothing_init")) };
+        assert!(result.unwrap_err().to_string().starts_with("failed to load extension: "));
+        assert!(unsafe { conn.load_extension("/path/to/\0", None) }.is_err());
+        conn.error_level(owsql::OwsqlErrorLevel::Debug).unwrap();
+        let sql = conn.ow("SELECT load_extension(") + "/path/to/nothing" + &conn.ow(");");
+        assert!(conn.execute(&sql).unwrap_err().to_string().contains("not authorized"));
+    }
+
+    #[test]
+    fn fts_match() {
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE VIRTUAL TABLE docs USING fts5(title, body);
+                              INSERT INTO docs VALUES ('a', 'It''s a \"quoted\" word'), ('b', 'NEAR the end');")).unwrap();
+        let search = |query| conn.ow("SELECT title FROM docs WHERE") + &conn.fts_match("docs", "body", query) + &conn.ow("ORDER BY title;");
+        assert_eq!(conn.rows(search("quoted")).unwrap().len(), 1);
+        assert_eq!(conn.rows(search("\"quoted\" it's")).unwrap()[0].get("title"), Some("a"));
+        assert_eq!(conn.rows(search("NEAR(the")).unwrap()[0].get("title"), Some("b"));
+        assert_eq!(conn.count(search("quoted OR end")), Ok(0));
+        assert_eq!(conn.count(search("' OR 1=1 --")), Ok(0));
+        assert_eq!(conn.count(search(" * ")), err!("empty search query"));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM docs WHERE") + &conn.fts_match("docs", "body; --", "a")), err!("invalid identifier"));
+        owsql::testing::assert_injection_safe(&conn, |query| conn.ow("SELECT * FROM docs WHERE") + &conn.fts_match("docs", "body", &format!("a {}", query)));
+    }
+
+    #[test]
+    fn geometry() {
+        let conn = prepare();
+        let insert = |geometry: String| conn.ow("INSERT INTO users VALUES('Dave',") + &geometry + &conn.ow(");");
+        assert_eq!(conn.actual_sql(insert(conn.point(1.5, -2.0))), Ok("INSERT INTO users VALUES('Dave', GeomFromText( 'POINT(1.5 -2)' ) ); ".to_string()));
+        assert_eq!(conn.execute(insert(conn.point(f64::NAN, 0.0))), err!("invalid coordinate"));
+        assert_eq!(conn.execute(insert(conn.geometry_wkt("POINT(1 2)')); DROP TABLE users; --"))), err!("invalid geometry"));
+        assert_eq!(conn.execute(insert(conn.geometry_wkt("POLYGON((0 0, 1 0, 1 1, 0 1))"))), err!("invalid geometry"));
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users;")).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn ow_enum() {
+        use owsql::SqlEnum;
+        owsql::owsql_enum! {
+            enum Order {
+                Young => "age ASC",
+                Old => "age DESC",
+            }
+        }
+        let conn = prepare();
+        let order = |order| conn.ow("SELECT name FROM users ORDER BY") + &conn.ow_enum(order) + &conn.ow(";");
+        assert_eq!(conn.rows(order(Order::Young)).unwrap()[0].get("name"), Some("Alice"));
+        assert_eq!(conn.rows(order(Order::Old)).unwrap()[0].get("name"), Some("Bob"));
+        assert_eq!(Order::from_sql("age desc"), Some(Order::Old));
+        assert_eq!(Order::from_sql("age DESC, (SELECT 1)"), None);
+    }
+
+    #[test]
+    fn order_by() {
+        use owsql::{SortOrder, SqlEnum};
+        let conn = prepare();
+        let sort = |column, order| {
+            conn.ow("SELECT name FROM users") + &conn.order_by(&[(column, SortOrder::from_sql(order).unwrap_or_default())]) + &conn.ow(";")
+        };
+        assert_eq!(conn.rows(sort("age", "DESC")).unwrap()[0].get("name"), Some("Bob"));
+        assert_eq!(conn.rows(sort("users.name", "desc; --")).unwrap()[0].get("name"), Some("Alice"));
+        assert_eq!(conn.rows(sort("age; DROP TABLE users", "ASC")), err!("invalid identifier"));
+        assert_eq!(conn.rows(sort("main.users.age", "ASC")), err!("invalid identifier"));
+        assert_eq!(conn.rows(sort("(SELECT 1)", "ASC")), err!("invalid identifier"));
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users") + &conn.order_by(&[]) + &conn.ow(";")), err!("empty column list"));
+        let sql = conn.ow("SELECT age, COUNT(*) AS n FROM users") + &conn.group_by(&["age", "users.name"]) + &conn.ow(";");
+        assert_eq!(conn.rows(sql).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn cond() {
+        use owsql::Cond;
+        let conn = prepare();
+        let names = |cond: Cond| {
+            let sql = conn.ow("SELECT name FROM users") + &conn.where_clause(&cond) + &conn.ow("ORDER BY name;");
+            conn.rows(sql).map(|rows| rows.iter().map(|row| row.get("name").unwrap().to_string()).collect::<Vec<_>>())
+        };
+        let young = Cond::lt("age", conn.int(50));
+        assert_eq!(names(Cond::eq("name", "Bob").or(young.clone())), Ok(vec!["Alice".to_string(), "Bob".to_string()]));
+        assert_eq!(names(Cond::eq("name", "Bob").or(young.clone()).and(Cond::like("name", "A%"))), Ok(vec!["Alice".to_string()]));
+        assert_eq!(names(Cond::eq("name", "Bob").or(young.clone().and(Cond::like("name", "B%")))), Ok(vec!["Bob".to_string()]));
+        assert_eq!(names(!young.or(Cond::eq("users.name", "Bob"))), Ok(vec!["Carol".to_string()]));
+        assert_eq!(names(Cond::eq("name", "")), Ok(vec![]));
+        assert_eq!(names(Cond::eq("name", "' OR 1=1 --")), Ok(vec![]));
+        assert_eq!(names(Cond::is_null("name").or(Cond::is_not_null("age"))).map(|names| names.len()), Ok(3));
+        assert_eq!(names(Cond::eq("name = name OR name", "Alice")), err!("invalid identifier"));
+        let sql = conn.ow("SELECT age FROM users GROUP BY age HAVING") + &conn.cond(&Cond::ge("COUNT(*)", conn.int(1))) + &conn.ow(";");
+        assert_eq!(conn.rows(sql), err!("invalid identifier"));
+        owsql::testing::assert_injection_safe(&conn, |name| {
+            conn.ow("SELECT * FROM users") + &conn.where_clause(&Cond::eq("name", name).and(Cond::ne("name", name))) + &conn.ow(";")
+        });
+    }
+
+    #[test]
+    fn timestamp_columns() {
+        use owsql::{Cond, TimestampColumns};
+        let mut conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE posts (title TEXT, created_at TEXT, updated_at TEXT, deleted_at TEXT);")).unwrap();
+        conn.execute(conn.insert("posts", &[("title", "")]) + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM posts WHERE title = '' AND created_at IS NULL;")), Ok(1));
+        conn.set_timestamp_columns(TimestampColumns::standard());
+        conn.execute(conn.insert("posts", &[("title", "' OR 1=1 --")]) + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM posts WHERE created_at IS NOT NULL AND updated_at IS NOT NULL;")), Ok(1));
+        conn.execute(conn.update("posts", &[("title", "updated")]) + &conn.where_clause(&Cond::eq("title", "")) + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM posts WHERE title = 'updated' AND updated_at IS NOT NULL;")), Ok(1));
+        conn.execute(conn.soft_delete("posts") + &conn.where_clause(&Cond::eq("title", "updated")) + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM posts WHERE deleted_at IS NULL;")), Ok(1));
+        conn.execute(conn.update::<&str>("posts", &[]) + &conn.ow(";")).unwrap();
+        conn.set_timestamp_columns(TimestampColumns::default());
+        assert_eq!(conn.execute(conn.update::<&str>("posts", &[]) + &conn.ow(";")), err!("empty column list"));
+        assert_eq!(conn.execute(conn.insert::<&str>("posts", &[]) + &conn.ow(";")), err!("empty column list"));
+        assert_eq!(conn.execute(conn.insert("posts; --", &[("title", "a")]) + &conn.ow(";")), err!("invalid identifier"));
+        assert_eq!(conn.execute(conn.update("posts", &[("title = title, title", "a")]) + &conn.ow(";")), err!("invalid identifier"));
+        conn.execute(conn.soft_delete("posts") + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM posts;")), Ok(0));
+    }
+
+    #[test]
+    fn schema_prefix() {
+        let mut conn = prepare();
+        conn.attach(":memory:", "tenant_42").unwrap();
+        conn.execute(conn.ow("CREATE TABLE tenant_42.users (name TEXT, age INTEGER);")).unwrap();
+        assert_eq!(conn.set_schema_prefix("tenant_42\"; --"), err!("invalid schema name"));
+        assert_eq!(conn.schema_prefix(), None);
+        conn.set_schema_prefix("tenant_42").unwrap();
+        conn.execute(conn.insert("users", &[("name", "Dave")]) + &conn.ow(";")).unwrap();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM") + &conn.table("users") + &conn.ow(";")), Ok(1));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM") + &conn.table("main.users") + &conn.ow(";")), Ok(3));
+        assert_eq!(conn.count(conn.ow("SELECT * FROM") + &conn.table("users; --") + &conn.ow(";")), err!("invalid identifier"));
+        conn.clear_schema_prefix();
+        assert_eq!(conn.count(conn.ow("SELECT * FROM") + &conn.table("users") + &conn.ow(";")), Ok(3));
+    }
+
+    #[test]
+    fn watch() {
+        use std::time::Duration;
+        use owsql::{ChangeEvent, ChangeKind};
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE posts (title TEXT);")).unwrap();
+        let mut watcher = conn.watch("users").unwrap();
+        assert_eq!(watcher.table(), "users");
+        conn.execute(conn.ow("UPDATE users SET age = 43 WHERE name = 'Alice'; INSERT INTO posts VALUES ('a');")).unwrap();
+        conn.execute(conn.ow("DELETE FROM users WHERE name = 'Bob';")).unwrap();
+        assert_eq!(watcher.events(), Ok(vec![
+            ChangeEvent { table: "users".to_string(), kind: ChangeKind::Update, rowid: Some(1) },
+            ChangeEvent { table: "users".to_string(), kind: ChangeKind::Delete, rowid: Some(2) },
+        ]));
+        assert_eq!(watcher.next_event(Duration::from_millis(10)), Ok(None));
+        drop(watcher);
+        conn.execute(conn.ow("DELETE FROM users WHERE name = 'Carol';")).unwrap();
+        assert!(conn.watch("users; --").is_err());
+    }
+
+    #[test]
+    fn table_checksum() {
+        let conn = prepare();
+        let checksum = conn.table_checksum("users", &["name", "age"]).unwrap();
+        assert_eq!(checksum.rows, 3);
+        conn.execute(conn.ow("CREATE TABLE copy AS SELECT * FROM users ORDER BY age DESC;")).unwrap();
+        assert_eq!(conn.table_checksum("main.copy", &["name", "age"]), Ok(checksum));
+        assert_ne!(conn.table_checksum("copy", &["age", "name"]), Ok(checksum));
+        conn.execute(conn.ow("UPDATE copy SET age = NULL WHERE name = 'Carol';")).unwrap();
+        assert_ne!(conn.table_checksum("copy", &["name", "age"]), Ok(checksum));
+        assert_eq!(conn.table_checksum("users", &["name"]).map(|checksum| checksum.rows), Ok(3));
+        assert_eq!(conn.table_checksum("users; --", &["name"]), err!("invalid identifier"));
+        assert_eq!(conn.table_checksum("users", &["*"]), err!("invalid identifier"));
+        assert_eq!(conn.table_checksum("users", &[]), err!("empty column list"));
+    }
+
+    #[test]
+    fn assert_plan() {
+        use owsql::PlanExpectation;
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE INDEX users_age ON users (age);")).unwrap();
+        let by_age = conn.ow("SELECT * FROM users WHERE age =") + &conn.int(42) + &conn.ow(";");
+        let by_name = conn.ow("SELECT * FROM users WHERE name =") + "Alice" + &conn.ow(";");
+        conn.assert_plan(&by_age, &PlanExpectation::new().index_scan("users").uses_index("users", "users_age").no_full_scan("users"));
+        conn.assert_plan(&by_name, &PlanExpectation::new());
+        let e = conn.check_plan(&by_name, &PlanExpectation::new().no_full_scan("users")).unwrap_err();
+        assert_eq!(e, owsql::OwsqlError::Message("unexpected plan: full scan on users\nSCAN users".to_string()));
+        assert!(conn.check_plan(&by_name, &PlanExpectation::new().index_scan("users")).is_err());
+        assert!(conn.check_plan(&by_age, &PlanExpectation::new().uses_index("users", "users_name")).is_err());
+        assert_eq!(conn.count(conn.ow("SELECT * FROM users;")), Ok(3));
+    }
+
+    #[test]
+    fn add_rewriter() {
+        let mut conn = prepare();
+        conn.add_rewriter(|sql| format!("{} /* trace_id=abc */", sql.trim_end().trim_end_matches(';')));
+        conn.add_rewriter(|sql| sql.replace("ORDER BY age", "ORDER BY age DESC"));
+        let rows = conn.rows(conn.ow("SELECT name FROM users ORDER BY age;")).unwrap();
+        assert_eq!(rows[0].get("name"), Some("Bob"));
+        assert!(!conn.actual_sql(conn.ow("SELECT name FROM users ORDER BY age;")).unwrap().contains("trace_id"));
+        conn.clear_rewriters();
+        let rows = conn.rows(conn.ow("SELECT name FROM users ORDER BY age;")).unwrap();
+        assert_eq!(rows[0].get("name"), Some("Alice"));
+    }
+
+    #[test]
+    fn set_query_tag() {
+        let mut conn = prepare();
+        conn.set_query_tag("endpoint", "GET /users");
+        conn.set_query_tag("endpoint", "*/ DROP TABLE users; --");
+        let row = conn.single_row(conn.ow("SELECT name FROM users WHERE age = 42;")).unwrap();
+        assert_eq!(row.get("name"), Some("Alice"));
+        conn.execute(conn.ow("INSERT INTO users VALUES ('Dave', 18);")).unwrap();
+        conn.clear_query_tags();
+        assert_eq!(conn.rows(conn.ow("SELECT * FROM users;")).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn query_as() {
+        let conn = prepare();
+        let users = conn.query_as::<(String, i64)>(conn.ow("SELECT name, age FROM users ORDER BY age;")).unwrap();
+        assert_eq!(users, vec![("Alice".to_string(), 42), ("Carol".to_string(), 50), ("Bob".to_string(), 69)]);
+        let (count, average) = conn.query_as::<(u32, Option<f64>)>(conn.ow("SELECT COUNT(*), AVG(age) FROM users WHERE age > 100;")).unwrap().remove(0);
+        assert_eq!((count, average), (0, None));
+        assert_eq!(conn.query_as::<(i64,)>(conn.ow("SELECT name FROM users;")), err!("invalid row"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn query_stream() {
+        #[derive(serde::Deserialize)]
+        struct User {
+            name: String,
+            age:  u32,
+        }
+        let conn = prepare();
+        let mut names = Vec::new();
+        conn.query_stream::<User>(conn.ow("SELECT name, age FROM users ORDER BY age;"), |user| {
+            names.push(user.name);
+            user.age < 50
+        }).unwrap();
+        assert_eq!(names, vec!["Alice", "Carol"]);
+        let result = conn.query_stream::<User>(conn.ow("SELECT name FROM users;"), |_| true);
+        assert_eq!(result, err!("invalid row"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn query_stream_nested() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct User {
+            name: String,
+            age:  u32,
+        }
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Post {
+            title:  String,
+            author: User,
+            #[serde(rename = "editor_")]
+            editor: Option<User>,
+        }
+        let conn = prepare();
+        conn.execute(conn.ow("CREATE TABLE posts (title TEXT, author TEXT, editor TEXT);
+                              INSERT INTO posts VALUES ('Hello', 'Alice', 'Bob'), ('World', 'Carol', NULL);")).unwrap();
+        let mut posts = Vec::new();
+        conn.query_stream::<Post>(conn.ow("SELECT p.title, a.name AS author_name, a.age AS author_age, e.name AS editor_name, e.age AS editor_age
+                                           FROM posts p JOIN users a ON a.name = p.author LEFT JOIN users e ON e.name = p.editor
+                                           ORDER BY p.title;"), |post| {
+            posts.push(post);
+            true
+        }).unwrap();
+        assert_eq!(posts, vec![
+            Post { title: "Hello".into(), author: User { name: "Alice".into(), age: 42 }, editor: Some(User { name: "Bob".into(), age: 69 }) },
+            Post { title: "World".into(), author: User { name: "Carol".into(), age: 50 }, editor: None },
+        ]);
+    }
+
+    #[test]
+    fn next_id() {
+        let conn = prepare();
+        assert_eq!(conn.next_id("orders"), Ok(1));
+        assert_eq!(conn.next_id("orders"), Ok(2));
+        assert_eq!(conn.next_id("invoices"), Ok(1));
+        assert_eq!(conn.next_id("orders; DROP TABLE users"), err!("invalid identifier"));
+    }
+
+    #[test]
+    fn injection_safe() {
+        use owsql::testing::{assert_injection_safe, check_injection_safe};
+        let conn = prepare();
+        assert_injection_safe(&conn, |name| conn.ow("SELECT * FROM users WHERE name =") + name + &conn.ow("AND age >") + &conn.int(42));
+        assert_injection_safe(&conn, |age| conn.ow("SELECT * FROM users WHERE age =") + &conn.int(age));
+        let (payload, sql) = check_injection_safe(&conn, |name| {
+            conn.ow_runtime(format!("SELECT * FROM users WHERE name = {}", name)).unwrap_or_default()
+        }).unwrap_err();
+        assert!(!payload.is_empty());
+        assert!(sql.starts_with("SELECT * FROM users WHERE name ="));
+    }
+
+    #[test]
+    fn tokenize() {
+        let conn = prepare();
+        let sql = conn.ow("SELECT name FROM users WHERE age >=") + &conn.int(50) + &conn.ow("AND name <>") + "Bob' --";
+        let tokens = conn.tokenize(&sql).unwrap();
+        assert_eq!(tokens[0], Token::Keyword("SELECT".into()));
+        assert_eq!(tokens[6], Token::Symbol(">=".into()));
+        assert_eq!(tokens[7], Token::Number("50".into()));
+        assert_eq!(tokens[10], Token::Symbol("<>".into()));
+        assert_eq!(tokens[11], Token::Literal("'Bob'' --'".into()));
+        assert_eq!(tokens.iter().filter(|token| token.is_literal()).count(), 1);
+        assert_eq!(conn.tokenize(conn.ow("SELECT") + &conn.int("x")), err!("non integer"));
+    }
+
     mod should_panic {
         use owsql::params;
         use super::stmt;